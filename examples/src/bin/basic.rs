@@ -7,10 +7,11 @@ use std::rc::Rc;
 use examples::GradientRenderer;
 use tracing::{error, info, warn};
 use winit::application::ApplicationHandler;
-use winit::event::{ElementState, MouseButton, WindowEvent};
+use winit::event::WindowEvent;
 use winit::event_loop::{ActiveEventLoop, EventLoop};
 use winit::icon::{Icon, RgbaIcon};
 use winit::window::{Window, WindowAttributes, WindowId};
+use winit_extras::{ButtonSource, ElementState, MouseButton};
 
 fn load_icon(path: &Path) -> Result<Icon, Box<dyn Error>> {
     let image = image::open(path)?.into_rgba8();
@@ -48,11 +49,14 @@ impl ApplicationHandler for App {
             }
         };
 
-        let mut tray_attributes =
-            winit_extras::TrayIconAttributes::default().with_tooltip("Winit Tray Example");
-        if let Some(icon) = icon.clone() {
-            tray_attributes = tray_attributes.with_icon(icon);
-        }
+        let window_attributes = WindowAttributes::default()
+            .with_window_icon(icon)
+            .with_title("Winit Tray Example");
+
+        // Reuse the window's icon for the tray instead of loading it twice.
+        let tray_attributes = winit_extras::TrayIconAttributes::default()
+            .with_tooltip("Winit Tray Example")
+            .with_window_icon(&window_attributes);
 
         self.tray = match self.tray_manager.create_tray(tray_attributes) {
             Ok(tray) => Some(tray),
@@ -63,10 +67,6 @@ impl ApplicationHandler for App {
             }
         };
 
-        let window_attributes = WindowAttributes::default()
-            .with_window_icon(icon)
-            .with_title("Winit Tray Example");
-
         let window = match event_loop.create_window(window_attributes) {
             Ok(window) => Rc::new(window),
             Err(err) => {
@@ -76,7 +76,9 @@ impl ApplicationHandler for App {
             }
         };
 
-        self.renderer = Some(GradientRenderer::new(window.clone()));
+        self.renderer = GradientRenderer::new(window.clone())
+            .inspect_err(|err| error!(%err, "failed to create renderer, window will stay blank"))
+            .ok();
         window.request_redraw();
         self.window = Some(window);
     }
@@ -86,7 +88,7 @@ impl ApplicationHandler for App {
             match event {
                 winit_extras::Event::PointerButton {
                     state: ElementState::Released,
-                    button: winit::event::ButtonSource::Mouse(MouseButton::Left),
+                    button: ButtonSource::Mouse(MouseButton::Left),
                     ..
                 } => {
                     info!("tray icon left-clicked");
@@ -109,8 +111,10 @@ impl ApplicationHandler for App {
                 event_loop.exit();
             }
             WindowEvent::SurfaceResized(size) => {
-                if let Some(renderer) = &mut self.renderer {
-                    renderer.resize(size.width, size.height);
+                if let Some(renderer) = &mut self.renderer
+                    && let Err(err) = renderer.resize(size.width, size.height)
+                {
+                    warn!(%err, "failed to resize surface");
                 }
                 if let Some(window) = &self.window {
                     window.request_redraw();
@@ -119,7 +123,9 @@ impl ApplicationHandler for App {
             WindowEvent::RedrawRequested => {
                 if let (Some(renderer), Some(window)) = (&mut self.renderer, &self.window) {
                     let size = window.surface_size();
-                    renderer.render(size.width, size.height);
+                    if let Err(err) = renderer.render(size.width, size.height) {
+                        warn!(%err, "failed to render frame, skipping it");
+                    }
                     window.pre_present_notify();
                 }
             }
@@ -0,0 +1,399 @@
+//! Conformance run that exercises every tray feature on the current platform.
+//!
+//! Creates a tray with a context menu, a window context menu and, on Windows
+//! and macOS, a menu bar. It then changes them on a timer, logging whether
+//! each step worked, and exits once the tray has been removed. Unlike `full`
+//! this needs no interaction, so running it is a quick smoke test of the
+//! runtime setters on a new platform or after a refactor.
+//!
+//! Steps a backend doesn't support log a warning rather than stopping the
+//! run.
+
+use std::error::Error;
+use std::path::Path;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use examples::GradientRenderer;
+use tracing::{error, info, warn};
+use winit::application::ApplicationHandler;
+use winit::dpi::PhysicalPosition;
+use winit::event::{StartCause, WindowEvent};
+use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoop};
+use winit::icon::{Icon, RgbaIcon};
+use winit::window::{Window, WindowAttributes, WindowId};
+use winit_extras::context_menu::ContextMenu;
+use winit_extras::icon::StatusColor;
+use winit_extras::{Event, Manager, MenuEntry, MenuItem, TrayIcon, TrayIconAttributes};
+
+#[cfg(all(feature = "menu_bar", any(target_os = "windows", target_os = "macos")))]
+use winit_extras::{MenuBarManager, menu_bar::MenuBar, menu_bar::TopLevelMenu};
+
+/// Time between steps, long enough to see each change happen.
+const STEP_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How long a flashed tooltip shows, shorter than a step so it reverts
+/// before the next one.
+const FLASH_DURATION: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Action {
+    Toggle,
+    Extra,
+    Quit,
+}
+
+/// The icon a [`Step::SetIcon`] shows.
+#[derive(Debug, Clone, Copy)]
+enum TrayImage {
+    Placeholder,
+    Loaded,
+    None,
+}
+
+/// One change made to the tray, menus or manager.
+#[derive(Debug, Clone, Copy)]
+enum Step {
+    SetTooltip(Option<&'static str>),
+    FlashTooltip(&'static str),
+    SetIcon(TrayImage),
+    StatusDot(Option<StatusColor>),
+    SetInteractive(bool),
+    RelabelTrayItem(&'static str),
+    SetTrayMenu { extra: bool },
+    ReportTrayPosition,
+    RelabelMenuBarItem(&'static str),
+    ShowWindowMenu,
+    CloseWindowMenu,
+    Pause,
+    Resume,
+    RemoveTray,
+}
+
+const STEPS: &[Step] = &[
+    Step::SetTooltip(Some("Conformance: running")),
+    Step::FlashTooltip("Conformance: flashed"),
+    Step::SetTooltip(None),
+    Step::SetTooltip(Some("Conformance")),
+    Step::SetIcon(TrayImage::Placeholder),
+    Step::StatusDot(Some(StatusColor::Green)),
+    Step::StatusDot(Some(StatusColor::Red)),
+    Step::StatusDot(None),
+    Step::SetIcon(TrayImage::None),
+    Step::SetIcon(TrayImage::Loaded),
+    Step::SetInteractive(false),
+    Step::SetInteractive(true),
+    Step::RelabelTrayItem("Resume"),
+    Step::RelabelTrayItem("Pause"),
+    Step::SetTrayMenu { extra: true },
+    Step::SetTrayMenu { extra: false },
+    Step::ReportTrayPosition,
+    Step::RelabelMenuBarItem("Resume"),
+    Step::RelabelMenuBarItem("Pause"),
+    Step::ShowWindowMenu,
+    Step::CloseWindowMenu,
+    Step::Pause,
+    Step::Resume,
+    Step::RemoveTray,
+];
+
+fn load_icon(path: &Path) -> Result<Icon, Box<dyn Error>> {
+    let image = image::open(path)?.into_rgba8();
+    let (width, height) = image.dimensions();
+    let rgba = image.into_raw();
+    let icon = RgbaIcon::new(rgba, width, height)?;
+    Ok(Icon::from(icon))
+}
+
+fn tray_menu() -> Vec<MenuEntry<Action>> {
+    vec![
+        MenuEntry::Item(MenuItem::new(Action::Toggle, "Pause")),
+        MenuEntry::Separator,
+        MenuEntry::Item(MenuItem::new(Action::Quit, "Quit")),
+    ]
+}
+
+/// [`tray_menu`] with an extra item, for replacing the tray's menu.
+fn extended_tray_menu() -> Vec<MenuEntry<Action>> {
+    let mut menu = tray_menu();
+    menu.insert(1, MenuEntry::Item(MenuItem::new(Action::Extra, "Extra")));
+    menu
+}
+
+struct App {
+    window: Option<Rc<Box<dyn Window>>>,
+    manager: Manager<Action>,
+    tray: Option<Box<dyn TrayIcon<Action>>>,
+    /// The icon loaded from disk, shown again after the placeholder.
+    icon: Option<Icon>,
+    window_menu: Option<Rc<dyn ContextMenu>>,
+    #[cfg(all(feature = "menu_bar", any(target_os = "windows", target_os = "macos")))]
+    menu_bar_manager: MenuBarManager<Action>,
+    #[cfg(all(feature = "menu_bar", any(target_os = "windows", target_os = "macos")))]
+    menu_bar: Option<Box<dyn MenuBar<Action>>>,
+    renderer: Option<GradientRenderer>,
+    next_step: usize,
+    failures: usize,
+}
+
+impl App {
+    fn new(event_loop: &EventLoop) -> Self {
+        App {
+            window: None,
+            manager: Manager::new(event_loop),
+            tray: None,
+            icon: None,
+            window_menu: None,
+            #[cfg(all(feature = "menu_bar", any(target_os = "windows", target_os = "macos")))]
+            menu_bar_manager: MenuBarManager::new(event_loop),
+            #[cfg(all(feature = "menu_bar", any(target_os = "windows", target_os = "macos")))]
+            menu_bar: None,
+            renderer: None,
+            next_step: 0,
+            failures: 0,
+        }
+    }
+
+    #[cfg(all(feature = "menu_bar", any(target_os = "windows", target_os = "macos")))]
+    fn create_menu_bar(&mut self, window: &dyn Window) {
+        use winit::raw_window_handle::HasWindowHandle;
+
+        let menus = vec![TopLevelMenu::new("Conformance", tray_menu())];
+        let Ok(handle) = window.window_handle() else {
+            warn!("no window handle, skipping the menu bar");
+            return;
+        };
+        self.menu_bar = self
+            .menu_bar_manager
+            .builder(menus)
+            .parent_window(handle.as_raw())
+            .build()
+            .inspect_err(|err| error!(%err, "failed to create menu bar"))
+            .ok();
+    }
+
+    /// Runs `step`, returning an error if it didn't work on this platform.
+    fn run_step(&mut self, step: Step) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let tray = self.tray.as_ref().ok_or("tray was not created")?;
+        match step {
+            Step::SetTooltip(tooltip) => tray.set_tooltip(tooltip)?,
+            Step::FlashTooltip(tooltip) => tray.flash_tooltip(tooltip, FLASH_DURATION)?,
+            Step::SetIcon(image) => {
+                let icon = match image {
+                    TrayImage::Placeholder => Some(winit_extras::icon::placeholder()),
+                    TrayImage::Loaded => Some(self.icon.clone().ok_or("icon was not loaded")?),
+                    TrayImage::None => None,
+                };
+                tray.set_icon(icon.as_ref())?;
+            }
+            Step::StatusDot(color) => tray.set_status_dot(color)?,
+            Step::SetInteractive(interactive) => tray.set_interactive(interactive)?,
+            Step::RelabelTrayItem(label) => tray.set_label(&Action::Toggle, label)?,
+            Step::SetTrayMenu { extra } => tray.set_menu(if extra {
+                extended_tray_menu()
+            } else {
+                tray_menu()
+            })?,
+            Step::ReportTrayPosition => {
+                info!(
+                    last_pointer_position = ?tray.last_pointer_position(),
+                    is_in_overflow = ?tray.is_in_overflow(),
+                    "tray position"
+                );
+            }
+            Step::RelabelMenuBarItem(label) => {
+                #[cfg(all(feature = "menu_bar", any(target_os = "windows", target_os = "macos")))]
+                {
+                    let menu_bar = self.menu_bar.as_ref().ok_or("menu bar was not created")?;
+                    menu_bar.set_label(&Action::Toggle, label)?;
+                    info!(labels = ?menu_bar.top_level_labels(), "menu bar");
+                }
+                #[cfg(not(all(
+                    feature = "menu_bar",
+                    any(target_os = "windows", target_os = "macos")
+                )))]
+                {
+                    let _ = label;
+                    return Err("menu bars are not available on this platform".into());
+                }
+            }
+            Step::ShowWindowMenu => {
+                let menu = self
+                    .window_menu
+                    .as_ref()
+                    .ok_or("window menu was not created")?;
+                menu.show(PhysicalPosition::new(40, 40));
+            }
+            Step::CloseWindowMenu => {
+                let menu = self
+                    .window_menu
+                    .as_ref()
+                    .ok_or("window menu was not created")?;
+                menu.close();
+            }
+            Step::Pause => {
+                self.manager.set_paused(true);
+                if !self.manager.is_paused() {
+                    return Err("manager did not pause".into());
+                }
+            }
+            Step::Resume => {
+                self.manager.set_paused(false);
+                if self.manager.is_paused() {
+                    return Err("manager did not resume".into());
+                }
+            }
+            // `Event::AllTraysRemoved` ends the run.
+            Step::RemoveTray => self.tray = None,
+        }
+        Ok(())
+    }
+}
+
+impl ApplicationHandler for App {
+    fn new_events(&mut self, event_loop: &dyn ActiveEventLoop, cause: StartCause) {
+        if !matches!(cause, StartCause::ResumeTimeReached { .. }) {
+            return;
+        }
+        let Some(&step) = STEPS.get(self.next_step) else {
+            return;
+        };
+        self.next_step += 1;
+
+        match self.run_step(step) {
+            Ok(()) => info!(?step, "step passed"),
+            Err(err) => {
+                self.failures += 1;
+                warn!(?step, %err, "step failed");
+            }
+        }
+        if self.next_step < STEPS.len() {
+            event_loop.set_control_flow(ControlFlow::WaitUntil(Instant::now() + STEP_INTERVAL));
+        } else {
+            event_loop.set_control_flow(ControlFlow::Wait);
+        }
+    }
+
+    fn can_create_surfaces(&mut self, event_loop: &dyn ActiveEventLoop) {
+        let icon = load_icon(Path::new("assets/ferris.png"))
+            .inspect_err(|err| warn!(%err, "failed to load icon"))
+            .ok();
+        self.icon = icon.clone();
+
+        let mut tray_attributes = TrayIconAttributes::default()
+            .with_tooltip("Conformance")
+            .with_context_menu(tray_menu());
+        if let Some(icon) = icon.clone() {
+            tray_attributes = tray_attributes.with_icon(icon);
+        }
+        self.tray = match self.manager.create_tray(tray_attributes) {
+            Ok(tray) => Some(tray),
+            Err(err) => {
+                error!(%err, "failed to create tray");
+                event_loop.exit();
+                return;
+            }
+        };
+
+        let window_attributes = WindowAttributes::default()
+            .with_window_icon(icon)
+            .with_title("Conformance");
+        let window = match event_loop.create_window(window_attributes) {
+            Ok(window) => Rc::new(window),
+            Err(err) => {
+                error!(%err, "failed to create window");
+                event_loop.exit();
+                return;
+            }
+        };
+
+        self.window_menu = self
+            .manager
+            .create_menu(event_loop, window.as_ref(), tray_menu())
+            .inspect_err(|err| error!(%err, "failed to create window context menu"))
+            .ok();
+
+        #[cfg(all(feature = "menu_bar", any(target_os = "windows", target_os = "macos")))]
+        self.create_menu_bar(window.as_ref().as_ref());
+
+        self.renderer = GradientRenderer::new(window.clone())
+            .inspect_err(|err| error!(%err, "failed to create renderer, window will stay blank"))
+            .ok();
+        window.request_redraw();
+        self.window = Some(window);
+
+        info!(steps = STEPS.len(), "starting conformance run");
+        event_loop.set_control_flow(ControlFlow::WaitUntil(Instant::now() + STEP_INTERVAL));
+    }
+
+    fn proxy_wake_up(&mut self, event_loop: &dyn ActiveEventLoop) {
+        while let Ok(event) = self.manager.try_recv() {
+            match event {
+                Event::AllTraysRemoved => {
+                    info!(
+                        failures = self.failures,
+                        "tray removed, conformance run finished"
+                    );
+                    event_loop.exit();
+                }
+                Event::MenuItemClicked {
+                    id: Action::Quit, ..
+                } => event_loop.exit(),
+                event => info!(?event, "tray event"),
+            }
+        }
+
+        #[cfg(all(feature = "menu_bar", any(target_os = "windows", target_os = "macos")))]
+        while let Ok((_, event)) = self.menu_bar_manager.try_recv() {
+            info!(?event, "menu bar event");
+        }
+    }
+
+    fn window_event(
+        &mut self,
+        event_loop: &dyn ActiveEventLoop,
+        window_id: WindowId,
+        event: WindowEvent,
+    ) {
+        if self.manager.handle_window_event(window_id, &event) {
+            return;
+        }
+
+        match event {
+            WindowEvent::CloseRequested => {
+                info!("close requested, stopping");
+                event_loop.exit();
+            }
+            WindowEvent::SurfaceResized(size) => {
+                if let Some(renderer) = &mut self.renderer
+                    && let Err(err) = renderer.resize(size.width, size.height)
+                {
+                    warn!(%err, "failed to resize surface");
+                }
+                if let Some(window) = &self.window {
+                    window.request_redraw();
+                }
+            }
+            WindowEvent::RedrawRequested => {
+                if let (Some(renderer), Some(window)) = (&mut self.renderer, &self.window) {
+                    let size = window.surface_size();
+                    if let Err(err) = renderer.render(size.width, size.height) {
+                        warn!(%err, "failed to render frame, skipping it");
+                    }
+                    window.pre_present_notify();
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    tracing_subscriber::fmt::init();
+
+    let event_loop = EventLoop::new()?;
+    let app = App::new(&event_loop);
+    event_loop.run_app(app)?;
+
+    Ok(())
+}
@@ -12,7 +12,7 @@ use winit::event::{ElementState, MouseButton, WindowEvent};
 use winit::event_loop::{ActiveEventLoop, EventLoop};
 use winit::icon::{Icon, RgbaIcon};
 use winit::window::{Window, WindowAttributes, WindowId};
-use winit_extras::context_menu::ContextMenu;
+use winit_extras::context_menu::{ContextMenu, context_menu_position};
 use winit_extras::{Event, Manager, MenuEntry, MenuItem};
 
 type WindowHandle = Rc<Box<dyn Window>>;
@@ -38,7 +38,7 @@ enum Action {
 struct App {
     window: Option<WindowHandle>,
     tray: Manager<Action>,
-    tray_icon: Option<Box<dyn winit_extras::TrayIcon>>,
+    tray_icon: Option<Box<dyn winit_extras::TrayIcon<Action>>>,
     window_menu: Option<Rc<dyn ContextMenu>>,
     tray_menu: Option<Rc<dyn ContextMenu>>,
     surface: Option<SoftbufferSurface>,
@@ -187,7 +187,7 @@ impl ApplicationHandler for App {
                         menu.show_at_screen_pos(pos);
                     }
                 }
-                Event::MenuItemClicked { id } => match id {
+                Event::MenuItemClicked { id, .. } => match id {
                     Action::ShowWindow => {
                         if let Some(window) = &self.window {
                             window.focus_window();
@@ -215,23 +215,18 @@ impl ApplicationHandler for App {
         if self.tray.handle_window_event(wid, &event) {
             return;
         }
+        if let Some(position) = context_menu_position(&event) {
+            if let Some(menu) = &self.window_menu {
+                menu.show(position);
+            }
+            return;
+        }
 
         match event {
             WindowEvent::CloseRequested => {
                 info!("close requested");
                 event_loop.exit();
             }
-            WindowEvent::PointerButton {
-                state: ElementState::Released,
-                button: winit::event::ButtonSource::Mouse(MouseButton::Right),
-                position,
-                ..
-            } => {
-                if let Some(menu) = &self.window_menu {
-                    let pos = PhysicalPosition::new(position.x as i32, position.y as i32);
-                    menu.show(pos);
-                }
-            }
             WindowEvent::SurfaceResized(size) => {
                 if size.width > 0
                     && size.height > 0
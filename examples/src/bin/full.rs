@@ -21,6 +21,8 @@ use winit_extras_core::menu_bar::{MenuBar, MenuBarAttributes, MenuBarEvent, TopL
 
 #[cfg(all(feature = "menu_bar", any(target_os = "windows", target_os = "macos")))]
 use winit_extras::MenuBarManager;
+#[cfg(all(feature = "menu_bar", any(target_os = "windows", target_os = "macos")))]
+use winit_extras::MenuRole;
 
 #[cfg(feature = "context_menu")]
 #[cfg(feature = "context_menu")]
@@ -117,6 +119,7 @@ fn map_menu_entry<A: Clone, B: Clone>(entry: MenuEntry<A>, f: fn(A) -> B) -> Men
             MenuEntry::Item(MenuItem::new(f(item.id), &item.label).enabled(item.enabled))
         }
         MenuEntry::Separator => MenuEntry::Separator,
+        MenuEntry::StyledSeparator(style) => MenuEntry::StyledSeparator(style),
         MenuEntry::Submenu(sub) => MenuEntry::Submenu(Submenu::new(
             &sub.label,
             sub.items
@@ -138,11 +141,11 @@ struct App {
     window: Option<Rc<Box<dyn Window>>>,
     renderer: Option<GradientRenderer>,
     tray: Manager<AppAction>,
-    tray_icon: Option<Box<dyn winit_extras::TrayIcon>>,
+    tray_icon: Option<Box<dyn winit_extras::TrayIcon<AppAction>>>,
     #[cfg(all(feature = "menu_bar", any(target_os = "windows", target_os = "macos")))]
     menu_bar_manager: MenuBarManager<MenuBarAction>,
     #[cfg(all(feature = "menu_bar", any(target_os = "windows", target_os = "macos")))]
-    _menu_bar: Option<Box<dyn MenuBar>>,
+    _menu_bar: Option<Box<dyn MenuBar<MenuBarAction>>>,
     #[cfg(feature = "context_menu")]
     context_menu: Option<Rc<dyn ContextMenu>>,
     #[cfg(feature = "context_menu")]
@@ -288,7 +291,10 @@ impl App {
                         ],
                     )),
                     MenuEntry::Separator,
-                    MenuEntry::Item(MenuItem::new(MenuBarAction::Fullscreen, "Fullscreen")),
+                    MenuEntry::Item(
+                        MenuItem::new(MenuBarAction::Fullscreen, "Fullscreen")
+                            .role(MenuRole::ToggleFullscreen),
+                    ),
                 ],
             ),
             TopLevelMenu::new(
@@ -417,16 +423,13 @@ impl ApplicationHandler for App {
         // Create the menu bar
         #[cfg(all(feature = "menu_bar", any(target_os = "windows", target_os = "macos")))]
         {
-            #[cfg(target_os = "windows")]
+            // The window is also what the `ToggleFullscreen` role acts on.
             let menu_bar_attrs = {
                 use winit::raw_window_handle::HasWindowHandle;
                 MenuBarAttributes::new(Self::build_menu_bar())
                     .with_parent_window(window.window_handle().unwrap().as_raw())
             };
 
-            #[cfg(target_os = "macos")]
-            let menu_bar_attrs = MenuBarAttributes::new(Self::build_menu_bar());
-
             match self.menu_bar_manager.create_menu_bar(menu_bar_attrs) {
                 Ok(menu_bar) => {
                     info!("Menu bar created");
@@ -477,7 +480,9 @@ impl ApplicationHandler for App {
         }
 
         // Initialize renderer
-        self.renderer = Some(GradientRenderer::new(window.clone()));
+        self.renderer = GradientRenderer::new(window.clone())
+            .inspect_err(|err| error!(%err, "failed to create renderer, window will stay blank"))
+            .ok();
 
         window.request_redraw();
         self.window = Some(window);
@@ -507,7 +512,7 @@ impl ApplicationHandler for App {
                 winit_extras::Event::PointerButton { state, button, .. } => {
                     info!(?state, ?button, "Tray icon clicked");
                 }
-                winit_extras::Event::MenuItemClicked { id } => match id {
+                winit_extras::Event::MenuItemClicked { id, .. } => match id {
                     AppAction::Tray(tray_action) => {
                         info!(?tray_action, "Tray menu item clicked");
                         match tray_action {
@@ -623,18 +628,8 @@ impl ApplicationHandler for App {
                         }
                         info!("Theme: Dark");
                     }
-                    MenuBarAction::Fullscreen => {
-                        if let Some(window) = &self.window {
-                            let is_fullscreen = window.fullscreen().is_some();
-                            if is_fullscreen {
-                                window.set_fullscreen(None);
-                            } else {
-                                window.set_fullscreen(Some(
-                                    winit::monitor::Fullscreen::Borderless(None),
-                                ));
-                            }
-                        }
-                    }
+                    // The menu bar toggles fullscreen through the item's role.
+                    MenuBarAction::Fullscreen => info!("Toggled fullscreen"),
                     MenuBarAction::About => info!("About: winit_extras full example"),
                     MenuBarAction::Documentation => info!("Opening documentation..."),
                     MenuBarAction::CheckUpdates => info!("Checking for updates..."),
@@ -661,8 +656,10 @@ impl ApplicationHandler for App {
                 event_loop.exit();
             }
             WindowEvent::SurfaceResized(size) => {
-                if let Some(renderer) = &mut self.renderer {
-                    renderer.resize(size.width, size.height);
+                if let Some(renderer) = &mut self.renderer
+                    && let Err(err) = renderer.resize(size.width, size.height)
+                {
+                    warn!(%err, "failed to resize surface");
                 }
                 if let Some(window) = &self.window {
                     window.request_redraw();
@@ -671,7 +668,9 @@ impl ApplicationHandler for App {
             WindowEvent::RedrawRequested => {
                 if let (Some(renderer), Some(window)) = (&mut self.renderer, &self.window) {
                     let size = window.surface_size();
-                    renderer.render(size.width, size.height);
+                    if let Err(err) = renderer.render(size.width, size.height) {
+                        warn!(%err, "failed to render frame, skipping it");
+                    }
                     window.pre_present_notify();
                 }
             }
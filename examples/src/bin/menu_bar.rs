@@ -7,16 +7,16 @@ use std::error::Error;
 use std::rc::Rc;
 
 use examples::GradientRenderer;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 use winit::application::ApplicationHandler;
 use winit::event::WindowEvent;
 use winit::event_loop::{ActiveEventLoop, EventLoop};
 use winit::window::{Window, WindowAttributes, WindowId};
 
 #[cfg(all(feature = "menu_bar", any(target_os = "windows", target_os = "macos")))]
-use winit_extras::{MenuBarManager, MenuEntry, MenuItem};
+use winit_extras::{ItemAction, MenuBarManager, MenuEntry, MenuItem, MenuRole};
 #[cfg(all(feature = "menu_bar", any(target_os = "windows", target_os = "macos")))]
-use winit_extras_core::menu_bar::{MenuBar, MenuBarAttributes, MenuBarEvent, TopLevelMenu};
+use winit_extras_core::menu_bar::{MenuBar, MenuBarEvent, TopLevelMenu};
 
 /// Menu item identifiers using an enum for type safety.
 #[cfg(all(feature = "menu_bar", any(target_os = "windows", target_os = "macos")))]
@@ -56,7 +56,7 @@ struct App {
     #[cfg(all(feature = "menu_bar", any(target_os = "windows", target_os = "macos")))]
     menu_bar_manager: MenuBarManager<MenuId>,
     #[cfg(all(feature = "menu_bar", any(target_os = "windows", target_os = "macos")))]
-    _menu_bar: Option<Box<dyn MenuBar>>,
+    _menu_bar: Option<Box<dyn MenuBar<MenuId>>>,
     renderer: Option<GradientRenderer>,
 }
 
@@ -102,7 +102,9 @@ impl ApplicationHandler for App {
                         MenuEntry::Item(MenuItem::new(MenuAction::Save, "Save")),
                         MenuEntry::Item(MenuItem::new(MenuAction::SaveAs, "Save As...")),
                         MenuEntry::Separator,
-                        MenuEntry::Item(MenuItem::new(MenuAction::Quit, "Quit")),
+                        MenuEntry::Item(
+                            MenuItem::new(MenuAction::Quit, "Quit").role(MenuRole::Quit),
+                        ),
                     ],
                 ),
                 TopLevelMenu::new(
@@ -127,26 +129,28 @@ impl ApplicationHandler for App {
                 TopLevelMenu::new(
                     "Help",
                     vec![
-                        MenuEntry::Item(MenuItem::new(MenuAction::Documentation, "Documentation")),
+                        MenuEntry::Item(
+                            MenuItem::new(MenuAction::Documentation, "Documentation")
+                                .action(ItemAction::OpenUrl("https://docs.rs/winit".into())),
+                        ),
                         MenuEntry::Separator,
-                        MenuEntry::Item(MenuItem::new(MenuAction::About, "About")),
+                        MenuEntry::Item(
+                            MenuItem::new(MenuAction::About, "About").role(MenuRole::About),
+                        ),
                     ],
                 ),
             ];
 
-            // On Windows, we need to provide the parent window handle
-            #[cfg(target_os = "windows")]
-            let menu_bar_attrs = {
-                use winit::raw_window_handle::HasWindowHandle;
-                MenuBarAttributes::new(menus)
-                    .with_parent_window(window.window_handle().unwrap().as_raw())
-            };
+            // The parent window is required on Windows and ignored on macOS,
+            // where the menu bar is the global app menu
+            use winit::raw_window_handle::HasWindowHandle;
+            let menu_bar = self
+                .menu_bar_manager
+                .builder(menus)
+                .parent_window(window.window_handle().unwrap().as_raw())
+                .build();
 
-            // On macOS, no parent window is needed (global app menu)
-            #[cfg(target_os = "macos")]
-            let menu_bar_attrs = MenuBarAttributes::new(menus);
-
-            match self.menu_bar_manager.create_menu_bar(menu_bar_attrs) {
+            match menu_bar {
                 Ok(menu_bar) => {
                     info!("menu bar created successfully");
                     self._menu_bar = Some(menu_bar);
@@ -158,7 +162,9 @@ impl ApplicationHandler for App {
         }
 
         // Initialize renderer
-        self.renderer = Some(GradientRenderer::new(window.clone()));
+        self.renderer = GradientRenderer::new(window.clone())
+            .inspect_err(|err| error!(%err, "failed to create renderer, window will stay blank"))
+            .ok();
 
         // Request an initial redraw so the window appears on Wayland
         window.request_redraw();
@@ -190,7 +196,7 @@ impl ApplicationHandler for App {
                     MenuAction::ZoomOut => info!("Zooming out..."),
                     MenuAction::ResetZoom => info!("Zoom reset to 100%"),
                     MenuAction::About => info!("About dialog would appear"),
-                    MenuAction::Documentation => info!("Opening documentation..."),
+                    MenuAction::Documentation => info!("Opened documentation"),
                 }
             }
         }
@@ -203,8 +209,10 @@ impl ApplicationHandler for App {
                 event_loop.exit();
             }
             WindowEvent::SurfaceResized(size) => {
-                if let Some(renderer) = &mut self.renderer {
-                    renderer.resize(size.width, size.height);
+                if let Some(renderer) = &mut self.renderer
+                    && let Err(err) = renderer.resize(size.width, size.height)
+                {
+                    warn!(%err, "failed to resize surface");
                 }
                 if let Some(window) = &self.window {
                     window.request_redraw();
@@ -213,7 +221,9 @@ impl ApplicationHandler for App {
             WindowEvent::RedrawRequested => {
                 if let (Some(renderer), Some(window)) = (&mut self.renderer, &self.window) {
                     let size = window.surface_size();
-                    renderer.render(size.width, size.height);
+                    if let Err(err) = renderer.render(size.width, size.height) {
+                        warn!(%err, "failed to render frame, skipping it");
+                    }
                     window.pre_present_notify();
                 }
             }
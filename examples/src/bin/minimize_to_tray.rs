@@ -0,0 +1,171 @@
+//! Closing the window hides it to the tray instead of exiting.
+//!
+//! Left-click the tray icon to bring the window back. Use the tray's context
+//! menu to quit.
+
+use std::error::Error;
+use std::path::Path;
+use std::rc::Rc;
+
+use examples::GradientRenderer;
+use tracing::{error, info, warn};
+use winit::application::ApplicationHandler;
+use winit::event::WindowEvent;
+use winit::event_loop::{ActiveEventLoop, EventLoop};
+use winit::icon::{Icon, RgbaIcon};
+use winit::window::{Window, WindowAttributes, WindowId};
+use winit_extras::window::{minimize_to_tray, restore_from_tray};
+use winit_extras::{ButtonSource, ElementState, Event, Manager, MenuEntry, MenuItem, MouseButton};
+
+fn load_icon(path: &Path) -> Result<Icon, Box<dyn Error>> {
+    let image = image::open(path)?.into_rgba8();
+    let (width, height) = image.dimensions();
+    let rgba = image.into_raw();
+    let icon = RgbaIcon::new(rgba, width, height)?;
+    Ok(Icon::from(icon))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Action {
+    Show,
+    Quit,
+}
+
+struct App {
+    window: Option<Rc<Box<dyn Window>>>,
+    tray_manager: Manager<Action>,
+    tray: Option<Box<dyn winit_extras::TrayIcon<Action>>>,
+    renderer: Option<GradientRenderer>,
+}
+
+impl App {
+    fn new(event_loop: &EventLoop) -> Self {
+        App {
+            window: None,
+            tray_manager: Manager::new(event_loop),
+            tray: None,
+            renderer: None,
+        }
+    }
+
+    fn show_window(&self) {
+        if let Some(window) = &self.window {
+            restore_from_tray(window.as_ref().as_ref());
+        }
+    }
+}
+
+impl ApplicationHandler for App {
+    fn can_create_surfaces(&mut self, event_loop: &dyn ActiveEventLoop) {
+        let icon = match load_icon(Path::new("assets/ferris.png")) {
+            Ok(icon) => Some(icon),
+            Err(err) => {
+                warn!(%err, "failed to load icon");
+                None
+            }
+        };
+
+        let window_attributes = WindowAttributes::default()
+            .with_window_icon(icon)
+            .with_title("Minimize to Tray Example - close me!");
+
+        let tray_attributes = winit_extras::TrayIconAttributes::default()
+            .with_tooltip("Minimize to Tray Example")
+            .with_window_icon(&window_attributes)
+            .with_context_menu(vec![
+                MenuEntry::Item(MenuItem::new(Action::Show, "Show Window")),
+                MenuEntry::Separator,
+                MenuEntry::Item(MenuItem::new(Action::Quit, "Quit")),
+            ]);
+
+        // The tray is created up front and lives as long as the app, so the
+        // hidden window can always be brought back.
+        self.tray = match self.tray_manager.create_tray(tray_attributes) {
+            Ok(tray) => Some(tray),
+            Err(err) => {
+                error!(%err, "failed to create tray");
+                event_loop.exit();
+                return;
+            }
+        };
+
+        let window = match event_loop.create_window(window_attributes) {
+            Ok(window) => Rc::new(window),
+            Err(err) => {
+                error!(%err, "failed to create window");
+                event_loop.exit();
+                return;
+            }
+        };
+
+        self.renderer = GradientRenderer::new(window.clone())
+            .inspect_err(|err| error!(%err, "failed to create renderer, window will stay blank"))
+            .ok();
+        window.request_redraw();
+        self.window = Some(window);
+    }
+
+    fn proxy_wake_up(&mut self, event_loop: &dyn ActiveEventLoop) {
+        while let Ok(event) = self.tray_manager.try_recv() {
+            match event {
+                Event::PointerButton {
+                    state: ElementState::Released,
+                    button: ButtonSource::Mouse(MouseButton::Left),
+                    ..
+                } => {
+                    info!("restoring window from tray");
+                    self.show_window();
+                }
+                Event::MenuItemClicked { id, .. } => match id {
+                    Action::Show => self.show_window(),
+                    Action::Quit => {
+                        info!("quit selected, stopping");
+                        event_loop.exit();
+                    }
+                },
+                _ => {}
+            }
+        }
+    }
+
+    fn window_event(&mut self, _: &dyn ActiveEventLoop, _: WindowId, event: WindowEvent) {
+        match event {
+            WindowEvent::CloseRequested => {
+                info!("close requested, minimizing to tray");
+                if let Some(window) = &self.window {
+                    minimize_to_tray(window.as_ref().as_ref());
+                }
+            }
+            WindowEvent::SurfaceResized(size) => {
+                if let Some(renderer) = &mut self.renderer
+                    && let Err(err) = renderer.resize(size.width, size.height)
+                {
+                    warn!(%err, "failed to resize surface");
+                }
+                if let Some(window) = &self.window {
+                    window.request_redraw();
+                }
+            }
+            WindowEvent::RedrawRequested => {
+                if let (Some(renderer), Some(window)) = (&mut self.renderer, &self.window) {
+                    let size = window.surface_size();
+                    if let Err(err) = renderer.render(size.width, size.height) {
+                        warn!(%err, "failed to render frame, skipping it");
+                    }
+                    window.pre_present_notify();
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    tracing_subscriber::fmt::init();
+
+    let event_loop = EventLoop::new()?;
+    let app = App::new(&event_loop);
+    event_loop.run_app(app)?;
+
+    Ok(())
+}
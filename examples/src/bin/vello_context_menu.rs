@@ -40,7 +40,7 @@ enum Action {
 struct App {
     window: Option<WindowHandle>,
     tray: Manager<Action>,
-    tray_icon: Option<Box<dyn winit_extras::TrayIcon>>,
+    tray_icon: Option<Box<dyn winit_extras::TrayIcon<Action>>>,
     window_menu: Option<Rc<dyn ContextMenu>>,
     tray_menu: Option<Rc<dyn ContextMenu>>,
     surface: Option<SoftbufferSurface>,
@@ -192,7 +192,7 @@ impl ApplicationHandler for App {
                         menu.show_at_screen_pos(pos);
                     }
                 }
-                Event::MenuItemClicked { id } => match id {
+                Event::MenuItemClicked { id, .. } => match id {
                     Action::ShowWindow => {
                         if let Some(window) = &self.window {
                             window.focus_window();
@@ -3,6 +3,7 @@
 use std::num::NonZeroU32;
 use std::rc::Rc;
 
+use softbuffer::SoftBufferError;
 use winit::window::Window;
 
 type WindowHandle = Rc<Box<dyn Window>>;
@@ -17,40 +18,38 @@ pub struct GradientRenderer {
 
 impl GradientRenderer {
     /// Create a new gradient renderer for the given window.
-    pub fn new(window: Rc<Box<dyn Window>>) -> Self {
+    pub fn new(window: Rc<Box<dyn Window>>) -> Result<Self, SoftBufferError> {
         let size = window.surface_size();
-        let context = softbuffer::Context::new(window.clone()).unwrap();
-        let mut surface = softbuffer::Surface::new(&context, window).unwrap();
-        surface
-            .resize(
-                NonZeroU32::new(size.width).unwrap_or(NonZeroU32::MIN),
-                NonZeroU32::new(size.height).unwrap_or(NonZeroU32::MIN),
-            )
-            .unwrap();
+        let context = softbuffer::Context::new(window.clone())?;
+        let mut surface = softbuffer::Surface::new(&context, window)?;
+        surface.resize(non_zero(size.width), non_zero(size.height))?;
 
-        Self { context, surface }
+        Ok(Self { context, surface })
     }
 
     /// Resize the rendering surface.
-    pub fn resize(&mut self, width: u32, height: u32) {
+    ///
+    /// Zero sizes (e.g. a minimized window) are ignored.
+    pub fn resize(&mut self, width: u32, height: u32) -> Result<(), SoftBufferError> {
         if width > 0 && height > 0 {
-            let _ = self.surface.resize(
-                NonZeroU32::new(width).unwrap(),
-                NonZeroU32::new(height).unwrap(),
-            );
+            self.surface.resize(non_zero(width), non_zero(height))?;
         }
+        Ok(())
     }
 
     /// Render a gradient pattern to the window.
-    pub fn render(&mut self, width: u32, height: u32) {
-        let width = width as usize;
-        let height = height as usize;
-
+    ///
+    /// The surface is resized to `width` x `height` first, so a frame drawn
+    /// before the matching resize event arrives still fills the buffer.
+    pub fn render(&mut self, width: u32, height: u32) -> Result<(), SoftBufferError> {
         if width == 0 || height == 0 {
-            return;
+            return Ok(());
         }
+        self.resize(width, height)?;
 
-        let mut buffer = self.surface.buffer_mut().unwrap();
+        let width = width as usize;
+        let height = height as usize;
+        let mut buffer = self.surface.buffer_mut()?;
 
         for y in 0..height {
             for x in 0..width {
@@ -64,6 +63,10 @@ impl GradientRenderer {
             }
         }
 
-        buffer.present().unwrap();
+        buffer.present()
     }
 }
+
+fn non_zero(value: u32) -> NonZeroU32 {
+    NonZeroU32::new(value).unwrap_or(NonZeroU32::MIN)
+}
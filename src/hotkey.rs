@@ -0,0 +1,72 @@
+//! System-wide keyboard shortcuts.
+//!
+//! A hotkey fires even while none of the application's windows has focus,
+//! which makes it a natural companion to a tray icon: register one to show a
+//! hidden window or run a tray action from anywhere.
+//!
+//! ```ignore
+//! use winit_extras::{Accelerator, KeyCode, ModifiersState};
+//!
+//! let accelerator = Accelerator::new(ModifiersState::CONTROL | ModifiersState::SHIFT, KeyCode::KeyT);
+//! let _hotkey = winit_extras::hotkey::register(accelerator, || println!("Pressed!"))?;
+//! // The shortcut stays registered until `_hotkey` is dropped.
+//! ```
+
+use winit_extras_core::{Accelerator, TrayError};
+
+#[cfg(target_os = "windows")]
+use winit_extras_windows::hotkey as platform_hotkey;
+
+#[cfg(target_os = "macos")]
+use winit_extras_macos::hotkey as platform_hotkey;
+
+/// A registered system-wide shortcut. Dropping it unregisters the shortcut.
+#[derive(Debug)]
+pub struct HotKey {
+    #[cfg(any(target_os = "windows", target_os = "macos"))]
+    inner: platform_hotkey::HotKey,
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    accelerator: Accelerator,
+}
+
+impl HotKey {
+    /// The registered shortcut.
+    pub fn accelerator(&self) -> Accelerator {
+        #[cfg(any(target_os = "windows", target_os = "macos"))]
+        {
+            self.inner.accelerator()
+        }
+        #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+        {
+            self.accelerator
+        }
+    }
+}
+
+/// Register `accelerator` system-wide and call `callback` whenever it is
+/// pressed.
+///
+/// Register from the event loop thread; the callback runs there too.
+///
+/// # Platform-specific
+///
+/// - **Windows:** uses `RegisterHotKey`. Fails if another application
+///   already owns the shortcut.
+/// - **macOS:** uses Carbon's `RegisterEventHotKey`. Must be called on the
+///   main thread.
+/// - **Linux:** not supported yet; always returns an error.
+pub fn register(
+    accelerator: Accelerator,
+    callback: impl Fn() + 'static,
+) -> Result<HotKey, TrayError> {
+    #[cfg(any(target_os = "windows", target_os = "macos"))]
+    {
+        let inner = platform_hotkey::HotKey::register(accelerator, callback)?;
+        Ok(HotKey { inner })
+    }
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    {
+        let _ = (accelerator, callback);
+        Err(TrayError::Unsupported("global hotkeys"))
+    }
+}
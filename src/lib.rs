@@ -1,12 +1,58 @@
 pub use winit_extras_core::*;
 
 mod manager;
-pub use manager::{Manager, ManagerBuilder};
+pub use manager::{Manager, ManagerBuilder, PausedEvents};
+
+pub mod window;
+
+#[cfg(feature = "hotkey")]
+pub mod hotkey;
 
 #[cfg(all(feature = "menu_bar", any(target_os = "windows", target_os = "macos")))]
 pub mod menu_bar;
 #[cfg(all(feature = "menu_bar", any(target_os = "windows", target_os = "macos")))]
-pub use menu_bar::MenuBarManager;
+pub use menu_bar::{MenuBarBuilder, MenuBarManager};
+
+#[cfg(all(feature = "menu_bar", any(target_os = "windows", target_os = "macos")))]
+pub mod unified;
+#[cfg(all(feature = "menu_bar", any(target_os = "windows", target_os = "macos")))]
+pub use unified::{UnifiedEvent, WinitTray};
 
 #[cfg(feature = "vello_renderer")]
 pub use winit_extras_vello;
+
+/// The types most applications need, for a single glob import.
+///
+/// ```ignore
+/// use winit_extras::prelude::*;
+/// ```
+///
+/// Menu types need the `menu` feature (on by default), the menu bar manager
+/// the `menu_bar` feature, and hotkeys the `hotkey` feature. Accelerators
+/// come with either `menu` or `hotkey`.
+pub mod prelude {
+    pub use crate::{
+        ButtonMask, Event, Manager, ManagerBuilder, NotificationIcon, ScrollOrientation, TrayIcon,
+        TrayIconAttributes, icon::StatusColor, tray_icon_id::TrayIconId,
+    };
+
+    #[cfg(feature = "menu")]
+    pub use crate::{
+        ItemAction, MenuActivation, MenuEntries, MenuEntry, MenuItem, MenuRole, PrimaryAction,
+        SeparatorStyle, Submenu,
+    };
+
+    #[cfg(feature = "context_menu")]
+    pub use crate::context_menu::ContextMenu;
+
+    #[cfg(all(feature = "menu_bar", any(target_os = "windows", target_os = "macos")))]
+    pub use crate::menu_bar::{
+        MenuBar, MenuBarAttributes, MenuBarEvent, MenuBarManager, TopLevelMenu, TopLevelMenuId,
+    };
+
+    #[cfg(all(feature = "menu_bar", any(target_os = "windows", target_os = "macos")))]
+    pub use crate::unified::{UnifiedEvent, WinitTray};
+
+    #[cfg(any(feature = "menu", feature = "hotkey"))]
+    pub use crate::{Accelerator, KeyCode, ModifiersState};
+}
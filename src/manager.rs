@@ -1,13 +1,29 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use winit::event_loop::{EventLoop, EventLoopProxy};
+use winit_extras_core::tray_icon_id::TrayIconId;
+use winit_extras_core::{
+    Event, EventCallback, TrayError, TrayIcon, TrayIconAttributes, TrayIconRenderer,
+};
+#[cfg(feature = "menu")]
+use winit_extras_core::{MenuEntries, MenuEntry};
+
+#[cfg(feature = "context_menu")]
 use std::cell::RefCell;
+#[cfg(feature = "context_menu")]
 use std::rc::{Rc, Weak};
-use std::sync::Arc;
-
+#[cfg(feature = "context_menu")]
 use winit::event::WindowEvent;
-use winit::event_loop::{ActiveEventLoop, EventLoop, EventLoopProxy};
+#[cfg(feature = "context_menu")]
+use winit::event_loop::ActiveEventLoop;
+#[cfg(feature = "context_menu")]
 use winit::raw_window_handle::HasWindowHandle;
+#[cfg(feature = "context_menu")]
 use winit::window::WindowId;
+#[cfg(feature = "context_menu")]
 use winit_extras_core::context_menu::{ContextMenu, MenuRenderer};
-use winit_extras_core::{Event, EventCallback, TrayIcon, TrayIconAttributes, TrayIconRenderer};
 
 #[cfg(target_os = "windows")]
 use winit_extras_windows::NativeTrayIconRenderer;
@@ -18,10 +34,10 @@ use winit_extras_macos::NativeTrayIconRenderer;
 #[cfg(target_os = "linux")]
 use winit_extras_linux::NativeTrayIconRenderer;
 
-#[cfg(target_os = "windows")]
+#[cfg(all(target_os = "windows", feature = "context_menu"))]
 use winit_extras_windows::context_menu::NativeMenuRenderer as DefaultMenuRenderer;
 
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", feature = "context_menu"))]
 use winit_extras_macos::context_menu::NativeMenuRenderer as DefaultMenuRenderer;
 
 /// Entry point for tray icons and context menus.
@@ -42,17 +58,29 @@ use winit_extras_macos::context_menu::NativeMenuRenderer as DefaultMenuRenderer;
 /// while let Ok(event) = manager.try_recv() {
 ///     match event {
 ///         Event::PointerButton { .. } => { /* handle click */ }
-///         Event::MenuItemClicked { id } => { /* handle menu */ }
+///         Event::MenuItemClicked { id, .. } => { /* handle menu */ }
 ///     }
 /// }
 /// ```
+///
+/// Events only reach the application when it drains the channel, so call
+/// [`try_recv`](Self::try_recv) until it is empty on every wake-up. A tray
+/// whose events are never received looks like it does nothing when clicked;
+/// debug builds log a warning the first time that seems to be happening.
 pub struct Manager<T: Clone + Send + Sync + 'static = ()> {
     // The EventLoopProxy is cloned into the callback, which handles all wake-ups.
     // We keep this field so the proxy lives at least as long as the Manager, in
     // case we ever need to trigger a wake from a manager method directly.
-    _proxy: EventLoopProxy,
+    // `None` for standalone managers, which have no event loop to wake.
+    _proxy: Option<EventLoopProxy>,
     receiver: std::sync::mpsc::Receiver<Event<T>>,
     callback: EventCallback<T>,
+    /// Sends events on past the filter and pause, for replaying input that
+    /// already went through them.
+    deliver: EventCallback<T>,
+    backlog: Arc<Backlog>,
+    coalesce_wake_ups: bool,
+    pause: Arc<Pause<T>>,
     tray_renderer: Box<dyn TrayIconRenderer<T>>,
     #[cfg(feature = "context_menu")]
     menu_renderer: Box<dyn MenuRenderer<T>>,
@@ -60,6 +88,13 @@ pub struct Manager<T: Clone + Send + Sync + 'static = ()> {
     /// events via `handle_window_event`. Dead entries are swept on each call.
     #[cfg(feature = "context_menu")]
     menus: RefCell<Vec<Weak<dyn ContextMenu>>>,
+    /// Trays created by `create_tray_persistent`, kept until removed or
+    /// the manager is dropped.
+    persistent_trays: std::cell::RefCell<Vec<Box<dyn TrayIcon<T>>>>,
+    /// Trays created by `create_tray` that are still alive.
+    live_trays: Arc<LiveTrays<T>>,
+    #[cfg(feature = "menu")]
+    menu_id_check: Option<MenuIdCheck<T>>,
 }
 
 impl<T: Clone + Send + Sync + 'static> std::fmt::Debug for Manager<T> {
@@ -68,15 +103,287 @@ impl<T: Clone + Send + Sync + 'static> std::fmt::Debug for Manager<T> {
     }
 }
 
+impl<T: Clone + Send + Sync + 'static> Drop for Manager<T> {
+    fn drop(&mut self) {
+        // Fields drop in declaration order, which would close the channel
+        // before the last persistent tray sends `AllTraysRemoved` into it.
+        self.persistent_trays.get_mut().clear();
+    }
+}
+
+/// How long events may sit in the channel before a click is reported as
+/// going unhandled.
+const UNDRAINED_WARNING_DELAY: Duration = Duration::from_secs(5);
+
+/// Tracks events sent to the application that it hasn't received yet.
+#[derive(Debug)]
+struct Backlog {
+    pending: AtomicUsize,
+    last_drain: Mutex<Instant>,
+    warned: AtomicBool,
+    /// Set once the event loop has been woken, until the app finds the
+    /// channel empty. Only used when coalescing wake-ups.
+    wake_scheduled: AtomicBool,
+}
+
+impl Backlog {
+    fn new() -> Self {
+        Backlog {
+            pending: AtomicUsize::new(0),
+            last_drain: Mutex::new(Instant::now()),
+            warned: AtomicBool::new(false),
+            wake_scheduled: AtomicBool::new(false),
+        }
+    }
+
+    /// Record an attempt to receive, `received` being whether it got an event.
+    fn drained(&self, received: bool) {
+        if received {
+            self.pending.fetch_sub(1, Ordering::Relaxed);
+        }
+        *self.last_drain.lock().unwrap() = Instant::now();
+    }
+
+    /// Warn once if a click arrives while earlier events have been waiting
+    /// for longer than [`UNDRAINED_WARNING_DELAY`].
+    fn check_click(&self) {
+        if self.pending.load(Ordering::Relaxed) == 0 {
+            return;
+        }
+        let undrained_for = self.last_drain.lock().unwrap().elapsed();
+        if undrained_for >= UNDRAINED_WARNING_DELAY && !self.warned.swap(true, Ordering::Relaxed) {
+            tracing::warn!(
+                pending = self.pending.load(Ordering::Relaxed),
+                "A tray icon was clicked, but no events have been received for {:?}. \
+                 Call `Manager::try_recv` until it is empty on every wake-up.",
+                undrained_for
+            );
+        }
+    }
+}
+
+/// What happens to tray input while a [`Manager`] is
+/// [paused](Manager::set_paused).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum PausedEvents {
+    /// Discard input that arrives while paused.
+    #[default]
+    Drop,
+    /// Hold input back and deliver it, in order, once unpaused.
+    Buffer,
+}
+
+/// Holds back tray input while the manager is paused.
+struct Pause<T> {
+    paused: AtomicBool,
+    mode: PausedEvents,
+    // Also guards `paused` changes, so nothing is buffered after a flush.
+    buffered: Mutex<Vec<Event<T>>>,
+}
+
+impl<T> Pause<T> {
+    fn new(mode: PausedEvents) -> Self {
+        Pause {
+            paused: AtomicBool::new(false),
+            mode,
+            buffered: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Returns the event if it should be delivered now.
+    fn filter(&self, event: Event<T>) -> Option<Event<T>> {
+        // Only input is held back; state changes such as a new icon size
+        // still matter after a modal operation.
+        if !matches!(
+            event,
+            Event::PointerButton { .. }
+                | Event::MenuItemClicked { .. }
+                | Event::NotificationClicked { .. }
+        ) || !self.paused.load(Ordering::Acquire)
+        {
+            return Some(event);
+        }
+        let mut buffered = self.buffered.lock().unwrap();
+        if !self.paused.load(Ordering::Acquire) {
+            return Some(event);
+        }
+        if self.mode == PausedEvents::Buffer {
+            buffered.push(event);
+        }
+        None
+    }
+
+    /// Pauses or resumes, returning the events buffered while paused.
+    fn set_paused(&self, paused: bool) -> Vec<Event<T>> {
+        let mut buffered = self.buffered.lock().unwrap();
+        self.paused.store(paused, Ordering::Release);
+        if paused {
+            Vec::new()
+        } else {
+            std::mem::take(&mut *buffered)
+        }
+    }
+}
+
+/// Counts live trays so the last one to go can send
+/// [`Event::AllTraysRemoved`].
+struct LiveTrays<T> {
+    count: AtomicUsize,
+    callback: EventCallback<T>,
+}
+
+/// A tray returned by [`Manager::create_tray`], counted while it lives.
+struct ManagedTray<T: 'static> {
+    // Declared before `_guard` so the icon is gone by the time the event is
+    // sent.
+    inner: Box<dyn TrayIcon<T>>,
+    _guard: TrayGuard<T>,
+}
+
+/// Decrements the live tray count when dropped.
+///
+/// Holds the count weakly, so trays that outlive their manager don't keep
+/// it, or its event channel, alive.
+struct TrayGuard<T>(std::sync::Weak<LiveTrays<T>>);
+
+impl<T> Drop for TrayGuard<T> {
+    fn drop(&mut self) {
+        let Some(live_trays) = self.0.upgrade() else {
+            return;
+        };
+        if live_trays.count.fetch_sub(1, Ordering::AcqRel) == 1 {
+            (live_trays.callback)(Event::AllTraysRemoved);
+        }
+    }
+}
+
+impl<T> std::fmt::Debug for ManagedTray<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+impl<T> TrayIcon<T> for ManagedTray<T> {
+    fn id(&self) -> winit_extras_core::tray_icon_id::TrayIconId {
+        self.inner.id()
+    }
+
+    fn is_in_overflow(&self) -> Option<bool> {
+        self.inner.is_in_overflow()
+    }
+
+    fn last_pointer_position(&self) -> Option<winit::dpi::PhysicalPosition<f64>> {
+        self.inner.last_pointer_position()
+    }
+
+    fn set_tooltip(&self, tooltip: Option<&str>) -> Result<(), TrayError> {
+        self.inner.set_tooltip(tooltip)
+    }
+
+    fn flash_tooltip(&self, tooltip: &str, duration: Duration) -> Result<(), TrayError> {
+        self.inner.flash_tooltip(tooltip, duration)
+    }
+
+    fn show_notification(
+        &self,
+        title: &str,
+        body: &str,
+        icon: winit_extras_core::NotificationIcon,
+    ) -> Result<(), TrayError> {
+        self.inner.show_notification(title, body, icon)
+    }
+
+    fn set_icon(&self, icon: Option<&winit::icon::Icon>) -> Result<(), TrayError> {
+        self.inner.set_icon(icon)
+    }
+
+    fn set_status_dot(
+        &self,
+        color: Option<winit_extras_core::icon::StatusColor>,
+    ) -> Result<(), TrayError> {
+        self.inner.set_status_dot(color)
+    }
+
+    fn set_interactive(&self, interactive: bool) -> Result<(), TrayError> {
+        self.inner.set_interactive(interactive)
+    }
+
+    #[cfg(feature = "menu")]
+    fn set_label(&self, id: &T, label: &str) -> Result<(), TrayError>
+    where
+        T: PartialEq,
+    {
+        self.inner.set_label(id, label)
+    }
+
+    #[cfg(feature = "menu")]
+    fn set_menu(&self, menu: Vec<MenuEntry<T>>) -> Result<(), TrayError> {
+        self.inner.set_menu(menu)
+    }
+
+    #[cfg(feature = "menu")]
+    fn set_item_icon(&self, id: &T, icon: Option<winit::icon::Icon>) -> Result<(), TrayError>
+    where
+        T: PartialEq,
+    {
+        self.inner.set_item_icon(id, icon)
+    }
+
+    #[cfg(feature = "menu")]
+    fn open_menu(&self) -> Result<(), TrayError> {
+        self.inner.open_menu()
+    }
+}
+
+/// Keeps a [`TrayError`] from a renderer as it is and wraps any other error.
+fn into_tray_error(e: Box<dyn std::error::Error + Send + Sync>) -> TrayError {
+    match e.downcast::<TrayError>() {
+        Ok(e) => *e,
+        Err(e) => TrayError::Platform(e.to_string()),
+    }
+}
+
+/// The callback trays send their events to: runs the event filter, holds
+/// input back while paused, then passes the event to `deliver`.
 fn make_callback<T: Clone + Send + Sync + 'static>(
+    deliver: EventCallback<T>,
+    pause: Arc<Pause<T>>,
+    filter: Option<EventFilter<T>>,
+) -> EventCallback<T> {
+    Arc::new(move |event| {
+        if let Some(filter) = &filter
+            && !filter(&event)
+        {
+            return;
+        }
+        if let Some(event) = pause.filter(event) {
+            deliver(event);
+        }
+    })
+}
+
+/// Sends an event to the application's channel and wakes the event loop.
+fn make_delivery<T: Clone + Send + Sync + 'static>(
     sender: std::sync::mpsc::Sender<Event<T>>,
-    proxy: EventLoopProxy,
+    proxy: Option<EventLoopProxy>,
+    backlog: Arc<Backlog>,
+    coalesce_wake_ups: bool,
 ) -> EventCallback<T> {
     Arc::new(move |event| {
+        if cfg!(debug_assertions) && matches!(event, Event::PointerButton { .. }) {
+            backlog.check_click();
+        }
+        // Counted before sending so a receive can never see it go negative.
+        backlog.pending.fetch_add(1, Ordering::Relaxed);
         if let Err(e) = sender.send(event) {
+            backlog.pending.fetch_sub(1, Ordering::Relaxed);
             tracing::error!("Failed to send tray event: {e}");
         }
-        proxy.wake_up();
+        if let Some(proxy) = &proxy
+            && !(coalesce_wake_ups && backlog.wake_scheduled.swap(true, Ordering::SeqCst))
+        {
+            proxy.wake_up();
+        }
     })
 }
 
@@ -88,6 +395,29 @@ impl<T: Clone + Send + Sync + 'static> Manager<T> {
     }
 }
 
+#[cfg(any(
+    target_os = "windows",
+    all(target_os = "linux", feature = "vello_renderer")
+))]
+impl<T: Clone + Send + Sync + 'static> Manager<T> {
+    /// Create a tray manager for applications without a winit event loop,
+    /// such as daemons.
+    ///
+    /// Trays deliver their events from background threads: on Windows each
+    /// one runs its own message loop (see
+    /// `winit_extras_windows::ThreadedTray`), and on Linux the D-Bus thread
+    /// already does. Nothing wakes the caller, so block on
+    /// [`recv`](Self::recv) to wait for events.
+    pub fn new_standalone() -> Self {
+        let builder = ManagerBuilder::new(None);
+        #[cfg(target_os = "windows")]
+        let builder = builder.tray_renderer(winit_extras_windows::ThreadedTrayIconRenderer);
+        #[cfg(all(target_os = "linux", feature = "context_menu"))]
+        let builder = builder.menu_renderer(winit_extras_vello::VelloMenuRenderer::new());
+        builder.build()
+    }
+}
+
 // Linux has no native context-menu renderer, so the default here uses
 // `VelloMenuRenderer` from `winit_extras_vello`. Only available when the
 // `vello_renderer` feature is enabled.
@@ -103,15 +433,67 @@ impl<T: Clone + Send + Sync + 'static> Manager<T> {
 
 /// Builder for configuring a `Manager` with custom renderers.
 pub struct ManagerBuilder<T: Clone + Send + Sync + 'static> {
-    event_loop_proxy: EventLoopProxy,
+    event_loop_proxy: Option<EventLoopProxy>,
     sender: std::sync::mpsc::Sender<Event<T>>,
     receiver: std::sync::mpsc::Receiver<Event<T>>,
+    coalesce_wake_ups: bool,
+    paused_events: PausedEvents,
     tray_renderer: Option<Box<dyn TrayIconRenderer<T>>>,
     #[cfg(feature = "context_menu")]
     menu_renderer: Option<Box<dyn MenuRenderer<T>>>,
+    #[cfg(feature = "menu")]
+    menu_id_check: Option<MenuIdCheck<T>>,
+    event_filter: Option<EventFilter<T>>,
+}
+
+/// Decides whether an event is delivered, set by
+/// [`ManagerBuilder::event_filter`].
+type EventFilter<T> = Box<dyn Fn(&Event<T>) -> bool + Send + Sync>;
+
+/// How menus are checked for items sharing an ID, set by
+/// [`ManagerBuilder::check_menu_ids`].
+#[cfg(feature = "menu")]
+struct MenuIdCheck<T> {
+    /// Describes each group of items sharing an ID. A function pointer, as
+    /// it needs bounds on `T` the manager doesn't have.
+    duplicates: fn(&[MenuEntry<T>]) -> Vec<String>,
+    strict: bool,
+}
+
+#[cfg(feature = "menu")]
+fn describe_duplicate_ids<T: Eq + std::hash::Hash + std::fmt::Debug>(
+    entries: &[MenuEntry<T>],
+) -> Vec<String> {
+    entries
+        .duplicate_ids()
+        .iter()
+        .map(|group| {
+            let labels: Vec<&str> = group.iter().map(|item| item.label.as_str()).collect();
+            format!("{:?} ({})", group[0].id, labels.join(", "))
+        })
+        .collect()
 }
 
 impl<T: Clone + Send + Sync + 'static> ManagerBuilder<T> {
+    /// A builder with default settings, waking the event loop through
+    /// `event_loop_proxy` if there is one.
+    fn new(event_loop_proxy: Option<EventLoopProxy>) -> Self {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        ManagerBuilder {
+            event_loop_proxy,
+            sender,
+            receiver,
+            coalesce_wake_ups: false,
+            paused_events: PausedEvents::default(),
+            tray_renderer: None,
+            #[cfg(feature = "context_menu")]
+            menu_renderer: None,
+            #[cfg(feature = "menu")]
+            menu_id_check: None,
+            event_filter: None,
+        }
+    }
+
     pub fn tray_renderer(mut self, renderer: impl TrayIconRenderer<T> + 'static) -> Self {
         self.tray_renderer = Some(Box::new(renderer));
         self
@@ -123,14 +505,88 @@ impl<T: Clone + Send + Sync + 'static> ManagerBuilder<T> {
         self
     }
 
+    /// Wake the event loop only for the first of several undrained events.
+    ///
+    /// By default every tray event wakes the event loop. With coalescing, a
+    /// burst of events such as scrolls wakes it once, and it is woken again
+    /// only after [`Manager::try_recv`] has found the channel empty. Apps
+    /// must then drain the channel on every wake-up, or later events wait
+    /// until something else wakes the loop. Defaults to `false`.
+    pub fn coalesce_wake_ups(mut self, coalesce: bool) -> Self {
+        self.coalesce_wake_ups = coalesce;
+        self
+    }
+
+    /// Set what happens to tray input while the manager is
+    /// [paused](Manager::set_paused). Defaults to [`PausedEvents::Drop`].
+    pub fn paused_events(mut self, paused_events: PausedEvents) -> Self {
+        self.paused_events = paused_events;
+        self
+    }
+
+    /// Decide which events reach the channel.
+    ///
+    /// The filter sees every event before it is sent and drops those it
+    /// returns `false` for, before they are counted, buffered while
+    /// [paused](Manager::set_paused) or wake the event loop. Use it to
+    /// ignore buttons, drop repeats or route events elsewhere;
+    /// [`Event::tray_icon_id`] tells which tray an event came from.
+    ///
+    /// The filter runs on the thread that produced the event, such as the
+    /// Win32 window procedure or the D-Bus thread on Linux. It must be
+    /// cheap and must not block, or the tray stops responding.
+    pub fn event_filter(
+        mut self,
+        filter: impl Fn(&Event<T>) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.event_filter = Some(Box::new(filter));
+        self
+    }
+
+    /// Look for menu items that share an ID when trays and context menus
+    /// are created.
+    ///
+    /// Clicks on such items can't be told apart. Debug builds log a warning
+    /// naming the ID and the items' labels. With `strict`, creation also
+    /// fails with [`TrayError::DuplicateMenuId`] in every build. Off by
+    /// default, as it needs `T` to be hashable.
+    #[cfg(feature = "menu")]
+    pub fn check_menu_ids(mut self, strict: bool) -> Self
+    where
+        T: Eq + std::hash::Hash + std::fmt::Debug,
+    {
+        self.menu_id_check = Some(MenuIdCheck {
+            duplicates: describe_duplicate_ids::<T>,
+            strict,
+        });
+        self
+    }
+
     #[cfg(any(target_os = "windows", target_os = "macos"))]
     pub fn build(self) -> Manager<T> {
         let proxy = self.event_loop_proxy;
-        let callback = make_callback(self.sender, proxy.clone());
+        let backlog = Arc::new(Backlog::new());
+        let pause = Arc::new(Pause::new(self.paused_events));
+        let deliver = make_delivery(
+            self.sender,
+            proxy.clone(),
+            backlog.clone(),
+            self.coalesce_wake_ups,
+        );
+        let callback = make_callback(deliver.clone(), pause.clone(), self.event_filter);
         Manager {
             _proxy: proxy,
             receiver: self.receiver,
+            coalesce_wake_ups: self.coalesce_wake_ups,
+            pause,
+            persistent_trays: std::cell::RefCell::new(Vec::new()),
+            live_trays: Arc::new(LiveTrays {
+                count: AtomicUsize::new(0),
+                callback: callback.clone(),
+            }),
             callback,
+            deliver,
+            backlog,
             tray_renderer: self
                 .tray_renderer
                 .unwrap_or_else(|| Box::new(NativeTrayIconRenderer)),
@@ -140,6 +596,8 @@ impl<T: Clone + Send + Sync + 'static> ManagerBuilder<T> {
                 .unwrap_or_else(|| Box::new(DefaultMenuRenderer)),
             #[cfg(feature = "context_menu")]
             menus: RefCell::new(Vec::new()),
+            #[cfg(feature = "menu")]
+            menu_id_check: self.menu_id_check,
         }
     }
 
@@ -147,11 +605,28 @@ impl<T: Clone + Send + Sync + 'static> ManagerBuilder<T> {
     #[cfg(target_os = "linux")]
     pub fn build(self) -> Manager<T> {
         let proxy = self.event_loop_proxy;
-        let callback = make_callback(self.sender, proxy.clone());
+        let backlog = Arc::new(Backlog::new());
+        let pause = Arc::new(Pause::new(self.paused_events));
+        let deliver = make_delivery(
+            self.sender,
+            proxy.clone(),
+            backlog.clone(),
+            self.coalesce_wake_ups,
+        );
+        let callback = make_callback(deliver.clone(), pause.clone(), self.event_filter);
         Manager {
             _proxy: proxy,
             receiver: self.receiver,
+            coalesce_wake_ups: self.coalesce_wake_ups,
+            pause,
+            persistent_trays: std::cell::RefCell::new(Vec::new()),
+            live_trays: Arc::new(LiveTrays {
+                count: AtomicUsize::new(0),
+                callback: callback.clone(),
+            }),
             callback,
+            deliver,
+            backlog,
             tray_renderer: self
                 .tray_renderer
                 .unwrap_or_else(|| Box::new(NativeTrayIconRenderer)),
@@ -161,6 +636,8 @@ impl<T: Clone + Send + Sync + 'static> ManagerBuilder<T> {
                 .expect("Linux requires a menu renderer (e.g. VelloMenuRenderer). Use .menu_renderer() on the builder."),
             #[cfg(feature = "context_menu")]
             menus: RefCell::new(Vec::new()),
+            #[cfg(feature = "menu")]
+            menu_id_check: self.menu_id_check,
         }
     }
 }
@@ -168,27 +645,134 @@ impl<T: Clone + Send + Sync + 'static> ManagerBuilder<T> {
 impl<T: Clone + Send + Sync + 'static> Manager<T> {
     /// Start building a tray manager with custom renderers.
     pub fn builder(event_loop: &EventLoop) -> ManagerBuilder<T> {
-        let (sender, receiver) = std::sync::mpsc::channel();
-        ManagerBuilder {
-            event_loop_proxy: event_loop.create_proxy(),
-            sender,
-            receiver,
-            tray_renderer: None,
-            #[cfg(feature = "context_menu")]
-            menu_renderer: None,
-        }
+        ManagerBuilder::new(Some(event_loop.create_proxy()))
     }
 
     /// Create a tray icon.
+    ///
+    /// An icon that can't be shown is first swapped for the fallback or
+    /// placeholder icon, if the attributes have one (see
+    /// [`apply_fallback_icon`](TrayIconAttributes::apply_fallback_icon)),
+    /// with a warning logged. The attributes are then
+    /// [validated](TrayIconAttributes::validate), so
+    /// configuration mistakes fail with a [`TrayError`][crate::TrayError]
+    /// before any platform resources are created.
+    ///
+    /// Once every tray created here has been dropped, the manager sends
+    /// [`Event::AllTraysRemoved`].
+    ///
+    /// Dropping the returned handle removes the icon, so store it for as
+    /// long as the icon should show. A handle dropped straight away makes
+    /// the icon flash and vanish. Use
+    /// [`create_tray_persistent`](Self::create_tray_persistent) to have the
+    /// manager keep it instead.
     pub fn create_tray(
         &self,
-        attr: TrayIconAttributes,
-    ) -> Result<Box<dyn TrayIcon>, anyhow::Error> {
+        mut attr: TrayIconAttributes<T>,
+    ) -> Result<Box<dyn TrayIcon<T>>, TrayError> {
+        for (field, err) in attr.apply_fallback_icon() {
+            tracing::warn!(field, %err, "Tray icon can't be shown");
+        }
+        attr.validate()?;
+        #[cfg(feature = "menu")]
+        if let Some(menu) = attr.menu() {
+            self.check_menu_ids(menu)?;
+        }
         let tray = self
             .tray_renderer
             .create_tray(attr, self.callback.clone())
-            .map_err(|e| anyhow::anyhow!("{e}"))?;
-        Ok(tray)
+            .map_err(into_tray_error)?;
+        self.live_trays.count.fetch_add(1, Ordering::AcqRel);
+        Ok(Box::new(ManagedTray {
+            inner: tray,
+            _guard: TrayGuard(Arc::downgrade(&self.live_trays)),
+        }))
+    }
+
+    /// Reports items in `entries` that share an ID, as configured with
+    /// [`ManagerBuilder::check_menu_ids`].
+    #[cfg(feature = "menu")]
+    fn check_menu_ids(&self, entries: &[MenuEntry<T>]) -> Result<(), TrayError> {
+        let Some(check) = &self.menu_id_check else {
+            return Ok(());
+        };
+        if !check.strict && !cfg!(debug_assertions) {
+            return Ok(());
+        }
+        let duplicates = (check.duplicates)(entries);
+        for duplicate in &duplicates {
+            tracing::warn!(
+                "Menu items share the id {duplicate}, clicks on them can't be told apart"
+            );
+        }
+        if check.strict && !duplicates.is_empty() {
+            return Err(TrayError::DuplicateMenuId);
+        }
+        Ok(())
+    }
+
+    /// Create a tray icon that lives as long as the manager.
+    ///
+    /// The manager keeps the handle, so the icon can't disappear because
+    /// the handle was dropped. Use this for trays that only deliver events
+    /// and never change; keep the handle from
+    /// [`create_tray`](Self::create_tray) to update one. Returns the tray's
+    /// ID, which its events carry and
+    /// [`remove_persistent_tray`](Self::remove_persistent_tray) takes.
+    pub fn create_tray_persistent(
+        &self,
+        attr: TrayIconAttributes<T>,
+    ) -> Result<TrayIconId, TrayError> {
+        let tray = self.create_tray(attr)?;
+        let id = tray.id();
+        self.persistent_trays.borrow_mut().push(tray);
+        Ok(id)
+    }
+
+    /// Remove a tray created with
+    /// [`create_tray_persistent`](Self::create_tray_persistent).
+    ///
+    /// Returns `false` if the manager keeps no tray with this ID.
+    pub fn remove_persistent_tray(&self, id: TrayIconId) -> bool {
+        let mut trays = self.persistent_trays.borrow_mut();
+        let Some(index) = trays.iter().position(|tray| tray.id() == id) else {
+            return false;
+        };
+        let tray = trays.remove(index);
+        // Dropping the tray can send `AllTraysRemoved` through the callback.
+        drop(trays);
+        drop(tray);
+        true
+    }
+
+    /// Add an empty status item `width` points wide, to group the
+    /// application's trays in the menu bar (macOS only).
+    ///
+    /// macOS places each new status item to the left of the existing ones,
+    /// so create the spacer between the trays it should separate. It shows
+    /// nothing, sends no events and isn't counted for
+    /// [`Event::AllTraysRemoved`]. Dropping the handle removes it. Must be
+    /// called on the main thread. Returns an error on other platforms, whose
+    /// trays have no spacing to control.
+    pub fn create_spacer(&self, width: f64) -> Result<Box<dyn TrayIcon<T>>, TrayError> {
+        #[cfg(target_os = "macos")]
+        {
+            Ok(Box::new(winit_extras_macos::Spacer::new(width)?))
+        }
+        #[cfg(not(target_os = "macos"))]
+        {
+            let _ = width;
+            Err(TrayError::Unsupported("tray spacers"))
+        }
+    }
+
+    /// The callback trays created by this manager deliver their events to.
+    ///
+    /// Pass it to platform-specific constructors, such as
+    /// `winit_extras_windows::Tray::with_native_menu`, so their events arrive
+    /// through [`try_recv`](Self::try_recv) like any other tray's.
+    pub fn event_callback(&self) -> EventCallback<T> {
+        self.callback.clone()
     }
 
     /// Create a context menu.
@@ -202,17 +786,136 @@ impl<T: Clone + Send + Sync + 'static> Manager<T> {
         event_loop: &dyn ActiveEventLoop,
         window: &impl HasWindowHandle,
         items: Vec<winit_extras_core::MenuEntry<T>>,
-    ) -> Result<Rc<dyn ContextMenu>, anyhow::Error> {
+    ) -> Result<Rc<dyn ContextMenu>, TrayError> {
+        self.check_menu_ids(&items)?;
         let menu = self
             .menu_renderer
             .create_menu(event_loop, window, items, self.callback.clone())
-            .map_err(|e| anyhow::anyhow!("{e}"))?;
+            .map_err(into_tray_error)?;
 
         let rc: Rc<dyn ContextMenu> = Rc::from(menu);
         self.menus.borrow_mut().push(Rc::downgrade(&rc));
         Ok(rc)
     }
 
+    /// Create a context menu and show it at the mouse cursor.
+    ///
+    /// Saves tracking the pointer position for the common "open a menu
+    /// where the user clicked" case. Fails if the menu renderer can't read
+    /// the global cursor position; see [`ContextMenu::show_at_cursor`].
+    #[cfg(feature = "context_menu")]
+    pub fn show_menu_at_cursor(
+        &self,
+        event_loop: &dyn ActiveEventLoop,
+        window: &impl HasWindowHandle,
+        items: Vec<winit_extras_core::MenuEntry<T>>,
+    ) -> Result<Rc<dyn ContextMenu>, TrayError> {
+        let menu = self.create_menu(event_loop, window, items)?;
+        if !menu.show_at_cursor() {
+            return Err(TrayError::Platform(
+                "the menu renderer can't show menus at the cursor".to_owned(),
+            ));
+        }
+        Ok(menu)
+    }
+
+    /// Create a context menu and show it if `event` is a right-click in
+    /// `window`.
+    ///
+    /// Call this from `window_event()` to open a menu where the user
+    /// right-clicked, on release as [`context_menu_position`] describes.
+    /// `items` is only called when a menu is shown. Returns the menu, or
+    /// `None` if the event wasn't a right-click. The selection arrives
+    /// later as [`Event::MenuItemClicked`].
+    ///
+    /// [`context_menu_position`]: winit_extras_core::context_menu::context_menu_position
+    #[cfg(feature = "context_menu")]
+    pub fn show_menu_on_right_click(
+        &self,
+        event_loop: &dyn ActiveEventLoop,
+        window: &impl HasWindowHandle,
+        event: &WindowEvent,
+        items: impl FnOnce() -> Vec<winit_extras_core::MenuEntry<T>>,
+    ) -> Result<Option<Rc<dyn ContextMenu>>, TrayError> {
+        let Some(position) = winit_extras_core::context_menu::context_menu_position(event) else {
+            return Ok(None);
+        };
+        let menu = self.create_menu(event_loop, window, items())?;
+        menu.show(position);
+        Ok(Some(menu))
+    }
+
+    /// Open the system's notification settings, so users can allow the
+    /// app's notifications after the OS suppressed them.
+    ///
+    /// Opens the Notifications page of the Settings app on Windows and of
+    /// System Settings on macOS. On Linux it opens the control center panel
+    /// on GNOME and KDE Plasma, and fails with
+    /// [`std::io::ErrorKind::Unsupported`] on other desktops.
+    pub fn open_notification_settings(&self) -> std::io::Result<()> {
+        #[cfg(target_os = "windows")]
+        {
+            winit_extras_windows::open_notification_settings()
+        }
+        #[cfg(target_os = "macos")]
+        {
+            winit_extras_macos::open_notification_settings()
+        }
+        #[cfg(target_os = "linux")]
+        {
+            winit_extras_linux::open_notification_settings()
+        }
+    }
+
+    /// Whether the OS would show a notification now, for skipping
+    /// [`TrayIcon::show_notification`] while the user doesn't want to be
+    /// disturbed.
+    ///
+    /// Best effort: on Windows it is `false` during quiet hours,
+    /// presentations and full-screen apps, but Focus Assist can't be read
+    /// on its own. On Linux it is `false` without a notification server or
+    /// while the server reports Do Not Disturb through its `Inhibited`
+    /// property, which KDE Plasma does and GNOME doesn't. Always `true` on
+    /// macOS, where Focus status needs the user's permission.
+    pub fn notifications_allowed(&self) -> bool {
+        #[cfg(target_os = "windows")]
+        {
+            winit_extras_windows::notifications_allowed()
+        }
+        #[cfg(target_os = "macos")]
+        {
+            winit_extras_macos::notifications_allowed()
+        }
+        #[cfg(target_os = "linux")]
+        {
+            winit_extras_linux::notifications_allowed()
+        }
+    }
+
+    /// The scale factor of the display at `position`, such as the position
+    /// of a [`Event::PointerButton`].
+    ///
+    /// Divide tray positions by it to get logical coordinates. Uses
+    /// `GetDpiForMonitor` on Windows and the screen's `backingScaleFactor`
+    /// on macOS, where it must be called on the main thread. Returns `None`
+    /// on Linux, where tray hosts don't say which display was clicked, and
+    /// when no display contains the position.
+    pub fn scale_factor_at(&self, position: winit::dpi::PhysicalPosition<f64>) -> Option<f64> {
+        #[cfg(target_os = "windows")]
+        {
+            winit_extras_windows::scale_factor_at(position)
+        }
+        #[cfg(target_os = "macos")]
+        {
+            winit_extras_macos::scale_factor_at(position)
+        }
+        #[cfg(target_os = "linux")]
+        {
+            let _ = position;
+            None
+        }
+    }
+
     /// Forward a window event to all live context menus.
     ///
     /// Call this from `window_event()`. Returns `true` if any menu consumed the event.
@@ -234,11 +937,353 @@ impl<T: Clone + Send + Sync + 'static> Manager<T> {
 
     /// Receive an event, blocking until one is available.
     pub fn recv(&self) -> Result<Event<T>, std::sync::mpsc::RecvError> {
-        self.receiver.recv()
+        // Finding the channel empty ends a coalesced wake-up, as in
+        // `try_recv`, so the next event wakes the loop again.
+        match self.try_recv() {
+            Ok(event) => return Ok(event),
+            Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                return Err(std::sync::mpsc::RecvError);
+            }
+            Err(std::sync::mpsc::TryRecvError::Empty) => {}
+        }
+        let event = self.receiver.recv();
+        self.backlog.drained(event.is_ok());
+        event
     }
 
     /// Try to receive an event without blocking.
     pub fn try_recv(&self) -> Result<Event<T>, std::sync::mpsc::TryRecvError> {
-        self.receiver.try_recv()
+        let mut event = self.receiver.try_recv();
+        if self.coalesce_wake_ups && event.is_err() {
+            self.backlog.wake_scheduled.store(false, Ordering::SeqCst);
+            // An event sent just before the reset saw a wake-up still
+            // scheduled and didn't wake the loop, so pick it up now.
+            event = self.receiver.try_recv();
+        }
+        self.backlog.drained(event.is_ok());
+        event
+    }
+
+    /// Stop delivering tray input, or resume.
+    ///
+    /// For ignoring clicks during a modal operation without recreating the
+    /// tray. While paused, pointer events and menu selections are dropped or
+    /// held back as set by [`ManagerBuilder::paused_events`]; other events
+    /// are still delivered. Resuming delivers any held-back input.
+    pub fn set_paused(&self, paused: bool) {
+        // Held-back input already passed the event filter.
+        for event in self.pause.set_paused(paused) {
+            (self.deliver)(event);
+        }
+    }
+
+    /// Whether tray input is currently paused.
+    pub fn is_paused(&self) -> bool {
+        self.pause.paused.load(Ordering::Acquire)
+    }
+
+    /// Whether events are waiting to be received.
+    ///
+    /// Useful for asserting that the application drains every event, or for
+    /// deciding whether a wake-up needs any work.
+    pub fn has_pending(&self) -> bool {
+        self.backlog.pending.load(Ordering::Relaxed) > 0
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Manager<String> {
+    /// Create the tray described by `config`, such as one read from a JSON
+    /// or TOML file.
+    ///
+    /// Loads the configured icon, then creates the tray like
+    /// [`create_tray`](Self::create_tray); menu item IDs are the strings
+    /// from the configuration.
+    pub fn create_tray_from_config(
+        &self,
+        config: winit_extras_core::TrayConfig,
+    ) -> Result<Box<dyn TrayIcon<String>>, TrayError> {
+        self.create_tray(config.into_attributes()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct StubTray(TrayIconId);
+
+    impl<T> TrayIcon<T> for StubTray {
+        fn id(&self) -> TrayIconId {
+            self.0
+        }
+    }
+
+    struct StubTrayRenderer;
+
+    impl TrayIconRenderer<u32> for StubTrayRenderer {
+        fn create_tray(
+            &self,
+            _attributes: TrayIconAttributes<u32>,
+            _proxy: EventCallback<u32>,
+        ) -> Result<Box<dyn TrayIcon<u32>>, Box<dyn std::error::Error + Send + Sync>> {
+            Ok(Box::new(StubTray(TrayIconId::allocate())))
+        }
+    }
+
+    #[cfg(feature = "context_menu")]
+    struct StubMenuRenderer;
+
+    #[cfg(feature = "context_menu")]
+    impl<T: Clone + Send + Sync + 'static> MenuRenderer<T> for StubMenuRenderer {
+        fn create_menu(
+            &self,
+            _event_loop: &dyn ActiveEventLoop,
+            _window: &dyn HasWindowHandle,
+            _items: Vec<MenuEntry<T>>,
+            _proxy: EventCallback<T>,
+        ) -> Result<Box<dyn ContextMenu>, Box<dyn std::error::Error + Send + Sync>> {
+            Err("no menus in tests".into())
+        }
+    }
+
+    /// Counts wake-ups instead of waking an event loop.
+    #[derive(Debug, Default)]
+    struct WakeCounter(AtomicUsize);
+
+    impl winit::event_loop::EventLoopProxyProvider for WakeCounter {
+        fn wake_up(&self) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn builder(proxy: Option<EventLoopProxy>) -> ManagerBuilder<u32> {
+        let builder = ManagerBuilder::new(proxy).tray_renderer(StubTrayRenderer);
+        #[cfg(feature = "context_menu")]
+        let builder = builder.menu_renderer(StubMenuRenderer);
+        builder
+    }
+
+    fn click(id: u32) -> Event<u32> {
+        Event::MenuItemClicked {
+            id,
+            checked: None,
+            timestamp: Instant::now(),
+        }
+    }
+
+    /// Counts the error events logged while it is the default subscriber.
+    struct ErrorCounter(Arc<AtomicUsize>);
+
+    impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for ErrorCounter {
+        fn on_event(
+            &self,
+            event: &tracing::Event<'_>,
+            _ctx: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            if *event.metadata().level() == tracing::Level::ERROR {
+                self.0.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+    }
+
+    fn received(manager: &Manager<u32>) -> Vec<Event<u32>> {
+        std::iter::from_fn(|| manager.try_recv().ok()).collect()
+    }
+
+    #[test]
+    fn test_event_filter_drops_events() {
+        let manager = builder(None)
+            .event_filter(|event| !matches!(event, Event::MenuItemClicked { id: 2, .. }))
+            .build();
+        let callback = manager.event_callback();
+        let kept = click(1);
+        callback(kept.clone());
+        callback(click(2));
+        assert_eq!(received(&manager), vec![kept]);
+    }
+
+    #[test]
+    fn test_buffered_events_pass_filter_once() {
+        // Drops an event equal to the one before it.
+        let last = Mutex::new(None::<Event<u32>>);
+        let manager = builder(None)
+            .paused_events(PausedEvents::Buffer)
+            .event_filter(move |event| {
+                let mut last = last.lock().unwrap();
+                let repeat = last.as_ref() == Some(event);
+                *last = Some(event.clone());
+                !repeat
+            })
+            .build();
+        let callback = manager.event_callback();
+
+        manager.set_paused(true);
+        let event = click(1);
+        callback(event.clone());
+        callback(event.clone());
+        assert!(received(&manager).is_empty());
+
+        manager.set_paused(false);
+        assert_eq!(received(&manager), vec![event]);
+    }
+
+    #[test]
+    fn test_paused_drops_input_only() {
+        let manager = builder(None).build();
+        let callback = manager.event_callback();
+        let resized = Event::IconSizeChanged {
+            tray_icon_id: TrayIconId::from_raw(1),
+            size: winit::dpi::PhysicalSize::new(32, 32),
+        };
+
+        manager.set_paused(true);
+        assert!(manager.is_paused());
+        callback(click(1));
+        callback(resized.clone());
+        assert_eq!(received(&manager), vec![resized]);
+
+        manager.set_paused(false);
+        assert!(received(&manager).is_empty());
+    }
+
+    #[test]
+    fn test_coalesced_wake_ups() {
+        let counter = Arc::new(WakeCounter::default());
+        let manager = builder(Some(EventLoopProxy::new(counter.clone())))
+            .coalesce_wake_ups(true)
+            .build();
+        let callback = manager.event_callback();
+
+        callback(click(1));
+        callback(click(2));
+        assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+        assert!(manager.has_pending());
+
+        assert_eq!(received(&manager).len(), 2);
+        assert!(!manager.has_pending());
+        callback(click(3));
+        assert_eq!(counter.0.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_recv_ends_coalesced_wake_up() {
+        let counter = Arc::new(WakeCounter::default());
+        let manager = builder(Some(EventLoopProxy::new(counter.clone())))
+            .coalesce_wake_ups(true)
+            .build();
+        let callback = manager.event_callback();
+
+        callback(click(1));
+        assert!(matches!(
+            manager.recv(),
+            Ok(Event::MenuItemClicked { id: 1, .. })
+        ));
+        let sender = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            callback(click(2));
+        });
+        assert!(matches!(
+            manager.recv(),
+            Ok(Event::MenuItemClicked { id: 2, .. })
+        ));
+        sender.join().unwrap();
+        assert_eq!(counter.0.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_all_trays_removed_after_last_tray() {
+        let manager = builder(None).build();
+        let first = manager.create_tray(TrayIconAttributes::default()).unwrap();
+        let second = manager.create_tray(TrayIconAttributes::default()).unwrap();
+
+        drop(first);
+        assert!(received(&manager).is_empty());
+        drop(second);
+        assert_eq!(received(&manager), vec![Event::AllTraysRemoved]);
+    }
+
+    #[test]
+    fn test_drop_with_persistent_tray_logs_no_error() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let errors = Arc::new(AtomicUsize::new(0));
+        let subscriber = tracing_subscriber::registry().with(ErrorCounter(errors.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            let manager = builder(None).build();
+            manager
+                .create_tray_persistent(TrayIconAttributes::default())
+                .unwrap();
+            drop(manager);
+        });
+        assert_eq!(errors.load(Ordering::SeqCst), 0);
+    }
+
+    /// Keeps the attributes of the trays it creates, for checking what a
+    /// tray was created with.
+    #[cfg(feature = "serde")]
+    struct RecordingRenderer(std::rc::Rc<std::cell::RefCell<Vec<TrayIconAttributes<String>>>>);
+
+    #[cfg(feature = "serde")]
+    impl TrayIconRenderer<String> for RecordingRenderer {
+        fn create_tray(
+            &self,
+            attributes: TrayIconAttributes<String>,
+            _proxy: EventCallback<String>,
+        ) -> Result<Box<dyn TrayIcon<String>>, Box<dyn std::error::Error + Send + Sync>> {
+            self.0.borrow_mut().push(attributes);
+            Ok(Box::new(StubTray(TrayIconId::allocate())))
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_tray_from_config() {
+        let config: winit_extras_core::TrayConfig = serde_json::from_str(
+            r#"{
+                "tooltip": "Sync",
+                "icon": "placeholder",
+                "menu": [
+                    { "item": { "id": "pause", "label": "Pause syncing" } },
+                    { "submenu": { "label": "Open", "items": [
+                        { "item": { "id": "folder", "label": "Folder" } }
+                    ] } },
+                    { "separator": {} },
+                    { "item": { "id": "quit", "label": "Quit" } }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let created = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let builder = ManagerBuilder::new(None).tray_renderer(RecordingRenderer(created.clone()));
+        #[cfg(feature = "context_menu")]
+        let builder = builder.menu_renderer(StubMenuRenderer);
+        let manager = builder.build();
+
+        let _tray = manager.create_tray_from_config(config).unwrap();
+        let created = created.borrow();
+        let [attributes] = created.as_slice() else {
+            panic!("expected one tray");
+        };
+        assert_eq!(attributes.tooltip.as_deref(), Some("Sync"));
+        assert!(attributes.icon.is_some());
+        let menu = attributes.context_menu.as_deref().unwrap();
+        let items: Vec<_> = menu
+            .items()
+            .map(|item| (item.id.as_str(), item.label.as_str()))
+            .collect();
+        assert_eq!(
+            items,
+            [
+                ("pause", "Pause syncing"),
+                ("folder", "Folder"),
+                ("quit", "Quit")
+            ]
+        );
+        assert_eq!(
+            winit_extras_core::describe_menu(menu),
+            "Pause syncing\nOpen (1 item)\n  Folder\n---\nQuit\n"
+        );
     }
 }
@@ -5,8 +5,10 @@
 use std::marker::PhantomData;
 
 use winit::event_loop::{EventLoop, EventLoopProxy};
+use winit::raw_window_handle::RawWindowHandle;
+use winit_extras_core::TrayError;
 pub use winit_extras_core::menu_bar::{
-    MenuBar, MenuBarAttributes, MenuBarEvent, MenuBarId, MenuBarProxy, TopLevelMenu,
+    MenuBar, MenuBarAttributes, MenuBarEvent, MenuBarId, MenuBarProxy, TopLevelMenu, TopLevelMenuId,
 };
 
 #[cfg(target_os = "windows")]
@@ -96,11 +98,13 @@ impl<T: Clone + Send + Sync + 'static> MenuBarManager<T> {
     /// Create a menu bar with the given attributes.
     ///
     /// On macOS, this sets the application's main menu.
-    /// On Windows, the `parent_window` attribute must be set.
+    /// On Windows, the `parent_window` attribute must be set; use
+    /// [`builder`](Self::builder) to have the compiler check that.
     pub fn create_menu_bar(
         &self,
         attr: MenuBarAttributes<T>,
-    ) -> Result<Box<dyn MenuBar>, anyhow::Error> {
+    ) -> Result<Box<dyn MenuBar<T>>, TrayError> {
+        attr.validate()?;
         let menu_bar = platform_menu_bar::MenuBar::new(self.callback_proxy.clone(), attr)?;
         Ok(Box::new(menu_bar))
     }
@@ -114,10 +118,22 @@ impl<T: Clone + Send + Sync + 'static> MenuBarManager<T> {
     pub fn create_menu_bar_with_menus(
         &self,
         menus: Vec<TopLevelMenu<T>>,
-    ) -> Result<Box<dyn MenuBar>, anyhow::Error> {
+    ) -> Result<Box<dyn MenuBar<T>>, TrayError> {
         self.create_menu_bar(MenuBarAttributes::new(menus))
     }
 
+    /// Start building a menu bar with the given top-level menus.
+    ///
+    /// On Windows the builder only has a `build` method once
+    /// [`parent_window`](MenuBarBuilder::parent_window) has been called.
+    pub fn builder(&self, menus: Vec<TopLevelMenu<T>>) -> MenuBarBuilder<'_, T, NoParentWindow> {
+        MenuBarBuilder {
+            manager: self,
+            menus,
+            parent_window: NoParentWindow,
+        }
+    }
+
     /// Receive a menu bar event, blocking until one is available.
     pub fn recv(&self) -> Result<(MenuBarId, MenuBarEvent<T>), std::sync::mpsc::RecvError> {
         self.receiver.recv()
@@ -128,3 +144,62 @@ impl<T: Clone + Send + Sync + 'static> MenuBarManager<T> {
         self.receiver.try_recv()
     }
 }
+
+/// Builder state before a parent window has been set.
+#[derive(Debug, Clone, Copy)]
+pub struct NoParentWindow;
+
+/// Builder state once a parent window has been set.
+#[derive(Debug, Clone, Copy)]
+pub struct WithParentWindow(RawWindowHandle);
+
+/// Builder for a menu bar, created with [`MenuBarManager::builder`].
+///
+/// Tracks whether a parent window was set in its type, so forgetting it on
+/// Windows is a compile error rather than a failure at runtime.
+#[derive(Debug)]
+pub struct MenuBarBuilder<'a, T, P> {
+    manager: &'a MenuBarManager<T>,
+    menus: Vec<TopLevelMenu<T>>,
+    parent_window: P,
+}
+
+impl<'a, T, P> MenuBarBuilder<'a, T, P> {
+    /// Add a top-level menu after the ones already added.
+    pub fn menu(mut self, menu: impl Into<TopLevelMenu<T>>) -> Self {
+        self.menus.push(menu.into());
+        self
+    }
+
+    /// Set the window the menu bar is attached to.
+    ///
+    /// Required on Windows and ignored on macOS.
+    pub fn parent_window(
+        self,
+        parent_window: RawWindowHandle,
+    ) -> MenuBarBuilder<'a, T, WithParentWindow> {
+        MenuBarBuilder {
+            manager: self.manager,
+            menus: self.menus,
+            parent_window: WithParentWindow(parent_window),
+        }
+    }
+}
+
+impl<T: Clone + Send + Sync + 'static> MenuBarBuilder<'_, T, WithParentWindow> {
+    /// Create the menu bar.
+    pub fn build(self) -> Result<Box<dyn MenuBar<T>>, TrayError> {
+        self.manager.create_menu_bar(
+            MenuBarAttributes::new(self.menus).with_parent_window(self.parent_window.0),
+        )
+    }
+}
+
+#[cfg(target_os = "macos")]
+impl<T: Clone + Send + Sync + 'static> MenuBarBuilder<'_, T, NoParentWindow> {
+    /// Create the menu bar.
+    pub fn build(self) -> Result<Box<dyn MenuBar<T>>, TrayError> {
+        self.manager
+            .create_menu_bar(MenuBarAttributes::new(self.menus))
+    }
+}
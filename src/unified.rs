@@ -0,0 +1,106 @@
+//! One event stream for tray icons, context menus and the menu bar.
+//!
+//! An application using both a [`Manager`] and a [`MenuBarManager`] has two
+//! channels to drain on every wake-up. [`WinitTray`] owns one of each and
+//! hands out their events through a single [`try_recv`](WinitTray::try_recv),
+//! so the application has one drain loop:
+//!
+//! ```ignore
+//! let extras: WinitTray<TrayAction, MenuBarAction> = WinitTray::new(&event_loop);
+//! let tray = extras.manager().create_tray(attributes)?;
+//!
+//! // In proxy_wake_up:
+//! while let Ok(event) = extras.try_recv() {
+//!     match event {
+//!         UnifiedEvent::Tray(event) => { /* tray and context menu events */ }
+//!         UnifiedEvent::MenuBar(id, event) => { /* menu bar events */ }
+//!     }
+//! }
+//! ```
+//!
+//! The managers stay available for applications that prefer to keep the
+//! streams apart.
+
+use std::sync::mpsc::TryRecvError;
+
+use winit::event_loop::EventLoop;
+use winit_extras_core::Event;
+
+use crate::Manager;
+use crate::menu_bar::{MenuBarEvent, MenuBarId, MenuBarManager};
+
+/// An event from any of the managers owned by a [`WinitTray`].
+#[derive(Debug, Clone)]
+pub enum UnifiedEvent<T, M = T> {
+    /// An event from a tray icon or context menu.
+    Tray(Event<T>),
+    /// An event from a menu bar.
+    MenuBar(MenuBarId, MenuBarEvent<M>),
+}
+
+/// A [`Manager`] and a [`MenuBarManager`] drained as one.
+///
+/// `T` is the action type of tray and context menus, and `M` that of menu
+/// bars.
+pub struct WinitTray<T: Clone + Send + Sync + 'static = (), M = T> {
+    manager: Manager<T>,
+    menu_bar_manager: MenuBarManager<M>,
+}
+
+impl<T: Clone + Send + Sync + 'static, M> std::fmt::Debug for WinitTray<T, M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WinitTray")
+            .field("manager", &self.manager)
+            .field("menu_bar_manager", &self.menu_bar_manager)
+            .finish()
+    }
+}
+
+impl<T: Clone + Send + Sync + 'static, M: Clone + Send + Sync + 'static> WinitTray<T, M> {
+    /// Create both managers with their defaults.
+    pub fn new(event_loop: &EventLoop) -> Self {
+        Self::from_managers(Manager::new(event_loop), MenuBarManager::new(event_loop))
+    }
+
+    /// Combine managers that were configured separately, for example with
+    /// [`Manager::builder`].
+    pub fn from_managers(manager: Manager<T>, menu_bar_manager: MenuBarManager<M>) -> Self {
+        WinitTray {
+            manager,
+            menu_bar_manager,
+        }
+    }
+
+    /// The manager for tray icons and context menus.
+    pub fn manager(&self) -> &Manager<T> {
+        &self.manager
+    }
+
+    /// The manager for menu bars.
+    pub fn menu_bar_manager(&self) -> &MenuBarManager<M> {
+        &self.menu_bar_manager
+    }
+
+    /// Split back into the individual managers.
+    pub fn into_managers(self) -> (Manager<T>, MenuBarManager<M>) {
+        (self.manager, self.menu_bar_manager)
+    }
+
+    /// Try to receive an event from either manager without blocking.
+    ///
+    /// Tray events are returned first when both have some waiting. As with
+    /// [`Manager::try_recv`], call this until it is empty on every wake-up.
+    pub fn try_recv(&self) -> Result<UnifiedEvent<T, M>, TryRecvError> {
+        let tray_error = match self.manager.try_recv() {
+            Ok(event) => return Ok(UnifiedEvent::Tray(event)),
+            Err(err) => err,
+        };
+        match self.menu_bar_manager.try_recv() {
+            Ok((id, event)) => Ok(UnifiedEvent::MenuBar(id, event)),
+            Err(TryRecvError::Disconnected) if tray_error == TryRecvError::Disconnected => {
+                Err(TryRecvError::Disconnected)
+            }
+            Err(_) => Err(TryRecvError::Empty),
+        }
+    }
+}
@@ -0,0 +1,43 @@
+//! Helpers for working with an application's existing windows.
+
+use winit::icon::Icon;
+use winit::raw_window_handle::HasWindowHandle;
+use winit::window::Window;
+
+/// Hides `window` so the application keeps running in the tray.
+///
+/// Call this from `WindowEvent::CloseRequested` instead of exiting to get the
+/// usual "close minimizes to tray" behavior, and bring the window back with
+/// [`restore_from_tray`]. Keep the [`TrayIcon`][crate::TrayIcon] handle
+/// alive while the window is hidden, since it is then the only way back.
+///
+/// Hidden windows are also removed from the Windows taskbar. On macOS the
+/// app keeps its Dock icon.
+pub fn minimize_to_tray(window: &dyn Window) {
+    window.set_visible(false);
+}
+
+/// Shows a window hidden with [`minimize_to_tray`] and gives it focus.
+pub fn restore_from_tray(window: &dyn Window) {
+    window.set_visible(true);
+    window.focus_window();
+}
+
+/// Returns a copy of the icon currently set on `window`, for reuse as a tray
+/// icon.
+///
+/// Only Windows can read a window's icon back. Elsewhere this returns `None`;
+/// keep the [`Icon`] you passed to `with_window_icon` and clone it instead, or
+/// use [`TrayIconAttributes::with_window_icon`][crate::TrayIconAttributes::with_window_icon].
+pub fn clone_window_icon(window: &(impl HasWindowHandle + ?Sized)) -> Option<Icon> {
+    #[cfg(target_os = "windows")]
+    {
+        winit_extras_windows::icon::window_icon(window)
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        let _ = window;
+        None
+    }
+}
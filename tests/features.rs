@@ -0,0 +1,63 @@
+//! Builds tray and menu configurations with whichever features are enabled,
+//! so every feature combination has tests touching its API.
+//!
+//! Run it once per combination, for example:
+//!
+//! ```text
+//! cargo test --no-default-features --features menu_bar --test features
+//! ```
+
+use winit_extras::prelude::*;
+
+#[test]
+fn test_tray_attributes() {
+    let attributes = TrayIconAttributes::<u32>::default()
+        .with_tooltip("Features")
+        .with_button_filter(ButtonMask::LEFT);
+
+    #[cfg(feature = "menu")]
+    let attributes = attributes
+        .with_context_menu(vec![
+            MenuEntry::Item(MenuItem::new(1, "Open")),
+            MenuEntry::Separator,
+            MenuEntry::Item(MenuItem::new(2, "Quit")),
+        ])
+        .with_primary_action(PrimaryAction::ShowMenu)
+        .with_flatten_submenus(true);
+
+    assert!(attributes.validate().is_ok());
+
+    let summary = attributes.describe();
+    assert!(summary.contains("tooltip: Some(\"Features\")"));
+    assert_eq!(
+        summary.contains("context_menu: 2 items"),
+        cfg!(feature = "menu")
+    );
+}
+
+#[test]
+fn test_menu_events_follow_the_feature() {
+    assert_eq!(Event::<u32>::SUPPORTS_MENU_EVENTS, cfg!(feature = "menu"));
+}
+
+#[cfg(feature = "context_menu")]
+#[test]
+fn test_context_menu_position_ignores_other_events() {
+    use winit::event::WindowEvent;
+    use winit_extras::context_menu::context_menu_position;
+
+    assert_eq!(context_menu_position(&WindowEvent::Focused(true)), None);
+}
+
+#[cfg(feature = "menu_bar")]
+#[test]
+fn test_menu_bar_attributes() {
+    use winit_extras::menu_bar::{MenuBarAttributes, TopLevelMenu};
+
+    let attributes = MenuBarAttributes::new(vec![TopLevelMenu::new(
+        "File",
+        vec![MenuEntry::Item(MenuItem::new(1, "Quit"))],
+    )]);
+    // Menu bars belong to a window on Windows.
+    assert_eq!(attributes.validate().is_ok(), !cfg!(target_os = "windows"));
+}
@@ -0,0 +1,103 @@
+//! Checks that the crate builds and basic trays work with every menu
+//! feature turned off.
+//!
+//! Only compiled without the `menu` feature:
+//!
+//! ```text
+//! cargo test --no-default-features --test no_menu
+//! ```
+#![cfg(not(feature = "menu"))]
+
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use winit_extras::prelude::*;
+use winit_extras::{
+    ButtonSource, ElementState, EventCallback, MouseButton, TrayError, TrayIconRenderer,
+};
+
+/// A tray that reports a click on its icon as soon as it is created.
+#[derive(Debug)]
+struct ClickingTray {
+    id: TrayIconId,
+}
+
+impl TrayIcon<u32> for ClickingTray {
+    fn id(&self) -> TrayIconId {
+        self.id
+    }
+}
+
+struct ClickingRenderer;
+
+impl TrayIconRenderer<u32> for ClickingRenderer {
+    fn create_tray(
+        &self,
+        attributes: TrayIconAttributes<u32>,
+        proxy: EventCallback<u32>,
+    ) -> Result<Box<dyn TrayIcon<u32>>, Box<dyn std::error::Error + Send + Sync>> {
+        let id = TrayIconId::allocate();
+        for button in [MouseButton::Left, MouseButton::Right] {
+            if attributes.button_filter.contains(button) {
+                proxy(Event::PointerButton {
+                    tray_icon_id: id,
+                    state: ElementState::Released,
+                    position: Default::default(),
+                    button: ButtonSource::Mouse(button),
+                    timestamp: Instant::now(),
+                });
+            }
+        }
+        Ok(Box::new(ClickingTray { id }))
+    }
+}
+
+type Recorded = Arc<Mutex<Vec<Event<u32>>>>;
+
+fn recording_callback() -> (EventCallback<u32>, Recorded) {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let recorded = events.clone();
+    let callback: EventCallback<u32> = Arc::new(move |event| recorded.lock().unwrap().push(event));
+    (callback, events)
+}
+
+#[test]
+fn test_attributes_without_menu() {
+    let attributes = TrayIconAttributes::<u32>::default()
+        .with_tooltip("No menu")
+        .with_template_icon(false)
+        .with_registration_retries(0)
+        .with_button_filter(ButtonMask::LEFT);
+
+    assert!(attributes.validate().is_ok());
+    assert_eq!(attributes.tooltip.as_deref(), Some("No menu"));
+    assert_eq!(attributes.button_filter, ButtonMask::LEFT);
+}
+
+#[test]
+fn test_tray_without_menu_delivers_pointer_events() {
+    let (callback, events) = recording_callback();
+    let attributes = TrayIconAttributes::default().with_button_filter(ButtonMask::LEFT);
+    let tray = ClickingRenderer.create_tray(attributes, callback).unwrap();
+
+    let events = events.lock().unwrap();
+    assert_eq!(events.len(), 1);
+    assert!(matches!(
+        events[0],
+        Event::PointerButton {
+            tray_icon_id,
+            button: ButtonSource::Mouse(MouseButton::Left),
+            ..
+        } if tray_icon_id == tray.id()
+    ));
+    assert!(!events[0].is_menu_event());
+    const { assert!(!Event::<u32>::SUPPORTS_MENU_EVENTS) };
+
+    // The optional methods keep their defaults.
+    assert_eq!(tray.last_pointer_position(), None);
+    assert_eq!(tray.is_in_overflow(), None);
+    assert_eq!(
+        tray.set_status_dot(Some(StatusColor::Green)),
+        Err(TrayError::Unsupported("status dots"))
+    );
+}
@@ -0,0 +1,459 @@
+//! Keyboard shortcuts.
+
+use std::borrow::Cow;
+use std::fmt;
+use std::str::FromStr;
+
+use winit::keyboard::{KeyCode, ModifiersState};
+
+/// A key pressed together with modifier keys, such as `Ctrl+Shift+K`.
+///
+/// Keys are identified by physical position ([`KeyCode`]), so a shortcut
+/// stays on the same key regardless of keyboard layout.
+///
+/// Accelerators can also be parsed from strings such as `"CmdOrCtrl+Shift+K"`,
+/// so shortcuts can come from configuration files. Parts are separated by
+/// `+`, matched case-insensitively, and the key comes last:
+///
+/// | Modifier | Meaning |
+/// | --- | --- |
+/// | `Ctrl`, `Control` | [`ModifiersState::CONTROL`] |
+/// | `Shift` | [`ModifiersState::SHIFT`] |
+/// | `Alt`, `Option` | [`ModifiersState::ALT`] |
+/// | `Super`, `Meta`, `Cmd`, `Command`, `Win` | [`ModifiersState::META`] |
+/// | `CmdOrCtrl`, `CommandOrControl` | Command on macOS, Ctrl elsewhere |
+///
+/// | Key | Names |
+/// | --- | --- |
+/// | Letters and digits | `A` to `Z`, `0` to `9` |
+/// | Function keys | `F1` to `F24` |
+/// | Editing | `Space`, `Enter` (`Return`), `Escape` (`Esc`), `Tab`, `Backspace`, `Delete` (`Del`), `Insert` |
+/// | Navigation | `Home`, `End`, `PageUp`, `PageDown`, `Left`, `Up`, `Right`, `Down` (or `ArrowLeft` and so on) |
+/// | System | `PrintScreen`, `Pause` |
+/// | Punctuation | `-`, `=`, `,`, `.`, `;`, `/`, `` ` ``, `[`, `\`, `]`, `'`, or their [`KeyCode`] names such as `Minus` |
+///
+/// Formatting with [`Display`](fmt::Display) gives a string that parses back
+/// to the same accelerator, such as `Ctrl+Shift+K`. For showing a shortcut
+/// to users, [`display_string`](Self::display_string) follows the platform's
+/// own conventions instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Accelerator {
+    /// Modifier keys that must be held. [`ModifiersState::META`] is the
+    /// Windows key on Windows and Command on macOS.
+    pub modifiers: ModifiersState,
+    /// The key that triggers the shortcut.
+    pub key: KeyCode,
+}
+
+impl Accelerator {
+    /// Create an accelerator from modifiers and a key.
+    pub fn new(modifiers: ModifiersState, key: KeyCode) -> Self {
+        Self { modifiers, key }
+    }
+
+    /// The shortcut as menus on this platform show it: symbols such as
+    /// `⇧⌘S` on macOS, and text such as `Ctrl+Shift+S` elsewhere.
+    ///
+    /// Unlike [`Display`](fmt::Display), the result is not meant to be
+    /// parsed back.
+    pub fn display_string(&self) -> String {
+        if cfg!(target_os = "macos") {
+            self.symbol_string()
+        } else {
+            self.text_string()
+        }
+    }
+
+    /// macOS style: modifier symbols in Control, Option, Shift, Command order,
+    /// followed by the key, with no separators.
+    fn symbol_string(&self) -> String {
+        let modifiers = [
+            (ModifiersState::CONTROL, "⌃"),
+            (ModifiersState::ALT, "⌥"),
+            (ModifiersState::SHIFT, "⇧"),
+            (ModifiersState::META, "⌘"),
+        ];
+        let mut display: String = modifiers
+            .into_iter()
+            .filter(|&(modifier, _)| self.modifiers.contains(modifier))
+            .map(|(_, symbol)| symbol)
+            .collect();
+        let symbol = match self.key {
+            KeyCode::Enter => Some("↩"),
+            KeyCode::Escape => Some("⎋"),
+            KeyCode::Tab => Some("⇥"),
+            KeyCode::Backspace => Some("⌫"),
+            KeyCode::Delete => Some("⌦"),
+            KeyCode::Home => Some("↖"),
+            KeyCode::End => Some("↘"),
+            KeyCode::PageUp => Some("⇞"),
+            KeyCode::PageDown => Some("⇟"),
+            KeyCode::ArrowLeft => Some("←"),
+            KeyCode::ArrowUp => Some("↑"),
+            KeyCode::ArrowRight => Some("→"),
+            KeyCode::ArrowDown => Some("↓"),
+            _ => None,
+        };
+        match symbol {
+            Some(symbol) => display.push_str(symbol),
+            None => display.push_str(&self.key_name()),
+        }
+        display
+    }
+
+    /// Windows and Linux style: modifier names joined to the key with `+`.
+    fn text_string(&self) -> String {
+        let meta = if cfg!(windows) { "Win" } else { "Super" };
+        let modifiers = [
+            (ModifiersState::CONTROL, "Ctrl"),
+            (ModifiersState::ALT, "Alt"),
+            (ModifiersState::SHIFT, "Shift"),
+            (ModifiersState::META, meta),
+        ];
+        let mut display = String::new();
+        for (modifier, name) in modifiers {
+            if self.modifiers.contains(modifier) {
+                display.push_str(name);
+                display.push('+');
+            }
+        }
+        display.push_str(&self.key_name());
+        display
+    }
+
+    /// The name the key is written with, such as `K` or `PageUp`.
+    pub fn key_name(&self) -> Cow<'static, str> {
+        match KEY_NAMES.iter().find(|&&(_, key)| key == self.key) {
+            Some(&(name, _)) => Cow::Borrowed(name),
+            // Keys without a name can't be parsed back, but still show up
+            // readably in logs.
+            None => Cow::Owned(format!("{:?}", self.key)),
+        }
+    }
+}
+
+impl From<KeyCode> for Accelerator {
+    /// An accelerator for a key pressed on its own.
+    fn from(key: KeyCode) -> Self {
+        Self::new(ModifiersState::empty(), key)
+    }
+}
+
+/// Error returned when a string can't be parsed as an [`Accelerator`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum AcceleratorParseError {
+    /// The string was empty, or had an empty part as in `"Ctrl++K"`.
+    EmptyPart,
+    /// A part before the key is not a known modifier.
+    UnknownModifier(String),
+    /// The last part is not a known key.
+    UnknownKey(String),
+}
+
+impl fmt::Display for AcceleratorParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EmptyPart => write!(f, "accelerator has an empty part"),
+            Self::UnknownModifier(name) => write!(f, "unknown modifier `{name}` in accelerator"),
+            Self::UnknownKey(name) => write!(f, "unknown key `{name}` in accelerator"),
+        }
+    }
+}
+
+impl std::error::Error for AcceleratorParseError {}
+
+/// Key names accepted when parsing. The first name listed for a key is the
+/// one it is displayed with.
+const KEY_NAMES: &[(&str, KeyCode)] = &[
+    ("A", KeyCode::KeyA),
+    ("B", KeyCode::KeyB),
+    ("C", KeyCode::KeyC),
+    ("D", KeyCode::KeyD),
+    ("E", KeyCode::KeyE),
+    ("F", KeyCode::KeyF),
+    ("G", KeyCode::KeyG),
+    ("H", KeyCode::KeyH),
+    ("I", KeyCode::KeyI),
+    ("J", KeyCode::KeyJ),
+    ("K", KeyCode::KeyK),
+    ("L", KeyCode::KeyL),
+    ("M", KeyCode::KeyM),
+    ("N", KeyCode::KeyN),
+    ("O", KeyCode::KeyO),
+    ("P", KeyCode::KeyP),
+    ("Q", KeyCode::KeyQ),
+    ("R", KeyCode::KeyR),
+    ("S", KeyCode::KeyS),
+    ("T", KeyCode::KeyT),
+    ("U", KeyCode::KeyU),
+    ("V", KeyCode::KeyV),
+    ("W", KeyCode::KeyW),
+    ("X", KeyCode::KeyX),
+    ("Y", KeyCode::KeyY),
+    ("Z", KeyCode::KeyZ),
+    ("0", KeyCode::Digit0),
+    ("1", KeyCode::Digit1),
+    ("2", KeyCode::Digit2),
+    ("3", KeyCode::Digit3),
+    ("4", KeyCode::Digit4),
+    ("5", KeyCode::Digit5),
+    ("6", KeyCode::Digit6),
+    ("7", KeyCode::Digit7),
+    ("8", KeyCode::Digit8),
+    ("9", KeyCode::Digit9),
+    ("F1", KeyCode::F1),
+    ("F2", KeyCode::F2),
+    ("F3", KeyCode::F3),
+    ("F4", KeyCode::F4),
+    ("F5", KeyCode::F5),
+    ("F6", KeyCode::F6),
+    ("F7", KeyCode::F7),
+    ("F8", KeyCode::F8),
+    ("F9", KeyCode::F9),
+    ("F10", KeyCode::F10),
+    ("F11", KeyCode::F11),
+    ("F12", KeyCode::F12),
+    ("F13", KeyCode::F13),
+    ("F14", KeyCode::F14),
+    ("F15", KeyCode::F15),
+    ("F16", KeyCode::F16),
+    ("F17", KeyCode::F17),
+    ("F18", KeyCode::F18),
+    ("F19", KeyCode::F19),
+    ("F20", KeyCode::F20),
+    ("F21", KeyCode::F21),
+    ("F22", KeyCode::F22),
+    ("F23", KeyCode::F23),
+    ("F24", KeyCode::F24),
+    ("Space", KeyCode::Space),
+    ("Enter", KeyCode::Enter),
+    ("Return", KeyCode::Enter),
+    ("Escape", KeyCode::Escape),
+    ("Esc", KeyCode::Escape),
+    ("Tab", KeyCode::Tab),
+    ("Backspace", KeyCode::Backspace),
+    ("Delete", KeyCode::Delete),
+    ("Del", KeyCode::Delete),
+    ("Insert", KeyCode::Insert),
+    ("Home", KeyCode::Home),
+    ("End", KeyCode::End),
+    ("PageUp", KeyCode::PageUp),
+    ("PageDown", KeyCode::PageDown),
+    ("Left", KeyCode::ArrowLeft),
+    ("ArrowLeft", KeyCode::ArrowLeft),
+    ("Up", KeyCode::ArrowUp),
+    ("ArrowUp", KeyCode::ArrowUp),
+    ("Right", KeyCode::ArrowRight),
+    ("ArrowRight", KeyCode::ArrowRight),
+    ("Down", KeyCode::ArrowDown),
+    ("ArrowDown", KeyCode::ArrowDown),
+    ("PrintScreen", KeyCode::PrintScreen),
+    ("Pause", KeyCode::Pause),
+    ("-", KeyCode::Minus),
+    ("Minus", KeyCode::Minus),
+    ("=", KeyCode::Equal),
+    ("Equal", KeyCode::Equal),
+    (",", KeyCode::Comma),
+    ("Comma", KeyCode::Comma),
+    (".", KeyCode::Period),
+    ("Period", KeyCode::Period),
+    (";", KeyCode::Semicolon),
+    ("Semicolon", KeyCode::Semicolon),
+    ("/", KeyCode::Slash),
+    ("Slash", KeyCode::Slash),
+    ("`", KeyCode::Backquote),
+    ("Backquote", KeyCode::Backquote),
+    ("[", KeyCode::BracketLeft),
+    ("BracketLeft", KeyCode::BracketLeft),
+    ("\\", KeyCode::Backslash),
+    ("Backslash", KeyCode::Backslash),
+    ("]", KeyCode::BracketRight),
+    ("BracketRight", KeyCode::BracketRight),
+    ("'", KeyCode::Quote),
+    ("Quote", KeyCode::Quote),
+];
+
+/// The modifier `CmdOrCtrl` stands for on this platform.
+const CMD_OR_CTRL: ModifiersState = if cfg!(target_os = "macos") {
+    ModifiersState::META
+} else {
+    ModifiersState::CONTROL
+};
+
+fn parse_modifier(name: &str) -> Option<ModifiersState> {
+    let modifier = match name.to_ascii_lowercase().as_str() {
+        "ctrl" | "control" => ModifiersState::CONTROL,
+        "shift" => ModifiersState::SHIFT,
+        "alt" | "option" => ModifiersState::ALT,
+        "super" | "meta" | "cmd" | "command" | "win" => ModifiersState::META,
+        "cmdorctrl" | "commandorcontrol" => CMD_OR_CTRL,
+        _ => return None,
+    };
+    Some(modifier)
+}
+
+fn parse_key(name: &str) -> Option<KeyCode> {
+    KEY_NAMES
+        .iter()
+        .find(|(key_name, _)| key_name.eq_ignore_ascii_case(name))
+        .map(|&(_, key)| key)
+}
+
+impl FromStr for Accelerator {
+    type Err = AcceleratorParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split('+').map(str::trim);
+        // `split` always yields at least one part.
+        let key_name = parts.next_back().unwrap_or_default();
+
+        let mut modifiers = ModifiersState::empty();
+        for name in parts {
+            if name.is_empty() {
+                return Err(AcceleratorParseError::EmptyPart);
+            }
+            modifiers |= parse_modifier(name)
+                .ok_or_else(|| AcceleratorParseError::UnknownModifier(name.to_owned()))?;
+        }
+
+        if key_name.is_empty() {
+            return Err(AcceleratorParseError::EmptyPart);
+        }
+        let key = parse_key(key_name)
+            .ok_or_else(|| AcceleratorParseError::UnknownKey(key_name.to_owned()))?;
+        Ok(Self::new(modifiers, key))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Accelerator {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Accelerator {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl TryFrom<&str> for Accelerator {
+    type Error = AcceleratorParseError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl fmt::Display for Accelerator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let modifiers = [
+            (ModifiersState::CONTROL, "Ctrl"),
+            (ModifiersState::ALT, "Alt"),
+            (ModifiersState::SHIFT, "Shift"),
+            (ModifiersState::META, "Super"),
+        ];
+        for (modifier, name) in modifiers {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{name}+")?;
+            }
+        }
+        f.write_str(&self.key_name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_accelerator() {
+        let accelerator: Accelerator = "CmdOrCtrl+Shift+K".parse().unwrap();
+        assert_eq!(accelerator.modifiers, CMD_OR_CTRL | ModifiersState::SHIFT);
+        assert_eq!(accelerator.key, KeyCode::KeyK);
+
+        // Names are case-insensitive and may be padded.
+        assert_eq!(
+            Accelerator::try_from("ctrl + alt + delete"),
+            Ok(Accelerator::new(
+                ModifiersState::CONTROL | ModifiersState::ALT,
+                KeyCode::Delete
+            ))
+        );
+        assert_eq!("F5".parse(), Ok(Accelerator::from(KeyCode::F5)));
+        assert_eq!(
+            "Ctrl+-".parse(),
+            Ok(Accelerator::new(ModifiersState::CONTROL, KeyCode::Minus))
+        );
+
+        assert_eq!(
+            "Hyper+K".parse::<Accelerator>(),
+            Err(AcceleratorParseError::UnknownModifier("Hyper".into()))
+        );
+        assert_eq!(
+            "Ctrl+Shift".parse::<Accelerator>(),
+            Err(AcceleratorParseError::UnknownKey("Shift".into()))
+        );
+        assert_eq!(
+            "Ctrl+".parse::<Accelerator>(),
+            Err(AcceleratorParseError::EmptyPart)
+        );
+        assert_eq!(
+            "".parse::<Accelerator>(),
+            Err(AcceleratorParseError::EmptyPart)
+        );
+    }
+
+    #[test]
+    fn test_accelerator_display_round_trip() {
+        let accelerator = Accelerator::new(
+            ModifiersState::SHIFT | ModifiersState::CONTROL | ModifiersState::META,
+            KeyCode::KeyK,
+        );
+        assert_eq!(accelerator.to_string(), "Ctrl+Shift+Super+K");
+
+        for &(_, key) in KEY_NAMES {
+            let accelerator = Accelerator::new(ModifiersState::ALT, key);
+            assert_eq!(accelerator.to_string().parse(), Ok(accelerator));
+        }
+    }
+
+    #[test]
+    fn test_accelerator_display_string() {
+        let save_as = Accelerator::new(
+            ModifiersState::SHIFT | ModifiersState::CONTROL,
+            KeyCode::KeyS,
+        );
+        assert_eq!(save_as.symbol_string(), "⌃⇧S");
+        assert_eq!(save_as.text_string(), "Ctrl+Shift+S");
+
+        let all = Accelerator::new(
+            ModifiersState::META
+                | ModifiersState::SHIFT
+                | ModifiersState::ALT
+                | ModifiersState::CONTROL,
+            KeyCode::ArrowUp,
+        );
+        assert_eq!(all.symbol_string(), "⌃⌥⇧⌘↑");
+        let meta = if cfg!(windows) { "Win" } else { "Super" };
+        assert_eq!(all.text_string(), format!("Ctrl+Alt+Shift+{meta}+Up"));
+
+        // Keys without a symbol fall back to their name.
+        let plain = Accelerator::from(KeyCode::F5);
+        assert_eq!(plain.symbol_string(), "F5");
+        assert_eq!(plain.text_string(), "F5");
+
+        // The same configured shortcut renders natively on each platform.
+        let save_as: Accelerator = "CmdOrCtrl+Shift+S".parse().unwrap();
+        let expected = if cfg!(target_os = "macos") {
+            "⇧⌘S"
+        } else {
+            "Ctrl+Shift+S"
+        };
+        assert_eq!(save_as.display_string(), expected);
+    }
+}
@@ -0,0 +1,218 @@
+//! Trays described in configuration files.
+//!
+//! A [`TrayConfig`] holds a tray's tooltip, icon and context menu in a form
+//! serde can read from JSON, TOML or any other format, so applications can
+//! keep their whole tray in a file:
+//!
+//! ```toml
+//! tooltip = "Sync"
+//! icon = "placeholder"
+//!
+//! [[menu]]
+//! item = { id = "pause", label = "Pause syncing", checked = false }
+//!
+//! [[menu]]
+//! separator = {}
+//!
+//! [[menu]]
+//! item = { id = "quit", label = "Quit", accelerator = "CmdOrCtrl+Q" }
+//! ```
+//!
+//! Menu item IDs are strings, so the trays these build are
+//! `TrayIcon<String>`s.
+
+#[cfg(feature = "svg")]
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use winit::icon::{Icon, RgbaIcon};
+
+use crate::icon::IconError;
+use crate::{Accelerator, MenuEntry, MenuItem, Submenu, TrayError, TrayIconAttributes};
+
+/// A tray's tooltip, icon and context menu, as read from configuration.
+///
+/// Every field is optional; a missing menu gives a tray without one.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TrayConfig {
+    pub tooltip: Option<String>,
+    pub icon: Option<IconSource>,
+    pub menu: Vec<MenuEntryConfig>,
+}
+
+/// Where a [`TrayConfig`] gets its icon from.
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IconSource {
+    /// The [placeholder](crate::icon::placeholder) icon.
+    Placeholder,
+    /// An SVG file, relative to the working directory.
+    #[cfg(feature = "svg")]
+    Svg(PathBuf),
+    /// RGBA pixels, four bytes per pixel, row by row.
+    Rgba {
+        width: u32,
+        height: u32,
+        rgba: Vec<u8>,
+    },
+}
+
+/// An entry in a [`TrayConfig`]'s menu.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MenuEntryConfig {
+    Item(MenuItemConfig),
+    Submenu(SubmenuConfig),
+    /// Written as an empty table or object, since TOML has no unit values.
+    Separator {},
+}
+
+/// A clickable menu item. See [`MenuItem`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MenuItemConfig {
+    pub id: String,
+    pub label: String,
+    #[serde(default = "enabled", skip_serializing_if = "is_enabled")]
+    pub enabled: bool,
+    /// Makes the item a checkbox in this state.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checked: Option<bool>,
+    /// Written as a string such as `"CmdOrCtrl+Q"`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accelerator: Option<Accelerator>,
+}
+
+/// A submenu with nested entries. See [`Submenu`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SubmenuConfig {
+    pub label: String,
+    #[serde(default = "enabled", skip_serializing_if = "is_enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub items: Vec<MenuEntryConfig>,
+}
+
+fn enabled() -> bool {
+    true
+}
+
+fn is_enabled(enabled: &bool) -> bool {
+    *enabled
+}
+
+impl TrayConfig {
+    /// The attributes for creating the described tray, with its icon
+    /// loaded.
+    ///
+    /// Fails if the icon file can't be read or the icon is invalid.
+    pub fn into_attributes(self) -> Result<TrayIconAttributes<String>, TrayError> {
+        Ok(TrayIconAttributes {
+            tooltip: self.tooltip,
+            icon: self.icon.map(|icon| icon.load()).transpose()?,
+            context_menu: (!self.menu.is_empty()).then(|| menu_entries(self.menu)),
+            ..Default::default()
+        })
+    }
+}
+
+impl IconSource {
+    /// Read and decode the icon.
+    pub fn load(&self) -> Result<Icon, TrayError> {
+        match self {
+            IconSource::Placeholder => Ok(crate::icon::placeholder()),
+            #[cfg(feature = "svg")]
+            IconSource::Svg(path) => {
+                let data = std::fs::read(path).map_err(|e| {
+                    TrayError::Platform(format!("can't read icon {}: {e}", path.display()))
+                })?;
+                Ok(crate::icon::SvgIcon::from_data(&data)?.into())
+            }
+            IconSource::Rgba {
+                width,
+                height,
+                rgba,
+            } => RgbaIcon::new(rgba.clone(), *width, *height)
+                .map(Icon::from)
+                .map_err(|_| IconError::ConversionFailed.into()),
+        }
+    }
+}
+
+fn menu_entries(entries: Vec<MenuEntryConfig>) -> Vec<MenuEntry<String>> {
+    entries
+        .into_iter()
+        .map(|entry| match entry {
+            MenuEntryConfig::Item(item) => {
+                let mut menu_item = MenuItem::new(item.id, item.label).enabled(item.enabled);
+                menu_item.checked = item.checked;
+                menu_item.accelerator = item.accelerator;
+                MenuEntry::Item(menu_item)
+            }
+            MenuEntryConfig::Submenu(submenu) => MenuEntry::Submenu(
+                Submenu::new(submenu.label, menu_entries(submenu.items)).enabled(submenu.enabled),
+            ),
+            MenuEntryConfig::Separator {} => MenuEntry::Separator,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"{
+        "tooltip": "Sync",
+        "icon": "placeholder",
+        "menu": [
+            { "item": { "id": "pause", "label": "Pause syncing", "checked": false } },
+            { "submenu": { "label": "Open", "items": [
+                { "item": { "id": "folder", "label": "Folder" } },
+                { "item": { "id": "web", "label": "Website", "enabled": false } }
+            ] } },
+            { "separator": {} },
+            { "item": { "id": "quit", "label": "Quit", "accelerator": "Ctrl+Q" } }
+        ]
+    }"#;
+
+    #[test]
+    fn test_config_builds_attributes() {
+        let config: TrayConfig = serde_json::from_str(CONFIG).unwrap();
+        let attributes = config.into_attributes().unwrap();
+
+        assert_eq!(attributes.tooltip.as_deref(), Some("Sync"));
+        assert!(attributes.icon.is_some());
+        let menu = attributes.context_menu.unwrap();
+        assert_eq!(
+            crate::describe_menu(&menu),
+            "Pause syncing [ ]\n\
+             Open (2 items)\n  \
+               Folder\n  \
+               Website (disabled)\n\
+             ---\n\
+             Quit\n"
+        );
+        let MenuEntry::Item(quit) = &menu[3] else {
+            panic!("expected an item");
+        };
+        assert_eq!(quit.id, "quit");
+        assert_eq!(quit.accelerator, Some("Ctrl+Q".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_config_round_trip() {
+        let config: TrayConfig = serde_json::from_str(CONFIG).unwrap();
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(serde_json::from_str::<TrayConfig>(&json).unwrap(), config);
+    }
+
+    #[test]
+    fn test_config_rejects_bad_accelerator() {
+        let config =
+            r#"{ "menu": [ { "item": { "id": "a", "label": "A", "accelerator": "Ctrl+" } } ] }"#;
+        assert!(serde_json::from_str::<TrayConfig>(config).is_err());
+    }
+}
@@ -3,13 +3,30 @@
 use std::fmt;
 
 use rwh_06::HasWindowHandle;
-use winit::dpi::PhysicalPosition;
-use winit::event::WindowEvent;
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+use winit::event::{ButtonSource, ElementState, MouseButton, WindowEvent};
 use winit::event_loop::ActiveEventLoop;
 use winit::window::WindowId;
 
 use crate::{EventCallback, MenuEntry};
 
+/// Where `event` asks for a context menu, if it does.
+///
+/// Matches releasing the right mouse button, which is when menus
+/// conventionally open, and returns the position in the window's client
+/// area, ready for [`ContextMenu::show`].
+pub fn context_menu_position(event: &WindowEvent) -> Option<PhysicalPosition<i32>> {
+    match event {
+        WindowEvent::PointerButton {
+            state: ElementState::Released,
+            button: ButtonSource::Mouse(MouseButton::Right),
+            position,
+            ..
+        } => Some(PhysicalPosition::new(position.x as i32, position.y as i32)),
+        _ => None,
+    }
+}
+
 /// Handle to a live context menu popup.
 ///
 /// Returned by [`MenuRenderer::create_menu`]. The same menu can be shown
@@ -31,6 +48,31 @@ pub trait ContextMenu: fmt::Debug {
     /// the event position is already in screen coordinates.
     fn show_at_screen_pos(&self, position: PhysicalPosition<i32>);
 
+    /// Show the menu anchored to a rectangle in the parent window's client
+    /// area, such as the button that opens it.
+    ///
+    /// `position` is the rectangle's top-left corner. The menu opens below
+    /// the rectangle, aligned with its left edge, and flips above it when
+    /// there isn't room below. The default implementation shows the menu at
+    /// the rectangle's bottom-left corner without flipping.
+    fn show_for_rect(&self, position: PhysicalPosition<i32>, size: PhysicalSize<u32>) {
+        self.show(PhysicalPosition::new(
+            position.x,
+            position.y.saturating_add(size.height as i32),
+        ));
+    }
+
+    /// Show the menu at the current mouse cursor position, letting the
+    /// renderer pick which way it opens so it stays on screen.
+    ///
+    /// Returns `false` without showing anything if the renderer can't read
+    /// the global cursor position. The default implementation does that, so
+    /// renderers that track the pointer themselves should pass its last
+    /// screen position to [`show_at_screen_pos`](Self::show_at_screen_pos).
+    fn show_at_cursor(&self) -> bool {
+        false
+    }
+
     /// Close the menu if it is currently visible.
     ///
     /// Native menus dismiss automatically when an item is selected or the
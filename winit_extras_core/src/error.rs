@@ -0,0 +1,130 @@
+//! Errors reported when tray or menu bar configuration is invalid, or when
+//! the platform refuses to create or update them.
+
+use std::fmt;
+
+use crate::icon::IconError;
+
+/// Why a tray icon or menu bar could not be created or updated.
+///
+/// Returned by [`TrayIconAttributes::validate`][crate::TrayIconAttributes::validate]
+/// and `MenuBarAttributes::validate`, which check what they can before any
+/// platform resources are created, and by the platform backends once they
+/// talk to the OS.
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TrayError {
+    /// The icon can't be shown in the tray.
+    Icon(IconError),
+    /// The Windows window class name is empty.
+    EmptyClassName,
+    /// A menu bar was configured without a parent window on Windows, where
+    /// menu bars belong to a window.
+    MissingParentWindow,
+    /// The parent window handle is not one this platform can use, e.g. a
+    /// non-Win32 handle on Windows.
+    UnsupportedParentWindow,
+    /// Two menu items share an ID, so clicks on them can't be told apart.
+    DuplicateMenuId,
+    /// The call has to be made on the main thread on this platform.
+    NotMainThread,
+    /// The tray has no icon for the change to apply to, e.g. a status dot
+    /// on a tray created without one.
+    NoIcon,
+    /// The tray was created without a context menu, or with a
+    /// platform-native one the crate can't change.
+    NoContextMenu,
+    /// No menu item has the given ID.
+    UnknownMenuId,
+    /// The menu can't be changed while it is open.
+    MenuOpen,
+    /// The named feature isn't available on this platform.
+    Unsupported(&'static str),
+    /// The backend couldn't carry out the request; the message says why.
+    Platform(String),
+}
+
+impl fmt::Display for TrayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrayError::Icon(e) => write!(f, "invalid tray icon: {e}"),
+            TrayError::EmptyClassName => f.write_str("window class name must not be empty"),
+            TrayError::MissingParentWindow => {
+                f.write_str("a parent window is required for menu bars on Windows")
+            }
+            TrayError::UnsupportedParentWindow => {
+                f.write_str("unsupported parent window handle for this platform")
+            }
+            TrayError::DuplicateMenuId => f.write_str("two menu items share an id"),
+            TrayError::NotMainThread => f.write_str("must be called on the main thread"),
+            TrayError::NoIcon => f.write_str("tray has no icon"),
+            TrayError::NoContextMenu => f.write_str("tray has no context menu"),
+            TrayError::UnknownMenuId => f.write_str("no menu item with this id"),
+            TrayError::MenuOpen => f.write_str("can't change the menu while it is open"),
+            TrayError::Unsupported(feature) => {
+                write!(f, "{feature} are not supported on this platform")
+            }
+            TrayError::Platform(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for TrayError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TrayError::Icon(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<IconError> for TrayError {
+    fn from(e: IconError) -> Self {
+        TrayError::Icon(e)
+    }
+}
+
+impl From<std::io::Error> for TrayError {
+    fn from(e: std::io::Error) -> Self {
+        TrayError::Platform(e.to_string())
+    }
+}
+
+/// Error returned when a string doesn't name a variant of one of the
+/// option enums, such as [`StatusColor`][crate::icon::StatusColor].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ParseNameError {
+    kind: &'static str,
+    name: String,
+}
+
+impl ParseNameError {
+    pub(crate) fn new(kind: &'static str, name: &str) -> Self {
+        ParseNameError {
+            kind,
+            name: name.to_owned(),
+        }
+    }
+}
+
+impl fmt::Display for ParseNameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown {} `{}`", self.kind, self.name)
+    }
+}
+
+impl std::error::Error for ParseNameError {}
+
+/// Checks that `parent_window` is a handle this platform can parent to.
+pub(crate) fn check_parent_window(
+    parent_window: Option<&rwh_06::RawWindowHandle>,
+) -> Result<(), TrayError> {
+    match parent_window {
+        #[cfg(target_os = "windows")]
+        Some(rwh_06::RawWindowHandle::Win32(_)) | None => Ok(()),
+        #[cfg(target_os = "windows")]
+        Some(_) => Err(TrayError::UnsupportedParentWindow),
+        #[cfg(not(target_os = "windows"))]
+        _ => Ok(()),
+    }
+}
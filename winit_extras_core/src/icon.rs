@@ -0,0 +1,741 @@
+//! Icons pre-converted to the platform's native pixel format.
+//!
+//! Backends convert every [`Icon`] they receive into the format the OS tray
+//! API expects (BGRA plus an AND mask on Windows, PNG on macOS, big-endian
+//! ARGB32 on Linux). For large icons that conversion is noticeable, and by
+//! default it runs on the UI thread inside `create_tray`. [`PreparedIcon`]
+//! lets applications do that work ahead of time, on any thread.
+//!
+//! Only [`RgbaIcon`] and [`PreparedIcon`] can be converted, plus `SvgIcon`
+//! with the `svg` feature. Other backings, such as a platform icon winit
+//! loaded from a file or resource, can't be read back and are rejected with
+//! [`IconError::UnsupportedFormat`].
+//!
+//! SVG icons are rasterized when they are applied, at the size the tray
+//! draws icons at, and rasterized again when that size changes.
+
+use std::fmt;
+use std::sync::Arc;
+
+use winit::dpi::PhysicalSize;
+use winit::icon::{Icon, IconProvider, RgbaIcon};
+
+/// An icon whose pixels have already been converted for the current platform.
+///
+/// Create one with [`PreparedIcon::prepare`] (which is `Send` and can run on
+/// a worker thread) and turn it back into an [`Icon`] with [`Icon::from`].
+/// Backends recognize prepared icons and skip the conversion step, so the
+/// main-thread part of applying the icon is just a cheap handle creation.
+///
+/// Cloning is cheap: the pixel data is reference counted.
+#[derive(Debug, Clone)]
+pub struct PreparedIcon {
+    width: u32,
+    height: u32,
+    data: Arc<[u8]>,
+    #[cfg(target_os = "windows")]
+    mask: Arc<[u8]>,
+}
+
+/// Why an [`Icon`] could not be used as a tray icon.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IconError {
+    /// The icon is neither an [`RgbaIcon`] nor a [`PreparedIcon`], so its
+    /// pixels can't be read. Build the icon from RGBA data instead.
+    UnsupportedFormat,
+    /// The pixel data could not be converted to the platform's format.
+    ConversionFailed,
+    /// The SVG document could not be parsed.
+    #[cfg(feature = "svg")]
+    InvalidSvg,
+}
+
+impl fmt::Display for IconError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IconError::UnsupportedFormat => {
+                f.write_str("unsupported icon format, expected an RgbaIcon or PreparedIcon")
+            }
+            IconError::ConversionFailed => f.write_str("failed to convert icon pixel data"),
+            #[cfg(feature = "svg")]
+            IconError::InvalidSvg => f.write_str("failed to parse SVG icon"),
+        }
+    }
+}
+
+impl std::error::Error for IconError {}
+
+impl PreparedIcon {
+    /// Convert `icon` into the current platform's native format.
+    ///
+    /// Returns `None` if the icon is neither an [`RgbaIcon`] nor an
+    /// already-prepared icon, or if encoding fails. Use
+    /// [`try_prepare`](Self::try_prepare) to tell the two apart.
+    pub fn prepare(icon: &Icon) -> Option<Self> {
+        Self::try_prepare(icon).ok()
+    }
+
+    /// Convert `icon` into the current platform's native format, reporting
+    /// why it failed.
+    ///
+    /// SVG icons are rasterized at their intrinsic size.
+    pub fn try_prepare(icon: &Icon) -> Result<Self, IconError> {
+        #[cfg(feature = "svg")]
+        if let Some(svg) = icon.0.cast_ref::<SvgIcon>() {
+            return Self::try_prepare_sized(icon, svg.size());
+        }
+        Self::try_prepare_sized(icon, PhysicalSize::new(0, 0))
+    }
+
+    /// Like [`try_prepare`](Self::try_prepare), but rasterizes scalable
+    /// icons at `size`. Bitmap icons are converted at their own size.
+    pub fn try_prepare_sized(icon: &Icon, size: PhysicalSize<u32>) -> Result<Self, IconError> {
+        if let Some(prepared) = icon.0.cast_ref::<PreparedIcon>() {
+            return Ok(prepared.clone());
+        }
+
+        #[cfg(feature = "svg")]
+        if let Some(svg) = icon.0.cast_ref::<SvgIcon>() {
+            let rgba = svg.rasterize(size)?;
+            return Self::from_rgba(rgba.buffer(), rgba.width(), rgba.height())
+                .ok_or(IconError::ConversionFailed);
+        }
+        let _ = size;
+
+        let rgba = icon
+            .0
+            .cast_ref::<RgbaIcon>()
+            .ok_or(IconError::UnsupportedFormat)?;
+        Self::from_rgba(rgba.buffer(), rgba.width(), rgba.height())
+            .ok_or(IconError::ConversionFailed)
+    }
+
+    /// Convert a raw RGBA buffer into the current platform's native format.
+    ///
+    /// `rgba` must contain exactly `width * height` pixels.
+    pub fn from_rgba(rgba: &[u8], width: u32, height: u32) -> Option<Self> {
+        if rgba.len() != width as usize * height as usize * 4 {
+            return None;
+        }
+
+        #[cfg(target_os = "windows")]
+        {
+            let (data, mask) = rgba_to_bgra_with_mask(rgba);
+            Some(PreparedIcon {
+                width,
+                height,
+                data: data.into(),
+                mask: mask.into(),
+            })
+        }
+
+        #[cfg(target_os = "macos")]
+        {
+            let data = rgba_to_png(rgba, width, height)?;
+            Some(PreparedIcon {
+                width,
+                height,
+                data: data.into(),
+            })
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            let data = rgba_to_argb32_be(rgba);
+            Some(PreparedIcon {
+                width,
+                height,
+                data: data.into(),
+            })
+        }
+
+        #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+        {
+            Some(PreparedIcon {
+                width,
+                height,
+                data: rgba.into(),
+            })
+        }
+    }
+
+    /// Width of the icon in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Height of the icon in pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The converted pixel data.
+    ///
+    /// - **Windows:** BGRA pixels, top row first.
+    /// - **macOS:** a PNG-encoded image.
+    /// - **Linux:** ARGB32 pixels in network byte order, as used by
+    ///   StatusNotifierItem pixmaps.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// The AND mask passed to `CreateIcon` alongside [`data`](Self::data).
+    #[cfg(target_os = "windows")]
+    pub fn mask(&self) -> &[u8] {
+        &self.mask
+    }
+}
+
+impl IconProvider for PreparedIcon {}
+
+impl From<PreparedIcon> for Icon {
+    fn from(value: PreparedIcon) -> Self {
+        Icon(Arc::new(value))
+    }
+}
+
+/// Whether `icon` should be rasterized again when the tray's icon size
+/// changes, which is the case for SVG icons.
+pub fn is_scalable(icon: &Icon) -> bool {
+    #[cfg(feature = "svg")]
+    {
+        icon.0.cast_ref::<SvgIcon>().is_some()
+    }
+    #[cfg(not(feature = "svg"))]
+    {
+        let _ = icon;
+        false
+    }
+}
+
+/// Whether `icon` is in a format tray backends can read: an [`RgbaIcon`], a
+/// [`PreparedIcon`] or a scalable icon.
+pub fn is_supported(icon: &Icon) -> bool {
+    icon.0.cast_ref::<RgbaIcon>().is_some()
+        || icon.0.cast_ref::<PreparedIcon>().is_some()
+        || is_scalable(icon)
+}
+
+/// An icon described by an SVG document.
+///
+/// Backends rasterize it at the size the tray draws icons at, and again
+/// whenever that size changes, so it stays sharp at any display scale. Use
+/// [`rasterize`](Self::rasterize) to get an [`RgbaIcon`] for other uses,
+/// such as a window icon.
+///
+/// Text in the document is not rendered; convert it to paths first.
+#[cfg(feature = "svg")]
+#[derive(Debug, Clone)]
+pub struct SvgIcon {
+    tree: Arc<resvg::usvg::Tree>,
+}
+
+#[cfg(feature = "svg")]
+impl SvgIcon {
+    /// Parse an SVG document from its bytes. Gzip-compressed (`.svgz`) data
+    /// is accepted as well.
+    pub fn from_data(data: &[u8]) -> Result<Self, IconError> {
+        let tree = resvg::usvg::Tree::from_data(data, &resvg::usvg::Options::default())
+            .map_err(|_| IconError::InvalidSvg)?;
+        Ok(SvgIcon {
+            tree: Arc::new(tree),
+        })
+    }
+
+    /// Parse an SVG document from a string.
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(svg: &str) -> Result<Self, IconError> {
+        Self::from_data(svg.as_bytes())
+    }
+
+    /// The size the document declares, rounded up to whole pixels.
+    pub fn size(&self) -> PhysicalSize<u32> {
+        let size = self.tree.size();
+        PhysicalSize::new(size.width().ceil() as u32, size.height().ceil() as u32)
+    }
+
+    /// Render the icon into a `size` bitmap.
+    ///
+    /// The document keeps its aspect ratio and is centered if it doesn't
+    /// fill `size` exactly.
+    pub fn rasterize(&self, size: PhysicalSize<u32>) -> Result<RgbaIcon, IconError> {
+        use resvg::tiny_skia::{Pixmap, Transform};
+
+        let mut pixmap = Pixmap::new(size.width, size.height).ok_or(IconError::ConversionFailed)?;
+
+        let svg_size = self.tree.size();
+        let scale =
+            (size.width as f32 / svg_size.width()).min(size.height as f32 / svg_size.height());
+        let dx = (size.width as f32 - svg_size.width() * scale) / 2.0;
+        let dy = (size.height as f32 - svg_size.height() * scale) / 2.0;
+        let transform = Transform::from_scale(scale, scale).post_translate(dx, dy);
+        resvg::render(&self.tree, transform, &mut pixmap.as_mut());
+
+        // tiny-skia works in premultiplied alpha; icons are straight RGBA.
+        let rgba = pixmap
+            .pixels()
+            .iter()
+            .flat_map(|pixel| {
+                let color = pixel.demultiply();
+                [color.red(), color.green(), color.blue(), color.alpha()]
+            })
+            .collect();
+        RgbaIcon::new(rgba, size.width, size.height).map_err(|_| IconError::ConversionFailed)
+    }
+}
+
+#[cfg(feature = "svg")]
+impl IconProvider for SvgIcon {}
+
+#[cfg(feature = "svg")]
+impl From<SvgIcon> for Icon {
+    fn from(value: SvgIcon) -> Self {
+        Icon(Arc::new(value))
+    }
+}
+
+/// Color of a status dot drawn over a tray icon with [`with_status_dot`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StatusColor {
+    Green,
+    Yellow,
+    Red,
+    Gray,
+    /// Any other color, as red, green and blue components.
+    Rgb(u8, u8, u8),
+}
+
+impl StatusColor {
+    /// The color's red, green and blue components.
+    pub const fn rgb(self) -> [u8; 3] {
+        match self {
+            StatusColor::Green => [52, 199, 89],
+            StatusColor::Yellow => [255, 204, 0],
+            StatusColor::Red => [255, 59, 48],
+            StatusColor::Gray => [142, 142, 147],
+            StatusColor::Rgb(r, g, b) => [r, g, b],
+        }
+    }
+}
+
+/// Named colors are written in lowercase, others as `#rrggbb`.
+impl std::fmt::Display for StatusColor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StatusColor::Green => f.write_str("green"),
+            StatusColor::Yellow => f.write_str("yellow"),
+            StatusColor::Red => f.write_str("red"),
+            StatusColor::Gray => f.write_str("gray"),
+            StatusColor::Rgb(r, g, b) => write!(f, "#{r:02x}{g:02x}{b:02x}"),
+        }
+    }
+}
+
+impl std::str::FromStr for StatusColor {
+    type Err = crate::ParseNameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = |digits: &str| u8::from_str_radix(digits, 16).ok();
+        match s {
+            "green" => Ok(StatusColor::Green),
+            "yellow" => Ok(StatusColor::Yellow),
+            "red" => Ok(StatusColor::Red),
+            "gray" => Ok(StatusColor::Gray),
+            _ => s
+                .strip_prefix('#')
+                .filter(|digits| digits.len() == 6 && digits.bytes().all(|b| b.is_ascii_hexdigit()))
+                .and_then(|digits| {
+                    Some(StatusColor::Rgb(
+                        hex(&digits[0..2])?,
+                        hex(&digits[2..4])?,
+                        hex(&digits[4..6])?,
+                    ))
+                })
+                .ok_or_else(|| crate::ParseNameError::new("status color", s)),
+        }
+    }
+}
+
+/// Returns a copy of `icon` with a `color` dot in its bottom-right corner.
+///
+/// A thin transparent ring separates the dot from the icon so it stays
+/// visible on icons of a similar color. Scalable icons are rasterized at
+/// `size` first; bitmap icons keep their own size. Fails with
+/// [`IconError::UnsupportedFormat`] for a [`PreparedIcon`], whose original
+/// pixels are no longer available.
+pub fn with_status_dot(
+    icon: &Icon,
+    color: StatusColor,
+    size: PhysicalSize<u32>,
+) -> Result<Icon, IconError> {
+    let (mut pixels, width, height) = rgba_pixels(icon, size)?;
+    draw_status_dot(&mut pixels, width, height, color.rgb());
+    RgbaIcon::new(pixels, width, height)
+        .map(Icon::from)
+        .map_err(|_| IconError::ConversionFailed)
+}
+
+/// A plain gray square, for trays whose own icon can't be shown.
+///
+/// Used by [`TrayIconAttributes::with_placeholder_icon`][crate::TrayIconAttributes::with_placeholder_icon]
+/// so a bad icon doesn't leave the tray invisible.
+pub fn placeholder() -> Icon {
+    const SIZE: u32 = 32;
+    const MARGIN: u32 = 4;
+    let [r, g, b] = StatusColor::Gray.rgb();
+    let mut pixels = vec![0; (SIZE * SIZE * 4) as usize];
+    for y in MARGIN..SIZE - MARGIN {
+        for x in MARGIN..SIZE - MARGIN {
+            let index = ((y * SIZE + x) * 4) as usize;
+            pixels[index..index + 4].copy_from_slice(&[r, g, b, u8::MAX]);
+        }
+    }
+    Icon::from(RgbaIcon::new(pixels, SIZE, SIZE).expect("placeholder size is valid"))
+}
+
+/// Returns a copy of `icon` at half its opacity, the way disabled controls
+/// are drawn.
+///
+/// Accepts the same icons as [`with_status_dot`], so the two can be
+/// combined by dimming an icon that already has a dot.
+pub fn dimmed(icon: &Icon, size: PhysicalSize<u32>) -> Result<Icon, IconError> {
+    let (mut pixels, width, height) = rgba_pixels(icon, size)?;
+    for pixel in pixels.chunks_exact_mut(4) {
+        pixel[3] /= 2;
+    }
+    RgbaIcon::new(pixels, width, height)
+        .map(Icon::from)
+        .map_err(|_| IconError::ConversionFailed)
+}
+
+/// A copy of the straight-alpha RGBA pixels of `icon`, with its width and
+/// height. Scalable icons are rasterized at `size`.
+fn rgba_pixels(icon: &Icon, size: PhysicalSize<u32>) -> Result<(Vec<u8>, u32, u32), IconError> {
+    #[cfg(feature = "svg")]
+    let rasterized = match icon.0.cast_ref::<SvgIcon>() {
+        Some(svg) => Some(svg.rasterize(size)?),
+        None => None,
+    };
+    #[cfg(not(feature = "svg"))]
+    let rasterized: Option<RgbaIcon> = None;
+    let _ = size;
+
+    let rgba = match &rasterized {
+        Some(rgba) => rgba,
+        None => icon
+            .0
+            .cast_ref::<RgbaIcon>()
+            .ok_or(IconError::UnsupportedFormat)?,
+    };
+
+    Ok((rgba.buffer().to_vec(), rgba.width(), rgba.height()))
+}
+
+/// Draws an anti-aliased dot into the bottom-right corner of straight-alpha
+/// RGBA pixels, clearing a ring around it.
+fn draw_status_dot(rgba: &mut [u8], width: u32, height: u32, color: [u8; 3]) {
+    let radius = (width.min(height) as f32 * 0.2).max(1.5);
+    let gap = (radius / 4.0).max(1.0);
+    let (cx, cy) = (width as f32 - radius, height as f32 - radius);
+
+    let reach = radius + gap + 1.0;
+    let x_start = (cx - reach).max(0.0) as u32;
+    let y_start = (cy - reach).max(0.0) as u32;
+
+    for y in y_start..height {
+        for x in x_start..width {
+            let distance = (x as f32 + 0.5 - cx).hypot(y as f32 + 0.5 - cy);
+            let cleared = (radius + gap - distance + 0.5).clamp(0.0, 1.0);
+            let dot = (radius - distance + 0.5).clamp(0.0, 1.0);
+            if cleared == 0.0 {
+                continue;
+            }
+
+            let pixel = &mut rgba[(y * width + x) as usize * 4..][..4];
+            let base_alpha = pixel[3] as f32 / 255.0 * (1.0 - cleared);
+            let alpha = dot + base_alpha * (1.0 - dot);
+            if alpha > 0.0 {
+                for (channel, dot_channel) in pixel[..3].iter_mut().zip(color) {
+                    let value = (dot_channel as f32 * dot
+                        + *channel as f32 * base_alpha * (1.0 - dot))
+                        / alpha;
+                    *channel = value.round() as u8;
+                }
+            }
+            pixel[3] = (alpha * 255.0).round() as u8;
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn rgba_to_bgra_with_mask(rgba: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    let mut bgra = Vec::with_capacity(rgba.len());
+    let mut mask = Vec::with_capacity(rgba.len() / 4);
+
+    for pixel in rgba.chunks_exact(4) {
+        let (r, g, b, a) = (pixel[0], pixel[1], pixel[2], pixel[3]);
+        mask.push(a.wrapping_sub(u8::MAX));
+        bgra.extend_from_slice(&[b, g, r, a]);
+    }
+
+    (bgra, mask)
+}
+
+#[cfg(target_os = "macos")]
+fn rgba_to_png(rgba: &[u8], width: u32, height: u32) -> Option<Vec<u8>> {
+    use std::io::Cursor;
+
+    let mut png = Vec::new();
+
+    {
+        let mut encoder = png::Encoder::new(Cursor::new(&mut png), width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header().ok()?;
+        writer.write_image_data(rgba).ok()?;
+    }
+
+    Some(png)
+}
+
+#[cfg(target_os = "linux")]
+fn rgba_to_argb32_be(rgba: &[u8]) -> Vec<u8> {
+    let mut argb = Vec::with_capacity(rgba.len());
+
+    for pixel in rgba.chunks_exact(4) {
+        let (r, g, b, a) = (pixel[0], pixel[1], pixel[2], pixel[3]);
+        argb.extend_from_slice(&[a, r, g, b]);
+    }
+
+    argb
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prepare_off_thread_and_reuse() {
+        let rgba = RgbaIcon::new(vec![255, 0, 0, 255, 0, 255, 0, 128], 2, 1).unwrap();
+        let icon = Icon::from(rgba);
+
+        let prepared = std::thread::spawn(move || PreparedIcon::prepare(&icon))
+            .join()
+            .unwrap()
+            .unwrap();
+        assert_eq!((prepared.width(), prepared.height()), (2, 1));
+
+        // Preparing an already-prepared icon reuses the converted data.
+        let icon = Icon::from(prepared.clone());
+        let again = PreparedIcon::prepare(&icon).unwrap();
+        assert!(Arc::ptr_eq(&prepared.data, &again.data));
+    }
+
+    #[derive(Debug)]
+    struct FileIcon;
+
+    impl IconProvider for FileIcon {}
+
+    #[test]
+    fn test_try_prepare_rejects_unknown_icon() {
+        let icon = Icon(Arc::new(FileIcon));
+        assert_eq!(
+            PreparedIcon::try_prepare(&icon).unwrap_err(),
+            IconError::UnsupportedFormat
+        );
+        assert!(PreparedIcon::prepare(&icon).is_none());
+        assert!(!is_supported(&icon));
+    }
+
+    #[test]
+    fn test_status_dot_in_bottom_right_corner() {
+        let white = RgbaIcon::new([255; 16 * 16 * 4].to_vec(), 16, 16).unwrap();
+        let icon = with_status_dot(
+            &Icon::from(white),
+            StatusColor::Red,
+            PhysicalSize::new(0, 0),
+        )
+        .unwrap();
+        let rgba = icon.0.cast_ref::<RgbaIcon>().unwrap();
+        assert_eq!((rgba.width(), rgba.height()), (16, 16));
+
+        let pixel = |x: usize, y: usize| &rgba.buffer()[(y * 16 + x) * 4..][..4];
+        assert_eq!(pixel(13, 13), [255, 59, 48, 255]);
+        assert_eq!(pixel(0, 0), [255; 4]);
+        // The ring between the dot and the icon lets the background through.
+        assert!(pixel(8, 13)[3] < 255);
+
+        let prepared = Icon::from(PreparedIcon::from_rgba(&[0; 4], 1, 1).unwrap());
+        assert_eq!(
+            with_status_dot(&prepared, StatusColor::Green, PhysicalSize::new(0, 0)).unwrap_err(),
+            IconError::UnsupportedFormat
+        );
+    }
+
+    /// Deterministic pseudo-random RGBA buffers (xorshift32), so failures
+    /// reproduce without a property testing dependency.
+    fn random_buffers() -> impl Iterator<Item = Vec<u8>> {
+        let mut state = 0x9E37_79B9u32;
+        (1..=64).map(move |pixels| {
+            (0..pixels * 4)
+                .map(|_| {
+                    state ^= state << 13;
+                    state ^= state >> 17;
+                    state ^= state << 5;
+                    state as u8
+                })
+                .collect()
+        })
+    }
+
+    /// One opaque pixel of each primary, then a transparent one.
+    const PRIMARIES: [u8; 16] = [
+        255, 0, 0, 255, // red
+        0, 255, 0, 255, // green
+        0, 0, 255, 255, // blue
+        0, 0, 0, 0, // transparent
+    ];
+
+    #[test]
+    fn test_from_rgba_rejects_wrong_length() {
+        assert!(PreparedIcon::from_rgba(&[0; 7], 1, 2).is_none());
+        assert!(PreparedIcon::from_rgba(&[0; 12], 2, 2).is_none());
+        assert!(PreparedIcon::from_rgba(&PRIMARIES, 2, 2).is_some());
+    }
+
+    #[cfg(target_os = "windows")]
+    #[test]
+    fn test_bgra_primaries() {
+        let (bgra, mask) = rgba_to_bgra_with_mask(&PRIMARIES);
+        assert_eq!(
+            bgra,
+            [0, 0, 255, 255, 0, 255, 0, 255, 255, 0, 0, 255, 0, 0, 0, 0]
+        );
+        assert_eq!(mask[..3], [0, 0, 0]);
+        assert_ne!(mask[3], 0);
+    }
+
+    #[cfg(target_os = "windows")]
+    #[test]
+    fn test_bgra_round_trips() {
+        for rgba in random_buffers() {
+            let (bgra, mask) = rgba_to_bgra_with_mask(&rgba);
+            assert_eq!(bgra.len(), rgba.len());
+            assert_eq!(mask.len(), rgba.len() / 4);
+            for ((rgba, bgra), mask) in rgba.chunks_exact(4).zip(bgra.chunks_exact(4)).zip(&mask) {
+                assert_eq!([bgra[2], bgra[1], bgra[0], bgra[3]], rgba);
+                assert_eq!(*mask == 0, rgba[3] == u8::MAX);
+            }
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_argb32_be_primaries() {
+        let argb = rgba_to_argb32_be(&PRIMARIES);
+        let words: Vec<u32> = argb
+            .chunks_exact(4)
+            .map(|bytes| u32::from_be_bytes(bytes.try_into().unwrap()))
+            .collect();
+        assert_eq!(words, [0xFFFF_0000, 0xFF00_FF00, 0xFF00_00FF, 0]);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_argb32_be_round_trips() {
+        for rgba in random_buffers() {
+            let argb = rgba_to_argb32_be(&rgba);
+            assert_eq!(argb.len(), rgba.len());
+            for (rgba, argb) in rgba.chunks_exact(4).zip(argb.chunks_exact(4)) {
+                let [r, g, b, a] = rgba.try_into().unwrap();
+                let word = u32::from_be_bytes(argb.try_into().unwrap());
+                assert_eq!(word, u32::from_be_bytes([a, r, g, b]));
+            }
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    #[test]
+    fn test_png_round_trips() {
+        for rgba in random_buffers().chain([PRIMARIES.to_vec()]) {
+            let width = (rgba.len() / 4) as u32;
+            let png = rgba_to_png(&rgba, width, 1).unwrap();
+            let mut reader = png::Decoder::new(std::io::Cursor::new(png))
+                .read_info()
+                .unwrap();
+            let mut decoded = vec![0; reader.output_buffer_size()];
+            let info = reader.next_frame(&mut decoded).unwrap();
+            assert_eq!(info.color_type, png::ColorType::Rgba);
+            assert_eq!(&decoded[..info.buffer_size()], rgba);
+        }
+    }
+
+    #[test]
+    fn test_placeholder_is_supported() {
+        let icon = placeholder();
+        assert!(is_supported(&icon));
+        let rgba = icon.0.cast_ref::<RgbaIcon>().unwrap();
+        assert_eq!((rgba.width(), rgba.height()), (32, 32));
+        assert_eq!(rgba.buffer()[3], 0);
+    }
+
+    #[test]
+    fn test_dimmed_halves_opacity() {
+        let rgba = RgbaIcon::new(vec![255, 0, 0, 255, 0, 255, 0, 0], 2, 1).unwrap();
+        let icon = dimmed(&Icon::from(rgba), PhysicalSize::new(0, 0)).unwrap();
+        let rgba = icon.0.cast_ref::<RgbaIcon>().unwrap();
+        assert_eq!(rgba.buffer(), [255, 0, 0, 127, 0, 255, 0, 0]);
+    }
+
+    #[cfg(feature = "svg")]
+    #[test]
+    fn test_svg_rasterizes_at_requested_size() {
+        let svg = SvgIcon::from_str(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="8" height="4">
+                <rect width="8" height="4" fill="red"/>
+            </svg>"#,
+        )
+        .unwrap();
+        assert_eq!(svg.size(), PhysicalSize::new(8, 4));
+
+        // Letterboxed: the 2:1 document fills the middle half of a square.
+        let rgba = svg.rasterize(PhysicalSize::new(16, 16)).unwrap();
+        let pixel = |x: usize, y: usize| &rgba.buffer()[(y * 16 + x) * 4..][..4];
+        assert_eq!(pixel(8, 8), [255, 0, 0, 255]);
+        assert_eq!(pixel(8, 1)[3], 0);
+
+        let icon = Icon::from(svg);
+        assert!(is_scalable(&icon));
+        let prepared = PreparedIcon::try_prepare_sized(&icon, PhysicalSize::new(32, 32)).unwrap();
+        assert_eq!((prepared.width(), prepared.height()), (32, 32));
+    }
+
+    #[test]
+    fn test_status_color_names() {
+        for color in [
+            StatusColor::Green,
+            StatusColor::Gray,
+            StatusColor::Rgb(0x12, 0xab, 0xff),
+        ] {
+            assert_eq!(color.to_string().parse(), Ok(color));
+        }
+        assert_eq!("#12abff".parse(), Ok(StatusColor::Rgb(0x12, 0xab, 0xff)));
+        for invalid in ["Green", "#12ab", "#12abfg", "#1€345", "#+1+2+3"] {
+            assert_eq!(
+                invalid.parse::<StatusColor>().unwrap_err().to_string(),
+                format!("unknown status color `{invalid}`")
+            );
+        }
+    }
+
+    #[cfg(feature = "svg")]
+    #[test]
+    fn test_invalid_svg_is_rejected() {
+        assert_eq!(
+            SvgIcon::from_str("not an svg").unwrap_err(),
+            IconError::InvalidSvg
+        );
+    }
+}
@@ -4,11 +4,41 @@
 //! traits, and the renderer factory traits (`TrayIconRenderer`, `MenuRenderer`)
 //! that platform crates implement.
 
-use winit::{
-    dpi::PhysicalPosition,
-    event::{ButtonSource, ElementState},
-    icon::Icon,
-};
+use std::time::{Duration, Instant};
+
+use winit::icon::Icon;
+use winit::window::WindowAttributes;
+
+/// Implements `Display` and `FromStr` for a fieldless enum, with the name
+/// each variant is written as, so it can be read from configuration.
+macro_rules! variant_names {
+    ($ty:ty, $kind:literal, { $($variant:path => $name:literal),+ $(,)? }) => {
+        impl std::fmt::Display for $ty {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(match self {
+                    $($variant => $name,)+
+                })
+            }
+        }
+
+        impl std::str::FromStr for $ty {
+            type Err = $crate::error::ParseNameError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                match s {
+                    $($name => Ok($variant),)+
+                    _ => Err($crate::error::ParseNameError::new($kind, s)),
+                }
+            }
+        }
+    };
+}
+
+// Types used by `Event`, `PrimaryAction` and `Accelerator`, re-exported
+// regardless of features so applications use them through one path.
+pub use winit::dpi::{PhysicalPosition, PhysicalSize};
+pub use winit::event::{ButtonSource, ElementState, MouseButton};
+pub use winit::keyboard::{KeyCode, ModifiersState};
 
 #[cfg(feature = "menu")]
 pub mod menu;
@@ -21,8 +51,23 @@ pub mod context_menu;
 #[cfg(feature = "menu_bar")]
 pub mod menu_bar;
 
+#[cfg(feature = "serde")]
+pub mod config;
+#[cfg(feature = "serde")]
+pub use config::TrayConfig;
+
+pub mod accelerator;
+pub use accelerator::{Accelerator, AcceleratorParseError};
+
+pub mod error;
+pub use error::{ParseNameError, TrayError};
+
+pub mod icon;
 pub mod tray_icon_id;
 
+#[cfg(target_os = "linux")]
+pub use zvariant;
+
 /// Events produced by tray icon clicks and context menu selections.
 ///
 /// Delivered through the [`Manager`][`winit_extras::Manager`]'s event channel.
@@ -38,11 +83,152 @@ pub enum Event<T = ()> {
         state: ElementState,
         position: PhysicalPosition<f64>,
         button: ButtonSource,
+        /// When the OS registered the input.
+        ///
+        /// Taken from the native event where one is available (the message
+        /// time on Windows, `NSEvent.timestamp` on macOS) and otherwise from
+        /// when the backend received it, as on Linux where the D-Bus call
+        /// carries no time. Use it for click timing such as double-click
+        /// detection instead of the time the event reaches the app.
+        timestamp: Instant,
+    },
+
+    /// A tray icon was double-clicked.
+    ///
+    /// Sent after the events for the clicks themselves, so apps that only
+    /// act on double-clicks can ignore [`Event::PointerButton`]. The
+    /// `position` is in screen coordinates.
+    ///
+    /// # Platform-specific
+    ///
+    /// - **Windows:** decoded from the shell's double-click messages, which
+    ///   replace the second press, so that press isn't reported as a
+    ///   `PointerButton`.
+    /// - **macOS:** sent on the second press, as `NSEvent.clickCount`
+    ///   reports it.
+    /// - **Linux:** tray hosts don't report double-clicks, so two
+    ///   activations within 400 ms count as a left double-click.
+    DoubleClick {
+        tray_icon_id: tray_icon_id::TrayIconId,
+        button: MouseButton,
+        position: PhysicalPosition<f64>,
     },
 
     /// A menu item was clicked. Fires for both tray-triggered menus and
     /// programmatically-shown context menus.
-    MenuItemClicked { id: T },
+    MenuItemClicked {
+        id: T,
+        /// The item's check state after the click, or `None` if it is not
+        /// checkable.
+        ///
+        /// Clicking a checkable item flips its state. Tray menus and context
+        /// menus keep the new state for the next time they are shown; store
+        /// it if the app needs it across restarts or rebuilds its menus.
+        checked: Option<bool>,
+        /// When the OS registered the selection, with the same sources as
+        /// the `timestamp` of [`Event::PointerButton`].
+        timestamp: Instant,
+    },
+
+    /// The size the system draws tray icons at has changed, for example
+    /// after the display scale or the menu bar height changed.
+    ///
+    /// `size` is the new icon size in physical pixels. Recreate the tray
+    /// with an icon rendered at that size to keep it sharp. Emitted on
+    /// Windows and macOS; Linux tray hosts scale icons themselves.
+    IconSizeChanged {
+        tray_icon_id: tray_icon_id::TrayIconId,
+        size: PhysicalSize<u32>,
+    },
+
+    /// The user scrolled over a tray icon.
+    ///
+    /// `delta` is passed on as the tray host reports it, with its sign
+    /// giving the direction. `position` is the last known pointer position,
+    /// since the host doesn't send one with the scroll. Emitted on Linux;
+    /// the Windows shell and macOS don't forward wheel input to tray icons.
+    Scroll {
+        tray_icon_id: tray_icon_id::TrayIconId,
+        delta: f64,
+        orientation: ScrollOrientation,
+        position: PhysicalPosition<f64>,
+    },
+
+    /// The user clicked a notification shown with
+    /// [`TrayIcon::show_notification`].
+    ///
+    /// Emitted on Windows, where the shell reports clicks on the balloon or
+    /// toast, but not when it times out or is dismissed.
+    NotificationClicked {
+        tray_icon_id: tray_icon_id::TrayIconId,
+    },
+
+    /// The last live tray created by the manager was dropped.
+    ///
+    /// Lets apps quit, or show a fallback, once their icon is gone without
+    /// counting trays themselves. Sent each time the count drops to zero,
+    /// so creating a tray afterwards and dropping it sends it again. Only
+    /// trays made with `Manager::create_tray` are counted.
+    AllTraysRemoved,
+}
+
+impl<T> Event<T> {
+    /// Whether this build can deliver [`Event::MenuItemClicked`], which
+    /// needs the `menu` feature.
+    ///
+    /// Lets code that is generic over the feature set skip menu handling
+    /// without a `#[cfg]` of its own.
+    pub const SUPPORTS_MENU_EVENTS: bool = cfg!(feature = "menu");
+
+    /// The tray the event came from, if it belongs to one.
+    ///
+    /// `None` for menu clicks, which may come from a context menu, and for
+    /// [`Event::AllTraysRemoved`].
+    pub fn tray_icon_id(&self) -> Option<tray_icon_id::TrayIconId> {
+        match self {
+            Event::PointerButton { tray_icon_id, .. }
+            | Event::DoubleClick { tray_icon_id, .. }
+            | Event::IconSizeChanged { tray_icon_id, .. }
+            | Event::Scroll { tray_icon_id, .. }
+            | Event::NotificationClicked { tray_icon_id } => Some(*tray_icon_id),
+            Event::MenuItemClicked { .. } | Event::AllTraysRemoved => None,
+        }
+    }
+
+    /// Whether this is an [`Event::MenuItemClicked`].
+    pub fn is_menu_event(&self) -> bool {
+        matches!(self, Event::MenuItemClicked { .. })
+    }
+}
+
+/// The direction of an [`Event::Scroll`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ScrollOrientation {
+    Horizontal,
+    Vertical,
+}
+
+variant_names!(ScrollOrientation, "scroll orientation", {
+    ScrollOrientation::Horizontal => "horizontal",
+    ScrollOrientation::Vertical => "vertical",
+});
+
+/// The icon shown in a notification from
+/// [`TrayIcon::show_notification`].
+#[derive(Debug, Clone, Default)]
+pub enum NotificationIcon {
+    /// No icon.
+    #[default]
+    None,
+    /// The system's information icon.
+    Info,
+    /// The system's warning icon.
+    Warning,
+    /// The system's error icon.
+    Error,
+    /// An icon of the app's own.
+    Custom(Icon),
 }
 
 /// Shared callback used by platform backends to deliver [`Event`]s.
@@ -53,10 +239,165 @@ pub type EventCallback<T = ()> = std::sync::Arc<dyn Fn(Event<T>) + Send + Sync>;
 
 /// Handle to a live tray icon.
 ///
-/// Dropping the handle removes the icon from the system tray.
-pub trait TrayIcon: std::fmt::Debug {
+/// Dropping the handle removes the icon from the system tray. `T` is the
+/// menu item ID type, as in [`TrayIconAttributes`].
+pub trait TrayIcon<T = ()>: std::fmt::Debug {
     /// Returns the unique ID for this tray icon.
     fn id(&self) -> tray_icon_id::TrayIconId;
+
+    /// Whether the icon is tucked away in the notification area's overflow
+    /// flyout rather than shown on the taskbar.
+    ///
+    /// Users decide which icons are pinned, so an app can only use this to
+    /// point them at the setting when its icon is hidden. Only Windows can
+    /// tell; other platforms and backends return `None`, as does Windows
+    /// when the shell can't report the icon's position.
+    fn is_in_overflow(&self) -> Option<bool> {
+        None
+    }
+
+    /// Screen position of the most recent pointer event on the icon.
+    ///
+    /// A cheap way to place a popup "where the tray is" without asking the
+    /// platform for the icon's geometry. `None` until the icon is first
+    /// clicked, and on backends that don't report click positions.
+    fn last_pointer_position(&self) -> Option<PhysicalPosition<f64>> {
+        None
+    }
+
+    /// Change the text shown when hovering over the tray icon.
+    ///
+    /// An empty string and `None` both clear the tooltip. Fails if the
+    /// backend can't change it.
+    fn set_tooltip(&self, tooltip: Option<&str>) -> Result<(), TrayError> {
+        let _ = tooltip;
+        Err(TrayError::Unsupported("tooltip changes"))
+    }
+
+    /// Show `tooltip` for `duration`, then go back to the tooltip last set
+    /// with [`set_tooltip`](Self::set_tooltip) or the attributes.
+    ///
+    /// For transient status such as "Syncing... 50%". Flashing again before
+    /// the revert replaces the text and restarts the timer, and
+    /// `set_tooltip` ends the flash straight away. The revert is scheduled
+    /// by the backend, so the app doesn't have to run a timer of its own.
+    fn flash_tooltip(&self, tooltip: &str, duration: Duration) -> Result<(), TrayError> {
+        let _ = (tooltip, duration);
+        Err(TrayError::Unsupported("flashed tooltips"))
+    }
+
+    /// Show a notification from the tray, such as "Download complete".
+    ///
+    /// On Windows the shell shows it as a balloon or, since Windows 10, as a
+    /// toast, and reports a click on it as [`Event::NotificationClicked`].
+    /// `title` and `body` are cut to the 63 and 255 UTF-16 units the shell
+    /// accepts, and a notification with an empty body is not shown. Fails
+    /// on other platforms, or if the backend can't show notifications.
+    fn show_notification(
+        &self,
+        title: &str,
+        body: &str,
+        icon: NotificationIcon,
+    ) -> Result<(), TrayError> {
+        let _ = (title, body, icon);
+        Err(TrayError::Unsupported("notifications"))
+    }
+
+    /// Replace the tray's icon, for example to reflect an unread count or
+    /// the connection state.
+    ///
+    /// A status dot or dimming set on the tray is drawn over the new icon
+    /// too, and hover and pressed icons are kept. `None` removes the icon
+    /// where the platform allows: macOS shows an empty status item, or only
+    /// its title, while Windows falls back to the application icon as the
+    /// notification area can't show an icon without an image. Fails if the
+    /// icon can't be drawn or the backend can't change it.
+    fn set_icon(&self, icon: Option<&Icon>) -> Result<(), TrayError> {
+        let _ = icon;
+        Err(TrayError::Unsupported("icon changes"))
+    }
+
+    /// Draw a colored dot in the corner of the icon, or remove it with
+    /// `None`.
+    ///
+    /// The dot is drawn over the tray's icon, which must be an `RgbaIcon` or
+    /// a scalable icon (see [`with_status_dot`][icon::with_status_dot]). On
+    /// macOS the icon is shown in full color while a dot is set, since a
+    /// template image would tint the dot too. Fails if the tray has no icon
+    /// or the backend can't change it.
+    fn set_status_dot(&self, color: Option<icon::StatusColor>) -> Result<(), TrayError> {
+        let _ = color;
+        Err(TrayError::Unsupported("status dots"))
+    }
+
+    /// Make the tray ignore clicks while `interactive` is `false`, for
+    /// example during a blocking operation, instead of hiding it.
+    ///
+    /// While non-interactive, clicks produce no [`Event::PointerButton`] and
+    /// don't open the context menu, and the icon is drawn dimmed: by AppKit
+    /// on macOS, and at half opacity (see [`icon::dimmed`]) on Windows. Linux
+    /// tray hosts can't be asked to dim the icon, so there only clicks are
+    /// ignored. Fails if the backend can't ignore clicks.
+    fn set_interactive(&self, interactive: bool) -> Result<(), TrayError> {
+        let _ = interactive;
+        Err(TrayError::Unsupported("non-interactive trays"))
+    }
+
+    /// Change the label of the context menu item with the given `id`.
+    ///
+    /// Updates the live menu in place, so labels like "Pause" / "Resume"
+    /// can follow app state without recreating the tray. Fails if no item
+    /// has this ID or the backend can't change labels.
+    #[cfg(feature = "menu")]
+    fn set_label(&self, id: &T, label: &str) -> Result<(), TrayError>
+    where
+        T: PartialEq,
+    {
+        let _ = (id, label);
+        Err(TrayError::Unsupported("menu label changes"))
+    }
+
+    /// Replace the tray's context menu with `menu`.
+    ///
+    /// For menus whose structure follows app state, such as a "recent
+    /// files" submenu; [`set_label`](Self::set_label) is enough to retitle
+    /// an item. Clicks report the new items' IDs from then on. On macOS it
+    /// must be called on the main thread, and on Windows not while the menu
+    /// is open. Fails if the tray was created without a context menu or
+    /// with a platform-native one, or the backend can't change menus.
+    #[cfg(feature = "menu")]
+    fn set_menu(&self, menu: Vec<MenuEntry<T>>) -> Result<(), TrayError> {
+        let _ = menu;
+        Err(TrayError::Unsupported("menu changes"))
+    }
+
+    /// Change or remove the icon of the context menu item with the given
+    /// `id`.
+    ///
+    /// Like [`set_label`](Self::set_label), this updates the live menu.
+    /// Fails if no item has this ID or the backend can't change menu icons.
+    #[cfg(feature = "menu")]
+    fn set_item_icon(&self, id: &T, icon: Option<Icon>) -> Result<(), TrayError>
+    where
+        T: PartialEq,
+    {
+        let _ = (id, icon);
+        Err(TrayError::Unsupported("menu item icons"))
+    }
+
+    /// Open the tray's context menu as if the user had clicked the icon,
+    /// for example from a global hotkey.
+    ///
+    /// On Windows the menu opens at the icon, or at the cursor if the icon
+    /// is in the overflow flyout, once the tray's window handles the
+    /// request. On macOS it opens below the status item straight away and
+    /// blocks until it closes, so call it from the main thread. Linux tray
+    /// hosts show menus themselves and offer no way to open one, so it
+    /// fails there. Also fails if the tray has no context menu.
+    #[cfg(feature = "menu")]
+    fn open_menu(&self) -> Result<(), TrayError> {
+        Err(TrayError::Unsupported("menus opened from code"))
+    }
 }
 
 /// Factory trait for creating tray icons.
@@ -71,15 +412,160 @@ pub trait TrayIconRenderer<T: Clone + Send + Sync + 'static> {
     /// and interaction event produced by the tray icon.
     fn create_tray(
         &self,
-        attributes: TrayIconAttributes,
+        attributes: TrayIconAttributes<T>,
         proxy: EventCallback<T>,
-    ) -> Result<Box<dyn TrayIcon>, Box<dyn std::error::Error + Send + Sync>>;
+    ) -> Result<Box<dyn TrayIcon<T>>, Box<dyn std::error::Error + Send + Sync>>;
+}
+
+/// What a primary (left) click on a tray icon does.
+#[cfg(feature = "menu")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[non_exhaustive]
+pub enum PrimaryAction {
+    /// Only deliver the click as an [`Event::PointerButton`].
+    ///
+    /// This is the usual "left-click opens the app, right-click shows the
+    /// menu" behavior.
+    #[default]
+    Event,
+    /// Show the tray's context menu, the same as a secondary click.
+    ShowMenu,
+}
+
+#[cfg(feature = "menu")]
+variant_names!(PrimaryAction, "primary action", {
+    PrimaryAction::Event => "event",
+    PrimaryAction::ShowMenu => "show-menu",
+});
+
+#[cfg(feature = "menu")]
+impl PrimaryAction {
+    /// Whether a click with `button` should open the tray's context menu.
+    ///
+    /// The secondary (right) button always opens the menu; the primary
+    /// button only does so with [`PrimaryAction::ShowMenu`].
+    pub fn opens_menu(self, button: MouseButton) -> bool {
+        match button {
+            MouseButton::Right => true,
+            MouseButton::Left => self == PrimaryAction::ShowMenu,
+            _ => false,
+        }
+    }
+}
+
+/// How a tray's context menu is brought to the foreground (Windows only).
+///
+/// A popup menu only closes on clicks outside it while its owner window is
+/// in the foreground, so the tray brings its hidden window forward before
+/// showing the menu. Doing that unconditionally can take focus from the
+/// active app and make its taskbar button flash.
+#[cfg(feature = "menu")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[non_exhaustive]
+pub enum MenuActivation {
+    /// Always call `SetForegroundWindow` on the tray window, as the Win32
+    /// documentation for `TrackPopupMenu` recommends.
+    #[default]
+    Foreground,
+    /// Attach to the foreground window's input queue while bringing the
+    /// tray window forward, so Windows hands over the foreground without
+    /// flashing the taskbar.
+    AttachThreadInput,
+    /// Skip bringing the tray window forward when a window of the tray's
+    /// thread is already in the foreground. Otherwise the same as
+    /// [`MenuActivation::Foreground`].
+    IfNeeded,
+}
+
+#[cfg(feature = "menu")]
+variant_names!(MenuActivation, "menu activation", {
+    MenuActivation::Foreground => "foreground",
+    MenuActivation::AttachThreadInput => "attach-thread-input",
+    MenuActivation::IfNeeded => "if-needed",
+});
+
+/// Set of pointer buttons a tray icon reports [`Event::PointerButton`] for.
+///
+/// Combine buttons with `|`:
+///
+/// ```ignore
+/// let attributes = TrayIconAttributes::default()
+///     .with_button_filter(ButtonMask::LEFT | ButtonMask::RIGHT);
+/// ```
+///
+/// Filtering only affects events. A secondary click still opens the tray's
+/// context menu when [`ButtonMask::RIGHT`] is filtered out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ButtonMask(u8);
+
+impl ButtonMask {
+    /// No buttons.
+    pub const NONE: ButtonMask = ButtonMask(0);
+    /// The primary (left) button.
+    pub const LEFT: ButtonMask = ButtonMask(1 << 0);
+    /// The secondary (right) button.
+    pub const RIGHT: ButtonMask = ButtonMask(1 << 1);
+    /// The middle button.
+    pub const MIDDLE: ButtonMask = ButtonMask(1 << 2);
+    /// Back, forward and any other extra buttons.
+    pub const OTHER: ButtonMask = ButtonMask(1 << 3);
+    /// Every button. The default.
+    pub const ALL: ButtonMask = ButtonMask(0b1111);
+
+    /// Whether events for `button` are reported.
+    pub fn contains(self, button: MouseButton) -> bool {
+        let bit = match button {
+            MouseButton::Left => ButtonMask::LEFT,
+            MouseButton::Right => ButtonMask::RIGHT,
+            MouseButton::Middle => ButtonMask::MIDDLE,
+            _ => ButtonMask::OTHER,
+        };
+        self.0 & bit.0 != 0
+    }
+
+    /// Whether no buttons are reported.
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl Default for ButtonMask {
+    fn default() -> Self {
+        ButtonMask::ALL
+    }
+}
+
+impl std::ops::BitOr for ButtonMask {
+    type Output = ButtonMask;
+
+    fn bitor(self, rhs: ButtonMask) -> ButtonMask {
+        ButtonMask(self.0 | rhs.0)
+    }
+}
+
+impl std::ops::BitOrAssign for ButtonMask {
+    fn bitor_assign(&mut self, rhs: ButtonMask) {
+        self.0 |= rhs.0;
+    }
 }
 
 /// Configuration for creating a tray icon.
+///
+/// The type parameter `T` is the menu item ID type of the tray's
+/// `context_menu`, matching the [`Manager`][`winit_extras::Manager`]'s
+/// action type.
+///
+/// The menu fields (`context_menu`, `primary_action`, `menu_activation` and
+/// `flatten_submenus`) and their builders only exist with the `menu`
+/// feature. Code that sets a menu fails to compile without it, instead of
+/// creating a tray whose menu is silently missing; in a workspace that
+/// mixes features, enable `menu` on every crate that builds menus.
 #[derive(Debug)]
-pub struct TrayIconAttributes {
+pub struct TrayIconAttributes<T = ()> {
     /// Hover tooltip shown by the OS.
+    ///
+    /// On Linux the first line is shown as the tooltip's title and any
+    /// further lines as its description.
     pub tooltip: Option<String>,
 
     /// Window class name used internally on Windows.
@@ -90,27 +576,182 @@ pub struct TrayIconAttributes {
     pub class_name: String,
 
     /// Icon displayed in the system tray.
+    ///
+    /// Must be an `RgbaIcon` or a [`PreparedIcon`][icon::PreparedIcon];
+    /// creating the tray fails with [`IconError`][icon::IconError] for
+    /// anything else rather than showing a blank icon.
     pub icon: Option<Icon>,
 
+    /// Icon shown instead of [`icon`](Self::icon) if that can't be
+    /// converted for the platform.
+    ///
+    /// See [`apply_fallback_icon`](Self::apply_fallback_icon).
+    pub fallback_icon: Option<Icon>,
+
+    /// Show a gray square ([`icon::placeholder`]) when neither `icon` nor
+    /// `fallback_icon` can be shown, so the tray is never invisible.
+    /// Defaults to `false`.
+    pub placeholder_icon: bool,
+
+    /// Icon shown instead of [`icon`](Self::icon) while the pointer is over
+    /// the tray (Windows and macOS).
+    ///
+    /// Ignored on Linux, where tray hosts draw the icon and report no
+    /// hover. See [`with_icon_states`](Self::with_icon_states).
+    pub hover_icon: Option<Icon>,
+
+    /// Icon shown while a button is held down on the tray (Windows and
+    /// macOS). Falls back to [`hover_icon`](Self::hover_icon) if unset.
+    /// Ignored on Linux.
+    pub pressed_icon: Option<Icon>,
+
+    /// Whether the icon is rendered as a template image (macOS only).
+    ///
+    /// Template images are drawn as monochrome silhouettes tinted to match
+    /// the menu bar appearance. Disable this for full-color icons such as app
+    /// logos. Defaults to `true`. Ignored on other platforms.
+    pub template_icon: bool,
+
     /// Parent window handle.
     ///
     /// Currently only used on Windows, where the tray icon's hidden message
     /// window can be parented to an existing window.
     pub parent_window: Option<rwh_06::RawWindowHandle>,
+
+    /// How many more times to try registering with the tray host after the
+    /// first attempt fails (Linux only).
+    ///
+    /// On login the panel hosting the StatusNotifierWatcher may start after
+    /// the application. Retries back off exponentially, so the default of 2
+    /// gives the watcher roughly two seconds to appear. Ignored on other
+    /// platforms.
+    pub registration_retries: u32,
+
+    /// Which pointer buttons produce [`Event::PointerButton`].
+    ///
+    /// Events for other buttons are dropped by the backend before they reach
+    /// the event channel. Defaults to [`ButtonMask::ALL`].
+    pub button_filter: ButtonMask,
+
+    /// Extra properties served on the `org.kde.StatusNotifierItem` interface
+    /// (Linux only).
+    ///
+    /// For vendor extensions that some tray hosts read, such as Ayatana's
+    /// `XAyatanaLabel`. Properties the crate serves itself take precedence.
+    #[cfg(target_os = "linux")]
+    pub extra_sni_properties: std::collections::HashMap<String, zvariant::OwnedValue>,
+
+    /// Menu shown natively by the backend on secondary click.
+    ///
+    /// Selections are delivered as [`Event::MenuItemClicked`]. Pointer events
+    /// are still delivered for the click that opened the menu.
+    ///
+    /// On Linux the menu is exported over D-Bus and drawn by the desktop's
+    /// tray host, which decides when to open it.
+    #[cfg(feature = "menu")]
+    pub context_menu: Option<Vec<MenuEntry<T>>>,
+
+    /// What a primary click does. Defaults to [`PrimaryAction::Event`].
+    #[cfg(feature = "menu")]
+    pub primary_action: PrimaryAction,
+
+    /// How the context menu takes the foreground before it opens (Windows
+    /// only). Defaults to [`MenuActivation::Foreground`].
+    #[cfg(feature = "menu")]
+    pub menu_activation: MenuActivation,
+
+    /// Show the context menu as a single list, without submenus.
+    ///
+    /// A workaround for tray hosts that render submenus poorly. See
+    /// [`flatten_submenus`] for how entries are laid out. Defaults to `false`.
+    #[cfg(feature = "menu")]
+    pub flatten_submenus: bool,
+
+    #[cfg(not(feature = "menu"))]
+    _marker: std::marker::PhantomData<T>,
 }
 
-impl Default for TrayIconAttributes {
+impl<T> Default for TrayIconAttributes<T> {
     fn default() -> Self {
         TrayIconAttributes {
             tooltip: None,
             icon: None,
+            fallback_icon: None,
+            placeholder_icon: false,
+            hover_icon: None,
+            pressed_icon: None,
+            template_icon: true,
             class_name: "WinitExtrasTrayClass".to_string(),
             parent_window: None,
+            registration_retries: 2,
+            button_filter: ButtonMask::ALL,
+            #[cfg(target_os = "linux")]
+            extra_sni_properties: std::collections::HashMap::new(),
+            #[cfg(feature = "menu")]
+            context_menu: None,
+            #[cfg(feature = "menu")]
+            primary_action: PrimaryAction::default(),
+            #[cfg(feature = "menu")]
+            menu_activation: MenuActivation::default(),
+            #[cfg(feature = "menu")]
+            flatten_submenus: false,
+            #[cfg(not(feature = "menu"))]
+            _marker: std::marker::PhantomData,
         }
     }
 }
 
-impl TrayIconAttributes {
+/// How [`TrayIconAttributes::describe`] shows an optional attribute.
+fn set_or_none<V>(value: &Option<V>) -> &'static str {
+    if value.is_some() { "set" } else { "none" }
+}
+
+impl<T> TrayIconAttributes<T> {
+    /// Summarize the attributes for logs and bug reports.
+    ///
+    /// Unlike the derived `Debug`, icons are only reported as present and
+    /// the context menu is outlined with [`describe_menu`], one entry per
+    /// line.
+    pub fn describe(&self) -> String {
+        use std::fmt::Write;
+
+        let mut summary = String::new();
+        let _ = writeln!(summary, "tooltip: {:?}", self.tooltip);
+        let _ = writeln!(summary, "icon: {}", set_or_none(&self.icon));
+        let _ = writeln!(
+            summary,
+            "fallback_icon: {}",
+            set_or_none(&self.fallback_icon)
+        );
+        let _ = writeln!(
+            summary,
+            "hover_icon: {}, pressed_icon: {}",
+            set_or_none(&self.hover_icon),
+            set_or_none(&self.pressed_icon)
+        );
+        let _ = writeln!(summary, "template_icon: {}", self.template_icon);
+        let _ = writeln!(
+            summary,
+            "parent_window: {}",
+            set_or_none(&self.parent_window)
+        );
+        let _ = writeln!(summary, "button_filter: {:?}", self.button_filter);
+        #[cfg(feature = "menu")]
+        {
+            let _ = writeln!(summary, "primary_action: {:?}", self.primary_action);
+            let _ = writeln!(summary, "flatten_submenus: {}", self.flatten_submenus);
+            match &self.context_menu {
+                Some(entries) => {
+                    let count = menu::item_count_label(entries.item_count());
+                    let _ = writeln!(summary, "context_menu: {count}");
+                    menu::describe_into(entries, 1, &mut summary);
+                }
+                None => summary.push_str("context_menu: none\n"),
+            }
+        }
+        summary
+    }
+
     /// Set the tooltip text shown on hover.
     pub fn with_tooltip(mut self, title: impl Into<String>) -> Self {
         self.tooltip = Some(title.into());
@@ -123,6 +764,57 @@ impl TrayIconAttributes {
         self
     }
 
+    /// Set an icon to show if the one from [`with_icon`](Self::with_icon)
+    /// can't be converted.
+    pub fn with_fallback_icon(mut self, icon: Icon) -> Self {
+        self.fallback_icon = Some(icon);
+        self
+    }
+
+    /// Set the icon along with variants shown while the pointer is over the
+    /// tray and while it is pressed.
+    ///
+    /// Lets the tray react visually to the pointer, as many menu bar
+    /// utilities do. A state without its own icon shows `normal`, or for
+    /// `pressed` the hover icon. Only `normal` is used on Linux.
+    pub fn with_icon_states(
+        mut self,
+        normal: Icon,
+        hover: Option<Icon>,
+        pressed: Option<Icon>,
+    ) -> Self {
+        self.icon = Some(normal);
+        self.hover_icon = hover;
+        self.pressed_icon = pressed;
+        self
+    }
+
+    /// Show a placeholder square if no icon can be shown.
+    pub fn with_placeholder_icon(mut self, placeholder_icon: bool) -> Self {
+        self.placeholder_icon = placeholder_icon;
+        self
+    }
+
+    /// Use the icon from a window's attributes, if it has one.
+    ///
+    /// Saves loading the same image twice when the tray shows the app's
+    /// window icon. Cloning an [`Icon`] only bumps a reference count.
+    pub fn with_window_icon(mut self, window_attributes: &WindowAttributes) -> Self {
+        if let Some(icon) = &window_attributes.window_icon {
+            self.icon = Some(icon.clone());
+        }
+        self
+    }
+
+    /// Set whether the icon is rendered as a template image (macOS only).
+    ///
+    /// Pass `false` to show a full-color icon as-is instead of a monochrome
+    /// silhouette. Defaults to `true`.
+    pub fn with_template_icon(mut self, template_icon: bool) -> Self {
+        self.template_icon = template_icon;
+        self
+    }
+
     /// Override the Windows window class name.
     ///
     /// Must be unique per process on Windows. Ignored on other platforms.
@@ -136,4 +828,241 @@ impl TrayIconAttributes {
         self.parent_window = Some(parent_window);
         self
     }
+
+    /// Set how many times to retry registering with the tray host (Linux only).
+    ///
+    /// Pass `0` to try exactly once. Defaults to `2`.
+    pub fn with_registration_retries(mut self, retries: u32) -> Self {
+        self.registration_retries = retries;
+        self
+    }
+
+    /// Only report pointer events for the buttons in `buttons`.
+    pub fn with_button_filter(mut self, buttons: ButtonMask) -> Self {
+        self.button_filter = buttons;
+        self
+    }
+
+    /// Serve extra StatusNotifierItem properties (Linux only).
+    ///
+    /// ```ignore
+    /// let label = zvariant::OwnedValue::try_from(zvariant::Value::from("42%"))?;
+    /// let attributes = TrayIconAttributes::default().with_extra_sni_properties(
+    ///     [("XAyatanaLabel".to_string(), label)].into(),
+    /// );
+    /// ```
+    #[cfg(target_os = "linux")]
+    pub fn with_extra_sni_properties(
+        mut self,
+        properties: std::collections::HashMap<String, zvariant::OwnedValue>,
+    ) -> Self {
+        self.extra_sni_properties = properties;
+        self
+    }
+
+    /// Set the menu shown on secondary click.
+    #[cfg(feature = "menu")]
+    pub fn with_context_menu(mut self, items: Vec<MenuEntry<T>>) -> Self {
+        self.context_menu = Some(items);
+        self
+    }
+
+    /// Set what a primary click does.
+    #[cfg(feature = "menu")]
+    pub fn with_primary_action(mut self, primary_action: PrimaryAction) -> Self {
+        self.primary_action = primary_action;
+        self
+    }
+
+    /// Set how the context menu takes the foreground (Windows only).
+    #[cfg(feature = "menu")]
+    pub fn with_menu_activation(mut self, menu_activation: MenuActivation) -> Self {
+        self.menu_activation = menu_activation;
+        self
+    }
+
+    /// Set whether the context menu is flattened into a single list.
+    #[cfg(feature = "menu")]
+    pub fn with_flatten_submenus(mut self, flatten_submenus: bool) -> Self {
+        self.flatten_submenus = flatten_submenus;
+        self
+    }
+
+    /// Swap an icon that can't be converted for the
+    /// [`fallback_icon`](Self::fallback_icon), or for the placeholder if
+    /// [`placeholder_icon`](Self::placeholder_icon) is set.
+    ///
+    /// Returns why each icon that was passed over failed, labelled `"icon"`
+    /// or `"fallback_icon"`, so the caller can log them. With neither
+    /// replacement configured the icon is left alone and still fails
+    /// [`validate`](Self::validate).
+    /// [`Manager::create_tray`][`winit_extras::Manager::create_tray`] calls
+    /// this first and logs a warning for each failure.
+    pub fn apply_fallback_icon(&mut self) -> Vec<(&'static str, icon::IconError)> {
+        let mut failures = Vec::new();
+        match self.icon.as_ref().map(icon::PreparedIcon::try_prepare) {
+            Some(Ok(_)) => return failures,
+            Some(Err(err)) => failures.push(("icon", err)),
+            None => (),
+        }
+
+        let icon_failed = !failures.is_empty();
+        if icon_failed && let Some(fallback) = self.fallback_icon.take() {
+            match icon::PreparedIcon::try_prepare(&fallback) {
+                Ok(_) => {
+                    self.icon = Some(fallback);
+                    return failures;
+                }
+                Err(err) => failures.push(("fallback_icon", err)),
+            }
+        }
+        if self.placeholder_icon {
+            self.icon = Some(icon::placeholder());
+        }
+        failures
+    }
+
+    /// Check the attributes for mistakes that would otherwise only surface
+    /// once the platform tray is being created.
+    ///
+    /// [`Manager::create_tray`][`winit_extras::Manager::create_tray`] calls
+    /// this before touching the platform.
+    pub fn validate(&self) -> Result<(), TrayError> {
+        if let Some(icon) = &self.icon
+            && !icon::is_supported(icon)
+        {
+            return Err(TrayError::Icon(icon::IconError::UnsupportedFormat));
+        }
+        if [&self.hover_icon, &self.pressed_icon]
+            .into_iter()
+            .flatten()
+            .any(|icon| !icon::is_supported(icon))
+        {
+            return Err(TrayError::Icon(icon::IconError::UnsupportedFormat));
+        }
+        if cfg!(target_os = "windows") && self.class_name.is_empty() {
+            return Err(TrayError::EmptyClassName);
+        }
+        error::check_parent_window(self.parent_window.as_ref())
+    }
+
+    /// The context menu, if one was set.
+    #[cfg(feature = "menu")]
+    pub fn menu(&self) -> Option<&[MenuEntry<T>]> {
+        self.context_menu.as_deref()
+    }
+
+    /// Take the context menu for building, flattened if
+    /// [`flatten_submenus`](Self::flatten_submenus) is set.
+    #[cfg(feature = "menu")]
+    pub fn take_context_menu(&mut self) -> Option<Vec<MenuEntry<T>>> {
+        let items = self.context_menu.take()?;
+        Some(if self.flatten_submenus {
+            flatten_submenus(items)
+        } else {
+            items
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct FileIcon;
+
+    impl winit::icon::IconProvider for FileIcon {}
+
+    fn unsupported() -> Icon {
+        Icon(std::sync::Arc::new(FileIcon))
+    }
+
+    #[test]
+    fn test_fallback_icon_replaces_unsupported_icon() {
+        let fallback = icon::placeholder();
+        let mut attributes = TrayIconAttributes::<()>::default()
+            .with_icon(unsupported())
+            .with_fallback_icon(fallback.clone());
+        let failures = attributes.apply_fallback_icon();
+        assert_eq!(failures, [("icon", icon::IconError::UnsupportedFormat)]);
+        assert!(std::sync::Arc::ptr_eq(
+            &attributes.icon.as_ref().unwrap().0,
+            &fallback.0
+        ));
+        assert!(attributes.validate().is_ok());
+    }
+
+    #[test]
+    fn test_placeholder_only_when_opted_in() {
+        let mut attributes = TrayIconAttributes::<()>::default()
+            .with_icon(unsupported())
+            .with_fallback_icon(unsupported());
+        assert_eq!(attributes.apply_fallback_icon().len(), 2);
+        assert!(attributes.validate().is_err());
+
+        let mut attributes = TrayIconAttributes::<()>::default()
+            .with_icon(unsupported())
+            .with_placeholder_icon(true);
+        assert_eq!(attributes.apply_fallback_icon().len(), 1);
+        assert!(attributes.validate().is_ok());
+
+        // A working icon is left alone.
+        let icon = icon::placeholder();
+        let mut attributes = TrayIconAttributes::<()>::default()
+            .with_icon(icon.clone())
+            .with_placeholder_icon(true);
+        assert!(attributes.apply_fallback_icon().is_empty());
+        assert!(std::sync::Arc::ptr_eq(
+            &attributes.icon.as_ref().unwrap().0,
+            &icon.0
+        ));
+    }
+
+    #[test]
+    fn test_event_tray_icon_id() {
+        let id = tray_icon_id::TrayIconId::allocate();
+        let event = Event::<()>::IconSizeChanged {
+            tray_icon_id: id,
+            size: PhysicalSize::new(32, 32),
+        };
+        assert_eq!(event.tray_icon_id(), Some(id));
+        let event = Event::<()>::Scroll {
+            tray_icon_id: id,
+            delta: -1.0,
+            orientation: ScrollOrientation::Vertical,
+            position: PhysicalPosition::new(0.0, 0.0),
+        };
+        assert_eq!(event.tray_icon_id(), Some(id));
+        let event = Event::<()>::NotificationClicked { tray_icon_id: id };
+        assert_eq!(event.tray_icon_id(), Some(id));
+        assert_eq!(Event::<()>::AllTraysRemoved.tray_icon_id(), None);
+    }
+
+    #[test]
+    fn test_scroll_orientation_names() {
+        for orientation in [ScrollOrientation::Horizontal, ScrollOrientation::Vertical] {
+            assert_eq!(orientation.to_string().parse(), Ok(orientation));
+        }
+        assert_eq!("vertical".parse(), Ok(ScrollOrientation::Vertical));
+        assert!("Vertical".parse::<ScrollOrientation>().is_err());
+    }
+
+    #[test]
+    fn test_icon_states_are_validated() {
+        let icon = icon::placeholder();
+        let attributes = TrayIconAttributes::<()>::default().with_icon_states(
+            icon.clone(),
+            Some(icon.clone()),
+            None,
+        );
+        assert!(attributes.validate().is_ok());
+
+        let attributes =
+            TrayIconAttributes::<()>::default().with_icon_states(icon, None, Some(unsupported()));
+        assert_eq!(
+            attributes.validate(),
+            Err(TrayError::Icon(icon::IconError::UnsupportedFormat))
+        );
+    }
 }
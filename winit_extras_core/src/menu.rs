@@ -1,7 +1,12 @@
 //! Menu types for tray context menus.
 
+use std::borrow::Cow;
+use std::path::PathBuf;
+
 use winit::icon::Icon;
 
+use crate::Accelerator;
+
 /// A clickable menu item with a generic ID type.
 #[derive(Debug, Clone)]
 pub struct MenuItem<T> {
@@ -15,6 +20,15 @@ pub struct MenuItem<T> {
     pub checked: Option<bool>,
     /// Optional icon displayed next to the label.
     pub icon: Option<Icon>,
+    /// Standard action performed when the item is clicked.
+    pub action: Option<ItemAction>,
+    /// Platform-defined role of the item in a menu bar.
+    pub role: Option<MenuRole>,
+    /// Whether `&` in the label marks an access key. See
+    /// [`mnemonic`](Self::mnemonic).
+    pub mnemonic: bool,
+    /// Keyboard shortcut shown next to the label.
+    pub accelerator: Option<Accelerator>,
 }
 
 impl<T> MenuItem<T> {
@@ -26,6 +40,10 @@ impl<T> MenuItem<T> {
             enabled: true,
             checked: None,
             icon: None,
+            action: None,
+            role: None,
+            mnemonic: false,
+            accelerator: None,
         }
     }
 
@@ -46,6 +64,155 @@ impl<T> MenuItem<T> {
         self.icon = Some(icon);
         self
     }
+
+    /// Perform `action` when this item is clicked.
+    ///
+    /// The click is still reported as an [`Event::MenuItemClicked`].
+    ///
+    /// [`Event::MenuItemClicked`]: crate::Event::MenuItemClicked
+    pub fn action(mut self, action: ItemAction) -> Self {
+        self.action = Some(action);
+        self
+    }
+
+    /// Give this item a standard menu bar role.
+    pub fn role(mut self, role: MenuRole) -> Self {
+        self.role = Some(role);
+        self
+    }
+
+    /// Treat `&` in the label as an access key marker.
+    ///
+    /// By default labels are shown as written, so "Save & Exit" keeps its
+    /// ampersand. With mnemonics, "&Save" underlines the S on Windows and
+    /// `&&` stands for a literal ampersand; other platforms show the label
+    /// with the markers removed.
+    pub fn mnemonic(mut self, mnemonic: bool) -> Self {
+        self.mnemonic = mnemonic;
+        self
+    }
+
+    /// Show `accelerator` next to the label, in the form menus on this
+    /// platform use (see [`Accelerator::display_string`]).
+    ///
+    /// This only displays the shortcut. Except in the macOS menu bar, where
+    /// AppKit handles key equivalents, pressing it does not click the item;
+    /// register it as a global hotkey or handle the key in a window to make
+    /// it work.
+    pub fn accelerator(mut self, accelerator: Accelerator) -> Self {
+        self.accelerator = Some(accelerator);
+        self
+    }
+
+    /// Flip the check state the way clicking the item does.
+    ///
+    /// Returns the new state, or `None` if the item is not checkable.
+    pub fn toggle(&mut self) -> Option<bool> {
+        self.checked = self.checked.map(|checked| !checked);
+        self.checked
+    }
+}
+
+/// A standard action a [`MenuItem`] performs by itself when clicked.
+///
+/// Backends perform the action before reporting the click, so the
+/// application doesn't need a handler for common items like "Open
+/// Documentation". Launching requires the `open` feature; without it the
+/// action fails and a warning is logged.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ItemAction {
+    /// Open a URL with the default browser or URL handler.
+    OpenUrl(String),
+    /// Open a file or directory with its default application.
+    OpenPath(PathBuf),
+}
+
+impl ItemAction {
+    /// Launch the URL or path without waiting for the launched program.
+    pub fn perform(&self) -> std::io::Result<()> {
+        #[cfg(feature = "open")]
+        match self {
+            ItemAction::OpenUrl(url) => open::that_detached(url),
+            ItemAction::OpenPath(path) => open::that_detached(path),
+        }
+
+        #[cfg(not(feature = "open"))]
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "menu item actions require the `open` feature",
+        ))
+    }
+}
+
+/// A standard menu bar item whose placement and behavior follow platform
+/// conventions.
+///
+/// On macOS, items with the [`About`](Self::About),
+/// [`Preferences`](Self::Preferences), [`Services`](Self::Services),
+/// [`Hide`](Self::Hide) and [`Quit`](Self::Quit) roles are moved into the
+/// application menu, wherever they are declared. Roles with a standard
+/// AppKit action (everything but `Preferences`) are sent to the responder
+/// chain with their usual shortcut, and are not reported as
+/// [`Event::MenuItemClicked`](crate::Event::MenuItemClicked).
+///
+/// On Windows, `Quit`, `Minimize` and `Zoom` act on the window owning the
+/// menu bar: `Quit` asks it to close, `Zoom` toggles maximizing. `Services`
+/// items are left out. Other roles are reported as clicks like any item.
+///
+/// The [`MinimizeWindow`](Self::MinimizeWindow),
+/// [`ToggleFullscreen`](Self::ToggleFullscreen) and
+/// [`CloseWindow`](Self::CloseWindow) roles act on the menu bar's
+/// `parent_window` on both platforms and are then reported as clicks too, so
+/// the application can keep its own state in sync. They need a window handle
+/// to be supplied; without one they are only reported as clicks.
+///
+/// Tray and context menus ignore roles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MenuRole {
+    /// Show the application's about panel.
+    About,
+    /// Open the application's settings. Always reported as a click.
+    Preferences,
+    /// The system Services submenu (macOS only).
+    Services,
+    /// Hide the application.
+    Hide,
+    /// Quit the application.
+    Quit,
+    /// Undo the last edit in the focused view.
+    Undo,
+    /// Redo the last undone edit in the focused view.
+    Redo,
+    /// Cut the selection to the clipboard.
+    Cut,
+    /// Copy the selection to the clipboard.
+    Copy,
+    /// Paste from the clipboard.
+    Paste,
+    /// Select everything in the focused view.
+    SelectAll,
+    /// Minimize the window.
+    Minimize,
+    /// Zoom (maximize or restore) the window.
+    Zoom,
+    /// Minimize the parent window, then report the click.
+    MinimizeWindow,
+    /// Enter or leave borderless fullscreen on the parent window, then report
+    /// the click.
+    ToggleFullscreen,
+    /// Ask the parent window to close, then report the click.
+    CloseWindow,
+}
+
+impl MenuRole {
+    /// Whether this role acts on the parent window and is still reported as
+    /// a click.
+    pub fn is_window_action(self) -> bool {
+        matches!(
+            self,
+            Self::MinimizeWindow | Self::ToggleFullscreen | Self::CloseWindow
+        )
+    }
 }
 
 /// A submenu containing nested menu entries.
@@ -57,6 +224,9 @@ pub struct Submenu<T> {
     pub enabled: bool,
     /// Nested menu entries.
     pub items: Vec<MenuEntry<T>>,
+    /// Whether `&` in the label marks an access key, as for
+    /// [`MenuItem::mnemonic`].
+    pub mnemonic: bool,
 }
 
 impl<T> Submenu<T> {
@@ -66,6 +236,7 @@ impl<T> Submenu<T> {
             label: label.into(),
             enabled: true,
             items,
+            mnemonic: false,
         }
     }
 
@@ -74,6 +245,50 @@ impl<T> Submenu<T> {
         self.enabled = enabled;
         self
     }
+
+    /// Treat `&` in the label as an access key marker. See
+    /// [`MenuItem::mnemonic`].
+    pub fn mnemonic(mut self, mnemonic: bool) -> Self {
+        self.mnemonic = mnemonic;
+        self
+    }
+}
+
+/// Doubles every `&` so Windows shows it instead of taking it as an access
+/// key marker.
+pub fn escape_mnemonics(label: &str) -> Cow<'_, str> {
+    if label.contains('&') {
+        Cow::Owned(label.replace('&', "&&"))
+    } else {
+        Cow::Borrowed(label)
+    }
+}
+
+/// Removes access key markers from a label written for
+/// [`mnemonic`](MenuItem::mnemonic) parsing, turning `&&` back into `&`.
+pub fn strip_mnemonics(label: &str) -> Cow<'_, str> {
+    if !label.contains('&') {
+        return Cow::Borrowed(label);
+    }
+    let mut stripped = String::with_capacity(label.len());
+    let mut chars = label.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '&' => stripped.extend(chars.next()),
+            c => stripped.push(c),
+        }
+    }
+    Cow::Owned(stripped)
+}
+
+/// The text shown for `label`, with access key markers removed if
+/// `mnemonic` is set.
+pub fn display_label(label: &str, mnemonic: bool) -> Cow<'_, str> {
+    if mnemonic {
+        strip_mnemonics(label)
+    } else {
+        Cow::Borrowed(label)
+    }
 }
 
 /// An entry in a menu, which can be an item, submenu, or separator.
@@ -85,4 +300,416 @@ pub enum MenuEntry<T> {
     Submenu(Submenu<T>),
     /// A visual separator line.
     Separator,
+    /// A separator drawn in a specific style.
+    ///
+    /// Native menus have a single kind of separator and show every style as
+    /// a plain [`MenuEntry::Separator`]; custom renderers draw the style.
+    StyledSeparator(SeparatorStyle),
+}
+
+/// How a [`MenuEntry::StyledSeparator`] is drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SeparatorStyle {
+    /// A line inset from both edges of the menu, like a plain separator.
+    #[default]
+    Inset,
+    /// A line spanning the full width of the menu.
+    FullWidth,
+    /// Empty space without a line, for grouping items.
+    Spacer,
+}
+
+variant_names!(SeparatorStyle, "separator style", {
+    SeparatorStyle::Inset => "inset",
+    SeparatorStyle::FullWidth => "full-width",
+    SeparatorStyle::Spacer => "spacer",
+});
+
+/// Indents a flattened item's label once per submenu level. An em space,
+/// since some tray hosts trim leading ASCII spaces from labels.
+const FLATTENED_INDENT: &str = "\u{2003}\u{2003}";
+
+/// Turn a menu tree into a single-level list.
+///
+/// Each submenu is replaced by its entries, in place, with their labels
+/// indented one step per level of nesting: an "Open" item in a "Recent"
+/// submenu is shown as an indented "Open" right where "Recent" was. Items
+/// of a disabled submenu are disabled. Item IDs are unchanged, so clicks
+/// are reported exactly as in the nested menu.
+pub fn flatten_submenus<T>(entries: Vec<MenuEntry<T>>) -> Vec<MenuEntry<T>> {
+    let mut flattened = Vec::with_capacity(entries.len());
+    flatten_into(entries, 0, true, &mut flattened);
+    flattened
+}
+
+fn flatten_into<T>(
+    entries: Vec<MenuEntry<T>>,
+    depth: usize,
+    enabled: bool,
+    flattened: &mut Vec<MenuEntry<T>>,
+) {
+    for entry in entries {
+        match entry {
+            MenuEntry::Item(mut item) => {
+                item.label.insert_str(0, &FLATTENED_INDENT.repeat(depth));
+                item.enabled &= enabled;
+                flattened.push(MenuEntry::Item(item));
+            }
+            MenuEntry::Submenu(submenu) => flatten_into(
+                submenu.items,
+                depth + 1,
+                enabled && submenu.enabled,
+                flattened,
+            ),
+            separator => flattened.push(separator),
+        }
+    }
+}
+
+/// Outline a menu tree for logs and bug reports.
+///
+/// Each entry gets a line, indented two spaces per submenu level: items show
+/// their label followed by `[x]` or `[ ]` when checkable and `(disabled)`,
+/// `(icon)` or their role where those apply, separators show as `---`, and
+/// submenus show how many items they hold. Item IDs and icon pixels are left
+/// out, so the outline stays short and needs no bounds on `T`.
+pub fn describe_menu<T>(entries: &[MenuEntry<T>]) -> String {
+    let mut outline = String::new();
+    describe_into(entries, 0, &mut outline);
+    outline
+}
+
+pub(crate) fn item_count_label(count: usize) -> String {
+    if count == 1 {
+        "1 item".to_string()
+    } else {
+        format!("{count} items")
+    }
+}
+
+pub(crate) fn describe_into<T>(entries: &[MenuEntry<T>], depth: usize, outline: &mut String) {
+    use std::fmt::Write;
+
+    let indent = "  ".repeat(depth);
+    for entry in entries {
+        match entry {
+            MenuEntry::Item(item) => {
+                let _ = write!(outline, "{indent}{}", item.label);
+                match item.checked {
+                    Some(true) => outline.push_str(" [x]"),
+                    Some(false) => outline.push_str(" [ ]"),
+                    None => (),
+                }
+                if !item.enabled {
+                    outline.push_str(" (disabled)");
+                }
+                if item.icon.is_some() {
+                    outline.push_str(" (icon)");
+                }
+                if let Some(role) = item.role {
+                    let _ = write!(outline, " ({role:?})");
+                }
+                outline.push('\n');
+            }
+            MenuEntry::Submenu(submenu) => {
+                let _ = write!(
+                    outline,
+                    "{indent}{} ({})",
+                    submenu.label,
+                    item_count_label(submenu.items.item_count())
+                );
+                if !submenu.enabled {
+                    outline.push_str(" (disabled)");
+                }
+                outline.push('\n');
+                describe_into(&submenu.items, depth + 1, outline);
+            }
+            MenuEntry::Separator | MenuEntry::StyledSeparator(_) => {
+                let _ = writeln!(outline, "{indent}---");
+            }
+        }
+    }
+}
+
+/// Read-only helpers for inspecting a menu tree.
+///
+/// Implemented for `[MenuEntry<T>]`, so it is also available on
+/// `Vec<MenuEntry<T>>`. Useful for asserting menu structure in tests without
+/// a native round-trip.
+pub trait MenuEntries<T> {
+    /// Iterate over every [`MenuItem`] in the tree, depth first.
+    ///
+    /// Submenus are entered in place, so items are yielded in the same order
+    /// they appear when the menu is displayed. Separators and the submenus
+    /// themselves are skipped.
+    fn items(&self) -> MenuItems<'_, T>;
+
+    /// Number of [`MenuItem`]s in the tree, including those in submenus.
+    fn item_count(&self) -> usize {
+        self.items().count()
+    }
+
+    /// Find the first item with the given ID, searching submenus too.
+    fn find_item(&self, id: &T) -> Option<&MenuItem<T>>
+    where
+        T: PartialEq,
+    {
+        self.items().find(|item| item.id == *id)
+    }
+
+    /// Groups of items that share an ID, searching submenus too.
+    ///
+    /// Clicks on items with the same ID can't be told apart, which is easy
+    /// to miss when enum variants are reused across nested menus. Groups
+    /// and the items in them are in [`items`](Self::items) order.
+    fn duplicate_ids(&self) -> Vec<Vec<&MenuItem<T>>>
+    where
+        T: Eq + std::hash::Hash,
+    {
+        let mut groups: Vec<Vec<&MenuItem<T>>> = Vec::new();
+        let mut group_of = std::collections::HashMap::new();
+        for item in self.items() {
+            let index = *group_of.entry(&item.id).or_insert_with(|| {
+                groups.push(Vec::new());
+                groups.len() - 1
+            });
+            groups[index].push(item);
+        }
+        groups.retain(|group| group.len() > 1);
+        groups
+    }
+
+    /// Mutable variant of [`find_item`](Self::find_item).
+    fn find_item_mut(&mut self, id: &T) -> Option<&mut MenuItem<T>>
+    where
+        T: PartialEq;
+
+    /// The item at `index` in [`items`](Self::items) order.
+    ///
+    /// Backends number native menu items in this order, so an index reported
+    /// by the OS maps straight back to the item without comparing IDs.
+    fn item_mut(&mut self, index: usize) -> Option<&mut MenuItem<T>>;
+}
+
+impl<T> MenuEntries<T> for [MenuEntry<T>] {
+    fn items(&self) -> MenuItems<'_, T> {
+        MenuItems {
+            stack: vec![self.iter()],
+        }
+    }
+
+    fn find_item_mut(&mut self, id: &T) -> Option<&mut MenuItem<T>>
+    where
+        T: PartialEq,
+    {
+        for entry in self {
+            match entry {
+                MenuEntry::Item(item) if item.id == *id => return Some(item),
+                MenuEntry::Submenu(submenu) => {
+                    if let Some(item) = submenu.items.find_item_mut(id) {
+                        return Some(item);
+                    }
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    fn item_mut(&mut self, mut index: usize) -> Option<&mut MenuItem<T>> {
+        for entry in self {
+            match entry {
+                MenuEntry::Item(item) => {
+                    if index == 0 {
+                        return Some(item);
+                    }
+                    index -= 1;
+                }
+                MenuEntry::Submenu(submenu) => {
+                    let count = submenu.items.item_count();
+                    if index < count {
+                        return submenu.items.item_mut(index);
+                    }
+                    index -= count;
+                }
+                MenuEntry::Separator | MenuEntry::StyledSeparator(_) => {}
+            }
+        }
+        None
+    }
+}
+
+/// Depth-first iterator over the items of a menu tree.
+///
+/// Created by [`MenuEntries::items`].
+#[derive(Debug, Clone)]
+pub struct MenuItems<'a, T> {
+    stack: Vec<std::slice::Iter<'a, MenuEntry<T>>>,
+}
+
+impl<'a, T> Iterator for MenuItems<'a, T> {
+    type Item = &'a MenuItem<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(iter) = self.stack.last_mut() {
+            match iter.next() {
+                Some(MenuEntry::Item(item)) => return Some(item),
+                Some(MenuEntry::Submenu(submenu)) => self.stack.push(submenu.items.iter()),
+                Some(MenuEntry::Separator | MenuEntry::StyledSeparator(_)) => {}
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_menu() -> Vec<MenuEntry<u32>> {
+        vec![
+            MenuEntry::Item(MenuItem::new(1, "Open")),
+            MenuEntry::Separator,
+            MenuEntry::Submenu(Submenu::new(
+                "More",
+                vec![
+                    MenuEntry::Item(MenuItem::new(2, "Nested").checked(true)),
+                    MenuEntry::Submenu(Submenu::new(
+                        "Deeper",
+                        vec![MenuEntry::Item(MenuItem::new(3, "Deepest"))],
+                    )),
+                ],
+            )),
+            MenuEntry::StyledSeparator(SeparatorStyle::FullWidth),
+            MenuEntry::Item(MenuItem::new(4, "Quit")),
+        ]
+    }
+
+    #[test]
+    fn test_items_are_depth_first() {
+        let menu = sample_menu();
+        let ids: Vec<u32> = menu.items().map(|item| item.id).collect();
+        assert_eq!(ids, [1, 2, 3, 4]);
+        assert_eq!(menu.item_count(), 4);
+    }
+
+    #[test]
+    fn test_find_item() {
+        let mut menu = sample_menu();
+        assert_eq!(menu.find_item(&2).unwrap().checked, Some(true));
+        assert_eq!(menu.find_item(&3).unwrap().label, "Deepest");
+        assert!(menu.find_item(&5).is_none());
+
+        menu.find_item_mut(&3).unwrap().label = "Renamed".into();
+        assert_eq!(menu.find_item(&3).unwrap().label, "Renamed");
+    }
+
+    #[test]
+    fn test_duplicate_ids() {
+        let mut menu = sample_menu();
+        assert!(menu.duplicate_ids().is_empty());
+
+        menu.push(MenuEntry::Item(MenuItem::new(3, "Again")));
+        menu.push(MenuEntry::Item(MenuItem::new(1, "Open again")));
+        let labels: Vec<Vec<&str>> = menu
+            .duplicate_ids()
+            .iter()
+            .map(|group| group.iter().map(|item| item.label.as_str()).collect())
+            .collect();
+        assert_eq!(
+            labels,
+            [vec!["Open", "Open again"], vec!["Deepest", "Again"]]
+        );
+    }
+
+    #[test]
+    fn test_item_mut_and_toggle() {
+        let mut menu = sample_menu();
+        assert_eq!(menu.item_mut(0).unwrap().id, 1);
+        assert_eq!(menu.item_mut(2).unwrap().id, 3);
+        assert_eq!(menu.item_mut(3).unwrap().id, 4);
+        assert!(menu.item_mut(4).is_none());
+
+        let nested = menu.item_mut(1).unwrap();
+        assert_eq!(nested.toggle(), Some(false));
+        assert_eq!(menu.find_item(&2).unwrap().checked, Some(false));
+
+        // Items that are not checkable stay that way.
+        assert_eq!(menu.item_mut(0).unwrap().toggle(), None);
+    }
+
+    #[test]
+    fn test_flatten_submenus_indents_labels() {
+        let mut menu = sample_menu();
+        if let MenuEntry::Submenu(more) = &mut menu[2] {
+            more.enabled = false;
+        }
+
+        let flattened = flatten_submenus(menu);
+        assert!(
+            flattened
+                .iter()
+                .all(|entry| !matches!(entry, MenuEntry::Submenu(_)))
+        );
+
+        let labels: Vec<_> = flattened.items().map(|item| item.label.as_str()).collect();
+        let indent = FLATTENED_INDENT;
+        assert_eq!(
+            labels,
+            [
+                "Open".to_owned(),
+                format!("{indent}Nested"),
+                format!("{indent}{indent}Deepest"),
+                "Quit".to_owned()
+            ]
+        );
+        assert!(!flattened.find_item(&3).unwrap().enabled);
+        assert!(flattened.find_item(&4).unwrap().enabled);
+        assert_eq!(flattened.find_item(&2).unwrap().checked, Some(true));
+    }
+
+    #[test]
+    fn test_describe_menu() {
+        let mut menu = sample_menu();
+        menu.find_item_mut(&4).unwrap().enabled = false;
+        assert_eq!(
+            describe_menu(&menu),
+            "Open\n\
+             ---\n\
+             More (2 items)\n\
+             \x20 Nested [x]\n\
+             \x20 Deeper (1 item)\n\
+             \x20   Deepest\n\
+             ---\n\
+             Quit (disabled)\n"
+        );
+    }
+
+    #[test]
+    fn test_separator_style_names() {
+        for style in [
+            SeparatorStyle::Inset,
+            SeparatorStyle::FullWidth,
+            SeparatorStyle::Spacer,
+        ] {
+            assert_eq!(style.to_string().parse(), Ok(style));
+        }
+        assert_eq!("full-width".parse(), Ok(SeparatorStyle::FullWidth));
+        assert!("FullWidth".parse::<SeparatorStyle>().is_err());
+    }
+
+    #[test]
+    fn test_mnemonic_escaping() {
+        // Plain labels are escaped for Windows so the ampersand stays visible.
+        assert_eq!(escape_mnemonics("Save & Exit"), "Save && Exit");
+        assert!(matches!(escape_mnemonics("Save"), Cow::Borrowed(_)));
+
+        assert_eq!(strip_mnemonics("&Save && Exit"), "Save & Exit");
+        assert_eq!(strip_mnemonics("Trailing&"), "Trailing");
+        assert_eq!(display_label("&File", true), "File");
+        assert_eq!(display_label("&File", false), "&File");
+    }
 }
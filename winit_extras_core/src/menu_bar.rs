@@ -4,14 +4,27 @@
 //! On Windows, this creates a menu bar attached to a window.
 
 use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
+use crate::TrayError;
 use crate::menu::{MenuEntry, Submenu};
 
+static NEXT_ID: AtomicUsize = AtomicUsize::new(1);
+static NEXT_TOP_LEVEL_ID: AtomicUsize = AtomicUsize::new(1);
+
 /// Identifier of a menu bar. Unique for each menu bar instance.
+///
+/// Like [`TrayIconId`](crate::tray_icon_id::TrayIconId), IDs only increase
+/// and are never reused within a process.
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct MenuBarId(usize);
 
 impl MenuBarId {
+    /// Allocate the ID for a new menu bar.
+    pub fn allocate() -> Self {
+        Self(NEXT_ID.fetch_add(1, Ordering::Relaxed))
+    }
+
     /// Convert the `MenuBarId` into the underlying integer.
     ///
     /// This is useful if you need to pass the ID across an FFI boundary, or store it in an atomic.
@@ -33,6 +46,38 @@ impl fmt::Debug for MenuBarId {
     }
 }
 
+/// Identifier of a top-level menu added with [`MenuBar::add_top_level`].
+///
+/// Unique across all menu bars, like [`MenuBarId`].
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TopLevelMenuId(usize);
+
+impl TopLevelMenuId {
+    /// Allocate the ID for a new top-level menu.
+    pub fn allocate() -> Self {
+        Self(NEXT_TOP_LEVEL_ID.fetch_add(1, Ordering::Relaxed))
+    }
+
+    /// Convert the `TopLevelMenuId` into the underlying integer.
+    pub const fn into_raw(self) -> usize {
+        self.0
+    }
+
+    /// Construct a `TopLevelMenuId` from the underlying integer.
+    ///
+    /// This should only be called with integers returned from
+    /// [`TopLevelMenuId::into_raw`].
+    pub const fn from_raw(id: usize) -> Self {
+        Self(id)
+    }
+}
+
+impl fmt::Debug for TopLevelMenuId {
+    fn fmt(&self, fmtr: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(fmtr)
+    }
+}
+
 /// A top-level menu in a menu bar (e.g., "File", "Edit", "View").
 #[derive(Debug, Clone)]
 pub struct TopLevelMenu<T> {
@@ -40,6 +85,9 @@ pub struct TopLevelMenu<T> {
     pub label: String,
     /// Menu entries under this top-level menu.
     pub items: Vec<MenuEntry<T>>,
+    /// Whether `&` in the label marks an access key, as for
+    /// [`MenuItem::mnemonic`](crate::menu::MenuItem::mnemonic).
+    pub mnemonic: bool,
 }
 
 impl<T> TopLevelMenu<T> {
@@ -48,8 +96,16 @@ impl<T> TopLevelMenu<T> {
         Self {
             label: label.into(),
             items,
+            mnemonic: false,
         }
     }
+
+    /// Treat `&` in the label as an access key marker, e.g. "&File" for
+    /// Alt+F on Windows.
+    pub fn mnemonic(mut self, mnemonic: bool) -> Self {
+        self.mnemonic = mnemonic;
+        self
+    }
 }
 
 impl<T> From<Submenu<T>> for TopLevelMenu<T> {
@@ -57,6 +113,7 @@ impl<T> From<Submenu<T>> for TopLevelMenu<T> {
         Self {
             label: submenu.label,
             items: submenu.items,
+            mnemonic: submenu.mnemonic,
         }
     }
 }
@@ -76,7 +133,9 @@ pub enum MenuBarEvent<T = ()> {
 pub type MenuBarProxy<T = ()> = std::sync::Arc<dyn Fn(MenuBarId, MenuBarEvent<T>) + Send + Sync>;
 
 /// Trait for menu bar operations.
-pub trait MenuBar: fmt::Debug {
+///
+/// `T` is the menu item ID type, as in [`MenuBarAttributes`].
+pub trait MenuBar<T = ()>: fmt::Debug {
     /// Get the unique identifier for this menu bar.
     fn id(&self) -> MenuBarId;
 
@@ -85,6 +144,49 @@ pub trait MenuBar: fmt::Debug {
     /// On macOS, this resets the application menu to empty.
     /// On Windows, this removes the menu bar from the window.
     fn remove(&self);
+
+    /// Number of top-level menus, read back from the native menu.
+    fn item_count(&self) -> usize;
+
+    /// Labels of the top-level menus in order, read back from the native
+    /// menu as displayed, without access key markers.
+    fn top_level_labels(&self) -> Vec<String>;
+
+    /// Change the label of the menu item with the given `id`.
+    ///
+    /// Updates the native item in place instead of rebuilding the menu bar.
+    /// The label is read the same way as the item's original one, following
+    /// its [`mnemonic`](crate::menu::MenuItem::mnemonic) setting. Fails if no
+    /// item has this ID.
+    fn set_label(
+        &self,
+        id: &T,
+        label: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>>
+    where
+        T: PartialEq;
+
+    /// Append a top-level menu after the existing ones.
+    ///
+    /// Only the new menu is built, so menus can come and go at runtime, for
+    /// example as plugins load, without recreating the menu bar. On macOS
+    /// items with an application menu role stay in the added menu. The
+    /// returned ID removes the menu again with
+    /// [`remove_top_level`](Self::remove_top_level).
+    fn add_top_level(
+        &self,
+        menu: TopLevelMenu<T>,
+    ) -> Result<TopLevelMenuId, Box<dyn std::error::Error + Send + Sync>>;
+
+    /// Remove a top-level menu added with
+    /// [`add_top_level`](Self::add_top_level), along with its items.
+    ///
+    /// Fails if no menu with this ID was added to this menu bar, including
+    /// for menus it was created with.
+    fn remove_top_level(
+        &self,
+        id: TopLevelMenuId,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
 }
 
 /// Configuration for creating a menu bar.
@@ -92,7 +194,8 @@ pub trait MenuBar: fmt::Debug {
 pub struct MenuBarAttributes<T = ()> {
     /// Top-level menus in the menu bar.
     pub menus: Vec<TopLevelMenu<T>>,
-    /// Parent window handle (required on Windows, ignored on macOS).
+    /// Parent window handle (required on Windows). On macOS it is only used
+    /// by the window [`MenuRole`](crate::MenuRole)s.
     pub parent_window: Option<rwh_06::RawWindowHandle>,
 }
 
@@ -127,4 +230,16 @@ impl<T> MenuBarAttributes<T> {
         self.parent_window = Some(parent_window);
         self
     }
+
+    /// Check the attributes for mistakes that would otherwise only surface
+    /// once the platform menu bar is being created.
+    ///
+    /// Fails with [`TrayError::MissingParentWindow`] on Windows if no parent
+    /// window was set.
+    pub fn validate(&self) -> Result<(), TrayError> {
+        if cfg!(target_os = "windows") && self.parent_window.is_none() {
+            return Err(TrayError::MissingParentWindow);
+        }
+        crate::error::check_parent_window(self.parent_window.as_ref())
+    }
 }
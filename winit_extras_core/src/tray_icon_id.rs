@@ -1,14 +1,30 @@
 use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(1);
 
 /// Unique identifier for a tray icon.
 ///
 /// Obtained via [`TrayIcon::id`][`crate::TrayIcon::id`]. Every [`Event`][`crate::Event`]
 /// that originates from a tray icon click carries the `TrayIconId` of the icon
 /// that was clicked, so applications with multiple trays can disambiguate events.
+///
+/// IDs are never reused within a process: each tray gets an ID greater than
+/// those of all trays created before it, even after they were dropped.
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct TrayIconId(usize);
 
 impl TrayIconId {
+    /// Allocate the ID for a new tray icon.
+    ///
+    /// Every backend gets its IDs from here. Custom [`TrayIconRenderer`]s
+    /// should too, so their IDs can't collide with the built-in trays.
+    ///
+    /// [`TrayIconRenderer`]: crate::TrayIconRenderer
+    pub fn allocate() -> Self {
+        Self(NEXT_ID.fetch_add(1, Ordering::Relaxed))
+    }
+
     /// Convert the `TrayIconId` into the underlying integer.
     ///
     /// Useful for passing the ID across an FFI boundary or storing it in an atomic.
@@ -29,3 +45,31 @@ impl fmt::Debug for TrayIconId {
         self.0.fmt(fmtr)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allocated_ids_are_unique_and_increasing() {
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                std::thread::spawn(|| {
+                    let ids: Vec<_> = (0..1000).map(|_| TrayIconId::allocate()).collect();
+                    assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+                    ids
+                })
+            })
+            .collect();
+
+        let mut ids: Vec<_> = threads
+            .into_iter()
+            .flat_map(|thread| thread.join().unwrap())
+            .collect();
+        let count = ids.len();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), count);
+        assert!(ids[0].into_raw() > 0);
+    }
+}
@@ -1,9 +1,21 @@
-use crate::util::SniIcon;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::util::{SniIcon, split_tooltip};
 use dpi::PhysicalPosition;
 use tracing::trace;
 use winit_core::event::{ButtonSource, ElementState, MouseButton};
-use winit_extras_core::{Event, EventCallback, tray_icon_id::TrayIconId};
-use zbus::zvariant::ObjectPath;
+use winit_extras_core::{
+    ButtonMask, Event, EventCallback, ScrollOrientation, tray_icon_id::TrayIconId,
+};
+use zbus::message::{Header, Message};
+use zbus::names::{InterfaceName, MemberName};
+use zbus::object_server::{DispatchResult, Interface, SignalEmitter};
+use zbus::zvariant::{ObjectPath, OwnedValue, Value};
+use zbus::{Connection, ObjectServer, fdo};
 
 /// StatusNotifierItem D-Bus interface implementation.
 ///
@@ -11,11 +23,93 @@ use zbus::zvariant::ObjectPath;
 /// `org.kde.StatusNotifierItem` D-Bus interface.
 pub struct StatusNotifierItemInterface<T> {
     pub(crate) id: String,
+    /// The item's name, which hosts show in lists of tray items.
     pub(crate) title: String,
+    /// Shown on hover; the first line is the title, the rest the description.
+    pub(crate) tooltip: Option<String>,
     pub(crate) icon_pixmap: Vec<SniIcon>,
     pub(crate) tray_icon_id: TrayIconId,
     pub(crate) proxy: EventCallback<T>,
     pub(crate) menu: Option<ObjectPath<'static>>,
+    pub(crate) item_is_menu: bool,
+    /// Shared with the `Tray`, which reads it back on the app's thread.
+    pub(crate) last_pointer_position: Arc<Mutex<Option<PhysicalPosition<f64>>>>,
+    pub(crate) button_filter: ButtonMask,
+    /// Shared with the `Tray`; clicks are ignored while it is `false`.
+    pub(crate) interactive: Arc<AtomicBool>,
+    /// When the last activation that didn't complete a double-click
+    /// arrived.
+    pub(crate) last_activation: Option<Instant>,
+}
+
+/// How close two activations have to be to count as a double-click, since
+/// tray hosts don't report double-clicks themselves.
+const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(400);
+
+// Not derived, since that would require `T: Clone` and the callback is shared
+impl<T> Clone for StatusNotifierItemInterface<T> {
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id.clone(),
+            title: self.title.clone(),
+            tooltip: self.tooltip.clone(),
+            icon_pixmap: self.icon_pixmap.clone(),
+            tray_icon_id: self.tray_icon_id,
+            proxy: self.proxy.clone(),
+            menu: self.menu.clone(),
+            item_is_menu: self.item_is_menu,
+            last_pointer_position: self.last_pointer_position.clone(),
+            button_filter: self.button_filter,
+            interactive: self.interactive.clone(),
+            last_activation: self.last_activation,
+        }
+    }
+}
+
+impl<T> StatusNotifierItemInterface<T> {
+    fn set_last_pointer_position(&self, position: PhysicalPosition<f64>) {
+        if let Ok(mut last) = self.last_pointer_position.lock() {
+            *last = Some(position);
+        }
+    }
+
+    /// Reports a click, unless `button` is filtered out or the tray isn't
+    /// interactive. The host only tells us about activations, so they are
+    /// reported as releases.
+    fn send_click(&self, position: PhysicalPosition<f64>, button: MouseButton) {
+        if !self.button_filter.contains(button) || !self.interactive.load(Ordering::Relaxed) {
+            return;
+        }
+        (self.proxy)(Event::PointerButton {
+            tray_icon_id: self.tray_icon_id,
+            state: ElementState::Released,
+            position,
+            button: ButtonSource::Mouse(button),
+            timestamp: Instant::now(),
+        });
+    }
+
+    /// Reports a left double-click if the previous activation was recent
+    /// enough.
+    fn check_double_click(&mut self, position: PhysicalPosition<f64>) {
+        let now = Instant::now();
+        let is_double_click = self
+            .last_activation
+            .is_some_and(|last| now.duration_since(last) <= DOUBLE_CLICK_INTERVAL);
+        // A third activation starts a new double-click.
+        self.last_activation = (!is_double_click).then_some(now);
+        if !is_double_click
+            || !self.button_filter.contains(MouseButton::Left)
+            || !self.interactive.load(Ordering::Relaxed)
+        {
+            return;
+        }
+        (self.proxy)(Event::DoubleClick {
+            tray_icon_id: self.tray_icon_id,
+            button: MouseButton::Left,
+            position,
+        });
+    }
 }
 
 #[zbus::interface(name = "org.kde.StatusNotifierItem")]
@@ -25,12 +119,9 @@ impl<T: Clone + Send + Sync + 'static> StatusNotifierItemInterface<T> {
         trace!(x, y, "StatusNotifierItem::Activate called");
 
         let position = PhysicalPosition::new(x as f64, y as f64);
-        (self.proxy)(Event::PointerButton {
-            tray_icon_id: self.tray_icon_id,
-            state: ElementState::Released,
-            position,
-            button: ButtonSource::Mouse(MouseButton::Left),
-        });
+        self.set_last_pointer_position(position);
+        self.send_click(position, MouseButton::Left);
+        self.check_double_click(position);
     }
 
     /// Called when the user performs a secondary activation (typically right-click).
@@ -38,24 +129,44 @@ impl<T: Clone + Send + Sync + 'static> StatusNotifierItemInterface<T> {
         trace!(x, y, "StatusNotifierItem::SecondaryActivate called");
 
         let position = PhysicalPosition::new(x as f64, y as f64);
-        (self.proxy)(Event::PointerButton {
-            tray_icon_id: self.tray_icon_id,
-            state: ElementState::Released,
-            position,
-            button: ButtonSource::Mouse(MouseButton::Right),
-        });
+        self.set_last_pointer_position(position);
+        self.send_click(position, MouseButton::Right);
+    }
+
+    /// Called when the host asks the item to show its context menu (typically right-click).
+    fn context_menu(&mut self, x: i32, y: i32) {
+        trace!(x, y, "StatusNotifierItem::ContextMenu called");
+
+        let position = PhysicalPosition::new(x as f64, y as f64);
+        self.set_last_pointer_position(position);
+        self.send_click(position, MouseButton::Right);
     }
 
     /// Called when the user scrolls on the tray icon.
     fn scroll(&mut self, delta: i32, orientation: &str) {
         trace!(delta, orientation, "StatusNotifierItem::Scroll called");
 
-        let position = PhysicalPosition::new(0.0, 0.0);
-        (self.proxy)(Event::PointerButton {
+        let orientation = if orientation.eq_ignore_ascii_case("horizontal") {
+            ScrollOrientation::Horizontal
+        } else if orientation.eq_ignore_ascii_case("vertical") {
+            ScrollOrientation::Vertical
+        } else {
+            return;
+        };
+        if !self.interactive.load(Ordering::Relaxed) {
+            return;
+        }
+        let position = self
+            .last_pointer_position
+            .lock()
+            .ok()
+            .and_then(|last| *last)
+            .unwrap_or_default();
+        (self.proxy)(Event::Scroll {
             tray_icon_id: self.tray_icon_id,
-            state: ElementState::Released,
+            delta: delta as f64,
+            orientation,
             position,
-            button: ButtonSource::Mouse(MouseButton::Middle),
         });
     }
 
@@ -65,7 +176,7 @@ impl<T: Clone + Send + Sync + 'static> StatusNotifierItemInterface<T> {
         &self.id
     }
 
-    /// The title/tooltip for the tray icon.
+    /// The application name for the tray icon.
     #[zbus(property)]
     fn title(&self) -> &str {
         &self.title
@@ -135,7 +246,13 @@ impl<T: Clone + Send + Sync + 'static> StatusNotifierItemInterface<T> {
     /// Format: (icon_name, icon_pixmap, title, description)
     #[zbus(property)]
     fn tool_tip(&self) -> (String, Vec<SniIcon>, String, String) {
-        (String::new(), vec![], self.title.clone(), String::new())
+        let (title, description) = self.tooltip.as_deref().map_or(("", ""), split_tooltip);
+        (
+            String::new(),
+            vec![],
+            title.to_string(),
+            description.to_string(),
+        )
     }
 
     /// Icon theme path (not used).
@@ -152,9 +269,201 @@ impl<T: Clone + Send + Sync + 'static> StatusNotifierItemInterface<T> {
             .unwrap_or_else(|| ObjectPath::try_from("/").expect("Invalid root path"))
     }
 
-    /// Whether the item is a menu itself.
+    /// Whether the item only supports showing its menu, so the host opens
+    /// it on primary activation too.
     #[zbus(property)]
     fn item_is_menu(&self) -> bool {
-        false
+        self.item_is_menu
+    }
+}
+
+/// The object served at the StatusNotifierItem path: the interface plus any
+/// extra properties the application asked for.
+///
+/// The `interface` macro only serves properties known at compile time, so
+/// this implements [`Interface`] by hand, forwarding to the generated
+/// implementation and answering for the extra properties itself.
+#[derive(Clone)]
+pub struct StatusNotifierItem<T> {
+    pub(crate) interface: StatusNotifierItemInterface<T>,
+    pub(crate) extra_properties: Arc<HashMap<String, OwnedValue>>,
+}
+
+fn clone_value(value: &OwnedValue) -> fdo::Result<OwnedValue> {
+    value
+        .try_clone()
+        .map_err(|e| fdo::Error::Failed(format!("Failed to copy property: {e}")))
+}
+
+#[async_trait::async_trait]
+impl<T: Clone + Send + Sync + 'static> Interface for StatusNotifierItem<T> {
+    fn name() -> InterfaceName<'static> {
+        StatusNotifierItemInterface::<T>::name()
+    }
+
+    fn spawn_tasks_for_methods(&self) -> bool {
+        self.interface.spawn_tasks_for_methods()
+    }
+
+    async fn get(
+        &self,
+        property_name: &str,
+        server: &ObjectServer,
+        connection: &Connection,
+        header: Option<&Header<'_>>,
+        emitter: &SignalEmitter<'_>,
+    ) -> Option<fdo::Result<OwnedValue>> {
+        match (self.interface)
+            .get(property_name, server, connection, header, emitter)
+            .await
+        {
+            Some(value) => Some(value),
+            None => self.extra_properties.get(property_name).map(clone_value),
+        }
+    }
+
+    async fn get_all(
+        &self,
+        server: &ObjectServer,
+        connection: &Connection,
+        header: Option<&Header<'_>>,
+        emitter: &SignalEmitter<'_>,
+    ) -> fdo::Result<HashMap<String, OwnedValue>> {
+        let mut properties = (self.interface)
+            .get_all(server, connection, header, emitter)
+            .await?;
+        for (name, value) in self.extra_properties.iter() {
+            if !properties.contains_key(name) {
+                properties.insert(name.clone(), clone_value(value)?);
+            }
+        }
+        Ok(properties)
+    }
+
+    fn set<'call>(
+        &'call self,
+        property_name: &'call str,
+        value: &'call Value<'_>,
+        server: &'call ObjectServer,
+        connection: &'call Connection,
+        header: Option<&'call Header<'_>>,
+        emitter: &'call SignalEmitter<'_>,
+    ) -> DispatchResult<'call> {
+        (self.interface).set(property_name, value, server, connection, header, emitter)
+    }
+
+    async fn set_mut(
+        &mut self,
+        property_name: &str,
+        value: &Value<'_>,
+        server: &ObjectServer,
+        connection: &Connection,
+        header: Option<&Header<'_>>,
+        emitter: &SignalEmitter<'_>,
+    ) -> Option<fdo::Result<()>> {
+        (self.interface)
+            .set_mut(property_name, value, server, connection, header, emitter)
+            .await
+    }
+
+    fn call<'call>(
+        &'call self,
+        server: &'call ObjectServer,
+        connection: &'call Connection,
+        msg: &'call Message,
+        name: MemberName<'call>,
+    ) -> DispatchResult<'call> {
+        (self.interface).call(server, connection, msg, name)
+    }
+
+    fn call_mut<'call>(
+        &'call mut self,
+        server: &'call ObjectServer,
+        connection: &'call Connection,
+        msg: &'call Message,
+        name: MemberName<'call>,
+    ) -> DispatchResult<'call> {
+        (self.interface).call_mut(server, connection, msg, name)
+    }
+
+    fn introspect_to_writer(&self, writer: &mut dyn Write, level: usize) {
+        let mut xml = String::new();
+        self.interface.introspect_to_writer(&mut xml, level);
+
+        // Slot the extra properties in before the closing tag.
+        let closing = format!("{:indent$}</interface>\n", "", indent = level);
+        let body = xml.strip_suffix(&closing).unwrap_or(&xml);
+        writer.write_str(body).unwrap();
+        for (name, value) in self.extra_properties.iter() {
+            writeln!(
+                writer,
+                r#"{:indent$}<property name="{name}" type="{}" access="read"/>"#,
+                "",
+                value.value_signature(),
+                indent = level + 2
+            )
+            .unwrap();
+        }
+        writer.write_str(&closing).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Events = Arc<Mutex<Vec<Event<()>>>>;
+
+    fn interface() -> (StatusNotifierItemInterface<()>, Events) {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let interface = StatusNotifierItemInterface {
+            id: "test".to_owned(),
+            title: "Test".to_owned(),
+            tooltip: None,
+            icon_pixmap: Vec::new(),
+            tray_icon_id: TrayIconId::allocate(),
+            proxy: Arc::new(move |event| sink.lock().unwrap().push(event)),
+            menu: None,
+            item_is_menu: false,
+            last_pointer_position: Arc::new(Mutex::new(None)),
+            button_filter: ButtonMask::ALL,
+            interactive: Arc::new(AtomicBool::new(true)),
+            last_activation: None,
+        };
+        (interface, events)
+    }
+
+    fn double_clicks(events: &Mutex<Vec<Event<()>>>) -> usize {
+        events
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|event| matches!(event, Event::DoubleClick { .. }))
+            .count()
+    }
+
+    #[test]
+    fn test_two_activations_make_a_double_click() {
+        let (mut interface, events) = interface();
+        let position = PhysicalPosition::new(1.0, 2.0);
+
+        interface.check_double_click(position);
+        assert_eq!(double_clicks(&events), 0);
+        interface.check_double_click(position);
+        assert_eq!(double_clicks(&events), 1);
+        // The third activation starts over.
+        interface.check_double_click(position);
+        assert_eq!(double_clicks(&events), 1);
+    }
+
+    #[test]
+    fn test_slow_activations_are_not_a_double_click() {
+        let (mut interface, events) = interface();
+        let position = PhysicalPosition::new(1.0, 2.0);
+
+        interface.last_activation = Instant::now().checked_sub(DOUBLE_CLICK_INTERVAL * 2);
+        interface.check_double_click(position);
+        assert_eq!(double_clicks(&events), 0);
     }
 }
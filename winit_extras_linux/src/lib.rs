@@ -4,20 +4,33 @@ mod dbus_interface;
 mod util;
 
 #[cfg(feature = "menu")]
-pub mod menu;
+mod menu;
 
+use std::cell::{Cell, RefCell};
 use std::marker::PhantomData;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::{Arc, Mutex};
 use std::thread;
+use std::time::{Duration, Instant};
 
-use anyhow::{Context, Result, anyhow};
 use tracing::{debug, error, trace, warn};
+use winit_core::icon::Icon;
+use winit_extras_core::icon::StatusColor;
 use winit_extras_core::{
-    EventCallback, TrayIcon as CoreTrayIcon, TrayIconAttributes, TrayIconRenderer,
+    EventCallback, PhysicalPosition, TrayError, TrayIcon as CoreTrayIcon, TrayIconAttributes,
+    TrayIconRenderer,
 };
 use zbus::blocking::Connection;
 
-use dbus_interface::StatusNotifierItemInterface;
-use util::{SniIcon, icon_to_sni_icon};
+pub use util::{notifications_allowed, open_notification_settings};
+
+use dbus_interface::{StatusNotifierItem, StatusNotifierItemInterface};
+#[cfg(feature = "menu")]
+use menu::{DbusMenu, MENU_OBJECT_PATH};
+use util::{Context, SniIcon, icon_to_sni_pixmaps, retry_with_backoff};
+#[cfg(feature = "menu")]
+use winit_extras_core::MenuEntry;
 
 /// Uses Linux StatusNotifierItem (D-Bus) APIs.
 pub struct NativeTrayIconRenderer;
@@ -25,30 +38,57 @@ pub struct NativeTrayIconRenderer;
 impl<T: Clone + Send + Sync + 'static> TrayIconRenderer<T> for NativeTrayIconRenderer {
     fn create_tray(
         &self,
-        attributes: TrayIconAttributes,
+        attributes: TrayIconAttributes<T>,
         proxy: EventCallback<T>,
-    ) -> Result<Box<dyn CoreTrayIcon>, Box<dyn std::error::Error + Send + Sync>> {
+    ) -> Result<Box<dyn CoreTrayIcon<T>>, Box<dyn std::error::Error + Send + Sync>> {
         let tray = Tray::new(proxy, attributes)?;
         Ok(Box::new(tray))
     }
 }
 
-static COUNTER: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(1);
-
 const SNI_OBJECT_PATH: &str = "/StatusNotifierItem";
 const SNI_WATCHER_SERVICE: &str = "org.kde.StatusNotifierWatcher";
 const SNI_WATCHER_PATH: &str = "/StatusNotifierWatcher";
 
+/// Delay before the first registration retry; doubled for each one after.
+const REGISTRATION_RETRY_DELAY: Duration = Duration::from_millis(700);
+
+/// How often the background thread checks that the session bus is still up.
+const CONNECTION_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How many times to try reconnecting after losing the session bus.
+const RECONNECT_RETRIES: u32 = 5;
+
+/// Delay before the first reconnect retry; doubled for each one after.
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
 /// Linux system tray icon implementation using StatusNotifierItem.
 pub struct Tray<T = ()> {
     internal_id: usize,
     // Handle to the background thread that processes D-Bus messages
     thread_handle: Option<thread::JoinHandle<()>>,
-    // Channel to signal the background thread to stop
-    shutdown_tx: Option<std::sync::mpsc::Sender<()>>,
+    // Channel to send commands to the background thread
+    command_tx: Option<std::sync::mpsc::Sender<Command>>,
+    // The current icon and status dot, for drawing one again when the other
+    // changes
+    icon_state: Mutex<IconState>,
+    // Updated by the D-Bus thread when the tray host reports a click
+    last_pointer_position: Arc<Mutex<Option<PhysicalPosition<f64>>>>,
+    // Read by the D-Bus thread before reporting a click
+    interactive: Arc<AtomicBool>,
+    // Shared with the D-Bus thread, which serves it
+    #[cfg(feature = "menu")]
+    menu: Option<DbusMenu<T>>,
     _marker: PhantomData<T>,
 }
 
+/// The icon a tray shows and the status dot drawn over it.
+#[derive(Default)]
+struct IconState {
+    icon: Option<Icon>,
+    status_dot: Option<StatusColor>,
+}
+
 impl<T> std::fmt::Debug for Tray<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Tray")
@@ -58,33 +98,86 @@ impl<T> std::fmt::Debug for Tray<T> {
 }
 
 impl<T: Clone + Send + Sync + 'static> Tray<T> {
-    pub fn new(proxy: EventCallback<T>, attr: TrayIconAttributes) -> Result<Self> {
-        let internal_id = COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-        let tray_icon_id = winit_extras_core::tray_icon_id::TrayIconId::from_raw(internal_id);
+    pub fn new(
+        proxy: EventCallback<T>,
+        #[cfg_attr(not(feature = "menu"), allow(unused_mut))] mut attr: TrayIconAttributes<T>,
+    ) -> Result<Self, TrayError> {
+        let tray_icon_id = winit_extras_core::tray_icon_id::TrayIconId::allocate();
+        let internal_id = tray_icon_id.into_raw();
 
         debug!(internal_id, "Creating new Linux tray icon");
 
         // Convert icon to SNI format
-        let icon_pixmap = if let Some(icon) = &attr.icon {
-            icon_to_sni_icon(icon).map(|i| vec![i]).unwrap_or_default()
-        } else {
-            Vec::new()
+        let icon_pixmap = match &attr.icon {
+            Some(icon) => icon_to_sni_pixmaps(icon, None)?,
+            None => Vec::new(),
         };
 
         // Generate unique ID for this tray
         let id = format!("winit_extras_{}", internal_id);
-        let title = attr.tooltip.unwrap_or_else(|| "Tray Icon".to_string());
+        let title = util::app_name();
+
+        #[cfg(feature = "menu")]
+        let item_is_menu = attr.primary_action == winit_extras_core::PrimaryAction::ShowMenu;
+        #[cfg(not(feature = "menu"))]
+        let item_is_menu = false;
+
+        let last_pointer_position = Arc::new(Mutex::new(None));
+        let interactive = Arc::new(AtomicBool::new(true));
+
+        #[cfg(feature = "menu")]
+        let menu = attr
+            .take_context_menu()
+            .map(|entries| DbusMenu::new(entries, proxy.clone(), interactive.clone()));
 
         // EventCallback is already Arc-wrapped
+        let interface = StatusNotifierItemInterface {
+            id,
+            title,
+            tooltip: attr.tooltip,
+            icon_pixmap,
+            tray_icon_id,
+            proxy,
+            menu: {
+                #[cfg(feature = "menu")]
+                {
+                    menu.as_ref().map(|_| {
+                        zbus::zvariant::ObjectPath::try_from(MENU_OBJECT_PATH)
+                            .expect("Invalid menu path")
+                    })
+                }
+                #[cfg(not(feature = "menu"))]
+                {
+                    None
+                }
+            },
+            item_is_menu,
+            last_pointer_position: last_pointer_position.clone(),
+            button_filter: attr.button_filter,
+            interactive: interactive.clone(),
+            last_activation: None,
+        };
+        let item = StatusNotifierItem {
+            interface,
+            extra_properties: Arc::new(attr.extra_sni_properties),
+        };
+        let registration_retries = attr.registration_retries;
 
-        // Create shutdown channel
-        let (shutdown_tx, shutdown_rx) = std::sync::mpsc::channel();
+        // Create command channel
+        let (command_tx, command_rx) = std::sync::mpsc::channel();
+
+        #[cfg(feature = "menu")]
+        let served_menu = menu.clone();
 
         // Spawn background thread for D-Bus message processing
         let thread_handle = thread::spawn(move || {
-            if let Err(e) =
-                run_dbus_service(id, title, icon_pixmap, tray_icon_id, proxy, shutdown_rx)
-            {
+            if let Err(e) = run_dbus_service(
+                item,
+                #[cfg(feature = "menu")]
+                served_menu,
+                registration_retries,
+                command_rx,
+            ) {
                 error!("D-Bus service error: {}", e);
             }
         });
@@ -92,16 +185,131 @@ impl<T: Clone + Send + Sync + 'static> Tray<T> {
         Ok(Tray {
             internal_id,
             thread_handle: Some(thread_handle),
-            shutdown_tx: Some(shutdown_tx),
+            command_tx: Some(command_tx),
+            icon_state: Mutex::new(IconState {
+                icon: attr.icon,
+                status_dot: None,
+            }),
+            last_pointer_position,
+            interactive,
+            #[cfg(feature = "menu")]
+            menu,
             _marker: PhantomData,
         })
     }
 }
 
-impl<T> CoreTrayIcon for Tray<T> {
+impl<T> CoreTrayIcon<T> for Tray<T> {
     fn id(&self) -> winit_extras_core::tray_icon_id::TrayIconId {
         winit_extras_core::tray_icon_id::TrayIconId::from_raw(self.internal_id)
     }
+
+    fn last_pointer_position(&self) -> Option<PhysicalPosition<f64>> {
+        *self.last_pointer_position.lock().ok()?
+    }
+
+    fn set_icon(&self, icon: Option<&Icon>) -> Result<(), TrayError> {
+        let mut state = self
+            .icon_state
+            .lock()
+            .map_err(|_| TrayError::Platform("tray icon state is poisoned".to_owned()))?;
+        // An empty list clears the icon; hosts show their own placeholder.
+        let pixmaps = match icon {
+            Some(icon) => icon_to_sni_pixmaps(icon, state.status_dot)?,
+            None => Vec::new(),
+        };
+        self.send(Command::SetIcon(pixmaps))?;
+        state.icon = icon.cloned();
+        Ok(())
+    }
+
+    fn set_status_dot(&self, color: Option<StatusColor>) -> Result<(), TrayError> {
+        let mut state = self
+            .icon_state
+            .lock()
+            .map_err(|_| TrayError::Platform("tray icon state is poisoned".to_owned()))?;
+        let icon = state.icon.as_ref().ok_or(TrayError::NoIcon)?;
+        let pixmaps = icon_to_sni_pixmaps(icon, color)?;
+        self.send(Command::SetIcon(pixmaps))?;
+        state.status_dot = color;
+        Ok(())
+    }
+
+    fn set_tooltip(&self, tooltip: Option<&str>) -> Result<(), TrayError> {
+        let tooltip = tooltip.filter(|tooltip| !tooltip.is_empty());
+        self.send(Command::SetTooltip(tooltip.map(str::to_owned)))
+    }
+
+    fn flash_tooltip(&self, tooltip: &str, duration: Duration) -> Result<(), TrayError> {
+        self.send(Command::FlashTooltip(tooltip.to_owned(), duration))
+    }
+
+    fn set_interactive(&self, interactive: bool) -> Result<(), TrayError> {
+        self.interactive.store(interactive, Ordering::Relaxed);
+        Ok(())
+    }
+
+    #[cfg(feature = "menu")]
+    fn set_label(&self, id: &T, label: &str) -> Result<(), TrayError>
+    where
+        T: PartialEq,
+    {
+        let revision = self
+            .menu
+            .as_ref()
+            .ok_or(TrayError::NoContextMenu)?
+            .update_item(id, |item| item.label = label.to_owned())?;
+        self.send(Command::LayoutUpdated(revision))
+    }
+
+    #[cfg(feature = "menu")]
+    fn set_item_icon(&self, id: &T, icon: Option<Icon>) -> Result<(), TrayError>
+    where
+        T: PartialEq,
+    {
+        let revision = self
+            .menu
+            .as_ref()
+            .ok_or(TrayError::NoContextMenu)?
+            .update_item(id, |item| item.icon = icon)?;
+        self.send(Command::LayoutUpdated(revision))
+    }
+
+    #[cfg(feature = "menu")]
+    fn set_menu(&self, menu: Vec<MenuEntry<T>>) -> Result<(), TrayError> {
+        let revision = self
+            .menu
+            .as_ref()
+            .ok_or(TrayError::NoContextMenu)?
+            .replace(menu)?;
+        self.send(Command::LayoutUpdated(revision))
+    }
+}
+
+impl<T> Tray<T> {
+    /// Hands `command` to the D-Bus service thread.
+    fn send(&self, command: Command) -> Result<(), TrayError> {
+        self.command_tx
+            .as_ref()
+            .and_then(|tx| tx.send(command).ok())
+            .ok_or_else(|| TrayError::Platform("the D-Bus service thread has exited".to_owned()))
+    }
+}
+
+/// Messages from a [`Tray`] to its D-Bus service thread.
+enum Command {
+    /// Advertise new icon pixmaps.
+    SetIcon(Vec<SniIcon>),
+    /// Advertise a new tooltip, or none.
+    SetTooltip(Option<String>),
+    /// Advertise a tooltip until the duration has passed, then the one last
+    /// set again.
+    FlashTooltip(String, Duration),
+    /// Tell the host the menu changed, giving its new revision.
+    #[cfg(feature = "menu")]
+    LayoutUpdated(u32),
+    /// Remove the tray and exit.
+    Shutdown,
 }
 
 impl<T> Drop for Tray<T> {
@@ -109,8 +317,8 @@ impl<T> Drop for Tray<T> {
         debug!(internal_id = self.internal_id, "Dropping Linux tray icon");
 
         // Signal the background thread to shutdown
-        if let Some(shutdown_tx) = self.shutdown_tx.take() {
-            let _ = shutdown_tx.send(());
+        if let Some(command_tx) = self.command_tx.take() {
+            let _ = command_tx.send(Command::Shutdown);
         }
 
         // Wait for the background thread to finish (with timeout)
@@ -127,7 +335,10 @@ impl<T> Drop for Tray<T> {
                 let _ = handle.join();
                 debug!("Background thread cleaned up successfully");
             } else {
-                warn!("Background thread did not exit cleanly within timeout");
+                warn!(
+                    internal_id = self.internal_id,
+                    "Background thread did not exit cleanly within timeout, tray may stay registered"
+                );
             }
         }
     }
@@ -138,46 +349,222 @@ impl<T> Drop for Tray<T> {
 /// This function:
 /// 1. Connects to the session bus
 /// 2. Registers the StatusNotifierItem interface
-/// 3. Registers with the StatusNotifierWatcher
-/// 4. Processes D-Bus messages in a loop until shutdown signal received
+/// 3. Registers with the StatusNotifierWatcher, retrying up to
+///    `registration_retries` times if it is not up yet
+/// 4. Handles commands until told to shut down, checking the connection
+///    every [`CONNECTION_CHECK_INTERVAL`] and reconnecting if it was lost
 fn run_dbus_service<T: Clone + Send + Sync + 'static>(
-    id: String,
-    title: String,
-    icon_pixmap: Vec<SniIcon>,
-    tray_icon_id: winit_extras_core::tray_icon_id::TrayIconId,
-    proxy: EventCallback<T>,
-    shutdown_rx: std::sync::mpsc::Receiver<()>,
-) -> Result<()> {
+    mut item: StatusNotifierItem<T>,
+    #[cfg(feature = "menu")] menu: Option<DbusMenu<T>>,
+    registration_retries: u32,
+    command_rx: std::sync::mpsc::Receiver<Command>,
+) -> Result<(), TrayError> {
     trace!("Starting D-Bus service thread");
 
-    // Connect to session bus
-    let connection = Connection::session().context("Failed to connect to D-Bus session bus")?;
+    let id = item.interface.id.clone();
+
+    // Set by any wait that sees the shutdown signal, so retries are cut short
+    let shutdown_requested = Cell::new(false);
+    // Icon and tooltip changes that arrived while waiting, applied once
+    // connected
+    let pending_icon = Cell::new(None);
+    let pending_tooltip = Cell::new(None);
+    #[cfg(feature = "menu")]
+    let pending_layout = Cell::new(None);
+    // The tooltip last set, which a flashed one reverts to at `revert_at`
+    let base_tooltip = RefCell::new(item.interface.tooltip.clone());
+    let revert_at = Cell::new(None);
+    // Queues a command's changes, returning `false` for shutdown
+    let queue = |command| match command {
+        Command::SetIcon(pixmaps) => {
+            pending_icon.set(Some(pixmaps));
+            true
+        }
+        Command::SetTooltip(tooltip) => {
+            revert_at.set(None);
+            base_tooltip.replace(tooltip.clone());
+            pending_tooltip.set(Some(tooltip));
+            true
+        }
+        Command::FlashTooltip(tooltip, duration) => {
+            revert_at.set(Instant::now().checked_add(duration));
+            pending_tooltip.set(Some(Some(tooltip)));
+            true
+        }
+        #[cfg(feature = "menu")]
+        Command::LayoutUpdated(revision) => {
+            pending_layout.set(Some(revision));
+            true
+        }
+        Command::Shutdown => {
+            shutdown_requested.set(true);
+            false
+        }
+    };
+    let wait = |delay| match command_rx.recv_timeout(delay) {
+        Err(RecvTimeoutError::Timeout) => true,
+        Ok(command) => queue(command),
+        Err(RecvTimeoutError::Disconnected) => {
+            shutdown_requested.set(true);
+            false
+        }
+    };
+    let revert_due = || revert_at.get().is_some_and(|at| at <= Instant::now());
+
+    let mut connection = serve(
+        &item,
+        #[cfg(feature = "menu")]
+        menu.as_ref(),
+        registration_retries,
+        wait,
+    )?;
 
-    debug!("Connected to D-Bus session bus");
+    // Keep the D-Bus connection alive until shutdown.
+    // Note: zbus automatically processes incoming messages in a background thread,
+    // this one only keeps the connection in scope and makes sure it is still up.
+    if shutdown_requested.get() {
+        debug!("Shutdown requested during registration, cleaning up");
+    } else {
+        debug!("D-Bus service thread running, waiting for shutdown signal");
+    }
 
-    // Create the StatusNotifierItem interface
-    let interface = StatusNotifierItemInterface {
-        id: id.clone(),
-        title,
-        icon_pixmap,
-        tray_icon_id,
-        proxy,
-        menu: {
-            #[cfg(feature = "menu")]
-            {
-                Some(zbus::zvariant::ObjectPath::try_from("/MenuBar").expect("Invalid menu path"))
+    while !shutdown_requested.get() {
+        if revert_due() {
+            revert_at.set(None);
+            pending_tooltip.set(Some(base_tooltip.borrow().clone()));
+        }
+        if let Some(pixmaps) = pending_icon.take() {
+            // Kept so a reconnect serves the current icon
+            item.interface.icon_pixmap = pixmaps;
+            if let Err(e) = update_icon::<T>(&connection, &item.interface.icon_pixmap) {
+                warn!(%id, "Failed to update tray icon: {}", e);
             }
-            #[cfg(not(feature = "menu"))]
-            {
-                None
+        }
+        if let Some(tooltip) = pending_tooltip.take() {
+            // Kept so a reconnect serves the current tooltip
+            item.interface.tooltip = tooltip;
+            if let Err(e) = update_tooltip::<T>(&connection, &item.interface.tooltip) {
+                warn!(%id, "Failed to update tray tooltip: {}", e);
             }
-        },
-    };
+        }
+        // The served menu already has the new entries
+        #[cfg(feature = "menu")]
+        if let Some(revision) = pending_layout.take()
+            && let Err(e) = update_layout(&connection, revision)
+        {
+            warn!(%id, "Failed to update tray menu: {}", e);
+        }
+
+        // Wake up in time to revert a flashed tooltip
+        let timeout = revert_at.get().map_or(CONNECTION_CHECK_INTERVAL, |at| {
+            at.saturating_duration_since(Instant::now())
+                .min(CONNECTION_CHECK_INTERVAL)
+        });
+        match command_rx.recv_timeout(timeout) {
+            Ok(Command::Shutdown) => {
+                debug!("Received shutdown signal, cleaning up");
+                break;
+            }
+            Ok(command) => {
+                queue(command);
+                continue;
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                debug!("Shutdown channel disconnected, exiting");
+                break;
+            }
+            Err(RecvTimeoutError::Timeout) if revert_due() => continue,
+            Err(RecvTimeoutError::Timeout) => {}
+        }
+
+        if is_connected(&connection) {
+            continue;
+        }
+
+        // The bus went away (e.g. the session bus was restarted). Everything
+        // registered on the old connection is gone, so start over.
+        warn!(%id, "Lost connection to D-Bus session bus, reconnecting");
+        let reconnection =
+            retry_with_backoff(RECONNECT_RETRIES, RECONNECT_DELAY, wait, |attempt| {
+                serve(
+                    &item,
+                    #[cfg(feature = "menu")]
+                    menu.as_ref(),
+                    registration_retries,
+                    wait,
+                )
+                .inspect_err(|e| {
+                    debug!(%id, attempt, "Reconnecting to D-Bus session bus failed: {}", e);
+                })
+            });
+        match reconnection {
+            Ok(new_connection) => {
+                debug!(%id, "Reconnected to D-Bus session bus");
+                connection = new_connection;
+            }
+            Err(_) if shutdown_requested.get() => {
+                debug!("Shutdown requested while reconnecting, exiting");
+                return Ok(());
+            }
+            Err(e) => {
+                error!(
+                    %id,
+                    "Giving up reconnecting to D-Bus session bus: {}. Tray icon will not appear.",
+                    e
+                );
+                return Err(e);
+            }
+        }
+    }
+
+    // Unregister from StatusNotifierWatcher before exiting
+    if let Err(e) = unregister_from_watcher(&connection, &id) {
+        warn!(%id, "Failed to unregister from StatusNotifierWatcher: {}", e);
+    }
+
+    // Remove the interface from the object server
+    match connection
+        .object_server()
+        .remove::<StatusNotifierItem<T>, _>(SNI_OBJECT_PATH)
+    {
+        Ok(true) => {}
+        Ok(false) => warn!(%id, "StatusNotifierItem interface was already removed"),
+        Err(e) => warn!(%id, "Failed to remove StatusNotifierItem interface: {}", e),
+    }
+    #[cfg(feature = "menu")]
+    if menu.is_some()
+        && let Err(e) = connection
+            .object_server()
+            .remove::<DbusMenu<T>, _>(MENU_OBJECT_PATH)
+    {
+        warn!(%id, "Failed to remove DBusMenu interface: {}", e);
+    }
+
+    debug!("D-Bus service thread exiting cleanly");
+    Ok(())
+}
+
+/// Connects to the session bus, serves a copy of `item` and of the tray's
+/// `menu` on it and registers with the StatusNotifierWatcher.
+///
+/// `wait` sleeps between registration attempts and returns `false` to stop
+/// retrying.
+fn serve<T: Clone + Send + Sync + 'static>(
+    item: &StatusNotifierItem<T>,
+    #[cfg(feature = "menu")] menu: Option<&DbusMenu<T>>,
+    registration_retries: u32,
+    wait: impl FnMut(Duration) -> bool,
+) -> Result<Connection, TrayError> {
+    let connection = Connection::session().context("Failed to connect to D-Bus session bus")?;
+
+    debug!("Connected to D-Bus session bus");
+
+    let id = item.interface.id.clone();
 
     // Register the interface at the object path
     connection
         .object_server()
-        .at(SNI_OBJECT_PATH, interface)
+        .at(SNI_OBJECT_PATH, item.clone())
         .context("Failed to register StatusNotifierItem interface")?;
 
     debug!(
@@ -185,54 +572,118 @@ fn run_dbus_service<T: Clone + Send + Sync + 'static>(
         "Registered StatusNotifierItem interface"
     );
 
-    // Register with StatusNotifierWatcher
-    if let Err(e) = register_with_watcher(&connection, &id) {
+    #[cfg(feature = "menu")]
+    if let Some(menu) = menu {
+        connection
+            .object_server()
+            .at(MENU_OBJECT_PATH, menu.clone())
+            .context("Failed to register DBusMenu interface")?;
+        debug!(path = MENU_OBJECT_PATH, "Registered DBusMenu interface");
+    }
+
+    // Register with StatusNotifierWatcher. The watcher is owned by the panel,
+    // which may not be up yet when the app is started on login, so retry a
+    // few times. A shutdown request cuts the wait short.
+    let registration = retry_with_backoff(
+        registration_retries,
+        REGISTRATION_RETRY_DELAY,
+        wait,
+        |attempt| {
+            register_with_watcher(&connection, &id).inspect_err(|e| {
+                debug!(%id, attempt, "StatusNotifierWatcher registration failed: {}", e);
+            })
+        },
+    );
+    if let Err(e) = registration {
         warn!(
+            %id,
             "Failed to register with StatusNotifierWatcher: {}. Tray icon may not appear.",
             e
         );
         // Continue anyway - some DEs might work without explicit registration
     }
 
-    // Keep the D-Bus connection alive and process messages until shutdown
-    // Note: zbus automatically processes incoming messages in a background thread,
-    // we just need to keep this thread alive and the connection in scope.
-    debug!("D-Bus service thread running, waiting for shutdown signal");
+    Ok(connection)
+}
 
-    match shutdown_rx.recv() {
-        Ok(_) => {
-            debug!("Received shutdown signal, cleaning up");
-        }
-        Err(_) => {
-            debug!("Shutdown channel disconnected, exiting");
-        }
-    }
+/// Replaces the served icon pixmaps and tells the tray host to reload them.
+fn update_icon<T: Clone + Send + Sync + 'static>(
+    connection: &Connection,
+    pixmaps: &[SniIcon],
+) -> Result<(), TrayError> {
+    let interface = connection
+        .object_server()
+        .interface::<_, StatusNotifierItem<T>>(SNI_OBJECT_PATH)
+        .context("StatusNotifierItem interface is not registered")?;
+    interface.get_mut().interface.icon_pixmap = pixmaps.to_vec();
 
-    // Unregister from StatusNotifierWatcher before exiting
-    if let Err(e) = unregister_from_watcher(&connection, &id) {
-        warn!("Failed to unregister from StatusNotifierWatcher: {}", e);
-    }
+    connection
+        .emit_signal(
+            None::<zbus::names::BusName<'_>>,
+            SNI_OBJECT_PATH,
+            "org.kde.StatusNotifierItem",
+            "NewIcon",
+            &(),
+        )
+        .context("Failed to emit NewIcon")?;
+    Ok(())
+}
 
-    // Remove the interface from the object server
-    let _ = connection
+/// Replaces the served tooltip and tells the tray host to reload it.
+fn update_tooltip<T: Clone + Send + Sync + 'static>(
+    connection: &Connection,
+    tooltip: &Option<String>,
+) -> Result<(), TrayError> {
+    let interface = connection
         .object_server()
-        .remove::<StatusNotifierItemInterface<T>, _>(SNI_OBJECT_PATH);
+        .interface::<_, StatusNotifierItem<T>>(SNI_OBJECT_PATH)
+        .context("StatusNotifierItem interface is not registered")?;
+    interface.get_mut().interface.tooltip = tooltip.clone();
 
-    debug!("D-Bus service thread exiting cleanly");
+    connection
+        .emit_signal(
+            None::<zbus::names::BusName<'_>>,
+            SNI_OBJECT_PATH,
+            "org.kde.StatusNotifierItem",
+            "NewToolTip",
+            &(),
+        )
+        .context("Failed to emit NewToolTip")?;
     Ok(())
 }
 
+/// Emits the DBusMenu `LayoutUpdated` signal, so hosts fetch the whole menu
+/// again.
+#[cfg(feature = "menu")]
+fn update_layout(connection: &Connection, revision: u32) -> Result<(), TrayError> {
+    connection
+        .emit_signal(
+            None::<zbus::names::BusName<'_>>,
+            MENU_OBJECT_PATH,
+            "com.canonical.dbusmenu",
+            "LayoutUpdated",
+            &(revision, 0i32),
+        )
+        .context("Failed to emit LayoutUpdated")?;
+    Ok(())
+}
+
+/// Checks whether the bus still answers on `connection`.
+fn is_connected(connection: &Connection) -> bool {
+    zbus::blocking::fdo::DBusProxy::new(connection).is_ok_and(|proxy| proxy.get_id().is_ok())
+}
+
 /// Registers this tray icon with the StatusNotifierWatcher.
 ///
 /// The StatusNotifierWatcher is a system service that keeps track of all
 /// active tray icons and notifies the desktop environment about them.
-fn register_with_watcher(connection: &Connection, _id: &str) -> Result<()> {
+fn register_with_watcher(connection: &Connection, _id: &str) -> Result<(), TrayError> {
     trace!("Registering with StatusNotifierWatcher");
 
     // Get the unique name of our connection
     let unique_name = connection
         .unique_name()
-        .ok_or_else(|| anyhow!("Failed to get D-Bus unique name"))?;
+        .context("Failed to get D-Bus unique name")?;
 
     // Create service name: unique_name + object_path
     let service_name = format!("{}{}", unique_name, SNI_OBJECT_PATH);
@@ -245,7 +696,8 @@ fn register_with_watcher(connection: &Connection, _id: &str) -> Result<()> {
         SNI_WATCHER_SERVICE,
         SNI_WATCHER_PATH,
         "org.kde.StatusNotifierWatcher",
-    )?;
+    )
+    .context("Failed to create StatusNotifierWatcher proxy")?;
 
     proxy
         .call::<&str, _, ()>("RegisterStatusNotifierItem", &service_name)
@@ -259,13 +711,13 @@ fn register_with_watcher(connection: &Connection, _id: &str) -> Result<()> {
 ///
 /// This should be called before the tray is destroyed to ensure the icon
 /// disappears from the system tray immediately.
-fn unregister_from_watcher(connection: &Connection, _id: &str) -> Result<()> {
+fn unregister_from_watcher(connection: &Connection, _id: &str) -> Result<(), TrayError> {
     trace!("Unregistering from StatusNotifierWatcher");
 
     // Get the unique name of our connection
     let unique_name = connection
         .unique_name()
-        .ok_or_else(|| anyhow!("Failed to get D-Bus unique name"))?;
+        .context("Failed to get D-Bus unique name")?;
 
     // Create service name: unique_name + object_path
     let service_name = format!("{}{}", unique_name, SNI_OBJECT_PATH);
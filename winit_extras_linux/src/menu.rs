@@ -1,17 +1,751 @@
-// Menu support for Linux tray icons using DBusMenu protocol.
-//
-// TODO: Implement com.canonical.dbusmenu interface
-// For now, this is a placeholder. Full menu support requires implementing
-// the DBusMenu specification, which is complex.
-//
-// See ksni's menu implementation for reference:
-// https://github.com/iovxw/ksni/blob/master/src/menu.rs
+//! Tray menus exported over D-Bus with the `com.canonical.dbusmenu`
+//! interface, which the tray host reads to draw the menu itself.
+//!
+//! Entries are identified by their position in the menu: the root is 0 and
+//! every entry, including separators and submenus, is numbered depth first
+//! from 1 in the order it is shown. Replacing the menu renumbers it and
+//! bumps the layout revision, so hosts refetch it before using the new ids.
 
 #![cfg(feature = "menu")]
 
-use winit_extras_core::MenuEntry;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
-pub fn create_menu<T>(_entries: &[MenuEntry<T>]) {
-    // TODO: Implement DBusMenu interface
-    unimplemented!("Menu support not yet implemented for Linux");
+use tracing::{trace, warn};
+use winit_core::keyboard::{KeyCode, ModifiersState};
+use winit_extras_core::{
+    Accelerator, Event, EventCallback, ItemAction, MenuEntries, MenuEntry, MenuItem, TrayError,
+};
+use zbus::fdo;
+use zbus::object_server::SignalEmitter;
+use zbus::zvariant::{OwnedValue, Value};
+
+/// Object path the menu is served at, advertised by the StatusNotifierItem.
+pub(crate) const MENU_OBJECT_PATH: &str = "/MenuBar";
+
+/// A menu node as sent to the host: its id, its properties and the layouts
+/// of its children, each wrapped in a variant.
+type Layout = (i32, HashMap<String, Value<'static>>, Vec<Value<'static>>);
+
+/// The `com.canonical.dbusmenu` object for a tray's context menu.
+///
+/// Clones share the menu, so the copy served again after reconnecting to
+/// the session bus keeps check states.
+#[derive(Clone)]
+pub(crate) struct DbusMenu<T> {
+    entries: Arc<Mutex<Vec<MenuEntry<T>>>>,
+    /// Bumped whenever the layout changes, so hosts refetch it.
+    revision: Arc<AtomicU32>,
+    proxy: EventCallback<T>,
+    /// Shared with the `Tray`; clicks are ignored while it is `false`.
+    interactive: Arc<AtomicBool>,
+}
+
+impl<T: Clone + Send + Sync + 'static> DbusMenu<T> {
+    pub(crate) fn new(
+        entries: Vec<MenuEntry<T>>,
+        proxy: EventCallback<T>,
+        interactive: Arc<AtomicBool>,
+    ) -> Self {
+        DbusMenu {
+            entries: Arc::new(Mutex::new(entries)),
+            revision: Arc::new(AtomicU32::new(1)),
+            proxy,
+            interactive,
+        }
+    }
+
+    /// Reports a click on the entry with `id`, flipping its check state.
+    ///
+    /// Returns whether the layout changed, which is the case for checkable
+    /// items. Clicks on separators, submenus and disabled items are ignored.
+    fn click(&self, id: i32) -> bool {
+        if !self.interactive.load(Ordering::Relaxed) {
+            return false;
+        }
+        let Ok(mut entries) = self.entries.lock() else {
+            return false;
+        };
+        let Some(MenuEntry::Item(item)) = entry_mut(&mut entries, id) else {
+            return false;
+        };
+        if !item.enabled {
+            return false;
+        }
+
+        let checked = item.toggle();
+        let item_id = item.id.clone();
+        let action = item.action.clone();
+        drop(entries);
+
+        perform_action(action.as_ref());
+        (self.proxy)(Event::MenuItemClicked {
+            id: item_id,
+            checked,
+            timestamp: Instant::now(),
+        });
+        checked.is_some()
+    }
+
+    fn lock(&self) -> fdo::Result<std::sync::MutexGuard<'_, Vec<MenuEntry<T>>>> {
+        self.entries
+            .lock()
+            .map_err(|_| fdo::Error::Failed("menu state is poisoned".into()))
+    }
+}
+
+#[zbus::interface(name = "com.canonical.dbusmenu")]
+impl<T: Clone + Send + Sync + 'static> DbusMenu<T> {
+    /// The layout below `parent_id`, `recursion_depth` levels deep or
+    /// entirely when negative, with only the properties in `property_names`
+    /// or all of them when empty.
+    fn get_layout(
+        &self,
+        parent_id: i32,
+        recursion_depth: i32,
+        property_names: Vec<String>,
+    ) -> fdo::Result<(u32, Layout)> {
+        trace!(parent_id, recursion_depth, "DBusMenu::GetLayout called");
+        let entries = self.lock()?;
+        let layout = node_layout(&entries, parent_id, recursion_depth, &property_names)
+            .ok_or_else(|| fdo::Error::InvalidArgs(format!("no menu entry {parent_id}")))?;
+        Ok((self.revision.load(Ordering::Relaxed), layout))
+    }
+
+    /// The properties of the entries in `ids`, or of every entry when
+    /// empty. Unknown ids are skipped.
+    fn get_group_properties(
+        &self,
+        ids: Vec<i32>,
+        property_names: Vec<String>,
+    ) -> fdo::Result<Vec<(i32, HashMap<String, Value<'static>>)>> {
+        let entries = self.lock()?;
+        let ids = if ids.is_empty() {
+            (1..=entry_count(&entries)).collect()
+        } else {
+            ids
+        };
+        Ok(ids
+            .into_iter()
+            .filter_map(|id| {
+                let entry = entry(&entries, id)?;
+                Some((id, filter_properties(properties(entry), &property_names)))
+            })
+            .collect())
+    }
+
+    fn get_property(&self, id: i32, name: &str) -> fdo::Result<Value<'static>> {
+        let entries = self.lock()?;
+        let entry = entry(&entries, id)
+            .ok_or_else(|| fdo::Error::InvalidArgs(format!("no menu entry {id}")))?;
+        properties(entry)
+            .remove(name)
+            .ok_or_else(|| fdo::Error::InvalidArgs(format!("no property {name} on entry {id}")))
+    }
+
+    async fn event(
+        &self,
+        id: i32,
+        event_id: &str,
+        _data: OwnedValue,
+        _timestamp: u32,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        trace!(id, event_id, "DBusMenu::Event called");
+        if event_id == "clicked" && self.click(id) {
+            self.layout_changed(&emitter).await;
+        }
+        Ok(())
+    }
+
+    /// Handles several events at once, returning the ids that don't exist.
+    async fn event_group(
+        &self,
+        events: Vec<(i32, String, OwnedValue, u32)>,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<Vec<i32>> {
+        let mut changed = false;
+        let mut unknown = Vec::new();
+        for (id, event_id, _, _) in events {
+            if entry(&self.lock()?, id).is_none() {
+                unknown.push(id);
+            } else if event_id == "clicked" {
+                changed |= self.click(id);
+            }
+        }
+        if changed {
+            self.layout_changed(&emitter).await;
+        }
+        Ok(unknown)
+    }
+
+    /// Called before a submenu opens. The menu is always up to date, so
+    /// the host never needs to refetch it.
+    fn about_to_show(&self, _id: i32) -> bool {
+        false
+    }
+
+    fn about_to_show_group(&self, _ids: Vec<i32>) -> (Vec<i32>, Vec<i32>) {
+        (Vec::new(), Vec::new())
+    }
+
+    #[zbus(signal)]
+    async fn layout_updated(
+        emitter: &SignalEmitter<'_>,
+        revision: u32,
+        parent: i32,
+    ) -> zbus::Result<()>;
+
+    /// Version of the DBusMenu protocol implemented.
+    #[zbus(property)]
+    fn version(&self) -> u32 {
+        3
+    }
+
+    #[zbus(property)]
+    fn text_direction(&self) -> &str {
+        "ltr"
+    }
+
+    #[zbus(property)]
+    fn status(&self) -> &str {
+        "normal"
+    }
+
+    #[zbus(property)]
+    fn icon_theme_path(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+impl<T> DbusMenu<T> {
+    /// Replaces the menu's entries, returning the new layout revision to
+    /// announce to the host.
+    pub(crate) fn replace(&self, entries: Vec<MenuEntry<T>>) -> Result<u32, TrayError> {
+        *self
+            .entries
+            .lock()
+            .map_err(|_| TrayError::Platform("menu state is poisoned".to_owned()))? = entries;
+        Ok(self.revision.fetch_add(1, Ordering::Relaxed) + 1)
+    }
+
+    /// Applies `change` to the item with `id`, returning the new layout
+    /// revision to announce to the host.
+    pub(crate) fn update_item(
+        &self,
+        id: &T,
+        change: impl FnOnce(&mut MenuItem<T>),
+    ) -> Result<u32, TrayError>
+    where
+        T: PartialEq,
+    {
+        let mut entries = self
+            .entries
+            .lock()
+            .map_err(|_| TrayError::Platform("menu state is poisoned".to_owned()))?;
+        change(entries.find_item_mut(id).ok_or(TrayError::UnknownMenuId)?);
+        drop(entries);
+        Ok(self.revision.fetch_add(1, Ordering::Relaxed) + 1)
+    }
+}
+
+impl<T: Clone + Send + Sync + 'static> DbusMenu<T> {
+    /// Bumps the revision and tells the host to fetch the menu again.
+    async fn layout_changed(&self, emitter: &SignalEmitter<'_>) {
+        let revision = self.revision.fetch_add(1, Ordering::Relaxed) + 1;
+        if let Err(e) = Self::layout_updated(emitter, revision, 0).await {
+            warn!("Failed to emit LayoutUpdated: {}", e);
+        }
+    }
+}
+
+/// Performs the clicked item's [`ItemAction`], if it has one.
+fn perform_action(action: Option<&ItemAction>) {
+    if let Some(action) = action
+        && let Err(e) = action.perform()
+    {
+        warn!(?action, "Failed to perform menu item action: {}", e);
+    }
+}
+
+/// The entries below `entry`.
+fn children<T>(entry: &MenuEntry<T>) -> &[MenuEntry<T>] {
+    match entry {
+        MenuEntry::Submenu(submenu) => &submenu.items,
+        _ => &[],
+    }
+}
+
+/// Number of entries in `entries` and all their submenus.
+fn entry_count<T>(entries: &[MenuEntry<T>]) -> i32 {
+    entries
+        .iter()
+        .map(|entry| 1 + entry_count(children(entry)))
+        .sum()
+}
+
+/// The indices leading to the entry with `id`, starting from `first_id`,
+/// the id of `entries[0]`.
+fn path_to<T>(entries: &[MenuEntry<T>], first_id: i32, id: i32) -> Option<Vec<usize>> {
+    let mut next_id = first_id;
+    for (index, entry) in entries.iter().enumerate() {
+        let size = 1 + entry_count(children(entry));
+        if id == next_id {
+            return Some(vec![index]);
+        }
+        if id < next_id + size {
+            let mut path = path_to(children(entry), next_id + 1, id)?;
+            path.insert(0, index);
+            return Some(path);
+        }
+        next_id += size;
+    }
+    None
+}
+
+fn entry<T>(entries: &[MenuEntry<T>], id: i32) -> Option<&MenuEntry<T>> {
+    let path = path_to(entries, 1, id)?;
+    let (last, parents) = path.split_last()?;
+    let mut entries = entries;
+    for index in parents {
+        entries = children(&entries[*index]);
+    }
+    entries.get(*last)
+}
+
+fn entry_mut<T>(entries: &mut [MenuEntry<T>], id: i32) -> Option<&mut MenuEntry<T>> {
+    let path = path_to(entries, 1, id)?;
+    let (last, parents) = path.split_last()?;
+    let mut entries = entries;
+    for index in parents {
+        entries = match &mut entries[*index] {
+            MenuEntry::Submenu(submenu) => &mut submenu.items,
+            _ => return None,
+        };
+    }
+    entries.get_mut(*last)
+}
+
+/// The layout of the node with `id`, which may be the root.
+fn node_layout<T>(
+    entries: &[MenuEntry<T>],
+    id: i32,
+    depth: i32,
+    property_names: &[String],
+) -> Option<Layout> {
+    if id == 0 {
+        let properties = HashMap::from([("children-display".to_owned(), Value::from("submenu"))]);
+        return Some((
+            0,
+            filter_properties(properties, property_names),
+            children_layout(entries, 1, depth, property_names),
+        ));
+    }
+    Some(entry_layout(entry(entries, id)?, id, depth, property_names))
+}
+
+fn entry_layout<T>(entry: &MenuEntry<T>, id: i32, depth: i32, property_names: &[String]) -> Layout {
+    (
+        id,
+        filter_properties(properties(entry), property_names),
+        children_layout(children(entry), id + 1, depth, property_names),
+    )
+}
+
+/// The layouts of `entries`, the first of which has `first_id`, going
+/// `depth` levels deep.
+fn children_layout<T>(
+    entries: &[MenuEntry<T>],
+    first_id: i32,
+    depth: i32,
+    property_names: &[String],
+) -> Vec<Value<'static>> {
+    if depth == 0 {
+        return Vec::new();
+    }
+    let mut next_id = first_id;
+    entries
+        .iter()
+        .map(|entry| {
+            let layout = entry_layout(entry, next_id, depth - 1, property_names);
+            next_id += 1 + entry_count(children(entry));
+            Value::from(layout)
+        })
+        .collect()
+}
+
+/// The DBusMenu properties of `entry`, leaving out those at their default.
+fn properties<T>(entry: &MenuEntry<T>) -> HashMap<String, Value<'static>> {
+    let mut properties = HashMap::new();
+    let mut set = |name: &str, value: Value<'static>| {
+        properties.insert(name.to_owned(), value);
+    };
+    match entry {
+        MenuEntry::Item(item) => {
+            set(
+                "label",
+                Value::from(dbusmenu_label(&item.label, item.mnemonic)),
+            );
+            if !item.enabled {
+                set("enabled", Value::from(false));
+            }
+            if let Some(checked) = item.checked {
+                set("toggle-type", Value::from("checkmark"));
+                set("toggle-state", Value::from(i32::from(checked)));
+            }
+            if let Some(accelerator) = item.accelerator {
+                set(
+                    "shortcut",
+                    Value::from(vec![dbusmenu_shortcut(accelerator)]),
+                );
+            }
+            if let Some(icon) = &item.icon {
+                match crate::util::icon_to_png(icon) {
+                    Ok(png) => set("icon-data", Value::from(png)),
+                    Err(e) => warn!("Failed to encode menu item icon: {}", e),
+                }
+            }
+        }
+        MenuEntry::Submenu(submenu) => {
+            set(
+                "label",
+                Value::from(dbusmenu_label(&submenu.label, submenu.mnemonic)),
+            );
+            if !submenu.enabled {
+                set("enabled", Value::from(false));
+            }
+            set("children-display", Value::from("submenu"));
+        }
+        // DBusMenu has no other kinds of separator.
+        MenuEntry::Separator | MenuEntry::StyledSeparator(_) => {
+            set("type", Value::from("separator"));
+        }
+    }
+    properties
+}
+
+fn filter_properties(
+    mut properties: HashMap<String, Value<'static>>,
+    property_names: &[String],
+) -> HashMap<String, Value<'static>> {
+    if !property_names.is_empty() {
+        properties.retain(|name, _| property_names.contains(name));
+    }
+    properties
+}
+
+/// The parts of a DBusMenu shortcut: modifier names, then the key's X
+/// keysym name, which hosts show in their own style.
+fn dbusmenu_shortcut(accelerator: Accelerator) -> Vec<String> {
+    let modifiers = [
+        (ModifiersState::CONTROL, "Control"),
+        (ModifiersState::ALT, "Alt"),
+        (ModifiersState::SHIFT, "Shift"),
+        (ModifiersState::META, "Super"),
+    ];
+    let mut parts: Vec<String> = modifiers
+        .into_iter()
+        .filter(|&(modifier, _)| accelerator.modifiers.contains(modifier))
+        .map(|(_, name)| name.to_owned())
+        .collect();
+    let keysym = match accelerator.key {
+        KeyCode::Enter => "Return",
+        KeyCode::Backspace => "BackSpace",
+        KeyCode::PageUp => "Page_Up",
+        KeyCode::PageDown => "Page_Down",
+        KeyCode::PrintScreen => "Print",
+        KeyCode::Space => "space",
+        KeyCode::Minus => "minus",
+        KeyCode::Equal => "equal",
+        KeyCode::Comma => "comma",
+        KeyCode::Period => "period",
+        KeyCode::Semicolon => "semicolon",
+        KeyCode::Slash => "slash",
+        KeyCode::Backquote => "grave",
+        KeyCode::BracketLeft => "bracketleft",
+        KeyCode::Backslash => "backslash",
+        KeyCode::BracketRight => "bracketright",
+        KeyCode::Quote => "apostrophe",
+        // Letters, digits, function keys and the rest are named the same.
+        _ => {
+            parts.push(accelerator.key_name().into_owned());
+            return parts;
+        }
+    };
+    parts.push(keysym.to_owned());
+    parts
+}
+
+/// Converts a label to DBusMenu's access key syntax, where `_` marks the
+/// key and `__` stands for an underscore.
+///
+/// With `mnemonic`, `&` markers become `_` and `&&` a literal ampersand,
+/// as on Windows; otherwise the label is shown as written.
+fn dbusmenu_label(label: &str, mnemonic: bool) -> String {
+    let mut converted = String::with_capacity(label.len());
+    let mut chars = label.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '_' => converted.push_str("__"),
+            '&' if mnemonic => match chars.peek() {
+                Some('&') => {
+                    chars.next();
+                    converted.push('&');
+                }
+                Some(_) => converted.push('_'),
+                None => {}
+            },
+            c => converted.push(c),
+        }
+    }
+    converted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use winit_extras_core::{MenuItem, Submenu};
+
+    fn sample_menu() -> Vec<MenuEntry<u32>> {
+        vec![
+            MenuEntry::Item(MenuItem::new(1, "Open")),
+            MenuEntry::Separator,
+            MenuEntry::Submenu(Submenu::new(
+                "More",
+                vec![
+                    MenuEntry::Item(MenuItem::new(2, "Nested").checked(false)),
+                    MenuEntry::Item(MenuItem::new(3, "Disabled").enabled(false)),
+                ],
+            )),
+            MenuEntry::Item(MenuItem::new(4, "Quit")),
+        ]
+    }
+
+    type Events = Arc<Mutex<Vec<Event<u32>>>>;
+
+    fn menu() -> (DbusMenu<u32>, Events) {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let menu = DbusMenu::new(
+            sample_menu(),
+            Arc::new(move |event| sink.lock().unwrap().push(event)),
+            Arc::new(AtomicBool::new(true)),
+        );
+        (menu, events)
+    }
+
+    fn ids(children: &[Value<'_>]) -> Vec<i32> {
+        children
+            .iter()
+            .map(|child| {
+                let Value::Structure(layout) = child else {
+                    panic!("layout is not a structure");
+                };
+                i32::try_from(&layout.fields()[0]).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_entries_are_numbered_depth_first() {
+        let entries = sample_menu();
+        assert_eq!(entry_count(&entries), 6);
+
+        let (_, properties, children) = node_layout(&entries, 0, -1, &[]).unwrap();
+        assert_eq!(properties["children-display"], Value::from("submenu"));
+        assert_eq!(ids(&children), [1, 2, 3, 6]);
+
+        let (id, properties, children) = node_layout(&entries, 3, -1, &[]).unwrap();
+        assert_eq!(id, 3);
+        assert_eq!(properties["label"], Value::from("More"));
+        assert_eq!(ids(&children), [4, 5]);
+
+        let properties = self::properties(entry(&entries, 5).unwrap());
+        assert_eq!(properties["label"], Value::from("Disabled"));
+        assert_eq!(properties["enabled"], Value::from(false));
+        assert!(node_layout(&entries, 7, -1, &[]).is_none());
+    }
+
+    #[test]
+    fn test_layout_depth_and_property_filter() {
+        let entries = sample_menu();
+        let (_, _, children) = node_layout(&entries, 0, 0, &[]).unwrap();
+        assert!(children.is_empty());
+
+        let names = ["type".to_owned()];
+        let (_, properties, children) = node_layout(&entries, 0, 1, &names).unwrap();
+        assert!(properties.is_empty());
+        assert_eq!(ids(&children), [1, 2, 3, 6]);
+        let Value::Structure(submenu) = &children[2] else {
+            panic!("layout is not a structure");
+        };
+        // One level deep, so the submenu's items are left out.
+        assert_eq!(submenu.fields()[2], Value::from(Vec::<Value<'_>>::new()));
+
+        let separator = properties_of(&entries, 2, &names);
+        assert_eq!(separator["type"], Value::from("separator"));
+        assert!(properties_of(&entries, 1, &names).is_empty());
+    }
+
+    fn properties_of(
+        entries: &[MenuEntry<u32>],
+        id: i32,
+        names: &[String],
+    ) -> HashMap<String, Value<'static>> {
+        filter_properties(properties(entry(entries, id).unwrap()), names)
+    }
+
+    #[test]
+    fn test_clicks_are_reported_by_item_id() {
+        let (menu, events) = menu();
+
+        // Clicking a checkable item flips it and changes the layout.
+        assert!(menu.click(4));
+        assert!(!menu.click(1));
+        let events = std::mem::take(&mut *events.lock().unwrap());
+        assert!(matches!(
+            events[..],
+            [
+                Event::MenuItemClicked {
+                    id: 2,
+                    checked: Some(true),
+                    ..
+                },
+                Event::MenuItemClicked {
+                    id: 1,
+                    checked: None,
+                    ..
+                },
+            ]
+        ));
+        let entries = menu.entries.lock().unwrap();
+        assert_eq!(
+            properties(entry(&entries, 4).unwrap())["toggle-state"],
+            Value::from(1)
+        );
+    }
+
+    #[test]
+    fn test_clicks_ignored_on_other_entries() {
+        let (menu, events) = menu();
+        // A separator, a submenu, a disabled item and an unknown id.
+        for id in [2, 3, 5, 7] {
+            assert!(!menu.click(id));
+        }
+        menu.interactive.store(false, Ordering::Relaxed);
+        assert!(!menu.click(1));
+        assert!(events.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_replaced_menu_reports_new_ids() {
+        let (menu, events) = menu();
+        let revision = menu.revision.load(Ordering::Relaxed);
+
+        let recent = vec![
+            MenuEntry::Submenu(Submenu::new(
+                "Recent",
+                vec![
+                    MenuEntry::Item(MenuItem::new(10, "a.txt")),
+                    MenuEntry::Item(MenuItem::new(11, "b.txt")),
+                ],
+            )),
+            MenuEntry::Item(MenuItem::new(12, "Quit")),
+        ];
+        assert_eq!(menu.replace(recent), Ok(revision + 1));
+
+        let (_, _, children) = node_layout(&menu.entries.lock().unwrap(), 0, -1, &[]).unwrap();
+        assert_eq!(ids(&children), [1, 4]);
+        assert!(!menu.click(3));
+        assert!(!menu.click(4));
+        let events = events.lock().unwrap();
+        assert!(matches!(
+            events[..],
+            [
+                Event::MenuItemClicked { id: 11, .. },
+                Event::MenuItemClicked { id: 12, .. },
+            ]
+        ));
+    }
+
+    #[test]
+    fn test_set_label_reads_back() {
+        use winit_extras_core::TrayIcon;
+
+        let (menu, _) = menu();
+        let (command_tx, command_rx) = std::sync::mpsc::channel();
+        let tray = crate::Tray {
+            internal_id: 0,
+            thread_handle: None,
+            command_tx: Some(command_tx),
+            icon_state: Default::default(),
+            last_pointer_position: Default::default(),
+            interactive: menu.interactive.clone(),
+            menu: Some(menu.clone()),
+            _marker: std::marker::PhantomData,
+        };
+
+        tray.set_label(&4, "Resume").unwrap();
+        assert_eq!(
+            menu.entries.lock().unwrap().find_item(&4).unwrap().label,
+            "Resume"
+        );
+        assert_eq!(
+            menu.get_property(6, "label").unwrap(),
+            Value::from("Resume")
+        );
+        assert!(matches!(
+            command_rx.try_recv(),
+            Ok(crate::Command::LayoutUpdated(revision)) if revision == menu.revision.load(Ordering::Relaxed)
+        ));
+        assert_eq!(tray.set_label(&9, "Missing"), Err(TrayError::UnknownMenuId));
+    }
+
+    #[test]
+    fn test_item_icon_is_served_as_png() {
+        let (menu, _) = menu();
+        let revision = menu.revision.load(Ordering::Relaxed);
+        let icon = winit_extras_core::icon::placeholder();
+
+        assert_eq!(
+            menu.update_item(&4, |item| item.icon = Some(icon)),
+            Ok(revision + 1)
+        );
+        assert_eq!(menu.update_item(&9, |_| {}), Err(TrayError::UnknownMenuId));
+        let entries = menu.entries.lock().unwrap();
+        let Value::Array(png) = &properties(entry(&entries, 6).unwrap())["icon-data"] else {
+            panic!("icon-data is not an array");
+        };
+        let signature: Vec<u8> = png.iter().map(|byte| u8::try_from(byte).unwrap()).collect();
+        assert!(signature.starts_with(b"\x89PNG"));
+    }
+
+    #[test]
+    fn test_dbusmenu_shortcut() {
+        let accelerator = Accelerator::new(
+            ModifiersState::SHIFT | ModifiersState::CONTROL,
+            KeyCode::KeyS,
+        );
+        assert_eq!(dbusmenu_shortcut(accelerator), ["Control", "Shift", "S"]);
+        assert_eq!(
+            dbusmenu_shortcut(Accelerator::new(ModifiersState::ALT, KeyCode::PageDown)),
+            ["Alt", "Page_Down"]
+        );
+
+        let entry = MenuEntry::Item(MenuItem::new(1, "Save").accelerator(accelerator));
+        assert_eq!(
+            properties(&entry)["shortcut"],
+            Value::from(vec![vec!["Control", "Shift", "S"]])
+        );
+    }
+
+    #[test]
+    fn test_dbusmenu_label() {
+        assert_eq!(dbusmenu_label("Save & Exit", false), "Save & Exit");
+        assert_eq!(dbusmenu_label("snake_case", false), "snake__case");
+        assert_eq!(dbusmenu_label("&Save && Exit", true), "_Save & Exit");
+        assert_eq!(dbusmenu_label("Trailing&", true), "Trailing");
+    }
 }
@@ -1,6 +1,72 @@
-use winit_core::icon::{Icon, RgbaIcon};
+use std::time::Duration;
+
+use dpi::PhysicalSize;
+use winit_core::icon::Icon;
+use winit_extras_core::TrayError;
+use winit_extras_core::icon::{IconError, PreparedIcon, StatusColor, with_status_dot};
 use zbus::zvariant::{OwnedValue, Type, Value};
 
+/// Opens the notification settings of the desktop's control center, where
+/// users allow or silence notifications per app.
+///
+/// Knows GNOME and KDE Plasma, read from `XDG_CURRENT_DESKTOP`; fails with
+/// [`std::io::ErrorKind::Unsupported`] on other desktops.
+pub fn open_notification_settings() -> std::io::Result<()> {
+    let desktop = std::env::var("XDG_CURRENT_DESKTOP").unwrap_or_default();
+    let (program, panel) = if desktop.split(':').any(|name| name == "KDE") {
+        ("systemsettings", "kcm_notifications")
+    } else if desktop.split(':').any(|name| name == "GNOME") {
+        ("gnome-control-center", "notifications")
+    } else {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            format!("no known notification settings for desktop `{desktop}`"),
+        ));
+    };
+    let mut child = std::process::Command::new(program).arg(panel).spawn()?;
+    // Reaped in the background, so the closed settings app doesn't linger
+    // as a zombie.
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+/// Whether a notification server would show notifications now.
+///
+/// `false` without a session bus or a running or activatable
+/// `org.freedesktop.Notifications` service, or while the server's
+/// `Inhibited` property is set, as KDE Plasma does for Do Not Disturb.
+/// Servers without that property, such as GNOME Shell's, count as allowing
+/// notifications.
+pub fn notifications_allowed() -> bool {
+    const SERVICE: &str = "org.freedesktop.Notifications";
+
+    let Ok(connection) = zbus::blocking::Connection::session() else {
+        return false;
+    };
+    let Ok(dbus) = zbus::blocking::fdo::DBusProxy::new(&connection) else {
+        return false;
+    };
+    let running = dbus
+        .name_has_owner(zbus::names::WellKnownName::from_static_str_unchecked(SERVICE).into())
+        .unwrap_or(false);
+    let activatable = || {
+        dbus.list_activatable_names()
+            .is_ok_and(|names| names.iter().any(|name| name.as_str() == SERVICE))
+    };
+    if !running && !activatable() {
+        return false;
+    }
+
+    let inhibited = zbus::blocking::Proxy::new(
+        &connection,
+        SERVICE,
+        "/org/freedesktop/Notifications",
+        SERVICE,
+    )
+    .and_then(|proxy| proxy.get_property::<bool>("Inhibited"));
+    !inhibited.unwrap_or(false)
+}
+
 /// SNI Icon structure matching the D-Bus specification.
 /// Icon pixmap format: a(iiay) - Array of (width: i32, height: i32, data: Vec<u8>)
 /// Data is in ARGB32 format.
@@ -11,46 +77,153 @@ pub struct SniIcon {
     pub data: Vec<u8>,
 }
 
-/// Converts a winit Icon (RGBA format) to SNI Icon format (ARGB32).
+/// Converts a winit Icon to SNI Icon format (ARGB32).
 ///
 /// The SNI specification requires icons as ARGB32 pixel data in network byte order (big-endian).
 /// Each pixel is represented as a 32-bit integer: (A << 24) | (R << 16) | (G << 8) | B
-pub(crate) fn icon_to_sni_icon(icon: &Icon) -> Option<SniIcon> {
-    // Try to downcast to RgbaIcon
-    let rgba = icon.0.cast_ref::<RgbaIcon>()?;
-    let buffer = rgba.buffer();
-    let width = rgba.width();
-    let height = rgba.height();
-
-    // Convert RGBA bytes to ARGB32 as 32-bit big-endian integers
-    // Input: R, G, B, A (repeating)
-    // Output: Each pixel as 4 bytes in big-endian: [A, R, G, B]
-    let pixel_count = (width * height) as usize;
-    let mut argb_data = Vec::with_capacity(pixel_count * 4);
-
-    for chunk in buffer.chunks_exact(4) {
-        let r = chunk[0];
-        let g = chunk[1];
-        let b = chunk[2];
-        let a = chunk[3];
-
-        // Pack into 32-bit ARGB value
-        let argb: u32 = ((a as u32) << 24) | ((r as u32) << 16) | ((g as u32) << 8) | (b as u32);
-
-        // Convert to big-endian bytes and push
-        argb_data.extend_from_slice(&argb.to_be_bytes());
+///
+/// Icons already converted with [`PreparedIcon::prepare`] are used as-is.
+pub(crate) fn icon_to_sni_icon(icon: &Icon) -> Result<SniIcon, IconError> {
+    Ok(prepared_to_sni_icon(PreparedIcon::try_prepare(icon)?))
+}
+
+/// Converts a winit Icon to the pixmaps advertised in `IconPixmap`.
+///
+/// SNI has no size change notification, so scalable icons are rasterized at
+/// each of [`SCALABLE_ICON_SIZES`] up front and the host picks the closest.
+/// Other icons produce a single pixmap.
+///
+/// With a `status_dot`, the dot is drawn over every pixmap.
+pub(crate) fn icon_to_sni_pixmaps(
+    icon: &Icon,
+    status_dot: Option<StatusColor>,
+) -> Result<Vec<SniIcon>, IconError> {
+    if !winit_extras_core::icon::is_scalable(icon) {
+        return Ok(vec![match status_dot {
+            Some(color) => {
+                icon_to_sni_icon(&with_status_dot(icon, color, PhysicalSize::new(0, 0))?)?
+            }
+            None => icon_to_sni_icon(icon)?,
+        }]);
+    }
+
+    SCALABLE_ICON_SIZES
+        .iter()
+        .map(|&size| {
+            let size = PhysicalSize::new(size, size);
+            match status_dot {
+                Some(color) => icon_to_sni_icon(&with_status_dot(icon, color, size)?),
+                None => PreparedIcon::try_prepare_sized(icon, size).map(prepared_to_sni_icon),
+            }
+        })
+        .collect()
+}
+
+/// Size menu item icons are drawn at, matching Windows and macOS.
+#[cfg(feature = "menu")]
+const MENU_ICON_SIZE: u32 = 16;
+
+/// Encodes a menu item icon as the PNG DBusMenu sends in `icon-data`.
+#[cfg(feature = "menu")]
+pub(crate) fn icon_to_png(icon: &Icon) -> Result<Vec<u8>, IconError> {
+    let prepared =
+        PreparedIcon::try_prepare_sized(icon, PhysicalSize::new(MENU_ICON_SIZE, MENU_ICON_SIZE))?;
+    // Prepared pixels are ARGB32 in network byte order.
+    let rgba: Vec<u8> = prepared
+        .data()
+        .chunks_exact(4)
+        .flat_map(|argb| [argb[1], argb[2], argb[3], argb[0]])
+        .collect();
+
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, prepared.width(), prepared.height());
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&rgba))
+        .map_err(|_| IconError::ConversionFailed)?;
+    Ok(png)
+}
+
+/// Sizes scalable icons are rasterized at, covering common panel heights at
+/// 1x and 2x scale.
+const SCALABLE_ICON_SIZES: [u32; 6] = [16, 22, 24, 32, 48, 64];
+
+fn prepared_to_sni_icon(prepared: PreparedIcon) -> SniIcon {
+    SniIcon {
+        width: prepared.width() as i32,
+        height: prepared.height() as i32,
+        data: prepared.data().to_vec(),
+    }
+}
+
+/// Name tray hosts show for the item, taken from the executable's file name.
+pub(crate) fn app_name() -> String {
+    std::env::current_exe()
+        .ok()
+        .and_then(|path| Some(path.file_stem()?.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| "Tray Icon".to_string())
+}
+
+/// Splits a tooltip into the title and description of the SNI `ToolTip`
+/// property: the first line is the title, the rest the description.
+pub(crate) fn split_tooltip(tooltip: &str) -> (&str, &str) {
+    match tooltip.split_once('\n') {
+        Some((title, description)) => (title.trim_end_matches('\r'), description),
+        None => (tooltip, ""),
+    }
+}
+
+/// Turns a D-Bus failure into a [`TrayError::Platform`] that says what was
+/// being attempted.
+pub(crate) trait Context<R> {
+    fn context(self, what: &str) -> Result<R, TrayError>;
+}
+
+impl<R, E: std::fmt::Display> Context<R> for Result<R, E> {
+    fn context(self, what: &str) -> Result<R, TrayError> {
+        self.map_err(|e| TrayError::Platform(format!("{what}: {e}")))
+    }
+}
+
+impl<R> Context<R> for Option<R> {
+    fn context(self, what: &str) -> Result<R, TrayError> {
+        self.ok_or_else(|| TrayError::Platform(what.to_owned()))
     }
+}
+
+/// Calls `attempt` until it succeeds, retrying at most `retries` times.
+///
+/// Before each retry `wait` is called with the backoff delay, which starts at
+/// `initial_delay` and doubles every time. `wait` returns `false` to give up
+/// early, in which case the last error is returned. `attempt` receives the
+/// 1-based attempt number.
+pub(crate) fn retry_with_backoff<R, E>(
+    retries: u32,
+    initial_delay: Duration,
+    mut wait: impl FnMut(Duration) -> bool,
+    mut attempt: impl FnMut(u32) -> Result<R, E>,
+) -> Result<R, E> {
+    let mut delay = initial_delay;
+    let mut number = 1;
 
-    Some(SniIcon {
-        width: width as i32,
-        height: height as i32,
-        data: argb_data,
-    })
+    loop {
+        match attempt(number) {
+            Ok(value) => return Ok(value),
+            Err(e) if number > retries || !wait(delay) => return Err(e),
+            Err(_) => {
+                delay = delay.saturating_mul(2);
+                number += 1;
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use winit_core::icon::RgbaIcon;
 
     #[test]
     fn test_rgba_to_argb32_conversion() {
@@ -73,4 +246,88 @@ mod tests {
         // Check first pixel is ARGB format: A=255, R=255, G=0, B=0
         assert_eq!(&sni_icon.data[0..4], &[255, 255, 0, 0]);
     }
+
+    #[test]
+    fn test_non_rgba_icon_is_rejected() {
+        #[derive(Debug)]
+        struct FileIcon;
+        impl winit_core::icon::IconProvider for FileIcon {}
+
+        let icon = Icon(std::sync::Arc::new(FileIcon));
+        assert_eq!(
+            icon_to_sni_icon(&icon).unwrap_err(),
+            IconError::UnsupportedFormat
+        );
+    }
+
+    #[test]
+    fn test_split_tooltip() {
+        assert_eq!(split_tooltip("Syncing"), ("Syncing", ""));
+        assert_eq!(
+            split_tooltip("Syncing\n3 of 10 files\nETA 2 min"),
+            ("Syncing", "3 of 10 files\nETA 2 min")
+        );
+        assert_eq!(split_tooltip("Syncing\r\n3 files"), ("Syncing", "3 files"));
+    }
+
+    /// A watcher that only comes up after `ready_after` registration attempts.
+    struct MockWatcher {
+        ready_after: u32,
+        calls: u32,
+    }
+
+    impl MockWatcher {
+        fn register(&mut self) -> Result<(), &'static str> {
+            self.calls += 1;
+            if self.calls > self.ready_after {
+                Ok(())
+            } else {
+                Err("org.kde.StatusNotifierWatcher was not provided by any .service files")
+            }
+        }
+    }
+
+    #[test]
+    fn test_retry_with_backoff_waits_for_delayed_watcher() {
+        let mut watcher = MockWatcher {
+            ready_after: 2,
+            calls: 0,
+        };
+        let mut waits = Vec::new();
+
+        let result = retry_with_backoff(
+            2,
+            Duration::from_millis(700),
+            |delay| {
+                waits.push(delay);
+                true
+            },
+            |_| watcher.register(),
+        );
+
+        assert!(result.is_ok());
+        assert_eq!(watcher.calls, 3);
+        assert_eq!(
+            waits,
+            [Duration::from_millis(700), Duration::from_millis(1400)]
+        );
+    }
+
+    #[test]
+    fn test_retry_with_backoff_gives_up() {
+        let mut watcher = MockWatcher {
+            ready_after: u32::MAX,
+            calls: 0,
+        };
+
+        let result = retry_with_backoff(2, Duration::ZERO, |_| true, |_| watcher.register());
+        assert!(result.is_err());
+        assert_eq!(watcher.calls, 3);
+
+        // Cancelling the wait stops retrying immediately.
+        watcher.calls = 0;
+        let result = retry_with_backoff(2, Duration::ZERO, |_| false, |_| watcher.register());
+        assert!(result.is_err());
+        assert_eq!(watcher.calls, 1);
+    }
 }
@@ -0,0 +1,53 @@
+//! Activation policy for tray-only apps.
+
+use objc2::MainThreadMarker;
+use objc2_app_kit::{NSApplication, NSApplicationActivationPolicy};
+use winit_extras_core::TrayError;
+
+/// How the app appears in the Dock and app switcher.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ActivationPolicy {
+    /// A normal app with a Dock icon and a menu bar.
+    Regular,
+    /// No Dock icon or menu bar, but can still be activated to take focus,
+    /// for example by its tray menus.
+    Accessory,
+}
+
+impl From<ActivationPolicy> for NSApplicationActivationPolicy {
+    fn from(policy: ActivationPolicy) -> Self {
+        match policy {
+            ActivationPolicy::Regular => NSApplicationActivationPolicy::Regular,
+            ActivationPolicy::Accessory => NSApplicationActivationPolicy::Accessory,
+        }
+    }
+}
+
+/// Set the app's activation policy.
+///
+/// An app without windows usually shouldn't show a Dock icon, which on macOS
+/// means running as an accessory app (`LSUIElement` in the bundle's
+/// Info.plist, or [`ActivationPolicy::Accessory`] at runtime). Status items
+/// persist either way, but an app that never becomes active can miss the
+/// first click on its menus, so tray-only apps should set the policy before
+/// creating their tray:
+///
+/// ```ignore
+/// winit_extras_macos::set_activation_policy(ActivationPolicy::Accessory)?;
+/// let tray = manager.create_tray(attributes)?;
+/// ```
+///
+/// Must be called on the main thread. winit applies its own default policy
+/// when the event loop starts, so call this from `can_create_surfaces` or
+/// later, or configure the policy through winit's macOS event loop builder.
+pub fn set_activation_policy(policy: ActivationPolicy) -> Result<(), TrayError> {
+    let mtm = MainThreadMarker::new().ok_or(TrayError::NotMainThread)?;
+
+    let app = NSApplication::sharedApplication(mtm);
+    if !app.setActivationPolicy(policy.into()) {
+        return Err(TrayError::Platform(format!(
+            "macOS refused to change the activation policy to {policy:?}"
+        )));
+    }
+    Ok(())
+}
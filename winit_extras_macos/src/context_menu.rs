@@ -1,15 +1,19 @@
 use std::cell::RefCell;
 
-use dpi::PhysicalPosition;
+use dpi::{PhysicalPosition, PhysicalSize};
 use objc2::rc::Retained;
 use objc2::{define_class, msg_send, sel, MainThreadMarker};
-use objc2_app_kit::{NSMenu, NSMenuItem, NSScreen};
-use objc2_core_foundation::{CGPoint, CGSize};
+use objc2_app_kit::{NSEvent, NSMenu, NSMenuItem, NSScreen};
+use objc2_core_foundation::{CGPoint, CGRect, CGSize};
 use objc2_foundation::{NSObject, NSString};
 use rwh_06::{HasWindowHandle, RawWindowHandle};
 use winit_core::event_loop::ActiveEventLoop;
 use winit_extras_core::context_menu::{ContextMenu as ContextMenuTrait, MenuRenderer};
-use winit_extras_core::{Event, EventCallback, MenuEntry};
+use winit_extras_core::menu::display_label;
+use winit_extras_core::{Event, EventCallback, MenuEntries, MenuEntry, TrayError};
+
+use crate::menu::set_key_equivalent;
+use crate::util;
 
 // Thread-local storage for popup menu results
 thread_local! {
@@ -44,26 +48,39 @@ impl PopupMenuTarget {
     }
 }
 
-fn show_context_menu_at_location<T: Clone>(
+/// Shows a popup menu and returns the index of the selected item in
+/// [`MenuEntries::items`] order.
+fn show_context_menu_at_location<T>(
     mtm: MainThreadMarker,
     items: &[MenuEntry<T>],
     screen_x: f64,
     screen_y: f64,
-) -> Option<T> {
+) -> Option<usize> {
+    show_context_menu_with(mtm, items, |_| CGPoint {
+        x: screen_x,
+        y: screen_y,
+    })
+}
+
+/// Like [`show_context_menu_at_location`], but `place` picks the screen
+/// location of the menu's top-left corner once the menu is built and its
+/// size is known.
+fn show_context_menu_with<T>(
+    mtm: MainThreadMarker,
+    items: &[MenuEntry<T>],
+    place: impl FnOnce(&NSMenu) -> CGPoint,
+) -> Option<usize> {
     if items.is_empty() {
         return None;
     }
 
     let menu = NSMenu::new(mtm);
-    let mut id_map: Vec<T> = Vec::new();
+    let mut item_count = 0;
     let target = PopupMenuTarget::new(mtm);
 
-    build_menu_for_popup(mtm, &menu, items, &mut id_map, &target);
+    build_menu_for_popup(mtm, &menu, items, &mut item_count, &target);
 
-    let location = CGPoint {
-        x: screen_x,
-        y: screen_y,
-    };
+    let location = place(&menu);
 
     POPUP_MENU_RESULT.with(|result| {
         *result.borrow_mut() = None;
@@ -72,31 +89,30 @@ fn show_context_menu_at_location<T: Clone>(
     let _displayed = menu.popUpMenuPositioningItem_atLocation_inView(None, location, None);
 
     POPUP_MENU_RESULT.with(|result| {
-        result.borrow_mut().take().and_then(|tag| {
-            if tag > 0 && tag <= id_map.len() {
-                Some(id_map[tag - 1].clone())
-            } else {
-                None
-            }
-        })
+        // Tags are 1-based item indices.
+        result
+            .borrow_mut()
+            .take()
+            .filter(|&tag| tag > 0 && tag <= item_count)
+            .map(|tag| tag - 1)
     })
 }
 
-fn build_menu_for_popup<T: Clone>(
+fn build_menu_for_popup<T>(
     mtm: MainThreadMarker,
     menu: &NSMenu,
     items: &[MenuEntry<T>],
-    id_map: &mut Vec<T>,
+    item_count: &mut usize,
     target: &PopupMenuTarget,
 ) {
     for entry in items {
         match entry {
-            MenuEntry::Separator => {
+            MenuEntry::Separator | MenuEntry::StyledSeparator(_) => {
                 let sep = NSMenuItem::separatorItem(mtm);
                 menu.addItem(&sep);
             }
             MenuEntry::Item(item) => {
-                let title = NSString::from_str(&item.label);
+                let title = NSString::from_str(&display_label(&item.label, item.mnemonic));
                 let menu_item = unsafe {
                     NSMenuItem::initWithTitle_action_keyEquivalent(
                         mtm.alloc(),
@@ -106,12 +122,12 @@ fn build_menu_for_popup<T: Clone>(
                     )
                 };
 
-                let tag = id_map.len() + 1;
-                menu_item.setTag(tag as isize);
-                id_map.push(item.id.clone());
+                *item_count += 1;
+                menu_item.setTag(*item_count as isize);
 
                 unsafe { menu_item.setTarget(Some(target)) };
                 menu_item.setEnabled(item.enabled);
+                set_key_equivalent(&menu_item, item.accelerator);
 
                 if let Some(checked) = item.checked {
                     menu_item.setState(if checked { 1 } else { 0 });
@@ -120,7 +136,7 @@ fn build_menu_for_popup<T: Clone>(
                 menu.addItem(&menu_item);
             }
             MenuEntry::Submenu(submenu) => {
-                let title = NSString::from_str(&submenu.label);
+                let title = NSString::from_str(&display_label(&submenu.label, submenu.mnemonic));
                 let sub_item = unsafe {
                     NSMenuItem::initWithTitle_action_keyEquivalent(
                         mtm.alloc(),
@@ -131,7 +147,7 @@ fn build_menu_for_popup<T: Clone>(
                 };
 
                 let sub_menu = NSMenu::new(mtm);
-                build_menu_for_popup(mtm, &sub_menu, &submenu.items, id_map, target);
+                build_menu_for_popup(mtm, &sub_menu, &submenu.items, item_count, target);
                 sub_item.setSubmenu(Some(&sub_menu));
                 sub_item.setEnabled(submenu.enabled);
                 menu.addItem(&sub_item);
@@ -145,6 +161,16 @@ pub fn show_context_menu_for_window<T: Clone>(
     items: &[MenuEntry<T>],
     position: PhysicalPosition<i32>,
 ) -> Option<T> {
+    show_context_menu_for_window_indexed(window, items, position).map(|(_, id)| id)
+}
+
+/// Like [`show_context_menu_for_window`], but also returns the index of the
+/// selected item in [`MenuEntries::items`] order.
+pub fn show_context_menu_for_window_indexed<T: Clone>(
+    window: &impl HasWindowHandle,
+    items: &[MenuEntry<T>],
+    position: PhysicalPosition<i32>,
+) -> Option<(usize, T)> {
     let mtm = MainThreadMarker::new()?;
     let handle = window.window_handle().ok()?;
 
@@ -182,20 +208,29 @@ pub fn show_context_menu_for_window<T: Clone>(
             let screen_rect: objc2_core_foundation::CGRect =
                 unsafe { msg_send![ns_window, convertRectToScreen: rect] };
 
-            show_context_menu_at_location(mtm, items, screen_rect.origin.x, screen_rect.origin.y)
+            let index = show_context_menu_at_location(
+                mtm,
+                items,
+                screen_rect.origin.x,
+                screen_rect.origin.y,
+            )?;
+            items
+                .items()
+                .nth(index)
+                .map(|item| (index, item.id.clone()))
         }
         _ => None,
     }
 }
 
 /// Menu alignment options (for API compatibility with Windows).
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum MenuAlignment {
     Auto,
 }
 
 pub struct ContextMenu<T> {
-    items: Vec<MenuEntry<T>>,
+    items: RefCell<Vec<MenuEntry<T>>>,
     proxy: EventCallback<T>,
     ns_view: *mut objc2::runtime::AnyObject,
 }
@@ -207,88 +242,148 @@ impl<T> std::fmt::Debug for ContextMenu<T> {
 }
 
 unsafe impl<T: Send> Send for ContextMenu<T> {}
-unsafe impl<T: Sync> Sync for ContextMenu<T> {}
 
 impl<T: Clone + Send + Sync + 'static> ContextMenu<T> {
     pub fn new(
         window: &(impl HasWindowHandle + ?Sized),
         items: Vec<MenuEntry<T>>,
         proxy: EventCallback<T>,
-    ) -> Result<Self, anyhow::Error> {
+    ) -> Result<Self, TrayError> {
         let handle = window
             .window_handle()
-            .map_err(|e| anyhow::anyhow!("Failed to get window handle: {}", e))?;
+            .map_err(|e| TrayError::Platform(format!("Failed to get window handle: {e}")))?;
 
         let ns_view = match handle.as_raw() {
             RawWindowHandle::AppKit(appkit_handle) => {
                 appkit_handle.ns_view.as_ptr() as *mut objc2::runtime::AnyObject
             }
-            _ => {
-                return Err(anyhow::anyhow!(
-                    "Invalid window handle type, expected AppKit"
-                ))
-            }
+            _ => return Err(TrayError::UnsupportedParentWindow),
         };
 
         Ok(Self {
-            items,
+            items: RefCell::new(items),
             proxy,
             ns_view,
         })
     }
 
     fn show_at_screen_pos_internal(&self, screen_x: f64, screen_y: f64) {
+        self.show_with(|_| CGPoint {
+            x: screen_x,
+            y: screen_y,
+        });
+    }
+
+    /// Shows the menu with its top-left corner where `place` says and
+    /// reports the selection.
+    fn show_with(&self, place: impl FnOnce(&NSMenu) -> CGPoint) {
         let Some(mtm) = MainThreadMarker::new() else {
             return;
         };
 
-        let result = show_context_menu_at_location(mtm, &self.items, screen_x, screen_y);
-        if let Some(id) = result {
-            (self.proxy)(Event::MenuItemClicked { id });
-        }
+        let mut items = self.items.borrow_mut();
+        let selected = show_context_menu_with(mtm, &items, place);
+        let Some(item) = selected.and_then(|index| items.item_mut(index)) else {
+            return;
+        };
+
+        let checked = item.toggle();
+        let id = item.id.clone();
+        crate::menu::perform_action(item.action.as_ref());
+        drop(items);
+
+        (self.proxy)(Event::MenuItemClicked {
+            id,
+            checked,
+            timestamp: util::current_event_time(mtm),
+        });
     }
-}
 
-impl<T: Clone + Send + Sync + 'static> ContextMenuTrait for ContextMenu<T> {
-    fn show(&self, position: PhysicalPosition<i32>) {
+    /// Converts a rectangle in the view's client area, in physical pixels,
+    /// to screen coordinates. Also returns the visible frame of the screen
+    /// the window is on.
+    fn client_rect_to_screen(
+        &self,
+        position: PhysicalPosition<i32>,
+        size: PhysicalSize<u32>,
+    ) -> Option<(CGRect, Option<CGRect>)> {
         if self.ns_view.is_null() {
-            return;
+            return None;
         }
 
         let ns_window: *mut objc2::runtime::AnyObject = unsafe { msg_send![self.ns_view, window] };
         if ns_window.is_null() {
-            return;
+            return None;
         }
 
         let scale: f64 = unsafe { msg_send![ns_window, backingScaleFactor] };
         let x = position.x as f64 / scale;
         let y = position.y as f64 / scale;
+        let width = size.width as f64 / scale;
+        let height = size.height as f64 / scale;
 
+        // `position` is the top-left corner; unflipped views measure from
+        // the bottom.
         let is_flipped: bool = unsafe { msg_send![self.ns_view, isFlipped] };
         let view_y = if is_flipped {
             y
         } else {
-            let bounds: objc2_core_foundation::CGRect = unsafe { msg_send![self.ns_view, bounds] };
-            bounds.size.height - y
+            let bounds: CGRect = unsafe { msg_send![self.ns_view, bounds] };
+            bounds.size.height - y - height
         };
 
-        let view_pt = CGPoint { x, y: view_y };
-        let win_pt: CGPoint = unsafe {
-            msg_send![self.ns_view, convertPoint: view_pt, toView: std::ptr::null::<objc2::runtime::AnyObject>()]
+        let view_rect = CGRect {
+            origin: CGPoint { x, y: view_y },
+            size: CGSize { width, height },
         };
-        let rect = objc2_core_foundation::CGRect {
-            origin: win_pt,
-            size: CGSize {
-                width: 0.0,
-                height: 0.0,
-            },
+        let win_rect: CGRect = unsafe {
+            msg_send![self.ns_view, convertRect: view_rect, toView: std::ptr::null::<objc2::runtime::AnyObject>()]
+        };
+        let screen_rect: CGRect = unsafe { msg_send![ns_window, convertRectToScreen: win_rect] };
+
+        let screen: *mut objc2::runtime::AnyObject = unsafe { msg_send![ns_window, screen] };
+        let visible_frame = (!screen.is_null()).then(|| {
+            let frame: CGRect = unsafe { msg_send![screen, visibleFrame] };
+            frame
+        });
+
+        Some((screen_rect, visible_frame))
+    }
+}
+
+impl<T: Clone + Send + Sync + 'static> ContextMenuTrait for ContextMenu<T> {
+    fn show(&self, position: PhysicalPosition<i32>) {
+        let Some((screen_rect, _)) = self.client_rect_to_screen(position, PhysicalSize::new(0, 0))
+        else {
+            return;
         };
-        let screen_rect: objc2_core_foundation::CGRect =
-            unsafe { msg_send![ns_window, convertRectToScreen: rect] };
 
         self.show_at_screen_pos_internal(screen_rect.origin.x, screen_rect.origin.y);
     }
 
+    fn show_for_rect(&self, position: PhysicalPosition<i32>, size: PhysicalSize<u32>) {
+        let Some((rect, visible_frame)) = self.client_rect_to_screen(position, size) else {
+            return;
+        };
+
+        // Screen coordinates grow upwards, so the rectangle's bottom edge is
+        // its origin.
+        self.show_with(|menu| {
+            let menu_height = menu.size().height;
+            let fits_below =
+                visible_frame.is_none_or(|frame| rect.origin.y - menu_height >= frame.origin.y);
+            let y = if fits_below {
+                rect.origin.y
+            } else {
+                rect.origin.y + rect.size.height + menu_height
+            };
+            CGPoint {
+                x: rect.origin.x,
+                y,
+            }
+        });
+    }
+
     fn show_at_screen_pos(&self, position: PhysicalPosition<i32>) {
         let Some(mtm) = MainThreadMarker::new() else {
             return;
@@ -303,6 +398,14 @@ impl<T: Clone + Send + Sync + 'static> ContextMenuTrait for ContextMenu<T> {
         self.show_at_screen_pos_internal(position.x as f64, screen_height - position.y as f64);
     }
 
+    fn show_at_cursor(&self) -> bool {
+        // Already in bottom-left origin screen coordinates, and AppKit keeps
+        // popup menus on screen by itself.
+        let location = NSEvent::mouseLocation();
+        self.show_at_screen_pos_internal(location.x, location.y);
+        true
+    }
+
     fn close(&self) {}
 }
 
@@ -0,0 +1,345 @@
+//! System-wide keyboard shortcuts using Carbon's `RegisterEventHotKey`.
+//!
+//! Carbon is still the only public API for global shortcuts that does not
+//! need the accessibility permission.
+
+use std::ffi::c_void;
+use std::ptr;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use objc2::MainThreadMarker;
+use tracing::warn;
+use winit_core::keyboard::{KeyCode, ModifiersState};
+use winit_extras_core::{Accelerator, TrayError};
+
+type OSStatus = i32;
+type EventTargetRef = *mut c_void;
+type EventHandlerRef = *mut c_void;
+type EventHandlerCallRef = *mut c_void;
+type EventRef = *mut c_void;
+type EventHotKeyRef = *mut c_void;
+type EventHandlerUPP = extern "C" fn(EventHandlerCallRef, EventRef, *mut c_void) -> OSStatus;
+
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct EventHotKeyID {
+    signature: u32,
+    id: u32,
+}
+
+#[repr(C)]
+struct EventTypeSpec {
+    event_class: u32,
+    event_kind: u32,
+}
+
+#[link(name = "Carbon", kind = "framework")]
+extern "C" {
+    fn GetApplicationEventTarget() -> EventTargetRef;
+    fn InstallEventHandler(
+        target: EventTargetRef,
+        handler: EventHandlerUPP,
+        num_types: usize,
+        list: *const EventTypeSpec,
+        user_data: *mut c_void,
+        out_ref: *mut EventHandlerRef,
+    ) -> OSStatus;
+    fn RemoveEventHandler(handler: EventHandlerRef) -> OSStatus;
+    fn RegisterEventHotKey(
+        key_code: u32,
+        modifiers: u32,
+        hot_key_id: EventHotKeyID,
+        target: EventTargetRef,
+        options: u32,
+        out_ref: *mut EventHotKeyRef,
+    ) -> OSStatus;
+    fn UnregisterEventHotKey(hot_key: EventHotKeyRef) -> OSStatus;
+    fn GetEventParameter(
+        event: EventRef,
+        name: u32,
+        desired_type: u32,
+        actual_type: *mut u32,
+        buffer_size: usize,
+        actual_size: *mut usize,
+        data: *mut c_void,
+    ) -> OSStatus;
+}
+
+const NO_ERR: OSStatus = 0;
+const EVENT_NOT_HANDLED_ERR: OSStatus = -9874;
+
+const EVENT_CLASS_KEYBOARD: u32 = u32::from_be_bytes(*b"keyb");
+const EVENT_HOT_KEY_PRESSED: u32 = 5;
+const EVENT_PARAM_DIRECT_OBJECT: u32 = u32::from_be_bytes(*b"----");
+const TYPE_EVENT_HOT_KEY_ID: u32 = u32::from_be_bytes(*b"hkid");
+
+/// Identifies our hotkeys to Carbon; arbitrary but fixed.
+const SIGNATURE: u32 = u32::from_be_bytes(*b"wtex");
+
+const CMD_KEY: u32 = 1 << 8;
+const SHIFT_KEY: u32 = 1 << 9;
+const OPTION_KEY: u32 = 1 << 11;
+const CONTROL_KEY: u32 = 1 << 12;
+
+static COUNTER: AtomicU32 = AtomicU32::new(1);
+
+struct HandlerData {
+    id: u32,
+    callback: Box<dyn Fn()>,
+}
+
+/// A registered system-wide shortcut. Dropping it unregisters the shortcut.
+///
+/// The callback runs on the main thread while the application is
+/// processing events.
+pub struct HotKey {
+    hot_key: EventHotKeyRef,
+    handler: EventHandlerRef,
+    data: *mut HandlerData,
+    accelerator: Accelerator,
+}
+
+impl std::fmt::Debug for HotKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HotKey")
+            .field("accelerator", &self.accelerator)
+            .finish()
+    }
+}
+
+impl HotKey {
+    /// Register `accelerator` system-wide and call `callback` when pressed.
+    ///
+    /// Must be called on the main thread. Fails if the key has no macOS
+    /// equivalent or the shortcut is already taken.
+    pub fn register(
+        accelerator: Accelerator,
+        callback: impl Fn() + 'static,
+    ) -> Result<Self, TrayError> {
+        let _mtm = MainThreadMarker::new().ok_or(TrayError::NotMainThread)?;
+        let key_code = virtual_key_code(accelerator.key).ok_or_else(|| {
+            TrayError::Platform(format!("{:?} cannot be used as a hotkey", accelerator.key))
+        })?;
+
+        let id = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let data = Box::into_raw(Box::new(HandlerData {
+            id,
+            callback: Box::new(callback),
+        }));
+
+        unsafe {
+            let target = GetApplicationEventTarget();
+
+            let event_type = EventTypeSpec {
+                event_class: EVENT_CLASS_KEYBOARD,
+                event_kind: EVENT_HOT_KEY_PRESSED,
+            };
+            let mut handler = ptr::null_mut();
+            let status = InstallEventHandler(
+                target,
+                hotkey_handler,
+                1,
+                &event_type,
+                data.cast(),
+                &mut handler,
+            );
+            if status != NO_ERR {
+                drop(Box::from_raw(data));
+                return Err(TrayError::Platform(format!(
+                    "Failed to install hotkey handler: OSStatus {}",
+                    status
+                )));
+            }
+
+            let hot_key_id = EventHotKeyID {
+                signature: SIGNATURE,
+                id,
+            };
+            let mut hot_key = ptr::null_mut();
+            let status = RegisterEventHotKey(
+                key_code,
+                carbon_modifiers(accelerator.modifiers),
+                hot_key_id,
+                target,
+                0,
+                &mut hot_key,
+            );
+            if status != NO_ERR {
+                RemoveEventHandler(handler);
+                drop(Box::from_raw(data));
+                return Err(TrayError::Platform(format!(
+                    "Failed to register hotkey {:?}: OSStatus {}",
+                    accelerator, status
+                )));
+            }
+
+            Ok(HotKey {
+                hot_key,
+                handler,
+                data,
+                accelerator,
+            })
+        }
+    }
+
+    /// The registered shortcut.
+    pub fn accelerator(&self) -> Accelerator {
+        self.accelerator
+    }
+}
+
+impl Drop for HotKey {
+    fn drop(&mut self) {
+        unsafe {
+            let status = UnregisterEventHotKey(self.hot_key);
+            if status != NO_ERR {
+                warn!(
+                    accelerator = ?self.accelerator,
+                    "Failed to unregister hotkey: OSStatus {}",
+                    status
+                );
+            }
+            RemoveEventHandler(self.handler);
+            drop(Box::from_raw(self.data));
+        }
+    }
+}
+
+extern "C" fn hotkey_handler(
+    _call_ref: EventHandlerCallRef,
+    event: EventRef,
+    user_data: *mut c_void,
+) -> OSStatus {
+    let data = unsafe { &*(user_data as *const HandlerData) };
+
+    let mut hot_key_id = EventHotKeyID::default();
+    let status = unsafe {
+        GetEventParameter(
+            event,
+            EVENT_PARAM_DIRECT_OBJECT,
+            TYPE_EVENT_HOT_KEY_ID,
+            ptr::null_mut(),
+            std::mem::size_of::<EventHotKeyID>(),
+            ptr::null_mut(),
+            (&mut hot_key_id as *mut EventHotKeyID).cast(),
+        )
+    };
+
+    // Every hotkey has its own handler; let the others see presses that
+    // aren't ours.
+    if status != NO_ERR || hot_key_id.signature != SIGNATURE || hot_key_id.id != data.id {
+        return EVENT_NOT_HANDLED_ERR;
+    }
+
+    (data.callback)();
+    NO_ERR
+}
+
+fn carbon_modifiers(modifiers: ModifiersState) -> u32 {
+    let mut flags = 0;
+    if modifiers.shift_key() {
+        flags |= SHIFT_KEY;
+    }
+    if modifiers.control_key() {
+        flags |= CONTROL_KEY;
+    }
+    if modifiers.alt_key() {
+        flags |= OPTION_KEY;
+    }
+    if modifiers.meta_key() {
+        flags |= CMD_KEY;
+    }
+    flags
+}
+
+/// Maps a physical key to its `kVK_*` code.
+fn virtual_key_code(key: KeyCode) -> Option<u32> {
+    use KeyCode::*;
+
+    let code = match key {
+        KeyA => 0x00,
+        KeyS => 0x01,
+        KeyD => 0x02,
+        KeyF => 0x03,
+        KeyH => 0x04,
+        KeyG => 0x05,
+        KeyZ => 0x06,
+        KeyX => 0x07,
+        KeyC => 0x08,
+        KeyV => 0x09,
+        KeyB => 0x0B,
+        KeyQ => 0x0C,
+        KeyW => 0x0D,
+        KeyE => 0x0E,
+        KeyR => 0x0F,
+        KeyY => 0x10,
+        KeyT => 0x11,
+        Digit1 => 0x12,
+        Digit2 => 0x13,
+        Digit3 => 0x14,
+        Digit4 => 0x15,
+        Digit6 => 0x16,
+        Digit5 => 0x17,
+        Equal => 0x18,
+        Digit9 => 0x19,
+        Digit7 => 0x1A,
+        Minus => 0x1B,
+        Digit8 => 0x1C,
+        Digit0 => 0x1D,
+        BracketRight => 0x1E,
+        KeyO => 0x1F,
+        KeyU => 0x20,
+        BracketLeft => 0x21,
+        KeyI => 0x22,
+        KeyP => 0x23,
+        Enter => 0x24,
+        KeyL => 0x25,
+        KeyJ => 0x26,
+        Quote => 0x27,
+        KeyK => 0x28,
+        Semicolon => 0x29,
+        Backslash => 0x2A,
+        Comma => 0x2B,
+        Slash => 0x2C,
+        KeyN => 0x2D,
+        KeyM => 0x2E,
+        Period => 0x2F,
+        Tab => 0x30,
+        Space => 0x31,
+        Backquote => 0x32,
+        Backspace => 0x33,
+        Escape => 0x35,
+        F17 => 0x40,
+        F18 => 0x4F,
+        F19 => 0x50,
+        F20 => 0x5A,
+        F5 => 0x60,
+        F6 => 0x61,
+        F7 => 0x62,
+        F3 => 0x63,
+        F8 => 0x64,
+        F9 => 0x65,
+        F11 => 0x67,
+        F13 => 0x69,
+        F16 => 0x6A,
+        F14 => 0x6B,
+        F10 => 0x6D,
+        F12 => 0x6F,
+        F15 => 0x71,
+        Insert => 0x72,
+        Home => 0x73,
+        PageUp => 0x74,
+        Delete => 0x75,
+        F4 => 0x76,
+        End => 0x77,
+        F2 => 0x78,
+        PageDown => 0x79,
+        F1 => 0x7A,
+        ArrowLeft => 0x7B,
+        ArrowRight => 0x7C,
+        ArrowDown => 0x7D,
+        ArrowUp => 0x7E,
+        _ => return None,
+    };
+    Some(code)
+}
@@ -1,4 +1,7 @@
 #![cfg(target_os = "macos")]
+mod activation;
+mod segment;
+mod spacer;
 mod util;
 
 #[cfg(feature = "menu")]
@@ -10,21 +13,45 @@ pub mod context_menu;
 #[cfg(feature = "menu_bar")]
 pub mod menu_bar;
 
-use dpi::PhysicalPosition;
+#[cfg(feature = "hotkey")]
+pub mod hotkey;
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::time::Duration;
+
+use dpi::{PhysicalPosition, PhysicalSize};
 use objc2::rc::Retained;
-use objc2::{define_class, msg_send, AllocAnyThread, DeclaredClass, MainThreadMarker};
+use objc2::{define_class, msg_send, sel, AllocAnyThread, DeclaredClass, MainThreadMarker};
 use objc2_app_kit::{
-    NSEvent, NSStatusBar, NSStatusItem, NSTrackingArea, NSTrackingAreaOptions,
-    NSVariableStatusItemLength, NSView,
+    NSApplicationDidChangeScreenParametersNotification, NSEvent, NSScreen, NSStatusBar,
+    NSStatusItem, NSTrackingArea, NSTrackingAreaOptions, NSVariableStatusItemLength, NSView,
 };
 use objc2_core_foundation::{CGPoint, CGRect, CGSize};
-use objc2_foundation::NSString;
-use tracing::trace;
+use objc2_foundation::{NSNotification, NSNotificationCenter, NSString, NSTimer};
+use tracing::{trace, warn};
 use winit_core::event::{ElementState, MouseButton};
+use winit_core::icon::Icon;
+use winit_extras_core::icon::{with_status_dot, IconError, StatusColor};
 use winit_extras_core::{
-    Event, EventCallback, TrayIcon as CoreTrayIcon, TrayIconAttributes, TrayIconRenderer,
+    ButtonMask, Event, EventCallback, TrayError, TrayIcon as CoreTrayIcon, TrayIconAttributes,
+    TrayIconRenderer,
 };
 
+#[cfg(feature = "menu")]
+use objc2_app_kit::{NSMenu, NSMenuItem};
+#[cfg(feature = "menu")]
+use std::rc::Rc;
+#[cfg(feature = "menu")]
+use winit_extras_core::menu::display_label;
+#[cfg(feature = "menu")]
+use winit_extras_core::{MenuEntry, PrimaryAction};
+
+pub use activation::{set_activation_policy, ActivationPolicy};
+pub use segment::Segment;
+pub use spacer::Spacer;
+pub use util::{notifications_allowed, open_notification_settings, scale_factor_at};
+
 use crate::util::icon_to_nsimage;
 
 /// Uses native macOS `NSStatusBar` / `NSStatusItem` APIs.
@@ -33,24 +60,42 @@ pub struct NativeTrayIconRenderer;
 impl<T: Clone + Send + Sync + 'static> TrayIconRenderer<T> for NativeTrayIconRenderer {
     fn create_tray(
         &self,
-        attributes: TrayIconAttributes,
+        attributes: TrayIconAttributes<T>,
         proxy: EventCallback<T>,
-    ) -> Result<Box<dyn winit_extras_core::TrayIcon>, Box<dyn std::error::Error + Send + Sync>>
+    ) -> Result<Box<dyn winit_extras_core::TrayIcon<T>>, Box<dyn std::error::Error + Send + Sync>>
     {
         let tray = Tray::new(proxy, attributes)?;
         Ok(Box::new(tray))
     }
 }
 
-static COUNTER: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(1);
-
 pub struct Tray<T = ()> {
     status_item: Retained<NSStatusItem>,
     tray_target: Retained<TrayTarget>,
     internal_id: usize,
+    #[cfg(feature = "menu")]
+    menu_items: RefCell<MenuItems<T>>,
+    /// Builds the menu for `set_menu`. `None` for trays without a menu or
+    /// with a native one.
+    #[cfg(feature = "menu")]
+    menu_builder: Option<MenuBuilder<T>>,
     _marker: std::marker::PhantomData<T>,
 }
 
+/// The ID of every item in a tray's menu, with the `NSMenuItem` built for it
+/// and whether its label uses access key markers.
+#[cfg(feature = "menu")]
+type MenuItems<T> = Vec<(T, Retained<NSMenuItem>, bool)>;
+
+/// Builds a tray's `NSMenu` from entries, returning it with its items.
+#[cfg(feature = "menu")]
+type MenuBuilder<T> = Box<
+    dyn Fn(
+        MainThreadMarker,
+        &[MenuEntry<T>],
+    ) -> Result<(Option<Retained<NSMenu>>, MenuItems<T>), TrayError>,
+>;
+
 impl<T> std::fmt::Debug for Tray<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Tray")
@@ -59,10 +104,41 @@ impl<T> std::fmt::Debug for Tray<T> {
     }
 }
 
+/// Where the pointer is relative to the status item, for picking the hover
+/// and pressed icons.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Pointer {
+    Outside,
+    Over,
+    Pressed,
+}
+
 // Instance variables for TrayTarget
 struct TrayTargetIvars {
     tray_icon_id: usize,
     status_item: Retained<NSStatusItem>,
+    icon_size: Cell<PhysicalSize<u32>>,
+    last_pointer_position: Cell<Option<PhysicalPosition<f64>>>,
+    /// The icon the tray was created with, or the last one set with
+    /// `set_icon`.
+    icon: RefCell<Option<Icon>>,
+    hover_icon: Option<Icon>,
+    pressed_icon: Option<Icon>,
+    pointer: Cell<Pointer>,
+    template_icon: bool,
+    status_dot: Cell<Option<StatusColor>>,
+    button_filter: ButtonMask,
+    /// Cleared by `set_interactive(false)` to ignore clicks.
+    interactive: Cell<bool>,
+    /// The tooltip last set, as opposed to one shown by `flash_tooltip`.
+    tooltip: RefCell<Option<String>>,
+    /// Reverts a flashed tooltip when it fires.
+    tooltip_timer: RefCell<Option<Retained<NSTimer>>>,
+    /// Replaced by `set_menu`.
+    #[cfg(feature = "menu")]
+    menu: RefCell<Option<Retained<NSMenu>>>,
+    #[cfg(feature = "menu")]
+    primary_action: PrimaryAction,
 }
 
 define_class!(
@@ -75,7 +151,9 @@ define_class!(
     impl TrayTarget {
         #[unsafe(method(mouseDown:))]
         fn on_mouse_down(&self, event: &NSEvent) {
+            self.set_pointer(Pointer::Pressed);
             self.send_mouse_event(event, MouseButton::Left, ElementState::Pressed);
+            self.send_double_click(event, MouseButton::Left);
             self.on_tray_click(MouseButton::Left);
         }
 
@@ -84,17 +162,21 @@ define_class!(
             let mtm = MainThreadMarker::from(self);
             let button = self.ivars().status_item.button(mtm).unwrap();
             button.highlight(false);
+            self.set_pointer(Pointer::Over);
             self.send_mouse_event(event, MouseButton::Left, ElementState::Released);
         }
 
         #[unsafe(method(rightMouseDown:))]
         fn on_right_mouse_down(&self, event: &NSEvent) {
+            self.set_pointer(Pointer::Pressed);
             self.send_mouse_event(event, MouseButton::Right, ElementState::Pressed);
+            self.send_double_click(event, MouseButton::Right);
             self.on_tray_click(MouseButton::Right);
         }
 
         #[unsafe(method(rightMouseUp:))]
         fn on_right_mouse_up(&self, event: &NSEvent) {
+            self.set_pointer(Pointer::Over);
             self.send_mouse_event(event, MouseButton::Right, ElementState::Released);
         }
 
@@ -148,9 +230,52 @@ define_class!(
             };
             self.addTrackingArea(&area);
         }
+
+        #[unsafe(method(mouseEntered:))]
+        fn on_mouse_entered(&self, _event: &NSEvent) {
+            self.set_pointer(Pointer::Over);
+        }
+
+        #[unsafe(method(mouseExited:))]
+        fn on_mouse_exited(&self, _event: &NSEvent) {
+            self.set_pointer(Pointer::Outside);
+        }
+    }
+
+    /// Display changes that can resize the status bar icon
+    impl TrayTarget {
+        #[unsafe(method(viewDidChangeBackingProperties))]
+        fn view_did_change_backing_properties(&self) {
+            let _: () = unsafe { msg_send![super(self), viewDidChangeBackingProperties] };
+            self.check_icon_size();
+        }
+
+        #[unsafe(method(screenParametersChanged:))]
+        fn screen_parameters_changed(&self, _notification: &NSNotification) {
+            self.check_icon_size();
+        }
+
+        #[unsafe(method(revertTooltip:))]
+        fn revert_tooltip(&self, _timer: &NSTimer) {
+            self.ivars().tooltip_timer.take();
+            let tooltip = self.ivars().tooltip.borrow().clone();
+            self.show_tooltip(tooltip.as_deref());
+        }
     }
 );
 
+/// The status bar thickness in pixels at the given backing scale.
+fn icon_size_for_scale(scale: f64) -> PhysicalSize<u32> {
+    let side = (NSStatusBar::systemStatusBar().thickness() * scale).round() as u32;
+    PhysicalSize::new(side, side)
+}
+
+/// The status bar thickness in pixels on the main screen, for items that
+/// aren't on screen yet.
+fn main_screen_icon_size(mtm: MainThreadMarker) -> PhysicalSize<u32> {
+    icon_size_for_scale(NSScreen::mainScreen(mtm).map_or(1.0, |screen| screen.backingScaleFactor()))
+}
+
 impl TrayTarget {
     fn update_dimensions(&self) {
         let mtm = MainThreadMarker::from(self);
@@ -158,7 +283,111 @@ impl TrayTarget {
         self.setFrame(button.frame());
     }
 
-    fn send_mouse_event(&self, _event: &NSEvent, button: MouseButton, state: ElementState) {
+    fn show_tooltip(&self, tooltip: Option<&str>) {
+        let mtm = MainThreadMarker::from(self);
+        if let Some(button) = self.ivars().status_item.button(mtm) {
+            button.setToolTip(tooltip.map(NSString::from_str).as_deref());
+            self.update_dimensions();
+        }
+    }
+
+    /// Stops a pending revert of a flashed tooltip.
+    fn cancel_tooltip_revert(&self) {
+        if let Some(timer) = self.ivars().tooltip_timer.take() {
+            timer.invalidate();
+        }
+    }
+
+    /// The status bar thickness in pixels of the screen showing the item.
+    fn current_icon_size(&self) -> PhysicalSize<u32> {
+        let mtm = MainThreadMarker::from(self);
+        match self.window() {
+            Some(window) => icon_size_for_scale(window.backingScaleFactor()),
+            None => main_screen_icon_size(mtm),
+        }
+    }
+
+    /// Reports a new icon size if a scale or menu bar height change altered it.
+    fn check_icon_size(&self) {
+        let size = self.current_icon_size();
+        if self.ivars().icon_size.replace(size) == size {
+            return;
+        }
+
+        let tray_icon_id =
+            winit_extras_core::tray_icon_id::TrayIconId::from_raw(self.ivars().tray_icon_id);
+        trace!(?size, "Tray icon size changed");
+
+        dispatch_tray_event(
+            self.ivars().tray_icon_id,
+            Event::IconSizeChanged { tray_icon_id, size },
+        );
+
+        let ivars = self.ivars();
+        let redraw = ivars.status_dot.get().is_some()
+            || self
+                .current_icon()
+                .is_some_and(|icon| winit_extras_core::icon::is_scalable(&icon));
+        if redraw {
+            if let Err(e) = self.update_image(size) {
+                warn!(?size, "Failed to rasterize tray icon: {}", e);
+            }
+        }
+    }
+
+    /// The icon for the pointer's current state.
+    fn current_icon(&self) -> Option<Icon> {
+        let ivars = self.ivars();
+        let state_icon = match ivars.pointer.get() {
+            _ if !ivars.interactive.get() => None,
+            Pointer::Outside => None,
+            Pointer::Over => ivars.hover_icon.as_ref(),
+            Pointer::Pressed => ivars.pressed_icon.as_ref().or(ivars.hover_icon.as_ref()),
+        };
+        state_icon.cloned().or_else(|| ivars.icon.borrow().clone())
+    }
+
+    /// Records where the pointer is and shows the matching icon, if the
+    /// tray has hover or pressed icons.
+    fn set_pointer(&self, pointer: Pointer) {
+        let ivars = self.ivars();
+        if ivars.hover_icon.is_none() && ivars.pressed_icon.is_none() {
+            return;
+        }
+        if ivars.pointer.replace(pointer) == pointer {
+            return;
+        }
+        let size = self.current_icon_size();
+        if let Err(e) = self.update_image(size) {
+            warn!(?size, ?pointer, "Failed to rasterize tray icon: {}", e);
+        }
+    }
+
+    /// Draws the icon for the pointer's state, with its status dot if one
+    /// is set, at `size`. Clears the image if the tray has no icon.
+    fn update_image(&self, size: PhysicalSize<u32>) -> Result<(), IconError> {
+        let ivars = self.ivars();
+        let nsimage = match (self.current_icon(), ivars.status_dot.get()) {
+            (None, _) => None,
+            // A template image would tint the dot along with the icon.
+            (Some(icon), Some(color)) => Some(icon_to_nsimage(
+                &with_status_dot(&icon, color, size)?,
+                false,
+                size,
+            )?),
+            (Some(icon), None) => Some(icon_to_nsimage(&icon, ivars.template_icon, size)?),
+        };
+        let mtm = MainThreadMarker::from(self);
+        if let Some(button) = ivars.status_item.button(mtm) {
+            button.setImage(nsimage.as_deref());
+        }
+        Ok(())
+    }
+
+    fn send_mouse_event(&self, event: &NSEvent, button: MouseButton, state: ElementState) {
+        if !self.ivars().interactive.get() {
+            return;
+        }
         let tray_icon_id =
             winit_extras_core::tray_icon_id::TrayIconId::from_raw(self.ivars().tray_icon_id);
 
@@ -167,61 +396,170 @@ impl TrayTarget {
         let position = PhysicalPosition::new(mouse_location.x, mouse_location.y);
 
         trace!(?button, ?state, ?position, "Tray mouse event");
+        self.ivars().last_pointer_position.set(Some(position));
+        if !self.ivars().button_filter.contains(button) {
+            return;
+        }
 
-        TRAY_EVENT_HANDLER.with(|handler| {
-            if let Some(handler) = handler.borrow().as_ref() {
-                handler(Event::PointerButton {
-                    tray_icon_id,
-                    state,
-                    position,
-                    button: winit_core::event::ButtonSource::Mouse(button),
-                });
-            }
-        });
+        dispatch_tray_event(
+            self.ivars().tray_icon_id,
+            Event::PointerButton {
+                tray_icon_id,
+                state,
+                position,
+                button: winit_core::event::ButtonSource::Mouse(button),
+                timestamp: util::event_time(event),
+            },
+        );
+    }
+
+    /// Reports a double-click if `event` is the second press of one.
+    fn send_double_click(&self, event: &NSEvent, button: MouseButton) {
+        if event.clickCount() != 2
+            || !self.ivars().interactive.get()
+            || !self.ivars().button_filter.contains(button)
+        {
+            return;
+        }
+        let mouse_location = NSEvent::mouseLocation();
+        dispatch_tray_event(
+            self.ivars().tray_icon_id,
+            Event::DoubleClick {
+                tray_icon_id: winit_extras_core::tray_icon_id::TrayIconId::from_raw(
+                    self.ivars().tray_icon_id,
+                ),
+                button,
+                position: PhysicalPosition::new(mouse_location.x, mouse_location.y),
+            },
+        );
     }
 
     fn on_tray_click(&self, _button: MouseButton) {
+        if !self.ivars().interactive.get() {
+            return;
+        }
         let mtm = MainThreadMarker::from(self);
         let ns_button = self.ivars().status_item.button(mtm).unwrap();
         ns_button.highlight(true);
+
+        #[cfg(feature = "menu")]
+        if self.show_menu_for(_button) {
+            // The menu tracks the mouse itself, so no mouseUp follows.
+            ns_button.highlight(false);
+            self.set_pointer(Pointer::Over);
+        }
+    }
+
+    /// Pops up the tray's context menu below the status item if `button`
+    /// should open it. Blocks until the menu is dismissed.
+    #[cfg(feature = "menu")]
+    fn show_menu_for(&self, button: MouseButton) -> bool {
+        self.ivars().primary_action.opens_menu(button) && self.show_menu()
+    }
+
+    /// Shows the context menu below the status item, returning `false` if
+    /// the tray has none.
+    #[cfg(feature = "menu")]
+    fn show_menu(&self) -> bool {
+        let ivars = self.ivars();
+        // Cloned, as a click can replace the menu while it is open.
+        let Some(menu) = ivars.menu.borrow().clone() else {
+            return false;
+        };
+
+        let mtm = MainThreadMarker::from(self);
+        let Some(ns_button) = ivars.status_item.button(mtm) else {
+            return false;
+        };
+
+        let location = CGPoint {
+            x: 0.0,
+            y: ns_button.bounds().size.height + 5.0,
+        };
+        menu.popUpMenuPositioningItem_atLocation_inView(None, location, Some(&ns_button));
+        true
     }
 }
 
-// Thread-local storage for the event handler callback
+// Thread-local storage for the event handler of each tray, keyed by the
+// tray's internal id.
 // This is necessary because we can't pass closures through Objective-C
-type TrayEventHandler = Box<dyn Fn(Event<()>) + Send + Sync>;
+type TrayEventHandler = Box<dyn Fn(Event<()>)>;
 thread_local! {
-    static TRAY_EVENT_HANDLER: std::cell::RefCell<Option<TrayEventHandler>> = const { std::cell::RefCell::new(None) };
+    static TRAY_EVENT_HANDLERS: RefCell<HashMap<usize, TrayEventHandler>> = RefCell::new(HashMap::new());
+}
+
+fn register_tray_event_handler(tray_id: usize, handler: TrayEventHandler) {
+    TRAY_EVENT_HANDLERS.with(|handlers| handlers.borrow_mut().insert(tray_id, handler));
+}
+
+fn unregister_tray_event_handler(tray_id: usize) {
+    TRAY_EVENT_HANDLERS.with(|handlers| handlers.borrow_mut().remove(&tray_id));
+}
+
+/// Delivers `event` to the handler of the tray with `tray_id`, if it is
+/// still alive.
+fn dispatch_tray_event(tray_id: usize, event: Event<()>) {
+    TRAY_EVENT_HANDLERS.with(|handlers| {
+        if let Some(handler) = handlers.borrow().get(&tray_id) {
+            handler(event);
+        }
+    });
 }
 
 impl<T: Clone + Send + Sync + 'static> Tray<T> {
-    pub fn new(proxy: EventCallback<T>, attr: TrayIconAttributes) -> Result<Self, anyhow::Error> {
-        let mtm = MainThreadMarker::new()
-            .ok_or_else(|| anyhow::anyhow!("Tray must be created on the main thread"))?;
+    pub fn new(proxy: EventCallback<T>, attr: TrayIconAttributes<T>) -> Result<Self, TrayError> {
+        Self::create(
+            proxy,
+            attr,
+            #[cfg(feature = "menu")]
+            None,
+        )
+    }
 
-        let internal_id = COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    /// Creates a tray that shows an existing `NSMenu` instead of building its
+    /// menu from [`MenuEntry`](winit_extras_core::MenuEntry) items.
+    ///
+    /// The menu opens on the same clicks as a `context_menu` would, which it
+    /// replaces. Selecting an item delivers [`Event::MenuItemClicked`] with
+    /// the id `tag_to_id` returns for the item's `tag`; items it maps to
+    /// `None` are ignored.
+    ///
+    /// The tray retains `menu` for as long as it lives, and takes over the
+    /// target and action of every item in it and its submenus. Items can
+    /// still be retitled, enabled or checked through the menu afterwards,
+    /// but items added later are not dispatched.
+    #[cfg(feature = "menu")]
+    pub fn with_native_menu(
+        proxy: EventCallback<T>,
+        attr: TrayIconAttributes<T>,
+        menu: Retained<NSMenu>,
+        tag_to_id: impl Fn(isize) -> Option<T> + 'static,
+    ) -> Result<Self, TrayError> {
+        let mtm = MainThreadMarker::new().ok_or(TrayError::NotMainThread)?;
+        menu::attach_native_menu(mtm, &menu, proxy.clone(), Rc::new(tag_to_id));
+        Self::create(proxy, attr, Some(menu))
+    }
 
-        // Set up the event handler
-        let proxy_clone = proxy.clone();
-        TRAY_EVENT_HANDLER.with(|handler| {
-            *handler.borrow_mut() = Some(Box::new(move |event| {
-                let typed_event = match event {
-                    Event::PointerButton {
-                        tray_icon_id,
-                        state,
-                        position,
-                        button,
-                    } => Event::PointerButton {
-                        tray_icon_id,
-                        state,
-                        position,
-                        button,
-                    },
-                    _ => return,
-                };
-                (proxy_clone)(typed_event);
-            }));
-        });
+    /// The status item backing this tray, for integration the
+    /// cross-platform API doesn't cover yet, such as setting a menu
+    /// delegate.
+    ///
+    /// This is a platform-specific interop point and not part of the
+    /// stable API. The tray configures the item's button, view and menu
+    /// itself, so changing those may be undone or break click handling.
+    pub fn ns_status_item(&self) -> &NSStatusItem {
+        &self.status_item
+    }
+
+    fn create(
+        proxy: EventCallback<T>,
+        #[cfg_attr(not(feature = "menu"), allow(unused_mut))] mut attr: TrayIconAttributes<T>,
+        #[cfg(feature = "menu")] native_menu: Option<Retained<NSMenu>>,
+    ) -> Result<Self, TrayError> {
+        let mtm = MainThreadMarker::new().ok_or(TrayError::NotMainThread)?;
+
+        let internal_id = winit_extras_core::tray_icon_id::TrayIconId::allocate().into_raw();
 
         // Create status item
         let status_item =
@@ -230,13 +568,12 @@ impl<T: Clone + Send + Sync + 'static> Tray<T> {
         // Get the button
         let button = status_item
             .button(mtm)
-            .ok_or_else(|| anyhow::anyhow!("Failed to get status item button"))?;
+            .ok_or_else(|| TrayError::Platform("Failed to get status item button".to_owned()))?;
 
         // Set the icon if provided
         if let Some(icon) = attr.icon.as_ref() {
-            if let Some(nsimage) = icon_to_nsimage(icon) {
-                button.setImage(Some(&nsimage));
-            }
+            let nsimage = icon_to_nsimage(icon, attr.template_icon, main_screen_icon_size(mtm))?;
+            button.setImage(Some(&nsimage));
         }
 
         // Set the tooltip if provided
@@ -245,12 +582,49 @@ impl<T: Clone + Send + Sync + 'static> Tray<T> {
             button.setToolTip(Some(&ns_tooltip));
         }
 
+        #[cfg(feature = "menu")]
+        let (menu, menu_items, menu_builder) = match (native_menu, attr.take_context_menu()) {
+            (Some(menu), _) => (Some(menu), Vec::new(), None),
+            (None, Some(items)) => {
+                let proxy = proxy.clone();
+                let tray_icon_id =
+                    winit_extras_core::tray_icon_id::TrayIconId::from_raw(internal_id);
+                let build: MenuBuilder<T> = Box::new(move |mtm, entries| {
+                    let menu = menu::create_menu(mtm, entries, proxy.clone(), tray_icon_id)?;
+                    let mut menu_items = Vec::new();
+                    if let Some(menu) = &menu {
+                        menu::collect_items(menu, entries, &mut menu_items);
+                    }
+                    Ok((menu, menu_items))
+                });
+                let (menu, menu_items) = build(mtm, &items)?;
+                (menu, menu_items, Some(build))
+            }
+            (None, None) => (None, Vec::new(), None),
+        };
+
         // Create the TrayTarget view and add it to the button
         let frame = button.frame();
 
         let target = mtm.alloc().set_ivars(TrayTargetIvars {
             tray_icon_id: internal_id,
             status_item: status_item.clone(),
+            icon_size: Cell::new(PhysicalSize::new(0, 0)),
+            last_pointer_position: Cell::new(None),
+            icon: RefCell::new(attr.icon.clone()),
+            hover_icon: attr.hover_icon.clone(),
+            pressed_icon: attr.pressed_icon.clone(),
+            pointer: Cell::new(Pointer::Outside),
+            template_icon: attr.template_icon,
+            status_dot: Cell::new(None),
+            button_filter: attr.button_filter,
+            interactive: Cell::new(true),
+            tooltip: RefCell::new(attr.tooltip.clone()),
+            tooltip_timer: RefCell::new(None),
+            #[cfg(feature = "menu")]
+            menu: RefCell::new(menu),
+            #[cfg(feature = "menu")]
+            primary_action: attr.primary_action,
         });
 
         let tray_target: Retained<TrayTarget> =
@@ -259,46 +633,302 @@ impl<T: Clone + Send + Sync + 'static> Tray<T> {
 
         button.addSubview(&tray_target);
 
+        // Track the size the icon is drawn at, so a later display change can
+        // be reported. Backing scale changes reach the view directly; menu
+        // bar height changes only come with a screen parameters change.
+        tray_target
+            .ivars()
+            .icon_size
+            .set(tray_target.current_icon_size());
+        unsafe {
+            NSNotificationCenter::defaultCenter().addObserver_selector_name_object(
+                &tray_target,
+                sel!(screenParametersChanged:),
+                Some(NSApplicationDidChangeScreenParametersNotification),
+                None,
+            );
+        }
+
+        // Route this tray's events to the proxy, now that nothing can fail
+        let proxy_clone = proxy.clone();
+        register_tray_event_handler(
+            internal_id,
+            Box::new(move |event| {
+                let typed_event = match event {
+                    Event::PointerButton {
+                        tray_icon_id,
+                        state,
+                        position,
+                        button,
+                        timestamp,
+                    } => Event::PointerButton {
+                        tray_icon_id,
+                        state,
+                        position,
+                        button,
+                        timestamp,
+                    },
+                    Event::DoubleClick {
+                        tray_icon_id,
+                        button,
+                        position,
+                    } => Event::DoubleClick {
+                        tray_icon_id,
+                        button,
+                        position,
+                    },
+                    Event::IconSizeChanged { tray_icon_id, size } => {
+                        Event::IconSizeChanged { tray_icon_id, size }
+                    }
+                    _ => return,
+                };
+                (proxy_clone)(typed_event);
+            }),
+        );
+
         Ok(Tray {
             status_item,
             tray_target,
             internal_id,
+            #[cfg(feature = "menu")]
+            menu_items: RefCell::new(menu_items),
+            #[cfg(feature = "menu")]
+            menu_builder,
             _marker: std::marker::PhantomData,
         })
     }
+}
 
-    pub fn set_tooltip(&self, tooltip: Option<&str>) -> Result<(), anyhow::Error> {
-        let mtm = MainThreadMarker::new()
-            .ok_or_else(|| anyhow::anyhow!("set_tooltip must be called on the main thread"))?;
+impl<T> Tray<T> {
+    pub fn set_tooltip(&self, tooltip: Option<&str>) -> Result<(), TrayError> {
+        MainThreadMarker::new().ok_or(TrayError::NotMainThread)?;
 
-        if let Some(button) = self.status_item.button(mtm) {
-            if let Some(tooltip_str) = tooltip {
-                let ns_tooltip = NSString::from_str(tooltip_str);
-                button.setToolTip(Some(&ns_tooltip));
-            } else {
-                button.setToolTip(None);
-            }
-            self.tray_target.update_dimensions();
+        self.tray_target.cancel_tooltip_revert();
+        self.tray_target
+            .ivars()
+            .tooltip
+            .replace(tooltip.map(str::to_owned));
+        self.tray_target.show_tooltip(tooltip);
+        Ok(())
+    }
+
+    /// Show `tooltip` for `duration`, then go back to the tooltip last set.
+    ///
+    /// The revert runs from an `NSTimer` on the main run loop.
+    pub fn flash_tooltip(&self, tooltip: &str, duration: Duration) -> Result<(), TrayError> {
+        MainThreadMarker::new().ok_or(TrayError::NotMainThread)?;
+
+        self.tray_target.cancel_tooltip_revert();
+        self.tray_target.show_tooltip(Some(tooltip));
+        let timer = unsafe {
+            NSTimer::scheduledTimerWithTimeInterval_target_selector_userInfo_repeats(
+                duration.as_secs_f64(),
+                &self.tray_target,
+                sel!(revertTooltip:),
+                None,
+                false,
+            )
+        };
+        self.tray_target.ivars().tooltip_timer.replace(Some(timer));
+        Ok(())
+    }
+
+    /// Show `title` next to the icon, or only the icon with `None`.
+    ///
+    /// Shorthand for [`set_segments`](Self::set_segments) with the icon
+    /// followed by plain text.
+    pub fn set_title(&self, title: Option<&str>) -> Result<(), TrayError> {
+        match title {
+            Some(title) => self.set_segments(&[Segment::Icon, Segment::Text(title.to_owned())]),
+            None => self.set_segments(&[Segment::Icon]),
         }
+    }
 
+    /// Compose the status item from the icon and text segments, such as a
+    /// VPN's name or a battery percentage next to the icon.
+    ///
+    /// The text segments are joined without separators, so include any
+    /// spacing in the text. The button has a single image, so
+    /// [`Segment::Icon`] may appear at most once, before or after all text;
+    /// leaving it out hides the icon.
+    pub fn set_segments(&self, segments: &[Segment]) -> Result<(), TrayError> {
+        let mtm = MainThreadMarker::new().ok_or(TrayError::NotMainThread)?;
+
+        let button = self
+            .status_item
+            .button(mtm)
+            .ok_or_else(|| TrayError::Platform("Failed to get status item button".to_owned()))?;
+        segment::apply(&button, segments)?;
+        self.tray_target.update_dimensions();
         Ok(())
     }
 }
 
-impl<T: Send + Sync> CoreTrayIcon for Tray<T> {
+impl<T: Send + Sync> CoreTrayIcon<T> for Tray<T> {
     fn id(&self) -> winit_extras_core::tray_icon_id::TrayIconId {
         winit_extras_core::tray_icon_id::TrayIconId::from_raw(self.internal_id)
     }
+
+    fn last_pointer_position(&self) -> Option<PhysicalPosition<f64>> {
+        self.tray_target.ivars().last_pointer_position.get()
+    }
+
+    fn set_tooltip(&self, tooltip: Option<&str>) -> Result<(), TrayError> {
+        let tooltip = tooltip.filter(|tooltip| !tooltip.is_empty());
+        Tray::set_tooltip(self, tooltip)
+    }
+
+    fn flash_tooltip(&self, tooltip: &str, duration: Duration) -> Result<(), TrayError> {
+        Tray::flash_tooltip(self, tooltip, duration)
+    }
+
+    fn set_icon(&self, icon: Option<&Icon>) -> Result<(), TrayError> {
+        MainThreadMarker::new().ok_or(TrayError::NotMainThread)?;
+        let previous = self.tray_target.ivars().icon.replace(icon.cloned());
+        if let Err(e) = self
+            .tray_target
+            .update_image(self.tray_target.current_icon_size())
+        {
+            self.tray_target.ivars().icon.replace(previous);
+            return Err(e.into());
+        }
+        self.tray_target.update_dimensions();
+        Ok(())
+    }
+
+    fn set_status_dot(&self, color: Option<StatusColor>) -> Result<(), TrayError> {
+        MainThreadMarker::new().ok_or(TrayError::NotMainThread)?;
+        let ivars = self.tray_target.ivars();
+        if ivars.icon.borrow().is_none() {
+            return Err(TrayError::NoIcon);
+        }
+        ivars.status_dot.set(color);
+        self.tray_target
+            .update_image(self.tray_target.current_icon_size())?;
+        Ok(())
+    }
+
+    fn set_interactive(&self, interactive: bool) -> Result<(), TrayError> {
+        let mtm = MainThreadMarker::new().ok_or(TrayError::NotMainThread)?;
+        self.tray_target.ivars().interactive.set(interactive);
+        if let Some(button) = self.status_item.button(mtm) {
+            button.setAppearsDisabled(!interactive);
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "menu")]
+    fn set_label(&self, id: &T, label: &str) -> Result<(), TrayError>
+    where
+        T: PartialEq,
+    {
+        let menu_items = self.menu_items.borrow();
+        let (_, menu_item, mnemonic) = menu_items
+            .iter()
+            .find(|(item_id, _, _)| item_id == id)
+            .ok_or(TrayError::UnknownMenuId)?;
+        menu_item.setTitle(&NSString::from_str(&display_label(label, *mnemonic)));
+        Ok(())
+    }
+
+    #[cfg(feature = "menu")]
+    fn set_menu(&self, menu: Vec<MenuEntry<T>>) -> Result<(), TrayError> {
+        let mtm = MainThreadMarker::new().ok_or(TrayError::NotMainThread)?;
+        let build = self.menu_builder.as_ref().ok_or(TrayError::NoContextMenu)?;
+        let (ns_menu, menu_items) = build(mtm, &menu)?;
+        *self.tray_target.ivars().menu.borrow_mut() = ns_menu;
+        *self.menu_items.borrow_mut() = menu_items;
+        Ok(())
+    }
+
+    #[cfg(feature = "menu")]
+    fn set_item_icon(&self, id: &T, icon: Option<Icon>) -> Result<(), TrayError>
+    where
+        T: PartialEq,
+    {
+        let menu_items = self.menu_items.borrow();
+        let (_, menu_item, _) = menu_items
+            .iter()
+            .find(|(item_id, _, _)| item_id == id)
+            .ok_or(TrayError::UnknownMenuId)?;
+        let image = icon
+            .map(|icon| icon_to_nsimage(&icon, false, PhysicalSize::new(16, 16)))
+            .transpose()?;
+        if let Some(image) = &image {
+            image.setSize(CGSize::new(16.0, 16.0));
+        }
+        menu_item.setImage(image.as_deref());
+        Ok(())
+    }
+
+    #[cfg(feature = "menu")]
+    fn open_menu(&self) -> Result<(), TrayError> {
+        MainThreadMarker::new().ok_or(TrayError::NotMainThread)?;
+        if !self.tray_target.show_menu() {
+            return Err(TrayError::NoContextMenu);
+        }
+        Ok(())
+    }
 }
 
 impl<T> Drop for Tray<T> {
     fn drop(&mut self) {
         // NSStatusItem must be removed on the main thread
         if let Some(_mtm) = MainThreadMarker::new() {
+            unregister_tray_event_handler(self.internal_id);
+            self.tray_target.cancel_tooltip_revert();
+            unsafe { NSNotificationCenter::defaultCenter().removeObserver(&self.tray_target) };
             NSStatusBar::systemStatusBar().removeStatusItem(&self.status_item);
             self.tray_target.removeFromSuperview();
         } else {
-            tracing::warn!("Tray dropped from non-main thread, status item will leak");
+            tracing::warn!(
+                internal_id = self.internal_id,
+                "Tray dropped from non-main thread, status item will leak"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+    use winit_extras_core::tray_icon_id::TrayIconId;
+
+    fn size_changed(tray_id: usize) -> Event<()> {
+        Event::IconSizeChanged {
+            tray_icon_id: TrayIconId::from_raw(tray_id),
+            size: PhysicalSize::new(22, 22),
+        }
+    }
+
+    #[test]
+    fn test_events_reach_only_their_own_tray() {
+        let first = TrayIconId::allocate().into_raw();
+        let second = TrayIconId::allocate().into_raw();
+        let received = Rc::new(RefCell::new(Vec::new()));
+        for tray_id in [first, second] {
+            let received = received.clone();
+            register_tray_event_handler(
+                tray_id,
+                Box::new(move |event| received.borrow_mut().push((tray_id, event))),
+            );
         }
+
+        dispatch_tray_event(first, size_changed(first));
+        dispatch_tray_event(second, size_changed(second));
+        assert_eq!(
+            *received.borrow(),
+            [(first, size_changed(first)), (second, size_changed(second))]
+        );
+
+        // A dropped tray's events go nowhere, and the other tray still gets
+        // its own.
+        unregister_tray_event_handler(first);
+        received.borrow_mut().clear();
+        dispatch_tray_event(first, size_changed(first));
+        dispatch_tray_event(second, size_changed(second));
+        assert_eq!(*received.borrow(), [(second, size_changed(second))]);
+        unregister_tray_event_handler(second);
     }
 }
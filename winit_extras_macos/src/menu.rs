@@ -1,18 +1,28 @@
-#![allow(dead_code)]
-
 use std::cell::RefCell;
 use std::collections::HashMap;
+use std::rc::Rc;
+use std::time::Instant;
 
 use objc2::rc::Retained;
 use objc2::{define_class, msg_send, sel, MainThreadMarker};
-use objc2_app_kit::{NSMenu, NSMenuItem};
+use objc2_app_kit::{NSEventModifierFlags, NSMenu, NSMenuItem};
 use objc2_foundation::{NSObject, NSString};
-use winit_extras_core::{Event, EventCallback, MenuEntry, MenuItem, Submenu};
+use winit_core::keyboard::{KeyCode, ModifiersState};
+use winit_extras_core::menu::display_label;
+use winit_extras_core::{
+    Accelerator, Event, EventCallback, ItemAction, MenuEntry, MenuItem, Submenu, TrayError,
+};
+
+use crate::util::current_event_time;
+
+// Callback run when a menu item is clicked, given the item and the time of
+// the click.
+type MenuCallback = Box<dyn Fn(&NSMenuItem, Instant)>;
 
 // Thread-local storage for menu item callbacks.
 // Maps menu item pointer address to callback function.
 thread_local! {
-    static MENU_CALLBACKS: RefCell<HashMap<usize, Box<dyn Fn()>>> = RefCell::new(HashMap::new());
+    static MENU_CALLBACKS: RefCell<HashMap<usize, MenuCallback>> = RefCell::new(HashMap::new());
 }
 
 // Instance variables for MenuTarget (none needed, we use the address as key)
@@ -28,9 +38,10 @@ define_class!(
         #[unsafe(method(menuItemClicked:))]
         fn menu_item_clicked(&self, sender: &NSMenuItem) {
             let key = sender as *const NSMenuItem as usize;
+            let timestamp = MainThreadMarker::new().map_or_else(Instant::now, current_event_time);
             MENU_CALLBACKS.with(|callbacks| {
                 if let Some(callback) = callbacks.borrow().get(&key) {
-                    callback();
+                    callback(sender, timestamp);
                 }
             });
         }
@@ -55,7 +66,7 @@ pub(crate) fn create_menu<T: Clone + Send + Sync + 'static>(
     entries: &[MenuEntry<T>],
     proxy: EventCallback<T>,
     tray_icon_id: winit_extras_core::tray_icon_id::TrayIconId,
-) -> Result<Option<Retained<NSMenu>>, anyhow::Error> {
+) -> Result<Option<Retained<NSMenu>>, TrayError> {
     if entries.is_empty() {
         return Ok(None);
     }
@@ -64,7 +75,7 @@ pub(crate) fn create_menu<T: Clone + Send + Sync + 'static>(
 
     for entry in entries {
         match entry {
-            MenuEntry::Separator => {
+            MenuEntry::Separator | MenuEntry::StyledSeparator(_) => {
                 let separator = NSMenuItem::separatorItem(mtm);
                 menu.addItem(&separator);
             }
@@ -82,14 +93,172 @@ pub(crate) fn create_menu<T: Clone + Send + Sync + 'static>(
     Ok(Some(menu))
 }
 
+/// Pairs the ID of every item in `entries` with the `NSMenuItem` built for
+/// it from `entries` and whether its label uses access key markers, so items
+/// can be updated later.
+pub(crate) fn collect_items<T: Clone>(
+    menu: &NSMenu,
+    entries: &[MenuEntry<T>],
+    items: &mut Vec<(T, Retained<NSMenuItem>, bool)>,
+) {
+    // Every entry was added as exactly one item, in order.
+    for (index, entry) in entries.iter().enumerate() {
+        let Some(menu_item) = menu.itemAtIndex(index as isize) else {
+            continue;
+        };
+        match entry {
+            MenuEntry::Item(item) => items.push((item.id.clone(), menu_item, item.mnemonic)),
+            MenuEntry::Submenu(submenu) => {
+                if let Some(child) = menu_item.submenu() {
+                    collect_items(&child, &submenu.items, items);
+                }
+            }
+            MenuEntry::Separator | MenuEntry::StyledSeparator(_) => {}
+        }
+    }
+}
+
+/// Routes clicks on the items of an application-built `NSMenu` to `proxy`.
+///
+/// Every item without a submenu gets a target that reports its `tag`
+/// through `id_for_tag`; tags mapped to `None` are ignored. Submenus are
+/// walked recursively. This replaces any target and action already set on
+/// the items.
+pub(crate) fn attach_native_menu<T: Clone + Send + Sync + 'static>(
+    mtm: MainThreadMarker,
+    menu: &NSMenu,
+    proxy: EventCallback<T>,
+    id_for_tag: Rc<dyn Fn(isize) -> Option<T>>,
+) {
+    for menu_item in menu.itemArray() {
+        if let Some(submenu) = menu_item.submenu() {
+            attach_native_menu(mtm, &submenu, proxy.clone(), id_for_tag.clone());
+            continue;
+        }
+        if menu_item.isSeparatorItem() {
+            continue;
+        }
+
+        let tag = menu_item.tag();
+        let proxy = proxy.clone();
+        let id_for_tag = id_for_tag.clone();
+        unsafe { menu_item.setAction(Some(sel!(menuItemClicked:))) };
+        set_callback(
+            mtm,
+            &menu_item,
+            Box::new(move |_, timestamp| {
+                if let Some(id) = id_for_tag(tag) {
+                    proxy(Event::MenuItemClicked {
+                        id,
+                        checked: None,
+                        timestamp,
+                    });
+                }
+            }),
+        );
+    }
+}
+
+/// Performs the clicked item's [`ItemAction`], if it has one.
+pub(crate) fn perform_action(action: Option<&ItemAction>) {
+    if let Some(action) = action {
+        if let Err(e) = action.perform() {
+            tracing::warn!(?action, "Failed to perform menu item action: {}", e);
+        }
+    }
+}
+
+/// Shows `accelerator`, if any, as the key equivalent of `menu_item`, which
+/// AppKit draws in its own symbols.
+pub(crate) fn set_key_equivalent(menu_item: &NSMenuItem, accelerator: Option<Accelerator>) {
+    let Some(accelerator) = accelerator else {
+        return;
+    };
+    let Some(key) = key_equivalent(accelerator) else {
+        tracing::debug!(?accelerator, "No key equivalent for menu accelerator");
+        return;
+    };
+
+    let modifiers = [
+        (ModifiersState::CONTROL, NSEventModifierFlags::Control),
+        (ModifiersState::ALT, NSEventModifierFlags::Option),
+        (ModifiersState::SHIFT, NSEventModifierFlags::Shift),
+        (ModifiersState::META, NSEventModifierFlags::Command),
+    ];
+    let mut mask = NSEventModifierFlags::empty();
+    for (modifier, flag) in modifiers {
+        if accelerator.modifiers.contains(modifier) {
+            mask |= flag;
+        }
+    }
+
+    menu_item.setKeyEquivalent(&NSString::from_str(&key.to_string()));
+    menu_item.setKeyEquivalentModifierMask(mask);
+}
+
+/// The character AppKit expects as the key equivalent for the accelerator's
+/// key. Special keys use the private-use characters of `NSEvent`'s function
+/// key constants, such as `NSUpArrowFunctionKey`.
+fn key_equivalent(accelerator: Accelerator) -> Option<char> {
+    let special = match accelerator.key {
+        KeyCode::Enter => '\r',
+        KeyCode::Tab => '\t',
+        KeyCode::Space => ' ',
+        KeyCode::Escape => '\u{1b}',
+        KeyCode::Backspace => '\u{8}',
+        KeyCode::ArrowUp => '\u{F700}',
+        KeyCode::ArrowDown => '\u{F701}',
+        KeyCode::ArrowLeft => '\u{F702}',
+        KeyCode::ArrowRight => '\u{F703}',
+        KeyCode::Insert => '\u{F727}',
+        KeyCode::Delete => '\u{F728}',
+        KeyCode::Home => '\u{F729}',
+        KeyCode::End => '\u{F72B}',
+        KeyCode::PageUp => '\u{F72C}',
+        KeyCode::PageDown => '\u{F72D}',
+        KeyCode::PrintScreen => '\u{F72E}',
+        KeyCode::Pause => '\u{F730}',
+        _ => {
+            // Letters, digits and punctuation are named by their character,
+            // and function keys `F1` on follow on from `NSF1FunctionKey`.
+            let name = accelerator.key_name();
+            let mut chars = name.chars();
+            return match (chars.next(), chars.next()) {
+                (Some(c), None) => Some(c.to_ascii_lowercase()),
+                _ => {
+                    let number: u32 = name.strip_prefix('F')?.parse().ok()?;
+                    char::from_u32(0xF704 + number.checked_sub(1)?)
+                }
+            };
+        }
+    };
+    Some(special)
+}
+
+/// Points `menu_item` at a new target that runs `callback` when clicked.
+fn set_callback(mtm: MainThreadMarker, menu_item: &NSMenuItem, callback: MenuCallback) {
+    let target = MenuTarget::new(mtm);
+
+    let key = menu_item as *const NSMenuItem as usize;
+    MENU_CALLBACKS.with(|callbacks| {
+        callbacks.borrow_mut().insert(key, callback);
+    });
+
+    // Set target and keep it alive
+    unsafe { menu_item.setTarget(Some(&target)) };
+    MENU_TARGETS.with(|targets| {
+        targets.borrow_mut().push(target);
+    });
+}
+
 /// Creates a single NSMenuItem from a MenuItem.
 fn create_menu_item<T: Clone + Send + Sync + 'static>(
     mtm: MainThreadMarker,
     item: &MenuItem<T>,
     proxy: EventCallback<T>,
     tray_icon_id: winit_extras_core::tray_icon_id::TrayIconId,
-) -> Result<Retained<NSMenuItem>, anyhow::Error> {
-    let title = NSString::from_str(&item.label);
+) -> Result<Retained<NSMenuItem>, TrayError> {
+    let title = NSString::from_str(&display_label(&item.label, item.mnemonic));
     let menu_item = unsafe {
         NSMenuItem::initWithTitle_action_keyEquivalent(
             mtm.alloc(),
@@ -99,29 +268,37 @@ fn create_menu_item<T: Clone + Send + Sync + 'static>(
         )
     };
 
-    // Create target for this menu item
-    let target = MenuTarget::new(mtm);
-
     // Store callback
     let id = item.id.clone();
+    let checkable = item.checked.is_some();
+    let action = item.action.clone();
     let _ = tray_icon_id;
-    let callback = Box::new(move || {
-        proxy(Event::MenuItemClicked { id: id.clone() });
-    });
-
-    let key = &*menu_item as *const NSMenuItem as usize;
-    MENU_CALLBACKS.with(|callbacks| {
-        callbacks.borrow_mut().insert(key, callback);
-    });
-
-    // Set target and keep it alive
-    unsafe { menu_item.setTarget(Some(&target)) };
-    MENU_TARGETS.with(|targets| {
-        targets.borrow_mut().push(target);
-    });
+    set_callback(
+        mtm,
+        &menu_item,
+        Box::new(move |menu_item, timestamp| {
+            // The menu is kept and shown again, so it carries the state.
+            let checked = checkable.then(|| {
+                let checked = menu_item.state() == 0;
+                menu_item.setState(if checked { 1 } else { 0 });
+                checked
+            });
+            perform_action(action.as_ref());
+            proxy(Event::MenuItemClicked {
+                id: id.clone(),
+                checked,
+                timestamp,
+            });
+        }),
+    );
 
     // Set enabled state
     menu_item.setEnabled(item.enabled);
+    set_key_equivalent(&menu_item, item.accelerator);
+
+    if let Some(checked) = item.checked {
+        menu_item.setState(if checked { 1 } else { 0 });
+    }
 
     Ok(menu_item)
 }
@@ -132,8 +309,8 @@ fn create_submenu<T: Clone + Send + Sync + 'static>(
     submenu: &Submenu<T>,
     proxy: EventCallback<T>,
     tray_icon_id: winit_extras_core::tray_icon_id::TrayIconId,
-) -> Result<Retained<NSMenuItem>, anyhow::Error> {
-    let title = NSString::from_str(&submenu.label);
+) -> Result<Retained<NSMenuItem>, TrayError> {
+    let title = NSString::from_str(&display_label(&submenu.label, submenu.mnemonic));
     let menu_item = unsafe {
         NSMenuItem::initWithTitle_action_keyEquivalent(
             mtm.alloc(),
@@ -153,3 +330,23 @@ fn create_submenu<T: Clone + Send + Sync + 'static>(
 
     Ok(menu_item)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_equivalent() {
+        let key = |modifiers, key| key_equivalent(Accelerator::new(modifiers, key));
+        assert_eq!(key(ModifiersState::META, KeyCode::KeyS), Some('s'));
+        assert_eq!(key(ModifiersState::SHIFT, KeyCode::Digit1), Some('1'));
+        assert_eq!(key(ModifiersState::empty(), KeyCode::Comma), Some(','));
+        assert_eq!(key(ModifiersState::empty(), KeyCode::F1), Some('\u{F704}'));
+        assert_eq!(key(ModifiersState::empty(), KeyCode::F12), Some('\u{F70F}'));
+        assert_eq!(
+            key(ModifiersState::empty(), KeyCode::ArrowUp),
+            Some('\u{F700}')
+        );
+        assert_eq!(key(ModifiersState::empty(), KeyCode::NumLock), None);
+    }
+}
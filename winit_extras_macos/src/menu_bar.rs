@@ -3,18 +3,22 @@
 //! On macOS, the menu bar is a global application menu bar managed by NSApplication.
 
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use objc2::rc::Retained;
+use objc2::rc::{Retained, Weak};
+use objc2::runtime::Sel;
 use objc2::{define_class, msg_send, sel, MainThreadMarker};
-use objc2_app_kit::{NSApplication, NSMenu, NSMenuItem};
-use objc2_foundation::{NSObject, NSString};
+use objc2_app_kit::{NSApplication, NSMenu, NSMenuItem, NSView, NSWindow};
+use objc2_foundation::{NSObject, NSProcessInfo, NSString};
+use rwh_06::RawWindowHandle;
+use winit_extras_core::menu::display_label;
 use winit_extras_core::menu_bar::{
     MenuBar as CoreMenuBar, MenuBarAttributes, MenuBarEvent, MenuBarId, MenuBarProxy, TopLevelMenu,
+    TopLevelMenuId,
 };
-use winit_extras_core::{MenuEntry, MenuItem, Submenu};
+use winit_extras_core::{MenuEntry, MenuItem, MenuRole, Submenu, TrayError};
 
-static COUNTER: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(1);
+use crate::menu::set_key_equivalent;
 
 // Thread-local storage for menu bar item callbacks.
 thread_local! {
@@ -56,13 +60,18 @@ thread_local! {
 }
 
 /// macOS menu bar implementation.
-pub struct MenuBar {
+pub struct MenuBar<T = ()> {
     internal_id: usize,
-    #[allow(dead_code)] // Kept to hold ownership of the menu
     main_menu: Retained<NSMenu>,
+    // The native item built for every menu item, for finding items by ID.
+    items: RefCell<Vec<(T, Retained<NSMenuItem>, bool)>>,
+    proxy: MenuBarProxy<T>,
+    parent: Option<Weak<NSWindow>>,
+    // Menus added with `add_top_level`.
+    added: RefCell<Vec<(TopLevelMenuId, Retained<NSMenuItem>)>>,
 }
 
-impl std::fmt::Debug for MenuBar {
+impl<T> std::fmt::Debug for MenuBar<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("MenuBar")
             .field("internal_id", &self.internal_id)
@@ -70,25 +79,33 @@ impl std::fmt::Debug for MenuBar {
     }
 }
 
-impl MenuBar {
+impl<T: Clone + Send + Sync + 'static> MenuBar<T> {
     /// Create a new menu bar with the given attributes.
-    pub fn new<T: Clone + Send + Sync + 'static>(
-        proxy: MenuBarProxy<T>,
-        attr: MenuBarAttributes<T>,
-    ) -> Result<Self, anyhow::Error> {
-        let mtm = MainThreadMarker::new()
-            .ok_or_else(|| anyhow::anyhow!("MenuBar must be created on the main thread"))?;
+    ///
+    /// Items with an application menu role are moved into a new first menu,
+    /// which macOS shows under the application's name. `parent_window` is
+    /// only needed by the window roles.
+    pub fn new(proxy: MenuBarProxy<T>, mut attr: MenuBarAttributes<T>) -> Result<Self, TrayError> {
+        let mtm = MainThreadMarker::new().ok_or(TrayError::NotMainThread)?;
+
+        route_app_menu_items(&mut attr.menus);
+        let parent = parent_window(attr.parent_window)?;
 
-        let internal_id = COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-        let menu_bar_id = MenuBarId::from_raw(internal_id);
+        let menu_bar_id = MenuBarId::allocate();
+        let internal_id = menu_bar_id.into_raw();
 
         // Create the main menu
         let main_menu = NSMenu::new(mtm);
 
         // Add top-level menus
+        let mut items = Vec::new();
         for top_level in &attr.menus {
-            let menu_item = create_top_level_menu(mtm, top_level, proxy.clone(), menu_bar_id)?;
+            let menu_item =
+                create_top_level_menu(mtm, top_level, proxy.clone(), menu_bar_id, parent.as_ref())?;
             main_menu.addItem(&menu_item);
+            if let Some(submenu) = menu_item.submenu() {
+                crate::menu::collect_items(&submenu, &top_level.items, &mut items);
+            }
         }
 
         // Set as the application's main menu
@@ -98,11 +115,15 @@ impl MenuBar {
         Ok(MenuBar {
             internal_id,
             main_menu,
+            items: RefCell::new(items),
+            proxy,
+            parent,
+            added: RefCell::new(Vec::new()),
         })
     }
 }
 
-impl CoreMenuBar for MenuBar {
+impl<T: Clone + Send + Sync + 'static> CoreMenuBar<T> for MenuBar<T> {
     fn id(&self) -> MenuBarId {
         MenuBarId::from_raw(self.internal_id)
     }
@@ -115,23 +136,243 @@ impl CoreMenuBar for MenuBar {
             app.setMainMenu(Some(&empty_menu));
         }
     }
+
+    fn item_count(&self) -> usize {
+        self.main_menu.numberOfItems().max(0) as usize
+    }
+
+    fn top_level_labels(&self) -> Vec<String> {
+        (0..self.main_menu.numberOfItems())
+            .filter_map(|index| self.main_menu.itemAtIndex(index))
+            .map(|item| item.title().to_string())
+            .collect()
+    }
+
+    fn set_label(&self, id: &T, label: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>>
+    where
+        T: PartialEq,
+    {
+        let items = self.items.borrow();
+        let (_, menu_item, mnemonic) = items
+            .iter()
+            .find(|(item_id, _, _)| item_id == id)
+            .ok_or("no menu item with this id")?;
+        menu_item.setTitle(&NSString::from_str(&display_label(label, *mnemonic)));
+        Ok(())
+    }
+
+    fn add_top_level(
+        &self,
+        menu: TopLevelMenu<T>,
+    ) -> Result<TopLevelMenuId, Box<dyn std::error::Error + Send + Sync>> {
+        let mtm = MainThreadMarker::new().ok_or("menu bars can only change on the main thread")?;
+
+        let menu_item = create_top_level_menu(
+            mtm,
+            &menu,
+            self.proxy.clone(),
+            MenuBarId::from_raw(self.internal_id),
+            self.parent.as_ref(),
+        )?;
+        self.main_menu.addItem(&menu_item);
+        if let Some(submenu) = menu_item.submenu() {
+            crate::menu::collect_items(&submenu, &menu.items, &mut self.items.borrow_mut());
+        }
+
+        let id = TopLevelMenuId::allocate();
+        self.added.borrow_mut().push((id, menu_item));
+        Ok(id)
+    }
+
+    fn remove_top_level(
+        &self,
+        id: TopLevelMenuId,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut added = self.added.borrow_mut();
+        let index = added
+            .iter()
+            .position(|(added_id, _)| *added_id == id)
+            .ok_or("no top-level menu with this id")?;
+        let (_, menu_item) = added.remove(index);
+        self.main_menu.removeItem(&menu_item);
+
+        let mut removed = HashSet::new();
+        if let Some(submenu) = menu_item.submenu() {
+            forget_callbacks(&submenu, &mut removed);
+        }
+        self.items
+            .borrow_mut()
+            .retain(|(_, item, _)| !removed.contains(&(&**item as *const NSMenuItem as usize)));
+        Ok(())
+    }
 }
 
-impl Drop for MenuBar {
+/// Drops the callbacks and targets of the items in `menu` and its
+/// submenus, recording the items in `removed`.
+fn forget_callbacks(menu: &NSMenu, removed: &mut HashSet<usize>) {
+    for menu_item in menu.itemArray() {
+        if let Some(submenu) = menu_item.submenu() {
+            forget_callbacks(&submenu, removed);
+        }
+        let key = &*menu_item as *const NSMenuItem as usize;
+        removed.insert(key);
+        MENU_BAR_CALLBACKS.with(|callbacks| callbacks.borrow_mut().remove(&key));
+
+        let Some(target) = menu_item.target() else {
+            continue;
+        };
+        let target = Retained::as_ptr(&target).cast::<MenuBarTarget>();
+        MENU_BAR_TARGETS.with(|targets| {
+            targets
+                .borrow_mut()
+                .retain(|kept| Retained::as_ptr(kept) != target)
+        });
+    }
+}
+
+impl<T> Drop for MenuBar<T> {
     fn drop(&mut self) {
         // Clean up menu bar callbacks associated with this menu bar
         // Note: We don't remove the main menu on drop since it would leave the app without a menu
     }
 }
 
+/// Moves every item with an application menu role into a new menu in front
+/// of `menus`, in the order macOS apps conventionally use.
+fn route_app_menu_items<T>(menus: &mut Vec<TopLevelMenu<T>>) {
+    let mut app_items = Vec::new();
+    for menu in menus.iter_mut() {
+        take_app_menu_items(&mut menu.items, &mut app_items);
+    }
+    if app_items.is_empty() {
+        return;
+    }
+
+    app_items.sort_by_key(|item| item.role.and_then(app_menu_group));
+
+    let mut entries = Vec::new();
+    let mut last_group = None;
+    for item in app_items {
+        let group = item.role.and_then(app_menu_group);
+        if last_group.is_some() && last_group != group {
+            entries.push(MenuEntry::Separator);
+        }
+        last_group = group;
+        entries.push(MenuEntry::Item(item));
+    }
+
+    // The title is replaced by the application's name, but set it anyway so
+    // `top_level_labels` reads sensibly.
+    let title = NSProcessInfo::processInfo().processName().to_string();
+    menus.insert(0, TopLevelMenu::new(title, entries));
+}
+
+/// Removes application menu items from `entries` and its submenus, along
+/// with separators left dangling by the removal.
+fn take_app_menu_items<T>(entries: &mut Vec<MenuEntry<T>>, app_items: &mut Vec<MenuItem<T>>) {
+    let before = app_items.len();
+    for entry in std::mem::take(entries) {
+        match entry {
+            MenuEntry::Item(item) if item.role.and_then(app_menu_group).is_some() => {
+                app_items.push(item);
+            }
+            MenuEntry::Submenu(mut submenu) => {
+                take_app_menu_items(&mut submenu.items, app_items);
+                entries.push(MenuEntry::Submenu(submenu));
+            }
+            entry => entries.push(entry),
+        }
+    }
+    if app_items.len() == before {
+        return;
+    }
+
+    let is_separator = |entry: &MenuEntry<T>| {
+        matches!(entry, MenuEntry::Separator | MenuEntry::StyledSeparator(_))
+    };
+    let mut previous_was_separator = true;
+    entries.retain(|entry| {
+        let separator = is_separator(entry);
+        let keep = !(separator && previous_was_separator);
+        previous_was_separator = separator;
+        keep
+    });
+    if entries.last().is_some_and(is_separator) {
+        entries.pop();
+    }
+}
+
+/// Where an item with `role` goes in the application menu, or `None` if it
+/// stays where it was declared.
+fn app_menu_group(role: MenuRole) -> Option<u8> {
+    match role {
+        MenuRole::About => Some(0),
+        MenuRole::Preferences => Some(1),
+        MenuRole::Services => Some(2),
+        MenuRole::Hide => Some(3),
+        MenuRole::Quit => Some(4),
+        _ => None,
+    }
+}
+
+/// The standard AppKit action for `role`, sent to the responder chain, and
+/// its usual key equivalent.
+///
+/// Roles without an action are delivered as clicks.
+fn role_action(role: MenuRole) -> (Option<Sel>, &'static str) {
+    match role {
+        MenuRole::About => (Some(sel!(orderFrontStandardAboutPanel:)), ""),
+        MenuRole::Preferences => (None, ","),
+        MenuRole::Services => (None, ""),
+        MenuRole::Hide => (Some(sel!(hide:)), "h"),
+        MenuRole::Quit => (Some(sel!(terminate:)), "q"),
+        MenuRole::Undo => (Some(sel!(undo:)), "z"),
+        // An uppercase key equivalent implies Shift.
+        MenuRole::Redo => (Some(sel!(redo:)), "Z"),
+        MenuRole::Cut => (Some(sel!(cut:)), "x"),
+        MenuRole::Copy => (Some(sel!(copy:)), "c"),
+        MenuRole::Paste => (Some(sel!(paste:)), "v"),
+        MenuRole::SelectAll => (Some(sel!(selectAll:)), "a"),
+        MenuRole::Minimize => (Some(sel!(performMiniaturize:)), "m"),
+        MenuRole::Zoom => (Some(sel!(performZoom:)), ""),
+        // Performed on the parent window by the click handler.
+        MenuRole::MinimizeWindow => (None, "m"),
+        MenuRole::ToggleFullscreen => (None, ""),
+        MenuRole::CloseWindow => (None, "w"),
+    }
+}
+
+/// The window of the `parent_window` view, if one was given.
+fn parent_window(handle: Option<RawWindowHandle>) -> Result<Option<Weak<NSWindow>>, TrayError> {
+    let ns_view = match handle {
+        Some(RawWindowHandle::AppKit(handle)) => handle.ns_view,
+        Some(_) => return Err(TrayError::UnsupportedParentWindow),
+        None => return Ok(None),
+    };
+    // SAFETY: an AppKit window handle points to a live NSView.
+    let view = unsafe { ns_view.cast::<NSView>().as_ref() };
+    Ok(view.window().map(|window| Weak::from_retained(&window)))
+}
+
+/// Performs one of the window roles that are also reported as clicks.
+fn perform_window_role(window: &NSWindow, role: MenuRole) {
+    match role {
+        MenuRole::MinimizeWindow => window.performMiniaturize(None),
+        MenuRole::ToggleFullscreen => window.toggleFullScreen(None),
+        MenuRole::CloseWindow => window.performClose(None),
+        _ => (),
+    }
+}
+
 /// Creates an NSMenuItem for a top-level menu.
 fn create_top_level_menu<T: Clone + Send + Sync + 'static>(
     mtm: MainThreadMarker,
     top_level: &TopLevelMenu<T>,
     proxy: MenuBarProxy<T>,
     menu_bar_id: MenuBarId,
-) -> Result<Retained<NSMenuItem>, anyhow::Error> {
-    let title = NSString::from_str(&top_level.label);
+    parent: Option<&Weak<NSWindow>>,
+) -> Result<Retained<NSMenuItem>, TrayError> {
+    let title = NSString::from_str(&display_label(&top_level.label, top_level.mnemonic));
     let menu_item = unsafe {
         NSMenuItem::initWithTitle_action_keyEquivalent(
             mtm.alloc(),
@@ -142,7 +383,7 @@ fn create_top_level_menu<T: Clone + Send + Sync + 'static>(
     };
 
     // Create the submenu for this top-level menu
-    let submenu = create_menu(mtm, &top_level.items, proxy, menu_bar_id)?;
+    let submenu = create_menu(mtm, &top_level.items, proxy, menu_bar_id, parent)?;
     submenu.setTitle(&title);
     menu_item.setSubmenu(Some(&submenu));
 
@@ -155,21 +396,23 @@ fn create_menu<T: Clone + Send + Sync + 'static>(
     entries: &[MenuEntry<T>],
     proxy: MenuBarProxy<T>,
     menu_bar_id: MenuBarId,
-) -> Result<Retained<NSMenu>, anyhow::Error> {
+    parent: Option<&Weak<NSWindow>>,
+) -> Result<Retained<NSMenu>, TrayError> {
     let menu = NSMenu::new(mtm);
 
     for entry in entries {
         match entry {
-            MenuEntry::Separator => {
+            MenuEntry::Separator | MenuEntry::StyledSeparator(_) => {
                 let separator = NSMenuItem::separatorItem(mtm);
                 menu.addItem(&separator);
             }
             MenuEntry::Item(item) => {
-                let menu_item = create_menu_item(mtm, item, proxy.clone(), menu_bar_id)?;
+                let menu_item = create_menu_item(mtm, item, proxy.clone(), menu_bar_id, parent)?;
                 menu.addItem(&menu_item);
             }
             MenuEntry::Submenu(submenu) => {
-                let submenu_item = create_submenu(mtm, submenu, proxy.clone(), menu_bar_id)?;
+                let submenu_item =
+                    create_submenu(mtm, submenu, proxy.clone(), menu_bar_id, parent)?;
                 menu.addItem(&submenu_item);
             }
         }
@@ -184,23 +427,49 @@ fn create_menu_item<T: Clone + Send + Sync + 'static>(
     item: &MenuItem<T>,
     proxy: MenuBarProxy<T>,
     menu_bar_id: MenuBarId,
-) -> Result<Retained<NSMenuItem>, anyhow::Error> {
-    let title = NSString::from_str(&item.label);
+    parent: Option<&Weak<NSWindow>>,
+) -> Result<Retained<NSMenuItem>, TrayError> {
+    let (role_selector, key_equivalent) = item.role.map_or((None, ""), role_action);
+
+    let title = NSString::from_str(&display_label(&item.label, item.mnemonic));
     let menu_item = unsafe {
         NSMenuItem::initWithTitle_action_keyEquivalent(
             mtm.alloc(),
             &title,
-            Some(sel!(menuItemClicked:)),
-            &NSString::from_str(""),
+            Some(role_selector.unwrap_or(sel!(menuItemClicked:))),
+            &NSString::from_str(key_equivalent),
         )
     };
+    menu_item.setEnabled(item.enabled);
+    // An explicit accelerator replaces the role's standard shortcut.
+    set_key_equivalent(&menu_item, item.accelerator);
+
+    if item.role == Some(MenuRole::Services) {
+        let services = NSMenu::new(mtm);
+        services.setTitle(&title);
+        menu_item.setSubmenu(Some(&services));
+        NSApplication::sharedApplication(mtm).setServicesMenu(Some(&services));
+        return Ok(menu_item);
+    }
+
+    // With no target, the action goes to the first responder that handles it.
+    if role_selector.is_some() {
+        return Ok(menu_item);
+    }
 
     // Create target for this menu item
     let target = MenuBarTarget::new(mtm);
 
     // Store callback
     let id = item.id.clone();
+    let action = item.action.clone();
+    let window_role = item.role.filter(|role| role.is_window_action());
+    let parent = parent.cloned();
     let callback = Box::new(move || {
+        if let (Some(role), Some(window)) = (window_role, parent.as_ref().and_then(Weak::load)) {
+            perform_window_role(&window, role);
+        }
+        crate::menu::perform_action(action.as_ref());
         proxy(
             menu_bar_id,
             MenuBarEvent::MenuItemClicked { id: id.clone() },
@@ -218,9 +487,6 @@ fn create_menu_item<T: Clone + Send + Sync + 'static>(
         targets.borrow_mut().push(target);
     });
 
-    // Set enabled state
-    menu_item.setEnabled(item.enabled);
-
     Ok(menu_item)
 }
 
@@ -230,8 +496,9 @@ fn create_submenu<T: Clone + Send + Sync + 'static>(
     submenu: &Submenu<T>,
     proxy: MenuBarProxy<T>,
     menu_bar_id: MenuBarId,
-) -> Result<Retained<NSMenuItem>, anyhow::Error> {
-    let title = NSString::from_str(&submenu.label);
+    parent: Option<&Weak<NSWindow>>,
+) -> Result<Retained<NSMenuItem>, TrayError> {
+    let title = NSString::from_str(&display_label(&submenu.label, submenu.mnemonic));
     let menu_item = unsafe {
         NSMenuItem::initWithTitle_action_keyEquivalent(
             mtm.alloc(),
@@ -242,7 +509,7 @@ fn create_submenu<T: Clone + Send + Sync + 'static>(
     };
 
     // Create the submenu
-    let submenu_menu = create_menu(mtm, &submenu.items, proxy, menu_bar_id)?;
+    let submenu_menu = create_menu(mtm, &submenu.items, proxy, menu_bar_id, parent)?;
     submenu_menu.setTitle(&title);
     menu_item.setSubmenu(Some(&submenu_menu));
 
@@ -0,0 +1,113 @@
+//! Icon and text segments shown in a status item.
+
+use objc2::rc::Retained;
+use objc2::runtime::AnyObject;
+use objc2_app_kit::{
+    NSCellImagePosition, NSColor, NSFont, NSFontAttributeName, NSForegroundColorAttributeName,
+    NSStatusBarButton,
+};
+use objc2_foundation::{NSAttributedString, NSDictionary, NSMutableAttributedString, NSString};
+use winit_extras_core::icon::StatusColor;
+use winit_extras_core::TrayError;
+
+/// A part of a status item, see [`Tray::set_segments`](crate::Tray::set_segments).
+#[derive(Debug, Clone, PartialEq)]
+pub enum Segment {
+    /// The tray's icon.
+    Icon,
+    /// Text in the menu bar's font and color.
+    Text(String),
+    /// Text whose digits all have the same width, so a changing number such
+    /// as a percentage doesn't make the status item jitter.
+    MonospacedDigits(String),
+    /// Text drawn in a color, such as red for an error state.
+    Colored(String, StatusColor),
+}
+
+impl Segment {
+    fn text(&self) -> Option<&str> {
+        match self {
+            Segment::Icon => None,
+            Segment::Text(text) | Segment::MonospacedDigits(text) | Segment::Colored(text, _) => {
+                Some(text)
+            }
+        }
+    }
+}
+
+/// Shows `segments` on `button`.
+pub(crate) fn apply(button: &NSStatusBarButton, segments: &[Segment]) -> Result<(), TrayError> {
+    let icons: Vec<usize> = segments
+        .iter()
+        .enumerate()
+        .filter(|(_, segment)| **segment == Segment::Icon)
+        .map(|(index, _)| index)
+        .collect();
+    let has_text = segments.iter().any(|segment| segment.text().is_some());
+
+    let image_position = match icons.as_slice() {
+        [] => NSCellImagePosition::NoImage,
+        [_] if !has_text => NSCellImagePosition::ImageOnly,
+        [0] => NSCellImagePosition::ImageLeft,
+        [index] if *index == segments.len() - 1 => NSCellImagePosition::ImageRight,
+        [_] => {
+            return Err(TrayError::Platform(
+                "the icon segment must come before or after all text".to_owned(),
+            ))
+        }
+        _ => {
+            return Err(TrayError::Platform(
+                "at most one icon segment is supported".to_owned(),
+            ))
+        }
+    };
+    button.setImagePosition(image_position);
+
+    if segments
+        .iter()
+        .all(|segment| matches!(segment, Segment::Icon | Segment::Text(_)))
+    {
+        // Plain text keeps the menu bar's own styling, including vibrancy.
+        let title: String = segments.iter().filter_map(Segment::text).collect();
+        button.setTitle(&NSString::from_str(&title));
+        return Ok(());
+    }
+
+    let title = NSMutableAttributedString::new();
+    let font = NSFont::menuBarFontOfSize(0.0);
+    for segment in segments {
+        let Some(text) = segment.text() else {
+            continue;
+        };
+        let (font, color) = match segment {
+            Segment::MonospacedDigits(_) => (
+                NSFont::monospacedDigitSystemFontOfSize_weight(font.pointSize(), 0.0),
+                NSColor::labelColor(),
+            ),
+            Segment::Colored(_, color) => {
+                let [r, g, b] = color.rgb();
+                (
+                    font.clone(),
+                    NSColor::colorWithSRGBRed_green_blue_alpha(
+                        r as f64 / 255.0,
+                        g as f64 / 255.0,
+                        b as f64 / 255.0,
+                        1.0,
+                    ),
+                )
+            }
+            _ => (font.clone(), NSColor::labelColor()),
+        };
+        title.appendAttributedString(&attributed(text, &font, &color));
+    }
+    button.setAttributedTitle(&title);
+    Ok(())
+}
+
+fn attributed(text: &str, font: &NSFont, color: &NSColor) -> Retained<NSAttributedString> {
+    let keys = unsafe { [NSFontAttributeName, NSForegroundColorAttributeName] };
+    let objects: [&AnyObject; 2] = [font, color];
+    let attributes = NSDictionary::from_slices(&keys, &objects);
+    // SAFETY: The font and color attributes hold an NSFont and an NSColor.
+    unsafe { NSAttributedString::new_with_attributes(&NSString::from_str(text), &attributes) }
+}
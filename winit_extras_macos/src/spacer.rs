@@ -0,0 +1,70 @@
+//! Empty status items that space out an application's menu extras.
+
+use objc2::rc::Retained;
+use objc2::MainThreadMarker;
+use objc2_app_kit::{NSStatusBar, NSStatusItem};
+use winit_extras_core::tray_icon_id::TrayIconId;
+use winit_extras_core::{TrayError, TrayIcon as CoreTrayIcon};
+
+/// A status item with nothing in it, taking up a fixed width of the menu
+/// bar.
+///
+/// macOS places each new status item to the left of the existing ones, so
+/// creating a spacer between two trays separates them into groups. It shows
+/// nothing and produces no events. Dropping it removes the space.
+pub struct Spacer {
+    internal_id: usize,
+    status_item: Retained<NSStatusItem>,
+}
+
+impl std::fmt::Debug for Spacer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Spacer")
+            .field("internal_id", &self.internal_id)
+            .field("width", &self.status_item.length())
+            .finish()
+    }
+}
+
+impl Spacer {
+    /// Add a spacer `width` points wide to the menu bar.
+    ///
+    /// Must be called on the main thread.
+    pub fn new(width: f64) -> Result<Self, TrayError> {
+        let mtm = MainThreadMarker::new().ok_or(TrayError::NotMainThread)?;
+        if !(width.is_finite() && width > 0.0) {
+            return Err(TrayError::Platform(format!(
+                "spacer width must be positive, got {width}"
+            )));
+        }
+
+        let status_item = NSStatusBar::systemStatusBar().statusItemWithLength(width);
+        if let Some(button) = status_item.button(mtm) {
+            button.setEnabled(false);
+        }
+
+        Ok(Spacer {
+            internal_id: TrayIconId::allocate().into_raw(),
+            status_item,
+        })
+    }
+}
+
+impl<T> CoreTrayIcon<T> for Spacer {
+    fn id(&self) -> TrayIconId {
+        TrayIconId::from_raw(self.internal_id)
+    }
+}
+
+impl Drop for Spacer {
+    fn drop(&mut self) {
+        if MainThreadMarker::new().is_some() {
+            NSStatusBar::systemStatusBar().removeStatusItem(&self.status_item);
+        } else {
+            tracing::warn!(
+                internal_id = self.internal_id,
+                "Spacer dropped from non-main thread, status item will leak"
+            );
+        }
+    }
+}
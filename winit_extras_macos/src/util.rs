@@ -1,26 +1,69 @@
+use std::time::{Duration, Instant};
+
+use dpi::{PhysicalPosition, PhysicalSize};
 use objc2::rc::Retained;
-use objc2::AllocAnyThread;
-use objc2_app_kit::NSImage;
-use objc2_foundation::{NSData, NSSize};
-use winit_core::icon::{Icon, RgbaIcon};
+use objc2::{AllocAnyThread, MainThreadMarker};
+use objc2_app_kit::{NSEvent, NSImage, NSScreen, NSWorkspace};
+use objc2_foundation::{NSData, NSProcessInfo, NSSize, NSString, NSURL};
+use winit_core::icon::Icon;
+use winit_extras_core::icon::{IconError, PreparedIcon};
 
-/// Converts a winit Icon to an NSImage for use in the status bar.
+/// The backing scale factor of the screen containing `position`, in the
+/// bottom-left origin screen coordinates tray events report.
 ///
-/// The image is configured as a template image for automatic dark mode support.
-pub(crate) fn icon_to_nsimage(icon: &Icon) -> Option<Retained<NSImage>> {
-    // Try to downcast to RgbaIcon
-    let rgba = icon.0.cast_ref::<RgbaIcon>()?;
+/// `None` if no screen contains it or when called off the main thread.
+pub fn scale_factor_at(position: PhysicalPosition<f64>) -> Option<f64> {
+    let mtm = MainThreadMarker::new()?;
+    NSScreen::screens(mtm)
+        .iter()
+        .find(|screen| {
+            let frame = screen.frame();
+            (frame.origin.x..frame.origin.x + frame.size.width).contains(&position.x)
+                && (frame.origin.y..frame.origin.y + frame.size.height).contains(&position.y)
+        })
+        .map(|screen| screen.backingScaleFactor())
+}
 
-    let width = rgba.width();
-    let height = rgba.height();
-    let buffer = rgba.buffer();
+/// Opens the Notifications pane of System Settings, where users allow or
+/// silence notifications per app.
+pub fn open_notification_settings() -> std::io::Result<()> {
+    let url = NSURL::URLWithString(&NSString::from_str(
+        "x-apple.systempreferences:com.apple.preference.notifications",
+    ))
+    .ok_or_else(|| std::io::Error::other("invalid settings URL"))?;
+    if !NSWorkspace::sharedWorkspace().openURL(&url) {
+        return Err(std::io::Error::other("failed to open System Settings"));
+    }
+    Ok(())
+}
 
-    // Convert RGBA to PNG
-    let png_data = rgba_to_png(buffer, width, height)?;
+/// Whether the system currently shows notifications. Always `true`: Focus
+/// status is only readable through the Intents framework, with the user's
+/// permission.
+pub fn notifications_allowed() -> bool {
+    true
+}
+
+/// Converts a winit Icon to an NSImage for use in the status bar.
+///
+/// When `template` is set, the image is configured as a template image for
+/// automatic dark mode support. Otherwise it is drawn in full color.
+/// Scalable icons are rasterized at `size` pixels.
+/// Icons already converted with [`PreparedIcon::prepare`] skip the PNG encoding step.
+pub(crate) fn icon_to_nsimage(
+    icon: &Icon,
+    template: bool,
+    size: PhysicalSize<u32>,
+) -> Result<Retained<NSImage>, IconError> {
+    let prepared = PreparedIcon::try_prepare_sized(icon, size)?;
+
+    let width = prepared.width();
+    let height = prepared.height();
 
     // Create NSImage from PNG data
-    let nsdata = NSData::from_vec(png_data);
-    let nsimage = NSImage::initWithData(NSImage::alloc(), &nsdata)?;
+    let nsdata = NSData::with_bytes(prepared.data());
+    let nsimage =
+        NSImage::initWithData(NSImage::alloc(), &nsdata).ok_or(IconError::ConversionFailed)?;
 
     // Scale to appropriate menu bar size (18pt height)
     let icon_height: f64 = 18.0;
@@ -28,26 +71,41 @@ pub(crate) fn icon_to_nsimage(icon: &Icon) -> Option<Retained<NSImage>> {
     let new_size = NSSize::new(icon_width, icon_height);
     nsimage.setSize(new_size);
 
-    // Set as template image for dark mode support
-    nsimage.setTemplate(true);
+    // Template images are tinted by the system to match the menu bar appearance
+    nsimage.setTemplate(template);
 
-    Some(nsimage)
+    Ok(nsimage)
 }
 
-/// Convert RGBA buffer to PNG bytes
-fn rgba_to_png(rgba: &[u8], width: u32, height: u32) -> Option<Vec<u8>> {
-    use std::io::Cursor;
+/// Converts an event's timestamp (seconds since boot) to an [`Instant`].
+pub(crate) fn event_time(event: &NSEvent) -> Instant {
+    let age = NSProcessInfo::processInfo().systemUptime() - event.timestamp();
+    let now = Instant::now();
+    Duration::try_from_secs_f64(age)
+        .ok()
+        .and_then(|age| now.checked_sub(age))
+        .unwrap_or(now)
+}
 
-    let mut png = Vec::new();
+/// The time of the event AppKit is currently dispatching, or now if there is
+/// none.
+#[cfg(feature = "menu")]
+pub(crate) fn current_event_time(mtm: objc2::MainThreadMarker) -> Instant {
+    objc2_app_kit::NSApplication::sharedApplication(mtm)
+        .currentEvent()
+        .map_or_else(Instant::now, |event| event_time(&event))
+}
 
-    {
-        let mut encoder = png::Encoder::new(Cursor::new(&mut png), width, height);
-        encoder.set_color(png::ColorType::Rgba);
-        encoder.set_depth(png::BitDepth::Eight);
+#[cfg(test)]
+mod tests {
+    use objc2_foundation::NSString;
 
-        let mut writer = encoder.write_header().ok()?;
-        writer.write_image_data(rgba).ok()?;
+    #[test]
+    fn test_nsstring_emoji_round_trip() {
+        let label = "\u{1F4BE} Save \u{2014} caf\u{e9}";
+        let string = NSString::from_str(label);
+        // NSString counts UTF-16 code units, so the emoji counts twice.
+        assert_eq!(string.length(), label.encode_utf16().count());
+        assert_eq!(string.to_string(), label);
     }
-
-    Some(png)
 }
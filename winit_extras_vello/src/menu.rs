@@ -1,7 +1,9 @@
 //! Vello-rendered context menu implementation.
 
+use std::borrow::Cow;
 use std::num::NonZeroU32;
 use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
 
 use rwh_06::{HasWindowHandle, RawWindowHandle};
 use skrifa::FontRef;
@@ -16,24 +18,38 @@ use winit::event::{ElementState, WindowEvent};
 use winit::event_loop::ActiveEventLoop;
 use winit::window::{Window, WindowAttributes, WindowId, WindowLevel};
 use winit_extras_core::context_menu::{ContextMenu as ContextMenuTrait, MenuRenderer};
-use winit_extras_core::{Event, EventCallback, MenuEntry};
+use winit_extras_core::menu::display_label;
+use winit_extras_core::{Event, EventCallback, MenuEntry, SeparatorStyle, TrayError};
 
 use crate::style::MenuStyle;
 
 /// Renders context menus using vello_cpu + softbuffer in a custom popup window.
 pub struct VelloMenuRenderer {
     style: MenuStyle,
+    click_outside_grace: Duration,
 }
 
 impl VelloMenuRenderer {
     pub fn new() -> Self {
+        Self::with_style(MenuStyle::default())
+    }
+
+    pub fn with_style(style: MenuStyle) -> Self {
         Self {
-            style: MenuStyle::default(),
+            style,
+            click_outside_grace: Duration::ZERO,
         }
     }
 
-    pub fn with_style(style: MenuStyle) -> Self {
-        Self { style }
+    /// Keep menus open when they lose focus within `grace` of being shown.
+    ///
+    /// Menus close when focus moves elsewhere, but some compositors take
+    /// focus away from a popup right after it is mapped, closing it before
+    /// the user can interact. A short grace, such as 200ms, works around
+    /// that. Defaults to zero.
+    pub fn with_click_outside_grace(mut self, grace: Duration) -> Self {
+        self.click_outside_grace = grace;
+        self
     }
 }
 
@@ -52,11 +68,17 @@ impl<T: Clone + Send + Sync + 'static> MenuRenderer<T> for VelloMenuRenderer {
         proxy: EventCallback<T>,
     ) -> Result<Box<dyn ContextMenuTrait>, Box<dyn std::error::Error + Send + Sync>> {
         let parent_handle = window.window_handle().ok().map(|h| h.as_raw());
-        let menu =
-            VelloContextMenu::new(event_loop, parent_handle, items, proxy, self.style.clone())
-                .map_err(|e| -> Box<dyn std::error::Error + Send + Sync> {
-                    Box::new(std::io::Error::other(e.to_string()))
-                })?;
+        let menu = VelloContextMenu::new(
+            event_loop,
+            parent_handle,
+            items,
+            proxy,
+            self.style.clone(),
+            self.click_outside_grace,
+        )
+        .map_err(|e| -> Box<dyn std::error::Error + Send + Sync> {
+            Box::new(std::io::Error::other(e.to_string()))
+        })?;
         Ok(Box::new(menu))
     }
 }
@@ -65,7 +87,8 @@ impl<T: Clone + Send + Sync + 'static> MenuRenderer<T> for VelloMenuRenderer {
 struct ItemLayout {
     y: u32,
     height: u32,
-    is_separator: bool,
+    /// How to draw the entry if it is a separator.
+    separator: Option<SeparatorStyle>,
     is_enabled: bool,
 }
 
@@ -78,6 +101,54 @@ struct MenuData<T> {
     menu_width: u32,
     menu_height: u32,
     proxy: EventCallback<T>,
+    grace: FocusGrace,
+}
+
+/// Holds back a focus loss right after the menu is shown, until the grace
+/// period has passed.
+#[derive(Debug)]
+struct FocusGrace {
+    /// How long after `shown_at` losing focus doesn't close the menu.
+    duration: Duration,
+    shown_at: Option<Instant>,
+    /// Focus was lost during the grace period and not regained since.
+    pending: bool,
+}
+
+impl FocusGrace {
+    fn new(duration: Duration) -> Self {
+        FocusGrace {
+            duration,
+            shown_at: None,
+            pending: false,
+        }
+    }
+
+    fn shown(&mut self, now: Instant) {
+        self.shown_at = Some(now);
+        self.pending = false;
+    }
+
+    fn in_grace(&self, now: Instant) -> bool {
+        self.shown_at
+            .is_some_and(|shown_at| now.duration_since(shown_at) < self.duration)
+    }
+
+    /// Records a focus loss, returning whether the menu should close now.
+    fn focus_lost(&mut self, now: Instant) -> bool {
+        self.pending = self.in_grace(now);
+        !self.pending
+    }
+
+    /// Whether a focus loss held back until `now` should close the menu,
+    /// `has_focus` being whether the menu got its focus back meanwhile.
+    fn expired(&mut self, now: Instant, has_focus: bool) -> bool {
+        if !self.pending || self.in_grace(now) {
+            return false;
+        }
+        self.pending = false;
+        !has_focus
+    }
 }
 
 /// Cached system UI font, loaded once on first use.
@@ -155,7 +226,8 @@ impl<T: Clone + Send + Sync + 'static> VelloContextMenu<T> {
         items: Vec<MenuEntry<T>>,
         proxy: EventCallback<T>,
         style: MenuStyle,
-    ) -> Result<Self, anyhow::Error> {
+        click_outside_grace: Duration,
+    ) -> Result<Self, TrayError> {
         // Calculate layout
         let (layout, menu_width, menu_height) = compute_layout(&items, &style);
 
@@ -168,15 +240,20 @@ impl<T: Clone + Send + Sync + 'static> VelloContextMenu<T> {
             .with_window_level(WindowLevel::AlwaysOnTop)
             .with_surface_size(PhysicalSize::new(menu_width, menu_height));
 
-        let window: Arc<dyn Window> = Arc::from(event_loop.create_window(attrs)?);
+        let window = event_loop
+            .create_window(attrs)
+            .map_err(|e| TrayError::Platform(e.to_string()))?;
+        let window: Arc<dyn Window> = Arc::from(window);
 
-        let context =
-            softbuffer::Context::new(window.clone()).map_err(|e| anyhow::anyhow!("{e}"))?;
+        let context = softbuffer::Context::new(window.clone())
+            .map_err(|e| TrayError::Platform(e.to_string()))?;
         let mut surface = softbuffer::Surface::new(&context, window.clone())
-            .map_err(|e| anyhow::anyhow!("{e}"))?;
+            .map_err(|e| TrayError::Platform(e.to_string()))?;
 
         if let (Some(w), Some(h)) = (NonZeroU32::new(menu_width), NonZeroU32::new(menu_height)) {
-            surface.resize(w, h).map_err(|e| anyhow::anyhow!("{e}"))?;
+            surface
+                .resize(w, h)
+                .map_err(|e| TrayError::Platform(e.to_string()))?;
         }
 
         // Prime the font cache on first menu creation.
@@ -190,6 +267,7 @@ impl<T: Clone + Send + Sync + 'static> VelloContextMenu<T> {
             menu_width,
             menu_height,
             proxy,
+            grace: FocusGrace::new(click_outside_grace),
         };
 
         Ok(Self {
@@ -214,6 +292,19 @@ impl<T: Clone + Send + Sync + 'static> VelloContextMenu<T> {
             return false;
         }
 
+        // A focus loss during the grace period closes the menu once the
+        // period is over, unless the menu got its focus back.
+        let expired = self
+            .data
+            .lock()
+            .unwrap()
+            .grace
+            .expired(Instant::now(), self.window.has_focus());
+        if expired {
+            self.window.set_visible(false);
+            return true;
+        }
+
         match event {
             WindowEvent::PointerMoved { position, .. } => {
                 let mut data = self.data.lock().unwrap();
@@ -230,21 +321,44 @@ impl<T: Clone + Send + Sync + 'static> VelloContextMenu<T> {
                 position,
                 ..
             } => {
-                let data = self.data.lock().unwrap();
+                let mut data = self.data.lock().unwrap();
                 if let Some(idx) = hit_test(&data.layout, position.y as u32)
                     && data.layout[idx].is_enabled
-                    && !data.layout[idx].is_separator
-                    && let Some(id) = get_item_id(&data.items, idx)
+                    && data.layout[idx].separator.is_none()
+                    && let Some(MenuEntry::Item(item)) = data.items.get_mut(idx)
                 {
+                    // Keep the new check state for the next time the menu opens.
+                    let checked = item.toggle();
+                    let id = item.id.clone();
+                    let action = item.action.clone();
                     let proxy = data.proxy.clone();
                     drop(data);
                     self.window.set_visible(false);
-                    (proxy)(Event::MenuItemClicked { id });
+                    if let Some(action) = action
+                        && let Err(e) = action.perform()
+                    {
+                        tracing::warn!(?action, "Failed to perform menu item action: {}", e);
+                    }
+                    (proxy)(Event::MenuItemClicked {
+                        id,
+                        checked,
+                        timestamp: Instant::now(),
+                    });
                     return true;
                 }
             }
             WindowEvent::Focused(false) => {
-                self.window.set_visible(false);
+                let close = self.data.lock().unwrap().grace.focus_lost(Instant::now());
+                if close {
+                    self.window.set_visible(false);
+                } else {
+                    // Take the focus back, so that a later click outside
+                    // still closes the menu.
+                    tracing::debug!(
+                        "Refocusing the menu after losing focus right after showing it"
+                    );
+                    self.window.focus_window();
+                }
             }
             WindowEvent::RedrawRequested => {
                 self.render();
@@ -276,10 +390,15 @@ impl<T: Clone + Send + Sync + 'static> VelloContextMenu<T> {
             let y = item_layout.y as f64;
             let item_h = item_layout.height as f64;
 
-            if item_layout.is_separator {
+            if let Some(separator) = item_layout.separator {
+                let inset = match separator {
+                    SeparatorStyle::Inset => 8.0,
+                    SeparatorStyle::FullWidth => 0.0,
+                    SeparatorStyle::Spacer => continue,
+                };
                 renderer.set_paint(rgba(style.separator_color));
                 let sep_y = y + item_h / 2.0;
-                renderer.fill_rect(&Rect::new(8.0, sep_y, w - 8.0, sep_y + 1.0));
+                renderer.fill_rect(&Rect::new(inset, sep_y, w - inset, sep_y + 1.0));
                 continue;
             }
 
@@ -326,7 +445,7 @@ impl<T: Clone + Send + Sync + 'static> VelloContextMenu<T> {
                 let font = system_font();
                 let glyphs = layout_text_simple(
                     font,
-                    label,
+                    &label,
                     font_size,
                     x_offset,
                     y as f32 + item_h as f32 * 0.72, // baseline approx
@@ -337,6 +456,27 @@ impl<T: Clone + Send + Sync + 'static> VelloContextMenu<T> {
                         .font_size(font_size)
                         .fill_glyphs(glyphs.into_iter());
                 }
+
+                // Shortcut, right-aligned
+                if let Some(shortcut) = get_item_shortcut(&data.items, i) {
+                    let char_width = font_size * 0.6;
+                    let shortcut_x = w as f32
+                        - style.padding_x as f32
+                        - shortcut.chars().count() as f32 * char_width;
+                    let glyphs = layout_text_simple(
+                        font,
+                        &shortcut,
+                        font_size,
+                        shortcut_x,
+                        y as f32 + item_h as f32 * 0.72,
+                    );
+                    if !glyphs.is_empty() {
+                        renderer
+                            .glyph_run(font)
+                            .font_size(font_size)
+                            .fill_glyphs(glyphs.into_iter());
+                    }
+                }
             }
         }
 
@@ -366,12 +506,12 @@ impl<T: Clone + Send + Sync + 'static> ContextMenuTrait for VelloContextMenu<T>
     }
 
     fn show_at_screen_pos(&self, position: PhysicalPosition<i32>) {
-        let data = self.data.lock().unwrap();
+        let mut data = self.data.lock().unwrap();
         let w = data.menu_width;
         let h = data.menu_height;
+        data.grace.shown(Instant::now());
         drop(data);
 
-        self.window.set_visible(true);
         self.window
             .set_outer_position(Position::Physical(PhysicalPosition::new(
                 position.x, position.y,
@@ -379,10 +519,38 @@ impl<T: Clone + Send + Sync + 'static> ContextMenuTrait for VelloContextMenu<T>
         let _ = self
             .window
             .request_surface_size(Size::Physical(PhysicalSize::new(w, h)));
+
+        // The window has no background of its own, so until the first
+        // `RedrawRequested` it shows whatever was behind it. Present the menu
+        // as soon as it is visible instead of waiting for that.
+        self.render();
+        self.window.set_visible(true);
+        self.present();
         self.window.focus_window();
         self.window.request_redraw();
     }
 
+    fn show_for_rect(&self, position: PhysicalPosition<i32>, size: PhysicalSize<u32>) {
+        let top_left = client_to_screen(self.parent_handle, position);
+        let top = top_left.y;
+        let bottom = top.saturating_add(size.height as i32);
+        let menu_height = self.data.lock().unwrap().menu_height as i32;
+
+        // Flip above the rectangle when the menu would run off the bottom of
+        // the monitor the popup is on.
+        let monitor_bottom = self.window.current_monitor().and_then(|monitor| {
+            let origin = monitor.position()?;
+            let mode = monitor.current_video_mode()?;
+            Some(origin.y + mode.size().height as i32)
+        });
+        let y = match monitor_bottom {
+            Some(monitor_bottom) if bottom + menu_height > monitor_bottom => top - menu_height,
+            _ => bottom,
+        };
+
+        self.show_at_screen_pos(PhysicalPosition::new(top_left.x, y));
+    }
+
     fn close(&self) {
         self.window.set_visible(false);
     }
@@ -403,11 +571,15 @@ fn compute_layout<T>(items: &[MenuEntry<T>], style: &MenuStyle) -> (Vec<ItemLayo
 
     for entry in items {
         match entry {
-            MenuEntry::Separator => {
+            MenuEntry::Separator | MenuEntry::StyledSeparator(_) => {
+                let separator = match entry {
+                    MenuEntry::StyledSeparator(separator) => *separator,
+                    _ => SeparatorStyle::Inset,
+                };
                 layout.push(ItemLayout {
                     y,
                     height: style.separator_height,
-                    is_separator: true,
+                    separator: Some(separator),
                     is_enabled: false,
                 });
                 y += style.separator_height;
@@ -416,21 +588,26 @@ fn compute_layout<T>(items: &[MenuEntry<T>], style: &MenuStyle) -> (Vec<ItemLayo
                 layout.push(ItemLayout {
                     y,
                     height: style.item_height,
-                    is_separator: false,
+                    separator: None,
                     is_enabled: item.enabled,
                 });
-                max_label_len = max_label_len.max(item.label.chars().count());
+                let mut label_len = display_label(&item.label, item.mnemonic).chars().count();
+                if let Some(accelerator) = item.accelerator {
+                    // +4 keeps a gap between the label and the shortcut.
+                    label_len += accelerator.display_string().chars().count() + 4;
+                }
+                max_label_len = max_label_len.max(label_len);
                 y += style.item_height;
             }
             MenuEntry::Submenu(sub) => {
                 layout.push(ItemLayout {
                     y,
                     height: style.item_height,
-                    is_separator: false,
+                    separator: None,
                     is_enabled: sub.enabled,
                 });
                 // +2 leaves room for the " >" submenu arrow indicator.
-                let label_with_arrow = sub.label.chars().count() + 2;
+                let label_with_arrow = display_label(&sub.label, sub.mnemonic).chars().count() + 2;
                 max_label_len = max_label_len.max(label_with_arrow);
                 y += style.item_height;
             }
@@ -448,25 +625,25 @@ fn compute_layout<T>(items: &[MenuEntry<T>], style: &MenuStyle) -> (Vec<ItemLayo
 
 fn hit_test(layout: &[ItemLayout], y: u32) -> Option<usize> {
     for (i, item) in layout.iter().enumerate() {
-        if y >= item.y && y < item.y + item.height && !item.is_separator {
+        if y >= item.y && y < item.y + item.height && item.separator.is_none() {
             return Some(i);
         }
     }
     None
 }
 
-fn get_item_id<T: Clone>(items: &[MenuEntry<T>], flat_index: usize) -> Option<T> {
+fn get_item_label<T>(items: &[MenuEntry<T>], flat_index: usize) -> Option<Cow<'_, str>> {
     match items.get(flat_index)? {
-        MenuEntry::Item(item) => Some(item.id.clone()),
-        _ => None,
+        MenuEntry::Item(item) => Some(display_label(&item.label, item.mnemonic)),
+        MenuEntry::Submenu(sub) => Some(display_label(&sub.label, sub.mnemonic)),
+        MenuEntry::Separator | MenuEntry::StyledSeparator(_) => None,
     }
 }
 
-fn get_item_label<T>(items: &[MenuEntry<T>], flat_index: usize) -> Option<&str> {
+fn get_item_shortcut<T>(items: &[MenuEntry<T>], flat_index: usize) -> Option<String> {
     match items.get(flat_index)? {
-        MenuEntry::Item(item) => Some(item.label.as_str()),
-        MenuEntry::Submenu(sub) => Some(sub.label.as_str()),
-        MenuEntry::Separator => None,
+        MenuEntry::Item(item) => item.accelerator.map(|a| a.display_string()),
+        _ => None,
     }
 }
 
@@ -551,3 +728,34 @@ fn client_to_screen(
     // Fallback: return as-is (caller should use show_at_screen_pos instead)
     position
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_focus_loss_in_grace_closes_after_it() {
+        let grace_period = Duration::from_millis(200);
+        let shown_at = Instant::now();
+        let mut grace = FocusGrace::new(grace_period);
+        grace.shown(shown_at);
+
+        assert!(!grace.focus_lost(shown_at + Duration::from_millis(50)));
+        assert!(!grace.expired(shown_at + Duration::from_millis(100), false));
+        assert!(grace.expired(shown_at + grace_period, false));
+        // Only reported once.
+        assert!(!grace.expired(shown_at + grace_period * 2, false));
+    }
+
+    #[test]
+    fn test_focus_regained_in_grace_keeps_menu_open() {
+        let grace_period = Duration::from_millis(200);
+        let shown_at = Instant::now();
+        let mut grace = FocusGrace::new(grace_period);
+        grace.shown(shown_at);
+
+        assert!(!grace.focus_lost(shown_at));
+        assert!(!grace.expired(shown_at + grace_period, true));
+        assert!(grace.focus_lost(shown_at + grace_period));
+    }
+}
@@ -1,19 +1,23 @@
 //! Context menu support for Windows.
 
-use dpi::PhysicalPosition;
+use std::cell::RefCell;
+
+use dpi::{PhysicalPosition, PhysicalSize};
 use rwh_06::{HasWindowHandle, RawWindowHandle};
-use windows_sys::Win32::Foundation::{HWND, POINT};
+use windows_sys::Win32::Foundation::{HWND, POINT, RECT};
 use windows_sys::Win32::Graphics::Gdi::ClientToScreen;
+use windows_sys::Win32::UI::WindowsAndMessaging::GetCursorPos;
 use winit_core::event_loop::ActiveEventLoop;
 use winit_extras_core::context_menu::{ContextMenu as ContextMenuTrait, MenuRenderer};
-use winit_extras_core::{Event, EventCallback, MenuEntry};
+use winit_extras_core::{Event, EventCallback, MenuActivation, MenuEntries, MenuEntry, TrayError};
 
 pub use crate::menu::MenuAlignment;
-use crate::menu::show_context_menu_with_alignment;
+use crate::menu::{show_popup_menu, show_popup_menu_for_rect};
+use crate::util;
 
 pub struct ContextMenu<T> {
     hwnd: HWND,
-    items: Vec<MenuEntry<T>>,
+    items: RefCell<Vec<MenuEntry<T>>>,
     alignment: MenuAlignment,
     proxy: EventCallback<T>,
 }
@@ -27,30 +31,27 @@ impl<T> std::fmt::Debug for ContextMenu<T> {
 }
 
 unsafe impl<T: Send> Send for ContextMenu<T> {}
-unsafe impl<T: Sync> Sync for ContextMenu<T> {}
 
 impl<T: Clone + Send + Sync + 'static> ContextMenu<T> {
     pub fn new(
         window: &(impl HasWindowHandle + ?Sized),
         items: Vec<MenuEntry<T>>,
         proxy: EventCallback<T>,
-    ) -> Result<Self, anyhow::Error> {
+    ) -> Result<Self, TrayError> {
         let handle = window
             .window_handle()
-            .map_err(|e| anyhow::anyhow!("Failed to get window handle: {}", e))?;
+            .map_err(|e| TrayError::Platform(format!("Failed to get window handle: {e}")))?;
 
         let hwnd = match handle.as_raw() {
             RawWindowHandle::Win32(win32_handle) => win32_handle.hwnd.get() as HWND,
             _ => {
-                return Err(anyhow::anyhow!(
-                    "Invalid window handle type, expected Win32"
-                ));
+                return Err(TrayError::UnsupportedParentWindow);
             }
         };
 
         Ok(Self {
             hwnd,
-            items,
+            items: RefCell::new(items),
             alignment: MenuAlignment::Auto,
             proxy,
         })
@@ -62,13 +63,37 @@ impl<T: Clone + Send + Sync + 'static> ContextMenu<T> {
     }
 
     fn show_at_screen_pos_internal(&self, x: i32, y: i32) {
-        let result = unsafe {
-            show_context_menu_with_alignment(self.hwnd, &self.items, x, y, self.alignment)
+        self.show_with(|items| unsafe {
+            show_popup_menu(
+                self.hwnd,
+                items,
+                x,
+                y,
+                self.alignment,
+                MenuActivation::Foreground,
+            )
+        });
+    }
+
+    /// Shows the menu with `show`, which returns the selected item's index,
+    /// and reports the selection.
+    fn show_with(&self, show: impl FnOnce(&[MenuEntry<T>]) -> Option<usize>) {
+        let mut items = self.items.borrow_mut();
+        let selected = show(&items);
+        let Some(item) = selected.and_then(|index| items.item_mut(index)) else {
+            return;
         };
 
-        if let Some(id) = result {
-            (self.proxy)(Event::MenuItemClicked { id });
-        }
+        let checked = item.toggle();
+        let id = item.id.clone();
+        crate::menu::perform_action(item.action.as_ref());
+        drop(items);
+
+        (self.proxy)(Event::MenuItemClicked {
+            id,
+            checked,
+            timestamp: util::message_time(),
+        });
     }
 }
 
@@ -88,6 +113,41 @@ impl<T: Clone + Send + Sync + 'static> ContextMenuTrait for ContextMenu<T> {
         self.show_at_screen_pos_internal(position.x, position.y);
     }
 
+    fn show_for_rect(&self, position: PhysicalPosition<i32>, size: PhysicalSize<u32>) {
+        let mut top_left = POINT {
+            x: position.x,
+            y: position.y,
+        };
+        unsafe {
+            ClientToScreen(self.hwnd, &mut top_left);
+        }
+        let rect = RECT {
+            left: top_left.x,
+            top: top_left.y,
+            right: top_left.x.saturating_add(size.width as i32),
+            bottom: top_left.y.saturating_add(size.height as i32),
+        };
+        self.show_with(|items| unsafe { show_popup_menu_for_rect(self.hwnd, items, rect) });
+    }
+
+    fn show_at_cursor(&self) -> bool {
+        let mut point = POINT { x: 0, y: 0 };
+        if unsafe { GetCursorPos(&mut point) } == 0 {
+            return false;
+        }
+        self.show_with(|items| unsafe {
+            show_popup_menu(
+                self.hwnd,
+                items,
+                point.x,
+                point.y,
+                MenuAlignment::Auto,
+                MenuActivation::Foreground,
+            )
+        });
+        true
+    }
+
     fn close(&self) {}
 }
 
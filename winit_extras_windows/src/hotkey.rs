@@ -0,0 +1,236 @@
+//! System-wide keyboard shortcuts using `RegisterHotKey`.
+
+use std::ptr;
+
+use tracing::warn;
+use windows_sys::Win32::{
+    Foundation::{HWND, LPARAM, LRESULT, WPARAM},
+    UI::{
+        Input::KeyboardAndMouse::{
+            HOT_KEY_MODIFIERS, MOD_ALT, MOD_CONTROL, MOD_NOREPEAT, MOD_SHIFT, MOD_WIN,
+            RegisterHotKey, UnregisterHotKey, VIRTUAL_KEY, VK_BACK, VK_DELETE, VK_DOWN, VK_END,
+            VK_ESCAPE, VK_F1, VK_HOME, VK_INSERT, VK_LEFT, VK_NEXT, VK_OEM_1, VK_OEM_2, VK_OEM_3,
+            VK_OEM_4, VK_OEM_5, VK_OEM_6, VK_OEM_7, VK_OEM_COMMA, VK_OEM_MINUS, VK_OEM_PERIOD,
+            VK_OEM_PLUS, VK_PAUSE, VK_PRIOR, VK_RETURN, VK_RIGHT, VK_SNAPSHOT, VK_SPACE, VK_TAB,
+            VK_UP,
+        },
+        WindowsAndMessaging::{
+            CreateWindowExW, DefWindowProcW, DestroyWindow, GWL_USERDATA, HWND_MESSAGE,
+            RegisterClassExW, WM_HOTKEY, WM_NCDESTROY, WNDCLASSEXW,
+        },
+    },
+};
+use winit_core::keyboard::{KeyCode, ModifiersState};
+use winit_extras_core::{Accelerator, TrayError};
+
+use crate::util;
+
+const CLASS_NAME: &str = "WinitExtrasHotKeyClass";
+
+/// Each hotkey gets its own window, so the ID only has to be unique per window.
+const HOTKEY_ID: i32 = 1;
+
+type HotKeyCallback = Box<dyn Fn()>;
+
+/// A registered system-wide shortcut. Dropping it unregisters the shortcut.
+///
+/// The callback runs on the thread that registered the shortcut, from its
+/// message loop, so register from the event loop thread.
+#[derive(Debug)]
+pub struct HotKey {
+    hwnd: HWND,
+    accelerator: Accelerator,
+}
+
+impl HotKey {
+    /// Register `accelerator` system-wide and call `callback` when pressed.
+    ///
+    /// Fails if the key has no Windows equivalent or another application
+    /// already registered the same shortcut.
+    pub fn register(
+        accelerator: Accelerator,
+        callback: impl Fn() + 'static,
+    ) -> Result<Self, TrayError> {
+        let vk = virtual_key(accelerator.key).ok_or_else(|| {
+            TrayError::Platform(format!("{:?} cannot be used as a hotkey", accelerator.key))
+        })?;
+
+        let hwnd = unsafe { create_message_window() }?;
+
+        let callback: Box<HotKeyCallback> = Box::new(Box::new(callback));
+        unsafe { util::set_window_long(hwnd, GWL_USERDATA, Box::into_raw(callback) as isize) };
+
+        let modifiers = hotkey_modifiers(accelerator.modifiers) | MOD_NOREPEAT;
+        if unsafe { RegisterHotKey(hwnd, HOTKEY_ID, modifiers, vk as u32) } == 0 {
+            let error = std::io::Error::last_os_error();
+            unsafe { DestroyWindow(hwnd) };
+            return Err(TrayError::Platform(format!(
+                "Failed to register hotkey {:?}: {}",
+                accelerator, error
+            )));
+        }
+
+        Ok(HotKey { hwnd, accelerator })
+    }
+
+    /// The registered shortcut.
+    pub fn accelerator(&self) -> Accelerator {
+        self.accelerator
+    }
+}
+
+impl Drop for HotKey {
+    fn drop(&mut self) {
+        unsafe {
+            if UnregisterHotKey(self.hwnd, HOTKEY_ID) == 0 {
+                warn!(
+                    accelerator = ?self.accelerator,
+                    "Failed to unregister hotkey: {}",
+                    std::io::Error::last_os_error()
+                );
+            }
+            // Frees the callback in `WM_NCDESTROY`.
+            DestroyWindow(self.hwnd);
+        }
+    }
+}
+
+unsafe fn create_message_window() -> Result<HWND, TrayError> {
+    let class_name = util::encode_wide(CLASS_NAME);
+
+    let class = WNDCLASSEXW {
+        cbSize: std::mem::size_of::<WNDCLASSEXW>() as u32,
+        lpfnWndProc: Some(hotkey_window_callback),
+        hInstance: util::get_instance_handle(),
+        lpszClassName: class_name.as_ptr(),
+        ..unsafe { std::mem::zeroed() }
+    };
+
+    // Fails harmlessly if an earlier hotkey already registered the class.
+    unsafe { RegisterClassExW(&class) };
+
+    let hwnd = unsafe {
+        CreateWindowExW(
+            0,
+            class_name.as_ptr(),
+            ptr::null(),
+            0,
+            0,
+            0,
+            0,
+            0,
+            HWND_MESSAGE,
+            ptr::null_mut(),
+            util::get_instance_handle(),
+            ptr::null(),
+        )
+    };
+
+    if hwnd.is_null() {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    Ok(hwnd)
+}
+
+unsafe extern "system" fn hotkey_window_callback(
+    window: HWND,
+    msg: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    let userdata = unsafe { util::get_window_long(window, GWL_USERDATA) } as *mut HotKeyCallback;
+
+    match msg {
+        WM_HOTKEY if wparam as i32 == HOTKEY_ID && !userdata.is_null() => {
+            let callback = unsafe { &*userdata };
+            callback();
+            0
+        }
+        WM_NCDESTROY => {
+            if !userdata.is_null() {
+                unsafe { util::set_window_long(window, GWL_USERDATA, 0) };
+                drop(unsafe { Box::from_raw(userdata) });
+            }
+            unsafe { DefWindowProcW(window, msg, wparam, lparam) }
+        }
+        _ => unsafe { DefWindowProcW(window, msg, wparam, lparam) },
+    }
+}
+
+fn hotkey_modifiers(modifiers: ModifiersState) -> HOT_KEY_MODIFIERS {
+    let mut flags = 0;
+    if modifiers.shift_key() {
+        flags |= MOD_SHIFT;
+    }
+    if modifiers.control_key() {
+        flags |= MOD_CONTROL;
+    }
+    if modifiers.alt_key() {
+        flags |= MOD_ALT;
+    }
+    if modifiers.meta_key() {
+        flags |= MOD_WIN;
+    }
+    flags
+}
+
+/// Maps a physical key to the virtual key it produces on a US layout.
+fn virtual_key(key: KeyCode) -> Option<VIRTUAL_KEY> {
+    use KeyCode::*;
+
+    let letters = [
+        KeyA, KeyB, KeyC, KeyD, KeyE, KeyF, KeyG, KeyH, KeyI, KeyJ, KeyK, KeyL, KeyM, KeyN, KeyO,
+        KeyP, KeyQ, KeyR, KeyS, KeyT, KeyU, KeyV, KeyW, KeyX, KeyY, KeyZ,
+    ];
+    if let Some(index) = letters.iter().position(|&k| k == key) {
+        return Some(b'A' as VIRTUAL_KEY + index as VIRTUAL_KEY);
+    }
+
+    let digits = [
+        Digit0, Digit1, Digit2, Digit3, Digit4, Digit5, Digit6, Digit7, Digit8, Digit9,
+    ];
+    if let Some(index) = digits.iter().position(|&k| k == key) {
+        return Some(b'0' as VIRTUAL_KEY + index as VIRTUAL_KEY);
+    }
+
+    let function_keys = [
+        F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12, F13, F14, F15, F16, F17, F18, F19, F20,
+        F21, F22, F23, F24,
+    ];
+    if let Some(index) = function_keys.iter().position(|&k| k == key) {
+        return Some(VK_F1 + index as VIRTUAL_KEY);
+    }
+
+    let vk = match key {
+        Space => VK_SPACE,
+        Enter => VK_RETURN,
+        Escape => VK_ESCAPE,
+        Tab => VK_TAB,
+        Backspace => VK_BACK,
+        Delete => VK_DELETE,
+        Insert => VK_INSERT,
+        Home => VK_HOME,
+        End => VK_END,
+        PageUp => VK_PRIOR,
+        PageDown => VK_NEXT,
+        ArrowLeft => VK_LEFT,
+        ArrowUp => VK_UP,
+        ArrowRight => VK_RIGHT,
+        ArrowDown => VK_DOWN,
+        PrintScreen => VK_SNAPSHOT,
+        Pause => VK_PAUSE,
+        Minus => VK_OEM_MINUS,
+        Equal => VK_OEM_PLUS,
+        Comma => VK_OEM_COMMA,
+        Period => VK_OEM_PERIOD,
+        Semicolon => VK_OEM_1,
+        Slash => VK_OEM_2,
+        Backquote => VK_OEM_3,
+        BracketLeft => VK_OEM_4,
+        Backslash => VK_OEM_5,
+        BracketRight => VK_OEM_6,
+        Quote => VK_OEM_7,
+        _ => return None,
+    };
+    Some(vk)
+}
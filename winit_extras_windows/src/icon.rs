@@ -0,0 +1,128 @@
+//! Reading icons back from existing windows.
+
+use std::ptr;
+
+use rwh_06::{HasWindowHandle, RawWindowHandle};
+use windows_sys::Win32::{
+    Foundation::HWND,
+    Graphics::Gdi::{
+        BI_RGB, BITMAP, BITMAPINFO, BITMAPINFOHEADER, DIB_RGB_COLORS, DeleteObject, GetDC,
+        GetDIBits, GetObjectW, HBITMAP, ReleaseDC,
+    },
+    UI::WindowsAndMessaging::{
+        GetIconInfo, HICON, ICON_BIG, ICON_SMALL, ICONINFO, SendMessageW, WM_GETICON,
+    },
+};
+use winit_core::icon::{Icon, RgbaIcon};
+
+/// Returns a copy of the icon currently set on `window`.
+///
+/// Reads the large icon (falling back to the small one) with `WM_GETICON`
+/// and converts it to an [`RgbaIcon`]. Returns `None` if the window has no
+/// icon or the icon can't be read.
+pub fn window_icon(window: &(impl HasWindowHandle + ?Sized)) -> Option<Icon> {
+    let RawWindowHandle::Win32(handle) = window.window_handle().ok()?.as_raw() else {
+        return None;
+    };
+    let hwnd = handle.hwnd.get() as HWND;
+
+    let mut hicon = unsafe { SendMessageW(hwnd, WM_GETICON, ICON_BIG as usize, 0) } as HICON;
+    if hicon.is_null() {
+        hicon = unsafe { SendMessageW(hwnd, WM_GETICON, ICON_SMALL as usize, 0) } as HICON;
+    }
+    if hicon.is_null() {
+        return None;
+    }
+
+    unsafe { hicon_to_icon(hicon) }
+}
+
+unsafe fn hicon_to_icon(hicon: HICON) -> Option<Icon> {
+    let mut info: ICONINFO = unsafe { std::mem::zeroed() };
+    if unsafe { GetIconInfo(hicon, &mut info) } == 0 {
+        return None;
+    }
+
+    let icon = unsafe { color_bitmap_to_icon(info.hbmColor) };
+
+    // GetIconInfo hands ownership of both bitmaps to the caller.
+    unsafe {
+        if !info.hbmColor.is_null() {
+            DeleteObject(info.hbmColor as _);
+        }
+        if !info.hbmMask.is_null() {
+            DeleteObject(info.hbmMask as _);
+        }
+    }
+
+    icon
+}
+
+unsafe fn color_bitmap_to_icon(hbitmap: HBITMAP) -> Option<Icon> {
+    // Monochrome icons only have a mask bitmap.
+    if hbitmap.is_null() {
+        return None;
+    }
+
+    let mut bitmap: BITMAP = unsafe { std::mem::zeroed() };
+    if unsafe {
+        GetObjectW(
+            hbitmap as _,
+            std::mem::size_of::<BITMAP>() as i32,
+            &mut bitmap as *mut _ as _,
+        )
+    } == 0
+    {
+        return None;
+    }
+
+    let width = bitmap.bmWidth;
+    let height = bitmap.bmHeight;
+    if width <= 0 || height <= 0 {
+        return None;
+    }
+
+    let mut bmi: BITMAPINFO = unsafe { std::mem::zeroed() };
+    bmi.bmiHeader.biSize = std::mem::size_of::<BITMAPINFOHEADER>() as u32;
+    bmi.bmiHeader.biWidth = width;
+    bmi.bmiHeader.biHeight = -height;
+    bmi.bmiHeader.biPlanes = 1;
+    bmi.bmiHeader.biBitCount = 32;
+    bmi.bmiHeader.biCompression = BI_RGB;
+
+    let mut pixels = vec![0u8; width as usize * height as usize * 4];
+
+    let hdc = unsafe { GetDC(ptr::null_mut()) };
+    if hdc.is_null() {
+        return None;
+    }
+    let lines = unsafe {
+        GetDIBits(
+            hdc,
+            hbitmap,
+            0,
+            height as u32,
+            pixels.as_mut_ptr() as _,
+            &mut bmi,
+            DIB_RGB_COLORS,
+        )
+    };
+    unsafe { ReleaseDC(ptr::null_mut(), hdc) };
+
+    if lines == 0 {
+        return None;
+    }
+
+    // Icons without an alpha channel leave it zeroed; treat them as opaque.
+    let has_alpha = pixels.chunks_exact(4).any(|pixel| pixel[3] != 0);
+    for pixel in pixels.chunks_exact_mut(4) {
+        pixel.swap(0, 2);
+        if !has_alpha {
+            pixel[3] = u8::MAX;
+        }
+    }
+
+    RgbaIcon::new(pixels, width as u32, height as u32)
+        .ok()
+        .map(Icon::from)
+}
@@ -1,11 +1,16 @@
 #![cfg(target_os = "windows")]
 
+pub mod icon;
 pub mod msg;
 mod util;
+pub use util::{notifications_allowed, open_notification_settings, scale_factor_at};
 
 mod tray;
 pub use tray::Tray;
 
+mod threaded;
+pub use threaded::ThreadedTray;
+
 use winit_extras_core::{EventCallback, TrayIconAttributes, TrayIconRenderer};
 
 /// Uses native Win32 system tray APIs (`Shell_NotifyIconW`).
@@ -14,15 +19,31 @@ pub struct NativeTrayIconRenderer;
 impl<T: Clone + Send + Sync + 'static> TrayIconRenderer<T> for NativeTrayIconRenderer {
     fn create_tray(
         &self,
-        attributes: TrayIconAttributes,
+        attributes: TrayIconAttributes<T>,
         proxy: EventCallback<T>,
-    ) -> Result<Box<dyn winit_extras_core::TrayIcon>, Box<dyn std::error::Error + Send + Sync>>
+    ) -> Result<Box<dyn winit_extras_core::TrayIcon<T>>, Box<dyn std::error::Error + Send + Sync>>
     {
         let tray = tray::Tray::new(proxy, attributes)?;
         Ok(Box::new(tray))
     }
 }
 
+/// Like [`NativeTrayIconRenderer`], but each tray runs its own message loop
+/// on a background thread. See [`ThreadedTray`].
+pub struct ThreadedTrayIconRenderer;
+
+impl<T: Clone + Send + Sync + 'static> TrayIconRenderer<T> for ThreadedTrayIconRenderer {
+    fn create_tray(
+        &self,
+        attributes: TrayIconAttributes<T>,
+        proxy: EventCallback<T>,
+    ) -> Result<Box<dyn winit_extras_core::TrayIcon<T>>, Box<dyn std::error::Error + Send + Sync>>
+    {
+        let tray = threaded::ThreadedTray::new(proxy, attributes)?;
+        Ok(Box::new(tray))
+    }
+}
+
 #[cfg(feature = "menu")]
 pub mod menu;
 
@@ -31,3 +52,6 @@ pub mod context_menu;
 
 #[cfg(feature = "menu_bar")]
 pub mod menu_bar;
+
+#[cfg(feature = "hotkey")]
+pub mod hotkey;
@@ -3,7 +3,7 @@
 use std::ptr;
 use std::sync::atomic::{AtomicU8, Ordering};
 
-use dpi::PhysicalPosition;
+use dpi::{PhysicalPosition, PhysicalSize};
 use rwh_06::{HasWindowHandle, RawWindowHandle};
 use windows_sys::Win32::{
     Foundation::{HWND, POINT, RECT},
@@ -11,23 +11,26 @@ use windows_sys::Win32::{
         Dwm::{DWMWA_USE_IMMERSIVE_DARK_MODE, DwmSetWindowAttribute},
         Gdi::{
             BI_RGB, BITMAPINFO, BITMAPINFOHEADER, ClientToScreen, CreateCompatibleDC,
-            CreateDIBSection, DIB_RGB_COLORS, DeleteDC, GetDC, GetMonitorInfoW, HBITMAP,
-            MONITOR_DEFAULTTONEAREST, MONITORINFO, MonitorFromPoint, ReleaseDC, SelectObject,
+            CreateDIBSection, DIB_RGB_COLORS, DeleteDC, DeleteObject, GetDC, GetMonitorInfoW,
+            HBITMAP, MONITOR_DEFAULTTONEAREST, MONITORINFO, MonitorFromPoint, ReleaseDC,
+            SelectObject,
         },
     },
     System::LibraryLoader::{GetProcAddress, LoadLibraryW},
+    System::Threading::{AttachThreadInput, GetCurrentThreadId},
     UI::WindowsAndMessaging::{
         AppendMenuW, CreatePopupMenu, DI_NORMAL, DestroyMenu, DrawIconEx, DrawMenuBar,
-        GetMenuItemCount, GetSubMenu, HMENU, MENUITEMINFOW, MF_CHECKED, MF_GRAYED, MF_POPUP,
+        GetForegroundWindow, GetMenuItemCount, GetMenuItemInfoW, GetSubMenu,
+        GetWindowThreadProcessId, HICON, HMENU, MENUITEMINFOW, MF_CHECKED, MF_GRAYED, MF_POPUP,
         MF_SEPARATOR, MF_STRING, MIIM_BITMAP, PostMessageW, SetForegroundWindow, SetMenuItemInfoW,
         TPM_BOTTOMALIGN, TPM_LEFTALIGN, TPM_RETURNCMD, TPM_RIGHTALIGN, TPM_RIGHTBUTTON,
-        TPM_TOPALIGN, TrackPopupMenu, WM_NULL,
+        TPM_TOPALIGN, TPM_VERTICAL, TPMPARAMS, TrackPopupMenu, TrackPopupMenuEx, WM_NULL,
     },
 };
 use winit_core::icon::Icon;
-use winit_extras_core::{MenuEntry, MenuItem, Submenu};
+use winit_extras_core::{ItemAction, MenuActivation, MenuEntries, MenuEntry, MenuItem, Submenu};
 
-use crate::util::encode_wide;
+use crate::util::{encode_item_label, encode_label, encode_wide};
 
 /// Dark mode preference for Windows context menus (Windows 10 1903+).
 ///
@@ -332,32 +335,32 @@ pub fn refresh_menu_bar_for_window(window: &impl HasWindowHandle) {
     }
 }
 
-/// Maps internal Windows menu IDs (u32) to user-provided IDs of type T.
-struct IdMap<T> {
-    ids: Vec<T>,
+/// Hands out Windows menu command IDs in build order.
+///
+/// Items are added depth-first, the same order [`MenuEntries::items`] walks
+/// them in, so command ID `n` belongs to the item at index `n - 1`.
+struct CommandIds {
+    next: u32,
 }
 
-impl<T: Clone> IdMap<T> {
+impl CommandIds {
     fn new() -> Self {
-        Self { ids: Vec::new() }
+        Self { next: 1 } // Windows menu IDs start from 1
     }
 
-    fn insert(&mut self, id: T) -> u32 {
-        let index = self.ids.len() as u32 + 1; // Windows menu IDs start from 1
-        self.ids.push(id);
-        index
+    fn next(&mut self) -> u32 {
+        let id = self.next;
+        self.next += 1;
+        id
     }
 
-    fn get(&self, index: u32) -> Option<T> {
-        if index == 0 {
-            return None;
-        }
-        self.ids.get((index - 1) as usize).cloned()
+    fn item_index(command: u32) -> Option<usize> {
+        command.checked_sub(1).map(|index| index as usize)
     }
 }
 
 /// Menu alignment options for context menus.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub enum MenuAlignment {
     /// Menu appears to the bottom-right of the position (top-left corner at position).
     /// This is the typical behavior for window context menus.
@@ -440,6 +443,34 @@ pub unsafe fn show_context_menu<T: Clone>(
     unsafe { show_context_menu_with_alignment(hwnd, items, x, y, MenuAlignment::TopLeft) }
 }
 
+/// # Safety
+/// The `hwnd` must be a valid window handle.
+///
+/// Like [`show_context_menu`], but also returns the index of the selected
+/// item in [`MenuEntries::items`] order.
+pub unsafe fn show_context_menu_indexed<T: Clone>(
+    hwnd: HWND,
+    items: &[MenuEntry<T>],
+    x: i32,
+    y: i32,
+) -> Option<(usize, T)> {
+    unsafe { show_context_menu_with_alignment_indexed(hwnd, items, x, y, MenuAlignment::TopLeft) }
+}
+
+/// # Safety
+/// The `hwnd` must be a valid window handle.
+///
+/// Like [`show_context_menu`], taking the foreground as `activation` says.
+pub(crate) unsafe fn show_tray_menu<T>(
+    hwnd: HWND,
+    items: &[MenuEntry<T>],
+    x: i32,
+    y: i32,
+    activation: MenuActivation,
+) -> Option<usize> {
+    unsafe { show_popup_menu(hwnd, items, x, y, MenuAlignment::TopLeft, activation) }
+}
+
 /// # Safety
 /// The `hwnd` must be a valid window handle.
 ///
@@ -451,8 +482,47 @@ pub unsafe fn show_context_menu_with_alignment<T: Clone>(
     y: i32,
     alignment: MenuAlignment,
 ) -> Option<T> {
-    let mut id_map = IdMap::new();
-    let hmenu = unsafe { build_popup_menu(items, &mut id_map) };
+    unsafe { show_context_menu_with_alignment_indexed(hwnd, items, x, y, alignment) }
+        .map(|(_, id)| id)
+}
+
+/// # Safety
+/// The `hwnd` must be a valid window handle.
+///
+/// Like [`show_context_menu_with_alignment`], but also returns the index of
+/// the selected item in [`MenuEntries::items`] order.
+pub unsafe fn show_context_menu_with_alignment_indexed<T: Clone>(
+    hwnd: HWND,
+    items: &[MenuEntry<T>],
+    x: i32,
+    y: i32,
+    alignment: MenuAlignment,
+) -> Option<(usize, T)> {
+    let index =
+        unsafe { show_popup_menu(hwnd, items, x, y, alignment, MenuActivation::Foreground) }?;
+    selected_item(items, index)
+}
+
+/// The index and ID of the item at `index` in [`MenuEntries::items`] order.
+fn selected_item<T: Clone>(items: &[MenuEntry<T>], index: usize) -> Option<(usize, T)> {
+    items
+        .items()
+        .nth(index)
+        .map(|item| (index, item.id.clone()))
+}
+
+/// Shows a popup menu and returns the index of the selected item in
+/// [`MenuEntries::items`] order.
+pub(crate) unsafe fn show_popup_menu<T>(
+    hwnd: HWND,
+    items: &[MenuEntry<T>],
+    x: i32,
+    y: i32,
+    alignment: MenuAlignment,
+    activation: MenuActivation,
+) -> Option<usize> {
+    let mut command_ids = CommandIds::new();
+    let hmenu = unsafe { build_popup_menu(items, &mut command_ids) };
     if hmenu.is_null() {
         return None;
     }
@@ -461,7 +531,7 @@ pub unsafe fn show_context_menu_with_alignment<T: Clone>(
         items
             .iter()
             .map(|item| match item {
-                MenuEntry::Item(_) | MenuEntry::Separator => 1,
+                MenuEntry::Item(_) | MenuEntry::Separator | MenuEntry::StyledSeparator(_) => 1,
                 MenuEntry::Submenu(sub) => 1 + count_items(&sub.items),
             })
             .sum()
@@ -489,20 +559,172 @@ pub unsafe fn show_context_menu_with_alignment<T: Clone>(
     };
 
     unsafe {
-        SetForegroundWindow(hwnd);
+        let activated = activate_for_menu(hwnd, activation);
         let selected = TrackPopupMenu(hmenu, flags, x, y, 0, hwnd, ptr::null());
-        PostMessageW(hwnd, WM_NULL, 0, 0);
+        drop(activated);
+        destroy_menu_tree(hmenu);
+
+        if selected > 0 {
+            CommandIds::item_index(selected as u32)
+        } else {
+            None
+        }
+    }
+}
+
+/// # Safety
+/// The `hwnd` must be a valid window handle.
+///
+/// Shows a context menu below `rect` (in screen coordinates), flipping it
+/// above the rectangle when there isn't room below. The menu never covers
+/// the rectangle.
+pub unsafe fn show_context_menu_for_rect<T: Clone>(
+    hwnd: HWND,
+    items: &[MenuEntry<T>],
+    rect: RECT,
+) -> Option<T> {
+    unsafe { show_context_menu_for_rect_indexed(hwnd, items, rect) }.map(|(_, id)| id)
+}
+
+/// # Safety
+/// The `hwnd` must be a valid window handle.
+///
+/// Like [`show_context_menu_for_rect`], but also returns the index of the
+/// selected item in [`MenuEntries::items`] order.
+pub unsafe fn show_context_menu_for_rect_indexed<T: Clone>(
+    hwnd: HWND,
+    items: &[MenuEntry<T>],
+    rect: RECT,
+) -> Option<(usize, T)> {
+    let index = unsafe { show_popup_menu_for_rect(hwnd, items, rect) }?;
+    selected_item(items, index)
+}
+
+/// Shows a popup menu beside `rect` and returns the index of the selected
+/// item in [`MenuEntries::items`] order.
+pub(crate) unsafe fn show_popup_menu_for_rect<T>(
+    hwnd: HWND,
+    items: &[MenuEntry<T>],
+    rect: RECT,
+) -> Option<usize> {
+    let mut command_ids = CommandIds::new();
+    let hmenu = unsafe { build_popup_menu(items, &mut command_ids) };
+    if hmenu.is_null() {
+        return None;
+    }
+
+    let params = TPMPARAMS {
+        cbSize: std::mem::size_of::<TPMPARAMS>() as u32,
+        rcExclude: rect,
+    };
+    let flags = TPM_LEFTALIGN | TPM_TOPALIGN | TPM_VERTICAL | TPM_RIGHTBUTTON | TPM_RETURNCMD;
+
+    unsafe {
+        let activated = activate_for_menu(hwnd, MenuActivation::Foreground);
+        let selected = TrackPopupMenuEx(hmenu, flags, rect.left, rect.bottom, hwnd, &params);
+        drop(activated);
         destroy_menu_tree(hmenu);
 
         if selected > 0 {
-            id_map.get(selected as u32)
+            CommandIds::item_index(selected as u32)
         } else {
             None
         }
     }
 }
 
-unsafe fn build_popup_menu<T: Clone>(items: &[MenuEntry<T>], id_map: &mut IdMap<T>) -> HMENU {
+/// # Safety
+/// The `hwnd` must be a valid window handle and `hmenu` a valid popup menu.
+///
+/// Shows an application-owned menu with tray alignment and returns the
+/// command id of the selected item. The menu is not destroyed.
+pub unsafe fn show_native_menu(hwnd: HWND, hmenu: HMENU, x: i32, y: i32) -> Option<u32> {
+    unsafe { show_native_tray_menu(hwnd, hmenu, x, y, MenuActivation::Foreground) }
+}
+
+/// # Safety
+/// The `hwnd` must be a valid window handle and `hmenu` a valid popup menu.
+///
+/// Like [`show_native_menu`], taking the foreground as `activation` says.
+pub(crate) unsafe fn show_native_tray_menu(
+    hwnd: HWND,
+    hmenu: HMENU,
+    x: i32,
+    y: i32,
+    activation: MenuActivation,
+) -> Option<u32> {
+    let flags = TPM_RIGHTALIGN | TPM_BOTTOMALIGN | TPM_RIGHTBUTTON | TPM_RETURNCMD;
+
+    unsafe {
+        let activated = activate_for_menu(hwnd, activation);
+        let selected = TrackPopupMenu(hmenu, flags, x, y, 0, hwnd, ptr::null());
+        drop(activated);
+
+        if selected > 0 {
+            Some(selected as u32)
+        } else {
+            None
+        }
+    }
+}
+
+/// Undoes what [`activate_for_menu`] did once the menu has closed.
+struct MenuActivationGuard {
+    hwnd: HWND,
+    attached_thread: Option<u32>,
+}
+
+impl Drop for MenuActivationGuard {
+    fn drop(&mut self) {
+        unsafe {
+            if let Some(thread) = self.attached_thread {
+                AttachThreadInput(GetCurrentThreadId(), thread, 0);
+            }
+            // Makes the next click on the tray icon show the menu again,
+            // see the remarks on `TrackPopupMenu`.
+            PostMessageW(self.hwnd, WM_NULL, 0, 0);
+        }
+    }
+}
+
+/// Brings `hwnd` to the foreground so the menu it owns closes when the user
+/// clicks elsewhere. Keep the guard alive until the menu has closed.
+unsafe fn activate_for_menu(hwnd: HWND, activation: MenuActivation) -> MenuActivationGuard {
+    let mut guard = MenuActivationGuard {
+        hwnd,
+        attached_thread: None,
+    };
+
+    unsafe {
+        let current_thread = GetCurrentThreadId();
+        let foreground = GetForegroundWindow();
+        let foreground_thread = if foreground.is_null() {
+            0
+        } else {
+            GetWindowThreadProcessId(foreground, ptr::null_mut())
+        };
+
+        match activation {
+            MenuActivation::IfNeeded if foreground_thread == current_thread => {}
+            MenuActivation::AttachThreadInput => {
+                if foreground_thread != 0
+                    && foreground_thread != current_thread
+                    && AttachThreadInput(current_thread, foreground_thread, 1) != 0
+                {
+                    guard.attached_thread = Some(foreground_thread);
+                }
+                SetForegroundWindow(hwnd);
+            }
+            _ => {
+                SetForegroundWindow(hwnd);
+            }
+        }
+    }
+
+    guard
+}
+
+unsafe fn build_popup_menu<T>(items: &[MenuEntry<T>], command_ids: &mut CommandIds) -> HMENU {
     let hmenu = unsafe { CreatePopupMenu() };
     if hmenu.is_null() {
         return hmenu;
@@ -510,9 +732,9 @@ unsafe fn build_popup_menu<T: Clone>(items: &[MenuEntry<T>], id_map: &mut IdMap<
 
     for item in items {
         match item {
-            MenuEntry::Item(item) => unsafe { add_menu_item(hmenu, item, id_map) },
-            MenuEntry::Submenu(submenu) => unsafe { add_submenu(hmenu, submenu, id_map) },
-            MenuEntry::Separator => unsafe {
+            MenuEntry::Item(item) => unsafe { add_menu_item(hmenu, item, command_ids) },
+            MenuEntry::Submenu(submenu) => unsafe { add_submenu(hmenu, submenu, command_ids) },
+            MenuEntry::Separator | MenuEntry::StyledSeparator(_) => unsafe {
                 AppendMenuW(hmenu, MF_SEPARATOR, 0, ptr::null());
             },
         }
@@ -521,7 +743,7 @@ unsafe fn build_popup_menu<T: Clone>(items: &[MenuEntry<T>], id_map: &mut IdMap<
     hmenu
 }
 
-unsafe fn add_menu_item<T: Clone>(hmenu: HMENU, item: &MenuItem<T>, id_map: &mut IdMap<T>) {
+unsafe fn add_menu_item<T>(hmenu: HMENU, item: &MenuItem<T>, command_ids: &mut CommandIds) {
     let mut flags = MF_STRING;
     if !item.enabled {
         flags |= MF_GRAYED;
@@ -530,8 +752,8 @@ unsafe fn add_menu_item<T: Clone>(hmenu: HMENU, item: &MenuItem<T>, id_map: &mut
         flags |= MF_CHECKED;
     }
 
-    let win_id = id_map.insert(item.id.clone());
-    let label = encode_wide(&item.label);
+    let win_id = command_ids.next();
+    let label = encode_item_label(item);
     unsafe { AppendMenuW(hmenu, flags, win_id as usize, label.as_ptr()) };
 
     if let Some(ref icon) = item.icon
@@ -541,9 +763,13 @@ unsafe fn add_menu_item<T: Clone>(hmenu: HMENU, item: &MenuItem<T>, id_map: &mut
     }
 }
 
-unsafe fn add_submenu<T: Clone>(hmenu: HMENU, submenu: &Submenu<T>, id_map: &mut IdMap<T>) {
-    let child_hmenu = unsafe { build_popup_menu(&submenu.items, id_map) };
+unsafe fn add_submenu<T>(hmenu: HMENU, submenu: &Submenu<T>, command_ids: &mut CommandIds) {
+    let child_hmenu = unsafe { build_popup_menu(&submenu.items, command_ids) };
     if child_hmenu.is_null() {
+        // Keep the IDs of later items in step with their indices.
+        for _ in 0..submenu.items.item_count() {
+            command_ids.next();
+        }
         return;
     }
 
@@ -552,7 +778,7 @@ unsafe fn add_submenu<T: Clone>(hmenu: HMENU, submenu: &Submenu<T>, id_map: &mut
         flags |= MF_GRAYED;
     }
 
-    let label = encode_wide(&submenu.label);
+    let label = encode_label(&submenu.label, submenu.mnemonic);
     unsafe { AppendMenuW(hmenu, flags, child_hmenu as usize, label.as_ptr()) };
 }
 
@@ -564,6 +790,17 @@ unsafe fn set_menu_item_bitmap(hmenu: HMENU, id: u32, hbitmap: HBITMAP) {
     unsafe { SetMenuItemInfoW(hmenu, id, 0, &info) };
 }
 
+/// Performs the clicked item's [`ItemAction`], if it has one.
+pub(crate) fn perform_action(action: Option<&ItemAction>) {
+    if let Some(action) = action
+        && let Err(e) = action.perform()
+    {
+        tracing::warn!(?action, "Failed to perform menu item action: {}", e);
+    }
+}
+
+/// Destroys `hmenu`, its submenus and the item bitmaps made by
+/// [`icon_to_hbitmap`], which `DestroyMenu` leaves behind.
 unsafe fn destroy_menu_tree(hmenu: HMENU) {
     let count = unsafe { GetMenuItemCount(hmenu) };
     for i in 0..count {
@@ -571,6 +808,17 @@ unsafe fn destroy_menu_tree(hmenu: HMENU) {
         if !submenu.is_null() {
             unsafe { destroy_menu_tree(submenu) };
         }
+
+        let mut info: MENUITEMINFOW = unsafe { std::mem::zeroed() };
+        info.cbSize = std::mem::size_of::<MENUITEMINFOW>() as u32;
+        info.fMask = MIIM_BITMAP;
+        // The predefined `HBMMENU_*` bitmaps are small values that aren't
+        // GDI objects, and menus built here never use them.
+        if unsafe { GetMenuItemInfoW(hmenu, i as u32, 1, &mut info) } != 0
+            && info.hbmpItem as isize > 11
+        {
+            unsafe { DeleteObject(info.hbmpItem as _) };
+        }
     }
     unsafe { DestroyMenu(hmenu) };
 }
@@ -578,7 +826,13 @@ unsafe fn destroy_menu_tree(hmenu: HMENU) {
 unsafe fn icon_to_hbitmap(icon: &Icon) -> Option<HBITMAP> {
     const SIZE: i32 = 16;
 
-    let hicon = crate::util::icon_to_hicon(icon)?;
+    let hicon =
+        crate::util::icon_to_hicon(icon, PhysicalSize::new(SIZE as u32, SIZE as u32)).ok()?;
+    unsafe { draw_icon_bitmap(hicon.handle(), SIZE) }
+}
+
+/// Draws `hicon` into a new `size` by `size` 32-bit bitmap.
+unsafe fn draw_icon_bitmap(hicon: HICON, size: i32) -> Option<HBITMAP> {
     let hdc_screen = unsafe { GetDC(ptr::null_mut()) };
     if hdc_screen.is_null() {
         return None;
@@ -592,8 +846,8 @@ unsafe fn icon_to_hbitmap(icon: &Icon) -> Option<HBITMAP> {
 
     let mut bmi: BITMAPINFO = unsafe { std::mem::zeroed() };
     bmi.bmiHeader.biSize = std::mem::size_of::<BITMAPINFOHEADER>() as u32;
-    bmi.bmiHeader.biWidth = SIZE;
-    bmi.bmiHeader.biHeight = -SIZE;
+    bmi.bmiHeader.biWidth = size;
+    bmi.bmiHeader.biHeight = -size;
     bmi.bmiHeader.biPlanes = 1;
     bmi.bmiHeader.biBitCount = 32;
     bmi.bmiHeader.biCompression = BI_RGB;
@@ -612,7 +866,7 @@ unsafe fn icon_to_hbitmap(icon: &Icon) -> Option<HBITMAP> {
 
     unsafe {
         let old_bitmap = SelectObject(hdc, hbitmap as _);
-        DrawIconEx(hdc, 0, 0, hicon, SIZE, SIZE, 0, ptr::null_mut(), DI_NORMAL);
+        DrawIconEx(hdc, 0, 0, hicon, size, size, 0, ptr::null_mut(), DI_NORMAL);
         SelectObject(hdc, old_bitmap);
 
         DeleteDC(hdc);
@@ -661,6 +915,19 @@ pub fn show_context_menu_for_window<T: Clone>(
     items: &[MenuEntry<T>],
     position: PhysicalPosition<i32>,
 ) -> Option<T> {
+    show_context_menu_for_window_indexed(window, items, position).map(|(_, id)| id)
+}
+
+/// Like [`show_context_menu_for_window`], but also returns the index of the
+/// selected item in [`MenuEntries::items`] order.
+///
+/// Useful when `T` is not unique across the menu and the caller needs to
+/// tell which of several items with the same ID was picked.
+pub fn show_context_menu_for_window_indexed<T: Clone>(
+    window: &impl HasWindowHandle,
+    items: &[MenuEntry<T>],
+    position: PhysicalPosition<i32>,
+) -> Option<(usize, T)> {
     let handle = window.window_handle().ok()?;
 
     match handle.as_raw() {
@@ -675,7 +942,13 @@ pub fn show_context_menu_for_window<T: Clone>(
             unsafe {
                 ClientToScreen(hwnd, &mut point);
                 // Use Auto alignment to smartly position menu based on screen bounds
-                show_context_menu_with_alignment(hwnd, items, point.x, point.y, MenuAlignment::Auto)
+                show_context_menu_with_alignment_indexed(
+                    hwnd,
+                    items,
+                    point.x,
+                    point.y,
+                    MenuAlignment::Auto,
+                )
             }
         }
         _ => None,
@@ -692,6 +965,17 @@ pub fn show_context_menu_for_window_at_screen_pos<T: Clone>(
     items: &[MenuEntry<T>],
     screen_position: PhysicalPosition<i32>,
 ) -> Option<T> {
+    show_context_menu_for_window_at_screen_pos_indexed(window, items, screen_position)
+        .map(|(_, id)| id)
+}
+
+/// Like [`show_context_menu_for_window_at_screen_pos`], but also returns the
+/// index of the selected item in [`MenuEntries::items`] order.
+pub fn show_context_menu_for_window_at_screen_pos_indexed<T: Clone>(
+    window: &impl HasWindowHandle,
+    items: &[MenuEntry<T>],
+    screen_position: PhysicalPosition<i32>,
+) -> Option<(usize, T)> {
     let handle = window.window_handle().ok()?;
 
     match handle.as_raw() {
@@ -699,7 +983,7 @@ pub fn show_context_menu_for_window_at_screen_pos<T: Clone>(
             let hwnd = win32_handle.hwnd.get() as HWND;
             unsafe {
                 // Use Auto alignment to smartly position menu based on screen bounds
-                show_context_menu_with_alignment(
+                show_context_menu_with_alignment_indexed(
                     hwnd,
                     items,
                     screen_position.x,
@@ -2,41 +2,65 @@
 //!
 //! On Windows, the menu bar is attached to a window using SetMenu().
 
-use std::collections::HashMap;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
 use std::ptr;
 
 use rwh_06::{HasWindowHandle, RawWindowHandle};
 use windows_sys::Win32::{
-    Foundation::{HWND, LPARAM, LRESULT, WPARAM},
+    Foundation::{FALSE, HWND, LPARAM, LRESULT, WPARAM},
+    Graphics::Gdi::{GetMonitorInfoW, MONITOR_DEFAULTTONEAREST, MONITORINFO, MonitorFromWindow},
     UI::{
         Shell::{DefSubclassProc, RemoveWindowSubclass, SetWindowSubclass},
         WindowsAndMessaging::{
-            AppendMenuW, CreateMenu, CreatePopupMenu, DestroyMenu, GetMenuItemCount, GetSubMenu,
-            HMENU, MF_CHECKED, MF_GRAYED, MF_POPUP, MF_SEPARATOR, MF_STRING, SetMenu, WM_COMMAND,
-            WM_NCDESTROY,
+            AppendMenuW, CreateMenu, CreatePopupMenu, DeleteMenu, DestroyMenu, DrawMenuBar,
+            GWL_STYLE, GetMenuItemCount, GetMenuStringW, GetSubMenu, GetWindowPlacement, HMENU,
+            HWND_TOP, IsZoomed, MENUITEMINFOW, MF_BYCOMMAND, MF_BYPOSITION, MF_CHECKED, MF_GRAYED,
+            MF_POPUP, MF_SEPARATOR, MF_STRING, MIIM_STRING, PostMessageW, SW_MAXIMIZE, SW_MINIMIZE,
+            SW_RESTORE, SWP_FRAMECHANGED, SWP_NOMOVE, SWP_NOOWNERZORDER, SWP_NOSIZE, SWP_NOZORDER,
+            SetMenu, SetMenuItemInfoW, SetWindowPlacement, SetWindowPos, ShowWindow,
+            WINDOWPLACEMENT, WM_CLOSE, WM_COMMAND, WM_NCDESTROY, WS_OVERLAPPEDWINDOW,
         },
     },
 };
+use winit_extras_core::menu::strip_mnemonics;
 use winit_extras_core::menu_bar::{
     MenuBar as CoreMenuBar, MenuBarAttributes, MenuBarEvent, MenuBarId, MenuBarProxy, TopLevelMenu,
+    TopLevelMenuId,
 };
-use winit_extras_core::{MenuEntry, MenuItem, Submenu};
+use winit_extras_core::{ItemAction, MenuEntry, MenuItem, MenuRole, Submenu, TrayError};
 
-use crate::util::encode_wide;
-
-static COUNTER: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(1);
+use crate::util::{
+    encode_item_label, encode_label, encode_shortcut_label, get_window_long, set_window_long,
+};
 
 const MENUBAR_SUBCLASS_ID: usize = 0x4D454E55; // "MENU" in hex
 
 struct MenuBarState<T> {
     id_map: HashMap<u32, T>,
+    actions: HashMap<u32, ItemAction>,
+    roles: HashMap<u32, MenuRole>,
+    // Command ids of items whose labels use access key markers.
+    mnemonics: HashSet<u32>,
     proxy: MenuBarProxy<T>,
     menu_bar_id: MenuBarId,
+    // The window's style and placement from before `ToggleFullscreen` made it
+    // fullscreen, restored when it is toggled again.
+    fullscreen_restore: Cell<Option<(isize, WINDOWPLACEMENT)>>,
 }
 
 impl<T: Clone + Send + Sync + 'static> MenuBarState<T> {
-    fn handle_command(&self, command_id: u32) -> bool {
+    fn handle_command(&self, hwnd: HWND, command_id: u32) -> bool {
+        if let Some(&role) = self.roles.get(&command_id) {
+            if role.is_window_action() {
+                unsafe { self.perform_window_role(hwnd, role) };
+            } else if unsafe { perform_role(hwnd, role) } {
+                return true;
+            }
+        }
         if let Some(id) = self.id_map.get(&command_id) {
+            crate::menu::perform_action(self.actions.get(&command_id));
             (self.proxy)(
                 self.menu_bar_id,
                 MenuBarEvent::MenuItemClicked { id: id.clone() },
@@ -45,23 +69,96 @@ impl<T: Clone + Send + Sync + 'static> MenuBarState<T> {
         }
         false
     }
+
+    /// Performs one of the window roles that are also reported as clicks.
+    unsafe fn perform_window_role(&self, hwnd: HWND, role: MenuRole) {
+        match role {
+            MenuRole::MinimizeWindow => unsafe {
+                ShowWindow(hwnd, SW_MINIMIZE);
+            },
+            MenuRole::CloseWindow => unsafe {
+                PostMessageW(hwnd, WM_CLOSE, 0, 0);
+            },
+            MenuRole::ToggleFullscreen => unsafe { self.toggle_fullscreen(hwnd) },
+            _ => (),
+        }
+    }
+
+    /// Makes `hwnd` cover its monitor without a frame, or restores it.
+    ///
+    /// The window's own state is changed rather than winit's, so
+    /// `Window::fullscreen` keeps reporting `None`.
+    unsafe fn toggle_fullscreen(&self, hwnd: HWND) {
+        if let Some((style, placement)) = self.fullscreen_restore.take() {
+            unsafe {
+                set_window_long(hwnd, GWL_STYLE, style);
+                SetWindowPlacement(hwnd, &placement);
+                SetWindowPos(
+                    hwnd,
+                    ptr::null_mut(),
+                    0,
+                    0,
+                    0,
+                    0,
+                    SWP_NOMOVE | SWP_NOSIZE | SWP_NOZORDER | SWP_NOOWNERZORDER | SWP_FRAMECHANGED,
+                );
+            }
+            return;
+        }
+
+        let style = unsafe { get_window_long(hwnd, GWL_STYLE) };
+        let mut placement: WINDOWPLACEMENT = unsafe { std::mem::zeroed() };
+        placement.length = std::mem::size_of::<WINDOWPLACEMENT>() as u32;
+        let mut info: MONITORINFO = unsafe { std::mem::zeroed() };
+        info.cbSize = std::mem::size_of::<MONITORINFO>() as u32;
+        let monitor = unsafe { MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST) };
+        if unsafe { GetWindowPlacement(hwnd, &mut placement) } == 0
+            || unsafe { GetMonitorInfoW(monitor, &mut info) } == 0
+        {
+            return;
+        }
+
+        let rect = info.rcMonitor;
+        unsafe {
+            set_window_long(hwnd, GWL_STYLE, style & !(WS_OVERLAPPEDWINDOW as isize));
+            SetWindowPos(
+                hwnd,
+                HWND_TOP,
+                rect.left,
+                rect.top,
+                rect.right - rect.left,
+                rect.bottom - rect.top,
+                SWP_NOOWNERZORDER | SWP_FRAMECHANGED,
+            );
+        }
+        self.fullscreen_restore.set(Some((style, placement)));
+    }
 }
 
 type CleanupFn = unsafe fn(HWND, *mut ());
 
 /// Windows menu bar implementation.
-pub struct MenuBar {
+pub struct MenuBar<T = ()> {
     internal_id: usize,
     hwnd: HWND,
     hmenu: HMENU,
     state_ptr: *mut (),
     cleanup: CleanupFn,
+    // Command id of every item, for finding items by ID.
+    commands: RefCell<Vec<(u32, T)>>,
+    // Command ids of items whose labels use access key markers.
+    mnemonics: RefCell<HashSet<u32>>,
+    // The command id the next item gets.
+    next_id: Cell<u32>,
+    // Menus added with `add_top_level`, with their popup menu and the
+    // command ids of their items.
+    added: RefCell<Vec<(TopLevelMenuId, HMENU, Range<u32>)>>,
 }
 
-unsafe impl Send for MenuBar {}
-unsafe impl Sync for MenuBar {}
+unsafe impl<T: Send> Send for MenuBar<T> {}
+unsafe impl<T: Sync> Sync for MenuBar<T> {}
 
-impl std::fmt::Debug for MenuBar {
+impl<T> std::fmt::Debug for MenuBar<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("MenuBar")
             .field("internal_id", &self.internal_id)
@@ -69,31 +166,28 @@ impl std::fmt::Debug for MenuBar {
     }
 }
 
-impl MenuBar {
+impl<T: Clone + Send + Sync + 'static> MenuBar<T> {
     /// Create a new menu bar with the given attributes.
     ///
     /// The `parent_window` attribute is required on Windows.
-    pub fn new<T: Clone + Send + Sync + 'static>(
-        proxy: MenuBarProxy<T>,
-        attr: MenuBarAttributes<T>,
-    ) -> Result<Self, anyhow::Error> {
+    pub fn new(proxy: MenuBarProxy<T>, attr: MenuBarAttributes<T>) -> Result<Self, TrayError> {
         let hwnd = match attr.parent_window {
             Some(RawWindowHandle::Win32(handle)) => handle.hwnd.get() as HWND,
-            Some(_) => {
-                return Err(anyhow::anyhow!(
-                    "Invalid window handle type, expected Win32"
-                ));
-            }
-            None => return Err(anyhow::anyhow!("parent_window is required on Windows")),
+            Some(_) => return Err(TrayError::UnsupportedParentWindow),
+            None => return Err(TrayError::MissingParentWindow),
         };
 
-        let internal_id = COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-        let menu_bar_id = MenuBarId::from_raw(internal_id);
+        let menu_bar_id = MenuBarId::allocate();
+        let internal_id = menu_bar_id.into_raw();
 
         let mut state = Box::new(MenuBarState {
             id_map: HashMap::new(),
+            actions: HashMap::new(),
+            roles: HashMap::new(),
+            mnemonics: HashSet::new(),
             proxy,
             menu_bar_id,
+            fullscreen_restore: Cell::new(None),
         });
 
         let mut next_id: u32 = 1;
@@ -117,6 +211,13 @@ impl MenuBar {
             return Err(std::io::Error::last_os_error().into());
         }
 
+        let commands = state
+            .id_map
+            .iter()
+            .map(|(command, id)| (*command, id.clone()))
+            .collect();
+        let mnemonics = state.mnemonics.clone();
+
         // Install window subclass to handle WM_COMMAND
         let state_ptr = Box::into_raw(state);
         let result = unsafe {
@@ -134,7 +235,9 @@ impl MenuBar {
                 destroy_menu_tree(hmenu);
                 drop(Box::from_raw(state_ptr));
             }
-            return Err(anyhow::anyhow!("Failed to install window subclass"));
+            return Err(TrayError::Platform(
+                "Failed to install window subclass".to_owned(),
+            ));
         }
 
         Ok(MenuBar {
@@ -143,18 +246,22 @@ impl MenuBar {
             hmenu,
             state_ptr: state_ptr as *mut (),
             cleanup: cleanup_subclass::<T>,
+            commands: RefCell::new(commands),
+            mnemonics: RefCell::new(mnemonics),
+            next_id: Cell::new(next_id),
+            added: RefCell::new(Vec::new()),
         })
     }
 
     /// Create a menu bar for a window that implements `HasWindowHandle`.
-    pub fn new_for_window<T: Clone + Send + Sync + 'static>(
+    pub fn new_for_window(
         window: &impl HasWindowHandle,
         proxy: MenuBarProxy<T>,
         menus: Vec<TopLevelMenu<T>>,
-    ) -> Result<Self, anyhow::Error> {
+    ) -> Result<Self, TrayError> {
         let handle = window
             .window_handle()
-            .map_err(|e| anyhow::anyhow!("Failed to get window handle: {}", e))?;
+            .map_err(|e| TrayError::Platform(format!("Failed to get window handle: {e}")))?;
 
         let attr = MenuBarAttributes {
             menus,
@@ -174,7 +281,7 @@ unsafe fn cleanup_subclass<T: Clone + Send + Sync + 'static>(hwnd: HWND, state_p
     }
 }
 
-impl CoreMenuBar for MenuBar {
+impl<T: Clone + Send + Sync + 'static> CoreMenuBar<T> for MenuBar<T> {
     fn id(&self) -> MenuBarId {
         MenuBarId::from_raw(self.internal_id)
     }
@@ -184,9 +291,122 @@ impl CoreMenuBar for MenuBar {
             SetMenu(self.hwnd, ptr::null_mut());
         }
     }
+
+    fn item_count(&self) -> usize {
+        let count = unsafe { GetMenuItemCount(self.hmenu) };
+        count.max(0) as usize
+    }
+
+    fn top_level_labels(&self) -> Vec<String> {
+        (0..self.item_count())
+            .map(|position| {
+                let label = unsafe { menu_string(self.hmenu, position as u32, MF_BYPOSITION) };
+                // Plain labels were escaped, so this also restores them.
+                strip_mnemonics(&label).into_owned()
+            })
+            .collect()
+    }
+
+    fn set_label(&self, id: &T, label: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>>
+    where
+        T: PartialEq,
+    {
+        let commands = self.commands.borrow();
+        let (command, _) = commands
+            .iter()
+            .find(|(_, item_id)| item_id == id)
+            .ok_or("no menu item with this id")?;
+
+        // Keep the accelerator shown after the tab.
+        let current = unsafe { menu_string(self.hmenu, *command, MF_BYCOMMAND) };
+        let shortcut = current.split_once('\t').map(|(_, shortcut)| shortcut);
+
+        // Only touch the text, so the item keeps its checked and enabled state.
+        let mut label =
+            encode_shortcut_label(label, self.mnemonics.borrow().contains(command), shortcut);
+        let info = MENUITEMINFOW {
+            cbSize: std::mem::size_of::<MENUITEMINFOW>() as u32,
+            fMask: MIIM_STRING,
+            dwTypeData: label.as_mut_ptr(),
+            ..unsafe { std::mem::zeroed() }
+        };
+        // Lookups by command id search submenus too.
+        if unsafe { SetMenuItemInfoW(self.hmenu, *command, FALSE, &info) } == 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok(())
+    }
+
+    fn add_top_level(
+        &self,
+        menu: TopLevelMenu<T>,
+    ) -> Result<TopLevelMenuId, Box<dyn std::error::Error + Send + Sync>> {
+        // SAFETY: the state lives until the menu bar is dropped, and is only
+        // read by the subclass while handling a command on this thread.
+        let state = unsafe { &mut *(self.state_ptr as *mut MenuBarState<T>) };
+
+        let mut next_id = self.next_id.get();
+        let first = next_id;
+        let popup = unsafe { add_top_level_menu(self.hmenu, &menu, &mut next_id, state)? };
+        self.next_id.set(next_id);
+        let commands = first..next_id;
+
+        self.commands.borrow_mut().extend(
+            commands
+                .clone()
+                .filter_map(|command| Some((command, state.id_map.get(&command)?.clone()))),
+        );
+        self.mnemonics.borrow_mut().extend(
+            commands
+                .clone()
+                .filter(|command| state.mnemonics.contains(command)),
+        );
+        unsafe { DrawMenuBar(self.hwnd) };
+
+        let id = TopLevelMenuId::allocate();
+        self.added.borrow_mut().push((id, popup, commands));
+        Ok(id)
+    }
+
+    fn remove_top_level(
+        &self,
+        id: TopLevelMenuId,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut added = self.added.borrow_mut();
+        let index = added
+            .iter()
+            .position(|(added_id, _, _)| *added_id == id)
+            .ok_or("no top-level menu with this id")?;
+        let (_, popup, commands) = added.remove(index);
+
+        let position = (0..unsafe { GetMenuItemCount(self.hmenu) })
+            .find(|&position| unsafe { GetSubMenu(self.hmenu, position) } == popup)
+            .ok_or("top-level menu is no longer in the menu bar")?;
+        // Also destroys the popup menu and its submenus.
+        if unsafe { DeleteMenu(self.hmenu, position as u32, MF_BYPOSITION) } == 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        unsafe { DrawMenuBar(self.hwnd) };
+
+        // SAFETY: as in `add_top_level`.
+        let state = unsafe { &mut *(self.state_ptr as *mut MenuBarState<T>) };
+        for command in commands.clone() {
+            state.id_map.remove(&command);
+            state.actions.remove(&command);
+            state.roles.remove(&command);
+            state.mnemonics.remove(&command);
+        }
+        self.commands
+            .borrow_mut()
+            .retain(|(command, _)| !commands.contains(command));
+        self.mnemonics
+            .borrow_mut()
+            .retain(|command| !commands.contains(command));
+        Ok(())
+    }
 }
 
-impl Drop for MenuBar {
+impl<T> Drop for MenuBar<T> {
     fn drop(&mut self) {
         unsafe {
             SetMenu(self.hwnd, ptr::null_mut());
@@ -224,7 +444,9 @@ unsafe extern "system" fn menubar_subclass_proc<T: Clone + Send + Sync + 'static
         let notification_code = ((wparam >> 16) & 0xFFFF) as u16;
 
         // notification_code == 0 means menu item, notification_code == 1 means accelerator
-        if (notification_code == 0 || notification_code == 1) && state.handle_command(command_id) {
+        if (notification_code == 0 || notification_code == 1)
+            && state.handle_command(hwnd, command_id)
+        {
             return 0;
         }
     }
@@ -237,20 +459,20 @@ unsafe fn add_top_level_menu<T: Clone + Send + Sync + 'static>(
     top_level: &TopLevelMenu<T>,
     next_id: &mut u32,
     state: &mut MenuBarState<T>,
-) -> Result<(), anyhow::Error> {
+) -> Result<HMENU, TrayError> {
     let hmenu_popup = unsafe { build_popup_menu(&top_level.items, next_id, state)? };
 
-    let label = encode_wide(&top_level.label);
+    let label = encode_label(&top_level.label, top_level.mnemonic);
     unsafe { AppendMenuW(hmenu_bar, MF_POPUP, hmenu_popup as usize, label.as_ptr()) };
 
-    Ok(())
+    Ok(hmenu_popup)
 }
 
 unsafe fn build_popup_menu<T: Clone + Send + Sync + 'static>(
     items: &[MenuEntry<T>],
     next_id: &mut u32,
     state: &mut MenuBarState<T>,
-) -> Result<HMENU, anyhow::Error> {
+) -> Result<HMENU, TrayError> {
     let hmenu = unsafe { CreatePopupMenu() };
     if hmenu.is_null() {
         return Err(std::io::Error::last_os_error().into());
@@ -264,7 +486,7 @@ unsafe fn build_popup_menu<T: Clone + Send + Sync + 'static>(
             MenuEntry::Submenu(submenu) => {
                 unsafe { add_submenu(hmenu, submenu, next_id, state)? };
             }
-            MenuEntry::Separator => {
+            MenuEntry::Separator | MenuEntry::StyledSeparator(_) => {
                 unsafe { AppendMenuW(hmenu, MF_SEPARATOR, 0, ptr::null()) };
             }
         }
@@ -279,6 +501,11 @@ unsafe fn add_menu_item<T: Clone + Send + Sync + 'static>(
     next_id: &mut u32,
     state: &mut MenuBarState<T>,
 ) {
+    // Windows has no Services menu.
+    if item.role == Some(MenuRole::Services) {
+        return;
+    }
+
     let mut flags = MF_STRING;
     if !item.enabled {
         flags |= MF_GRAYED;
@@ -290,10 +517,43 @@ unsafe fn add_menu_item<T: Clone + Send + Sync + 'static>(
     let win_id = *next_id;
     *next_id += 1;
 
-    let label = encode_wide(&item.label);
+    let label = encode_item_label(item);
     unsafe { AppendMenuW(hmenu, flags, win_id as usize, label.as_ptr()) };
 
     state.id_map.insert(win_id, item.id.clone());
+    if item.mnemonic {
+        state.mnemonics.insert(win_id);
+    }
+    if let Some(action) = &item.action {
+        state.actions.insert(win_id, action.clone());
+    }
+    if let Some(role) = item.role {
+        state.roles.insert(win_id, role);
+    }
+}
+
+/// Performs `role` on the menu bar's window if Windows has an equivalent.
+///
+/// Returns `false` for roles that are reported as clicks instead.
+unsafe fn perform_role(hwnd: HWND, role: MenuRole) -> bool {
+    match role {
+        MenuRole::Quit => unsafe {
+            PostMessageW(hwnd, WM_CLOSE, 0, 0);
+        },
+        MenuRole::Minimize => unsafe {
+            ShowWindow(hwnd, SW_MINIMIZE);
+        },
+        MenuRole::Zoom => unsafe {
+            let command = if IsZoomed(hwnd) != 0 {
+                SW_RESTORE
+            } else {
+                SW_MAXIMIZE
+            };
+            ShowWindow(hwnd, command);
+        },
+        _ => return false,
+    }
+    true
 }
 
 unsafe fn add_submenu<T: Clone + Send + Sync + 'static>(
@@ -301,7 +561,7 @@ unsafe fn add_submenu<T: Clone + Send + Sync + 'static>(
     submenu: &Submenu<T>,
     next_id: &mut u32,
     state: &mut MenuBarState<T>,
-) -> Result<(), anyhow::Error> {
+) -> Result<(), TrayError> {
     let child_hmenu = unsafe { build_popup_menu(&submenu.items, next_id, state)? };
 
     let mut flags = MF_POPUP;
@@ -309,12 +569,26 @@ unsafe fn add_submenu<T: Clone + Send + Sync + 'static>(
         flags |= MF_GRAYED;
     }
 
-    let label = encode_wide(&submenu.label);
+    let label = encode_label(&submenu.label, submenu.mnemonic);
     unsafe { AppendMenuW(hmenu, flags, child_hmenu as usize, label.as_ptr()) };
 
     Ok(())
 }
 
+/// Reads the label of an item, found by position or command id as `flags`
+/// says.
+unsafe fn menu_string(hmenu: HMENU, item: u32, flags: u32) -> String {
+    let len = unsafe { GetMenuStringW(hmenu, item, ptr::null_mut(), 0, flags) };
+    if len <= 0 {
+        return String::new();
+    }
+
+    let mut buffer = vec![0u16; len as usize + 1];
+    let len =
+        unsafe { GetMenuStringW(hmenu, item, buffer.as_mut_ptr(), buffer.len() as i32, flags) };
+    String::from_utf16_lossy(&buffer[..len.max(0) as usize])
+}
+
 /// Recursively destroys a menu and all its submenus.
 unsafe fn destroy_menu_tree(hmenu: HMENU) {
     let count = unsafe { GetMenuItemCount(hmenu) };
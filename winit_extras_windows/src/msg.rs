@@ -58,3 +58,16 @@ impl LazyMessageId {
 // Message sent by a `Window` when it wants to be destroyed by the main thread.
 // WPARAM and LPARAM are unused.
 pub(crate) static DESTROY_MSG_ID: LazyMessageId = LazyMessageId::new("WinitTray::DestroyMsg\0");
+
+// Message sent to a tray's window to open its context menu at the icon.
+// WPARAM and LPARAM are unused.
+pub(crate) static OPEN_MENU_MSG_ID: LazyMessageId = LazyMessageId::new("WinitTray::OpenMenuMsg\0");
+
+// Message Explorer broadcasts once the taskbar exists, including after a
+// restart that dropped every notification icon. WPARAM and LPARAM are unused.
+pub(crate) static TASKBAR_CREATED_MSG_ID: LazyMessageId = LazyMessageId::new("TaskbarCreated\0");
+
+// Message sent to a tray's window to revert a flashed tooltip after a delay.
+// WPARAM is the delay in milliseconds and LPARAM is unused.
+pub(crate) static FLASH_TOOLTIP_MSG_ID: LazyMessageId =
+    LazyMessageId::new("WinitTray::FlashTooltipMsg\0");
@@ -0,0 +1,265 @@
+//! Tray icons that pump their own messages.
+//!
+//! A [`Tray`] delivers events from the message loop of the thread that
+//! created it, which is normally the winit event loop. Applications without
+//! one, such as daemons, can use a [`ThreadedTray`] instead: it creates the
+//! tray on a background thread running its own `GetMessageW` loop, like the
+//! Linux backend does for D-Bus.
+
+use std::ffi::OsStr;
+use std::ptr;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use dpi::PhysicalPosition;
+use tracing::{debug, warn};
+use windows_sys::Win32::{
+    Foundation::HWND,
+    System::Threading::GetCurrentThreadId,
+    UI::WindowsAndMessaging::{
+        DispatchMessageW, GetMessageW, MSG, PM_REMOVE, PeekMessageW, PostThreadMessageW,
+        TranslateMessage, WM_QUIT,
+    },
+};
+use winit_core::icon::Icon;
+use winit_extras_core::icon::StatusColor;
+use winit_extras_core::{
+    EventCallback, NotificationIcon, TrayError, TrayIcon as CoreTrayIcon, TrayIconAttributes,
+};
+
+use crate::tray::{self, IconState, Tray};
+
+/// What the background thread reports back once the tray exists.
+struct Created {
+    hwnd: usize,
+    internal_id: u32,
+    thread_id: u32,
+    last_pointer_position: Arc<Mutex<Option<PhysicalPosition<f64>>>>,
+    icon_state: Arc<Mutex<IconState>>,
+    #[cfg(feature = "menu")]
+    has_menu: bool,
+}
+
+/// Moves the attributes to the tray thread.
+struct SendAttributes<T>(TrayIconAttributes<T>);
+
+// SAFETY: `parent_window`, the only field that isn't `Send`, is rejected
+// before the attributes are wrapped.
+unsafe impl<T: Send> Send for SendAttributes<T> {}
+
+impl<T> SendAttributes<T> {
+    // Taking `self` makes closures capture the whole wrapper rather than
+    // just the attributes inside it.
+    fn into_inner(self) -> TrayIconAttributes<T> {
+        self.0
+    }
+}
+
+/// A tray icon whose message window lives on its own thread.
+///
+/// Events are delivered through the proxy from that thread, so no event loop
+/// has to run on the thread that created the tray. Dropping the handle
+/// removes the icon and stops the thread.
+///
+/// Context menus still open from the tray thread. Menus and their labels
+/// can't be changed after creation, and `parent_window` is not supported.
+pub struct ThreadedTray<T = ()> {
+    hwnd: usize,
+    internal_id: u32,
+    thread_id: u32,
+    last_pointer_position: Arc<Mutex<Option<PhysicalPosition<f64>>>>,
+    icon_state: Arc<Mutex<IconState>>,
+    #[cfg(feature = "menu")]
+    has_menu: bool,
+    thread_handle: Option<thread::JoinHandle<()>>,
+    _marker: std::marker::PhantomData<T>,
+}
+
+impl<T> std::fmt::Debug for ThreadedTray<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ThreadedTray")
+            .field("hwnd", &self.hwnd)
+            .field("internal_id", &self.internal_id)
+            .field("thread_id", &self.thread_id)
+            .finish()
+    }
+}
+
+impl<T: Clone + Send + Sync + 'static> ThreadedTray<T> {
+    /// Spawns the tray thread and creates the tray on it.
+    ///
+    /// Blocks until the tray has been created, so creation errors are
+    /// returned here as with [`Tray::new`].
+    pub fn new(proxy: EventCallback<T>, attr: TrayIconAttributes<T>) -> Result<Self, TrayError> {
+        if attr.parent_window.is_some() {
+            return Err(TrayError::UnsupportedParentWindow);
+        }
+        let attr = SendAttributes(attr);
+
+        let (created_tx, created_rx) = std::sync::mpsc::channel();
+        let thread_handle = thread::Builder::new()
+            .name("winit_extras tray".to_string())
+            .spawn(move || {
+                let attr = attr.into_inner();
+                let tray = match Tray::new(proxy, attr) {
+                    Ok(tray) => tray,
+                    Err(e) => {
+                        let _ = created_tx.send(Err(e));
+                        return;
+                    }
+                };
+                let _ = created_tx.send(Ok(Created {
+                    hwnd: tray.hwnd() as usize,
+                    internal_id: tray.internal_id(),
+                    thread_id: unsafe { GetCurrentThreadId() },
+                    last_pointer_position: tray.last_pointer_position_cache(),
+                    icon_state: tray.icon_state(),
+                    #[cfg(feature = "menu")]
+                    has_menu: tray.has_menu(),
+                }));
+                drop(created_tx);
+
+                run_message_loop(tray);
+            })?;
+
+        let created = created_rx.recv().map_err(|_| {
+            TrayError::Platform("tray thread exited before creating the tray".to_owned())
+        })??;
+
+        debug!(
+            internal_id = created.internal_id,
+            thread_id = created.thread_id,
+            "Created tray on its own thread"
+        );
+
+        Ok(ThreadedTray {
+            hwnd: created.hwnd,
+            internal_id: created.internal_id,
+            thread_id: created.thread_id,
+            last_pointer_position: created.last_pointer_position,
+            icon_state: created.icon_state,
+            #[cfg(feature = "menu")]
+            has_menu: created.has_menu,
+            thread_handle: Some(thread_handle),
+            _marker: std::marker::PhantomData,
+        })
+    }
+
+    /// The hidden window that receives the tray's messages, on the tray
+    /// thread. See [`Tray::hwnd`].
+    #[inline]
+    pub fn hwnd(&self) -> HWND {
+        self.hwnd as HWND
+    }
+
+    /// The `uID` of the tray's `NOTIFYICONDATAW`. See
+    /// [`Tray::notify_icon_id`].
+    pub fn notify_icon_id(&self) -> u32 {
+        self.internal_id
+    }
+
+    pub fn set_tooltip<S: AsRef<OsStr>>(&self, tooltip: Option<S>) -> Result<(), TrayError> {
+        tray::set_tooltip(self.hwnd(), self.internal_id, &self.icon_state, tooltip)
+    }
+}
+
+/// Pumps messages for `tray` until `WM_QUIT`, then removes it.
+fn run_message_loop<T>(tray: Tray<T>) {
+    let mut msg: MSG = unsafe { std::mem::zeroed() };
+    while unsafe { GetMessageW(&mut msg, ptr::null_mut(), 0, 0) } > 0 {
+        unsafe {
+            TranslateMessage(&msg);
+            DispatchMessageW(&msg);
+        }
+    }
+
+    // Dropping the tray posts the message that removes the icon and destroys
+    // the window, so handle what is left in the queue before exiting.
+    drop(tray);
+    while unsafe { PeekMessageW(&mut msg, ptr::null_mut(), 0, 0, PM_REMOVE) } != 0 {
+        unsafe {
+            TranslateMessage(&msg);
+            DispatchMessageW(&msg);
+        }
+    }
+}
+
+impl<T> CoreTrayIcon<T> for ThreadedTray<T> {
+    fn id(&self) -> winit_extras_core::tray_icon_id::TrayIconId {
+        winit_extras_core::tray_icon_id::TrayIconId::from_raw(self.internal_id as usize)
+    }
+
+    fn last_pointer_position(&self) -> Option<PhysicalPosition<f64>> {
+        *self.last_pointer_position.lock().ok()?
+    }
+
+    fn set_tooltip(&self, tooltip: Option<&str>) -> Result<(), TrayError> {
+        tray::set_tooltip(
+            self.hwnd as HWND,
+            self.internal_id,
+            &self.icon_state,
+            tooltip,
+        )
+    }
+
+    fn flash_tooltip(&self, tooltip: &str, duration: Duration) -> Result<(), TrayError> {
+        tray::flash_tooltip(self.hwnd as HWND, self.internal_id, tooltip, duration)
+    }
+
+    fn show_notification(
+        &self,
+        title: &str,
+        body: &str,
+        icon: NotificationIcon,
+    ) -> Result<(), TrayError> {
+        tray::show_notification(self.hwnd as HWND, self.internal_id, title, body, icon)
+    }
+
+    fn set_icon(&self, icon: Option<&Icon>) -> Result<(), TrayError> {
+        tray::set_icon(self.hwnd as HWND, self.internal_id, &self.icon_state, icon)
+    }
+
+    fn set_status_dot(&self, color: Option<StatusColor>) -> Result<(), TrayError> {
+        tray::set_status_dot(self.hwnd as HWND, self.internal_id, &self.icon_state, color)
+    }
+
+    fn set_interactive(&self, interactive: bool) -> Result<(), TrayError> {
+        tray::set_interactive(
+            self.hwnd as HWND,
+            self.internal_id,
+            &self.icon_state,
+            interactive,
+        )
+    }
+
+    fn is_in_overflow(&self) -> Option<bool> {
+        tray::is_in_overflow(self.hwnd as HWND, self.internal_id)
+    }
+
+    #[cfg(feature = "menu")]
+    fn open_menu(&self) -> Result<(), TrayError> {
+        if !self.has_menu {
+            return Err(TrayError::NoContextMenu);
+        }
+        tray::open_menu(self.hwnd as HWND)
+    }
+}
+
+impl<T> Drop for ThreadedTray<T> {
+    fn drop(&mut self) {
+        if unsafe { PostThreadMessageW(self.thread_id, WM_QUIT, 0, 0) } == 0 {
+            warn!(
+                internal_id = self.internal_id,
+                "Failed to stop tray thread, tray icon will leak: {}",
+                std::io::Error::last_os_error()
+            );
+            return;
+        }
+        if let Some(handle) = self.thread_handle.take()
+            && handle.join().is_err()
+        {
+            warn!(internal_id = self.internal_id, "Tray thread panicked");
+        }
+    }
+}
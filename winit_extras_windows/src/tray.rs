@@ -1,31 +1,52 @@
 //! Tray icon implementation for Windows.
 
+use std::ffi::OsString;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use std::{cell::Cell, ffi::OsStr, ptr, rc::Rc};
 
-use dpi::PhysicalPosition;
+use dpi::{PhysicalPosition, PhysicalSize};
 use rwh_06::RawWindowHandle;
+#[cfg(feature = "menu")]
+use std::cell::RefCell;
+use tracing::warn;
+#[cfg(feature = "menu")]
+use windows_sys::Win32::UI::WindowsAndMessaging::HMENU;
 use windows_sys::Win32::{
-    Foundation::{HWND, LPARAM, LRESULT, POINT, TRUE, WPARAM},
+    Foundation::{HWND, LPARAM, LRESULT, POINT, RECT, S_OK, TRUE, WPARAM},
     System::LibraryLoader::GetModuleHandleW,
     UI::{
         Shell::{
-            NIF_ICON, NIF_MESSAGE, NIF_TIP, NIM_ADD, NIM_MODIFY, NOTIFYICONDATAW, Shell_NotifyIconW,
+            NIF_ICON, NIF_INFO, NIF_MESSAGE, NIF_TIP, NIIF_ERROR, NIIF_INFO, NIIF_LARGE_ICON,
+            NIIF_NONE, NIIF_USER, NIIF_WARNING, NIM_ADD, NIM_DELETE, NIM_MODIFY,
+            NIN_BALLOONUSERCLICK, NOTIFYICONDATAW, NOTIFYICONIDENTIFIER, Shell_NotifyIconGetRect,
+            Shell_NotifyIconW,
         },
         WindowsAndMessaging::{
-            CREATESTRUCTW, CS_HREDRAW, CS_VREDRAW, CW_USEDEFAULT, CreateWindowExW, DefWindowProcW,
-            DestroyWindow, GWL_USERDATA, GetCursorPos, HICON, IDI_APPLICATION, LoadIconW,
-            PostMessageW, RegisterClassExW, WM_CREATE, WM_LBUTTONDOWN, WM_LBUTTONUP,
-            WM_MBUTTONDOWN, WM_MBUTTONUP, WM_NCCREATE, WM_RBUTTONDOWN, WM_RBUTTONUP,
-            WM_XBUTTONDOWN, WM_XBUTTONUP, WNDCLASSEXW, WS_EX_LAYERED, WS_EX_NOACTIVATE,
+            CREATESTRUCTW, CS_HREDRAW, CS_VREDRAW, CW_USEDEFAULT, ChangeWindowMessageFilterEx,
+            CreateWindowExW, DefWindowProcW, DestroyWindow, GWL_USERDATA, GetCursorPos,
+            GetSystemMetrics, HICON, IDI_APPLICATION, KillTimer, LoadIconW, MSGFLT_ALLOW,
+            PostMessageW, RegisterClassExW, SM_CXICON, SM_CYICON, SetTimer, WM_APP, WM_CREATE,
+            WM_DISPLAYCHANGE, WM_DPICHANGED, WM_LBUTTONDBLCLK, WM_LBUTTONDOWN, WM_LBUTTONUP,
+            WM_MBUTTONDBLCLK, WM_MBUTTONDOWN, WM_MBUTTONUP, WM_MOUSEMOVE, WM_NCCREATE,
+            WM_NCDESTROY, WM_RBUTTONDBLCLK, WM_RBUTTONDOWN, WM_RBUTTONUP, WM_SETTINGCHANGE,
+            WM_TIMER, WM_XBUTTONDOWN, WM_XBUTTONUP, WNDCLASSEXW, WS_EX_LAYERED, WS_EX_NOACTIVATE,
             WS_EX_TOOLWINDOW, WS_EX_TRANSPARENT, WS_OVERLAPPED,
         },
     },
 };
 use winit_core::event::{ElementState, MouseButton};
-use winit_extras_core::{Event, EventCallback, TrayIcon as CoreTrayIcon, TrayIconAttributes};
+use winit_core::icon::Icon;
+use winit_extras_core::icon::{IconError, StatusColor, dimmed, with_status_dot};
+use winit_extras_core::{
+    ButtonMask, Event, EventCallback, NotificationIcon, TrayError, TrayIcon as CoreTrayIcon,
+    TrayIconAttributes,
+};
+#[cfg(feature = "menu")]
+use winit_extras_core::{MenuEntries, MenuEntry, PrimaryAction};
 
-use crate::msg::DESTROY_MSG_ID;
-use crate::util;
+use crate::msg::{DESTROY_MSG_ID, FLASH_TOOLTIP_MSG_ID, OPEN_MENU_MSG_ID, TASKBAR_CREATED_MSG_ID};
+use crate::util::{self, OwnedIcon};
 
 #[derive(Clone, Copy, Debug)]
 #[repr(transparent)]
@@ -40,14 +61,182 @@ impl SyncWindowHandle {
     }
 }
 
-static COUNTER: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(1);
-
 pub struct Tray<T = ()> {
     window_handle: SyncWindowHandle,
     internal_id: u32,
+    // Shared with the menu handler, which builds the menu from it on every
+    // show.
+    #[cfg(feature = "menu")]
+    menu_items: Option<Rc<RefCell<Vec<MenuEntry<T>>>>>,
+    // Whether there is a menu for `open_menu` to show, which may be a
+    // native one rather than `menu_items`.
+    #[cfg(feature = "menu")]
+    has_menu: bool,
+    // Updated by the window procedure on every pointer event.
+    last_pointer_position: Arc<Mutex<Option<PhysicalPosition<f64>>>>,
+    // Shared with the window procedure, which draws the icon again when the
+    // icon size changes.
+    icon_state: Arc<Mutex<IconState>>,
     _marker: std::marker::PhantomData<T>,
 }
 
+/// Where the pointer is relative to the icon, for picking the hover and
+/// pressed icons.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum Pointer {
+    #[default]
+    Outside,
+    Over,
+    Pressed,
+}
+
+/// The icon a tray was created with and what is drawn over it, along with
+/// the tooltip a flashed one reverts to.
+#[derive(Default)]
+pub(crate) struct IconState {
+    icon: Option<Icon>,
+    hover_icon: Option<Icon>,
+    pressed_icon: Option<Icon>,
+    pointer: Pointer,
+    status_dot: Option<StatusColor>,
+    // Set by `set_interactive(false)`: clicks are ignored and the icon is
+    // drawn dimmed.
+    disabled: bool,
+    // The tooltip last set, as opposed to one shown by `flash_tooltip`.
+    tooltip: Option<OsString>,
+}
+
+impl IconState {
+    /// The icon for the pointer's current state.
+    fn current_icon(&self) -> Option<&Icon> {
+        let state_icon = match self.pointer {
+            _ if self.disabled => None,
+            Pointer::Outside => None,
+            Pointer::Over => self.hover_icon.as_ref(),
+            Pointer::Pressed => self.pressed_icon.as_ref().or(self.hover_icon.as_ref()),
+        };
+        state_icon.or(self.icon.as_ref())
+    }
+
+    /// Whether the icon has to be drawn again when the icon size changes.
+    fn redraw_on_resize(&self) -> bool {
+        self.status_dot.is_some()
+            || self.disabled
+            || self
+                .current_icon()
+                .is_some_and(winit_extras_core::icon::is_scalable)
+    }
+
+    /// Draws the icon at `size`, or returns `None` if there is no icon.
+    fn to_hicon(&self, size: PhysicalSize<u32>) -> Result<Option<OwnedIcon>, IconError> {
+        let Some(icon) = self.current_icon() else {
+            return Ok(None);
+        };
+        let with_dot = match self.status_dot {
+            Some(color) => Some(with_status_dot(icon, color, size)?),
+            None => None,
+        };
+        let icon = with_dot.as_ref().unwrap_or(icon);
+        let hicon = if self.disabled {
+            util::icon_to_hicon(&dimmed(icon, size)?, size)?
+        } else {
+            util::icon_to_hicon(icon, size)?
+        };
+        Ok(Some(hicon))
+    }
+}
+
+/// Replaces the tray's icon and shows it, with the status dot and dimming
+/// drawn over it. Without an icon, the default one is shown.
+pub(crate) fn set_icon(
+    hwnd: HWND,
+    tray_icon_id: u32,
+    icon_state: &Mutex<IconState>,
+    icon: Option<&Icon>,
+) -> Result<(), TrayError> {
+    let mut state = icon_state
+        .lock()
+        .map_err(|_| TrayError::Platform("tray icon state is poisoned".to_owned()))?;
+    let previous = std::mem::replace(&mut state.icon, icon.cloned());
+    let drawn = match state.to_hicon(util::tray_icon_size(hwnd)) {
+        Ok(drawn) => drawn,
+        Err(e) => {
+            state.icon = previous;
+            return Err(e.into());
+        }
+    };
+    // The default icon is shared, so only a drawn one is destroyed.
+    let hicon = match &drawn {
+        Some(hicon) => Ok(hicon.handle()),
+        None => unsafe { default_tray_icon() }
+            .ok_or_else(|| TrayError::Platform("failed to load the default tray icon".to_owned())),
+    };
+    let shown = hicon.and_then(|hicon| {
+        if unsafe { update_tray_icon(hwnd, tray_icon_id, hicon) } {
+            Ok(())
+        } else {
+            Err(std::io::Error::last_os_error().into())
+        }
+    });
+    // Keep the state matching what the shell shows.
+    if shown.is_err() {
+        state.icon = previous;
+    }
+    shown
+}
+
+/// Changes the status dot and shows the result on the notification icon.
+pub(crate) fn set_status_dot(
+    hwnd: HWND,
+    tray_icon_id: u32,
+    icon_state: &Mutex<IconState>,
+    color: Option<StatusColor>,
+) -> Result<(), TrayError> {
+    let mut state = icon_state
+        .lock()
+        .map_err(|_| TrayError::Platform("tray icon state is poisoned".to_owned()))?;
+    if state.icon.is_none() {
+        return Err(TrayError::NoIcon);
+    }
+    let previous = std::mem::replace(&mut state.status_dot, color);
+    let hicon = match state.to_hicon(util::tray_icon_size(hwnd)) {
+        Ok(hicon) => hicon.expect("icon checked above"),
+        Err(e) => {
+            state.status_dot = previous;
+            return Err(e.into());
+        }
+    };
+    if !unsafe { update_tray_icon(hwnd, tray_icon_id, hicon.handle()) } {
+        state.status_dot = previous;
+        return Err(std::io::Error::last_os_error().into());
+    }
+    Ok(())
+}
+
+/// Makes the tray ignore clicks, or respond again, and redraws the icon
+/// dimmed to match.
+pub(crate) fn set_interactive(
+    hwnd: HWND,
+    tray_icon_id: u32,
+    icon_state: &Mutex<IconState>,
+    interactive: bool,
+) -> Result<(), TrayError> {
+    let mut state = icon_state
+        .lock()
+        .map_err(|_| TrayError::Platform("tray icon state is poisoned".to_owned()))?;
+    if state.disabled != interactive {
+        return Ok(());
+    }
+    state.disabled = !interactive;
+    let Some(hicon) = state.to_hicon(util::tray_icon_size(hwnd))? else {
+        return Ok(());
+    };
+    if !unsafe { update_tray_icon(hwnd, tray_icon_id, hicon.handle()) } {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    Ok(())
+}
+
 impl<T> std::fmt::Debug for Tray<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Tray")
@@ -58,59 +247,384 @@ impl<T> std::fmt::Debug for Tray<T> {
 }
 
 impl<T: Clone + Send + Sync + 'static> Tray<T> {
-    pub fn new(proxy: EventCallback<T>, attr: TrayIconAttributes) -> Result<Self, anyhow::Error> {
-        unsafe { init(proxy, attr) }
+    pub fn new(proxy: EventCallback<T>, attr: TrayIconAttributes<T>) -> Result<Self, TrayError> {
+        unsafe {
+            init(
+                proxy,
+                attr,
+                #[cfg(feature = "menu")]
+                None,
+            )
+        }
+    }
+
+    /// Creates a tray that shows an existing `HMENU` instead of building its
+    /// menu from [`MenuEntry`](winit_extras_core::MenuEntry) items.
+    ///
+    /// The menu opens on the same clicks as a `context_menu` would, which it
+    /// replaces. Selecting an item delivers
+    /// [`Event::MenuItemClicked`] with the id `command_to_id` returns for the
+    /// item's command id; items it maps to `None` are ignored.
+    ///
+    /// # Safety
+    ///
+    /// `hmenu` must be a valid popup menu handle whose items have non-zero
+    /// command ids. The application keeps ownership: the tray never destroys
+    /// the menu, and the menu must stay alive until the tray is dropped.
+    /// Call `DestroyMenu` yourself afterwards.
+    #[cfg(feature = "menu")]
+    pub unsafe fn with_native_menu(
+        proxy: EventCallback<T>,
+        attr: TrayIconAttributes<T>,
+        hmenu: HMENU,
+        command_to_id: impl Fn(u32) -> Option<T> + 'static,
+    ) -> Result<Self, TrayError> {
+        let native_menu = NativeMenu {
+            hmenu,
+            command_to_id: Box::new(command_to_id),
+        };
+        unsafe { init(proxy, attr, Some(native_menu)) }
     }
 
+    /// The hidden window that receives the tray's messages.
+    ///
+    /// Together with [`notify_icon_id`](Self::notify_icon_id) this
+    /// identifies the icon to `Shell_NotifyIconW`, for sending messages the
+    /// cross-platform API doesn't cover yet. This is a platform-specific
+    /// interop point and not part of the stable API.
     #[inline]
     pub fn hwnd(&self) -> HWND {
         self.window_handle.hwnd()
     }
 
-    pub fn set_tooltip<S: AsRef<OsStr>>(&self, tooltip: Option<S>) -> Result<(), anyhow::Error> {
-        let mut nid = NOTIFYICONDATAW {
-            cbSize: std::mem::size_of::<NOTIFYICONDATAW>() as u32,
-            uFlags: NIF_TIP,
-            hWnd: self.hwnd(),
-            uID: self.internal_id,
-            ..unsafe { std::mem::zeroed() }
-        };
-        if let Some(tooltip) = &tooltip {
-            let tip = util::encode_wide(tooltip);
-            #[allow(clippy::manual_memcpy)]
-            for i in 0..tip.len().min(128) {
-                nid.szTip[i] = tip[i];
-            }
-        }
+    /// The `uID` of the tray's `NOTIFYICONDATAW`, alongside
+    /// [`hwnd`](Self::hwnd).
+    ///
+    /// Changes made directly through the shell, such as replacing the
+    /// icon, are overwritten the next time the tray updates it.
+    pub fn notify_icon_id(&self) -> u32 {
+        self.internal_id
+    }
 
-        if unsafe { Shell_NotifyIconW(NIM_MODIFY, &mut nid as _) } == 0 {
-            return Err(std::io::Error::last_os_error().into());
+    pub fn set_tooltip<S: AsRef<OsStr>>(&self, tooltip: Option<S>) -> Result<(), TrayError> {
+        set_tooltip(self.hwnd(), self.internal_id, &self.icon_state, tooltip)
+    }
+
+    pub(crate) fn internal_id(&self) -> u32 {
+        self.internal_id
+    }
+
+    /// The position cache the window procedure updates, for sharing with
+    /// another thread.
+    pub(crate) fn last_pointer_position_cache(&self) -> Arc<Mutex<Option<PhysicalPosition<f64>>>> {
+        self.last_pointer_position.clone()
+    }
+
+    /// The icon state the window procedure draws from, for sharing with
+    /// another thread.
+    pub(crate) fn icon_state(&self) -> Arc<Mutex<IconState>> {
+        self.icon_state.clone()
+    }
+
+    #[cfg(feature = "menu")]
+    pub(crate) fn has_menu(&self) -> bool {
+        self.has_menu
+    }
+}
+
+/// Changes the tooltip of an existing notification icon, and remembers it
+/// for a flashed tooltip to revert to.
+pub(crate) fn set_tooltip<S: AsRef<OsStr>>(
+    hwnd: HWND,
+    tray_icon_id: u32,
+    icon_state: &Mutex<IconState>,
+    tooltip: Option<S>,
+) -> Result<(), TrayError> {
+    let mut state = icon_state
+        .lock()
+        .map_err(|_| TrayError::Platform("tray icon state is poisoned".to_owned()))?;
+    state.tooltip = tooltip.as_ref().map(|tooltip| tooltip.as_ref().to_owned());
+    show_tooltip(hwnd, tray_icon_id, tooltip)
+}
+
+/// Shows `tooltip` until the tray's window reverts it after `duration`.
+pub(crate) fn flash_tooltip(
+    hwnd: HWND,
+    tray_icon_id: u32,
+    tooltip: &str,
+    duration: Duration,
+) -> Result<(), TrayError> {
+    show_tooltip(hwnd, tray_icon_id, Some(tooltip))?;
+    // The timer has to be set on the window's own thread.
+    let millis = u32::try_from(duration.as_millis()).unwrap_or(u32::MAX);
+    if unsafe { PostMessageW(hwnd, FLASH_TOOLTIP_MSG_ID.get(), millis as WPARAM, 0) } == 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    Ok(())
+}
+
+/// Changes the tooltip shown by an existing notification icon.
+fn show_tooltip<S: AsRef<OsStr>>(
+    hwnd: HWND,
+    tray_icon_id: u32,
+    tooltip: Option<S>,
+) -> Result<(), TrayError> {
+    let mut nid = NOTIFYICONDATAW {
+        cbSize: std::mem::size_of::<NOTIFYICONDATAW>() as u32,
+        uFlags: NIF_TIP,
+        hWnd: hwnd,
+        uID: tray_icon_id,
+        ..unsafe { std::mem::zeroed() }
+    };
+    if let Some(tooltip) = &tooltip {
+        util::copy_wide(&mut nid.szTip, tooltip);
+    }
+
+    if unsafe { Shell_NotifyIconW(NIM_MODIFY, &mut nid as _) } == 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    Ok(())
+}
+
+/// Shows a balloon notification from an existing notification icon.
+pub(crate) fn show_notification(
+    hwnd: HWND,
+    tray_icon_id: u32,
+    title: &str,
+    body: &str,
+    icon: NotificationIcon,
+) -> Result<(), TrayError> {
+    let mut nid = NOTIFYICONDATAW {
+        cbSize: std::mem::size_of::<NOTIFYICONDATAW>() as u32,
+        uFlags: NIF_INFO,
+        hWnd: hwnd,
+        uID: tray_icon_id,
+        ..unsafe { std::mem::zeroed() }
+    };
+    util::copy_wide(&mut nid.szInfoTitle, title);
+    util::copy_wide(&mut nid.szInfo, body);
+    // The shell copies the balloon icon, so ours is dropped on return.
+    let mut balloon_icon = None;
+    nid.dwInfoFlags = match &icon {
+        NotificationIcon::None => NIIF_NONE,
+        NotificationIcon::Info => NIIF_INFO,
+        NotificationIcon::Warning => NIIF_WARNING,
+        NotificationIcon::Error => NIIF_ERROR,
+        NotificationIcon::Custom(icon) => {
+            let size = unsafe {
+                PhysicalSize::new(
+                    GetSystemMetrics(SM_CXICON) as u32,
+                    GetSystemMetrics(SM_CYICON) as u32,
+                )
+            };
+            let hicon = balloon_icon.insert(util::icon_to_hicon(icon, size)?);
+            nid.hBalloonIcon = hicon.handle();
+            NIIF_USER | NIIF_LARGE_ICON
         }
-        Ok(())
+    };
+
+    if unsafe { Shell_NotifyIconW(NIM_MODIFY, &mut nid as _) } == 0 {
+        return Err(std::io::Error::last_os_error().into());
     }
+    Ok(())
 }
 
-impl<T> CoreTrayIcon for Tray<T> {
+impl<T> CoreTrayIcon<T> for Tray<T> {
     fn id(&self) -> winit_extras_core::tray_icon_id::TrayIconId {
-        winit_extras_core::tray_icon_id::TrayIconId::from_raw(self.window_handle.hwnd() as usize)
+        winit_extras_core::tray_icon_id::TrayIconId::from_raw(self.internal_id as usize)
+    }
+
+    #[cfg(feature = "menu")]
+    fn set_label(&self, id: &T, label: &str) -> Result<(), TrayError>
+    where
+        T: PartialEq,
+    {
+        let items = self.menu_items.as_ref().ok_or(TrayError::NoContextMenu)?;
+        let mut items = items.try_borrow_mut().map_err(|_| TrayError::MenuOpen)?;
+        let item = items.find_item_mut(id).ok_or(TrayError::UnknownMenuId)?;
+        item.label = label.to_owned();
+        Ok(())
+    }
+
+    #[cfg(feature = "menu")]
+    fn set_menu(&self, menu: Vec<MenuEntry<T>>) -> Result<(), TrayError> {
+        // The menu is rebuilt from the items each time it opens.
+        let items = self.menu_items.as_ref().ok_or(TrayError::NoContextMenu)?;
+        *items.try_borrow_mut().map_err(|_| TrayError::MenuOpen)? = menu;
+        Ok(())
     }
+
+    #[cfg(feature = "menu")]
+    fn set_item_icon(&self, id: &T, icon: Option<Icon>) -> Result<(), TrayError>
+    where
+        T: PartialEq,
+    {
+        // The menu is rebuilt from the items each time it opens, so the new
+        // bitmap is made then and freed with the menu.
+        let items = self.menu_items.as_ref().ok_or(TrayError::NoContextMenu)?;
+        let mut items = items.try_borrow_mut().map_err(|_| TrayError::MenuOpen)?;
+        let item = items.find_item_mut(id).ok_or(TrayError::UnknownMenuId)?;
+        item.icon = icon;
+        Ok(())
+    }
+
+    #[cfg(feature = "menu")]
+    fn open_menu(&self) -> Result<(), TrayError> {
+        if !self.has_menu {
+            return Err(TrayError::NoContextMenu);
+        }
+        open_menu(self.window_handle.hwnd())
+    }
+
+    fn last_pointer_position(&self) -> Option<PhysicalPosition<f64>> {
+        *self.last_pointer_position.lock().ok()?
+    }
+
+    fn set_tooltip(&self, tooltip: Option<&str>) -> Result<(), TrayError> {
+        set_tooltip(
+            self.window_handle.hwnd(),
+            self.internal_id,
+            &self.icon_state,
+            tooltip,
+        )
+    }
+
+    fn flash_tooltip(&self, tooltip: &str, duration: Duration) -> Result<(), TrayError> {
+        flash_tooltip(
+            self.window_handle.hwnd(),
+            self.internal_id,
+            tooltip,
+            duration,
+        )
+    }
+
+    fn show_notification(
+        &self,
+        title: &str,
+        body: &str,
+        icon: NotificationIcon,
+    ) -> Result<(), TrayError> {
+        show_notification(
+            self.window_handle.hwnd(),
+            self.internal_id,
+            title,
+            body,
+            icon,
+        )
+    }
+
+    fn set_icon(&self, icon: Option<&Icon>) -> Result<(), TrayError> {
+        set_icon(
+            self.window_handle.hwnd(),
+            self.internal_id,
+            &self.icon_state,
+            icon,
+        )
+    }
+
+    fn set_status_dot(&self, color: Option<StatusColor>) -> Result<(), TrayError> {
+        set_status_dot(
+            self.window_handle.hwnd(),
+            self.internal_id,
+            &self.icon_state,
+            color,
+        )
+    }
+
+    fn set_interactive(&self, interactive: bool) -> Result<(), TrayError> {
+        set_interactive(
+            self.window_handle.hwnd(),
+            self.internal_id,
+            &self.icon_state,
+            interactive,
+        )
+    }
+
+    fn is_in_overflow(&self) -> Option<bool> {
+        is_in_overflow(self.window_handle.hwnd(), self.internal_id)
+    }
+}
+
+/// Asks the tray's window to open its context menu.
+#[cfg(feature = "menu")]
+pub(crate) fn open_menu(hwnd: HWND) -> Result<(), TrayError> {
+    if unsafe { PostMessageW(hwnd, OPEN_MENU_MSG_ID.get(), 0, 0) } == 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    Ok(())
+}
+
+/// The screen area of the notification icon, if the shell can tell.
+fn icon_rect(hwnd: HWND, tray_icon_id: u32) -> Option<RECT> {
+    let identifier = NOTIFYICONIDENTIFIER {
+        cbSize: std::mem::size_of::<NOTIFYICONIDENTIFIER>() as u32,
+        hWnd: hwnd,
+        uID: tray_icon_id,
+        ..unsafe { std::mem::zeroed() }
+    };
+    let mut rect: RECT = unsafe { std::mem::zeroed() };
+    if unsafe { Shell_NotifyIconGetRect(&identifier, &mut rect) } != S_OK {
+        return None;
+    }
+    Some(rect)
+}
+
+/// Where to open the context menu when asked to from code: the top center of
+/// the icon, or the cursor if the icon isn't on screen.
+#[cfg(feature = "menu")]
+fn menu_anchor(hwnd: HWND, tray_icon_id: u32) -> POINT {
+    if let Some(rect) = icon_rect(hwnd, tray_icon_id)
+        && rect.right > rect.left
+        && rect.bottom > rect.top
+    {
+        return POINT {
+            x: rect.left + (rect.right - rect.left) / 2,
+            y: rect.top,
+        };
+    }
+    let mut point = POINT { x: 0, y: 0 };
+    unsafe { GetCursorPos(&mut point) };
+    point
+}
+
+/// Whether the notification icon sits in the overflow flyout, if the shell
+/// can tell.
+pub(crate) fn is_in_overflow(hwnd: HWND, tray_icon_id: u32) -> Option<bool> {
+    let rect = icon_rect(hwnd, tray_icon_id)?;
+    // Icons in the closed overflow flyout have no on-screen area.
+    Some(rect.right <= rect.left || rect.bottom <= rect.top)
 }
 
 impl<T> Drop for Tray<T> {
     fn drop(&mut self) {
-        unsafe {
-            PostMessageW(self.window_handle.hwnd(), DESTROY_MSG_ID.get(), 0, 0);
+        // The window procedure removes the notification icon and frees the
+        // window data once the window is destroyed.
+        if unsafe { PostMessageW(self.window_handle.hwnd(), DESTROY_MSG_ID.get(), 0, 0) } == 0 {
+            warn!(
+                internal_id = self.internal_id,
+                "Failed to post tray destroy message, tray icon will leak: {}",
+                std::io::Error::last_os_error()
+            );
         }
     }
 }
 
+/// An application-owned menu shown in place of one built from `MenuEntry`s.
+#[cfg(feature = "menu")]
+struct NativeMenu<T> {
+    hmenu: HMENU,
+    command_to_id: Box<dyn Fn(u32) -> Option<T>>,
+}
+
 #[repr(C)]
 pub(crate) struct InitData<T> {
     vtable: InitDataVTable,
-    pub attributes: TrayIconAttributes,
+    pub attributes: TrayIconAttributes<T>,
     pub proxy: EventCallback<T>,
     pub runner: Rc<Runner>,
     pub tray: Option<Tray<T>>,
+    #[cfg(feature = "menu")]
+    native_menu: Option<NativeMenu<T>>,
 }
 
 #[derive(Default)]
@@ -143,11 +657,33 @@ type ErasedEventSender = Box<
         + Sync,
 >;
 
+type ErasedDoubleClickSender = Box<dyn Fn(HWND, PhysicalPosition<f64>, MouseButton)>;
+
+type ErasedIconSizeSender = Box<dyn Fn(HWND, PhysicalSize<u32>)>;
+
+type ErasedNotificationSender = Box<dyn Fn()>;
+
+/// Shows the tray's context menu at the given screen position.
+#[cfg(feature = "menu")]
+type ErasedMenuHandler = Box<dyn Fn(HWND, i32, i32)>;
+
 struct WindowData {
+    pub tray_id: u32,
     pub userdata_removed: Cell<bool>,
     pub recurse_depth: Cell<u32>,
     pub runner: Rc<Runner>,
     pub event_sender: ErasedEventSender,
+    pub double_click_sender: ErasedDoubleClickSender,
+    pub last_pointer_position: Arc<Mutex<Option<PhysicalPosition<f64>>>>,
+    pub icon_size: Cell<PhysicalSize<u32>>,
+    pub icon_size_sender: ErasedIconSizeSender,
+    pub notification_sender: ErasedNotificationSender,
+    pub icon_state: Arc<Mutex<IconState>>,
+    pub button_filter: ButtonMask,
+    #[cfg(feature = "menu")]
+    pub primary_action: PrimaryAction,
+    #[cfg(feature = "menu")]
+    pub menu_handler: Option<ErasedMenuHandler>,
 }
 
 impl WindowData {
@@ -158,8 +694,167 @@ impl WindowData {
         position: PhysicalPosition<f64>,
         button: winit_core::event::ButtonSource,
     ) {
+        if let Ok(mut last) = self.last_pointer_position.lock() {
+            *last = Some(position);
+        }
+        if let winit_core::event::ButtonSource::Mouse(mouse_button) = button
+            && !self.button_filter.contains(mouse_button)
+        {
+            return;
+        }
         (self.event_sender)(hwnd, state, position, button);
     }
+
+    fn send_double_click(&self, hwnd: HWND, position: PhysicalPosition<f64>, button: MouseButton) {
+        if let Ok(mut last) = self.last_pointer_position.lock() {
+            *last = Some(position);
+        }
+        if self.button_filter.contains(button) {
+            (self.double_click_sender)(hwnd, position, button);
+        }
+    }
+
+    /// Whether clicks are handled, which `set_interactive` turns off.
+    fn is_interactive(&self) -> bool {
+        self.icon_state.lock().map_or(true, |state| !state.disabled)
+    }
+
+    /// Records where the pointer is and shows the matching icon. Returns
+    /// whether the tray has hover or pressed icons to track this for.
+    fn set_pointer(&self, hwnd: HWND, pointer: Pointer) -> bool {
+        let Ok(mut state) = self.icon_state.lock() else {
+            return false;
+        };
+        if state.hover_icon.is_none() && state.pressed_icon.is_none() {
+            return false;
+        }
+        if state.pointer == pointer {
+            return true;
+        }
+        state.pointer = pointer;
+        match state.to_hicon(util::tray_icon_size(hwnd)) {
+            Ok(Some(hicon)) => {
+                if !unsafe { update_tray_icon(hwnd, self.tray_id, hicon.handle()) } {
+                    warn!(
+                        "Failed to update tray icon: {}",
+                        std::io::Error::last_os_error()
+                    );
+                }
+            }
+            Ok(None) => {}
+            Err(e) => warn!(?pointer, "Failed to rasterize tray icon: {}", e),
+        }
+        true
+    }
+
+    /// Shows the hover icon when the pointer moves onto the icon, and starts
+    /// watching for it to leave, which the shell doesn't report.
+    fn pointer_moved(&self, hwnd: HWND) {
+        let entered = self
+            .icon_state
+            .lock()
+            .is_ok_and(|state| state.pointer == Pointer::Outside && !state.disabled);
+        if entered && self.set_pointer(hwnd, Pointer::Over) {
+            unsafe { SetTimer(hwnd, HOVER_TIMER_ID, HOVER_POLL_MS, None) };
+        }
+    }
+
+    /// Restores the normal icon once the pointer has left the icon.
+    fn check_pointer_left(&self, hwnd: HWND) {
+        let mut point = POINT { x: 0, y: 0 };
+        if unsafe { GetCursorPos(&mut point) } == 0 {
+            return;
+        }
+        let over = icon_rect(hwnd, self.tray_id).is_some_and(|rect| {
+            point.x >= rect.left
+                && point.x < rect.right
+                && point.y >= rect.top
+                && point.y < rect.bottom
+        });
+        if !over {
+            unsafe { KillTimer(hwnd, HOVER_TIMER_ID) };
+            self.set_pointer(hwnd, Pointer::Outside);
+        }
+    }
+
+    /// Shows the tooltip last set again once a flashed one has expired.
+    fn revert_tooltip(&self, hwnd: HWND) {
+        unsafe { KillTimer(hwnd, TOOLTIP_TIMER_ID) };
+        let Ok(state) = self.icon_state.lock() else {
+            return;
+        };
+        if let Err(e) = show_tooltip(hwnd, self.tray_id, state.tooltip.as_ref()) {
+            warn!("Failed to revert flashed tooltip: {}", e);
+        }
+    }
+
+    /// Adds the icon again, as last shown, after an Explorer restart removed
+    /// it from the notification area.
+    fn restore_icon(&self, hwnd: HWND) {
+        let Ok(state) = self.icon_state.lock() else {
+            return;
+        };
+        // The new taskbar may use a different icon size.
+        let size = util::tray_icon_size(hwnd);
+        self.icon_size.set(size);
+        let hicon = state.to_hicon(size).unwrap_or_else(|e| {
+            warn!(?size, "Failed to rasterize tray icon: {}", e);
+            None
+        });
+        let handle = hicon.as_ref().map(OwnedIcon::handle);
+        if !unsafe { register_tray_icon(hwnd, self.tray_id, handle, state.tooltip.as_ref()) } {
+            warn!(
+                internal_id = self.tray_id,
+                "Failed to add tray icon again after the taskbar was recreated: {}",
+                std::io::Error::last_os_error()
+            );
+        }
+    }
+
+    /// Reports a new icon size if a display or DPI change altered it.
+    pub fn check_icon_size(&self, hwnd: HWND) {
+        let size = util::tray_icon_size(hwnd);
+        if self.icon_size.replace(size) == size {
+            return;
+        }
+        (self.icon_size_sender)(hwnd, size);
+
+        let Ok(state) = self.icon_state.lock() else {
+            return;
+        };
+        if state.redraw_on_resize() {
+            match state.to_hicon(size) {
+                Ok(Some(hicon)) => {
+                    if !unsafe { update_tray_icon(hwnd, self.tray_id, hicon.handle()) } {
+                        warn!(
+                            "Failed to update tray icon: {}",
+                            std::io::Error::last_os_error()
+                        );
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => warn!(?size, "Failed to rasterize tray icon: {}", e),
+            }
+        }
+    }
+
+    /// Opens the context menu at the icon, as [`Tray::open_menu`] asked.
+    #[cfg(feature = "menu")]
+    pub fn open_menu(&self, hwnd: HWND) {
+        if let Some(menu_handler) = &self.menu_handler {
+            let point = menu_anchor(hwnd, self.tray_id);
+            menu_handler(hwnd, point.x, point.y);
+        }
+    }
+
+    #[cfg(feature = "menu")]
+    pub fn show_menu_for(&self, hwnd: HWND, button: MouseButton, point: POINT) {
+        if let Some(menu_handler) = &self.menu_handler
+            && self.primary_action.opens_menu(button)
+        {
+            menu_handler(hwnd, point.x, point.y);
+        }
+    }
 }
 
 unsafe fn initdata_on_nccreate<T: Clone + Send + Sync + 'static>(
@@ -180,7 +875,7 @@ unsafe fn initdata_on_create<T: Clone + Send + Sync + 'static>(this: *mut std::f
 }
 
 impl<T: Clone + Send + Sync + 'static> InitData<T> {
-    fn new(attributes: TrayIconAttributes, proxy: EventCallback<T>, runner: Rc<Runner>) -> Self {
+    fn new(attributes: TrayIconAttributes<T>, proxy: EventCallback<T>, runner: Rc<Runner>) -> Self {
         Self {
             vtable: InitDataVTable {
                 on_nccreate: initdata_on_nccreate::<T>,
@@ -190,40 +885,144 @@ impl<T: Clone + Send + Sync + 'static> InitData<T> {
             proxy,
             runner,
             tray: None,
+            #[cfg(feature = "menu")]
+            native_menu: None,
         }
     }
 
-    unsafe fn create_tray(&self, window: HWND) -> Tray<T> {
+    unsafe fn create_tray(&mut self, window: HWND) -> Tray<T> {
+        // A native menu replaces the context menu entirely.
+        #[cfg(feature = "menu")]
+        let menu_items = match self.native_menu {
+            Some(_) => None,
+            None => self
+                .attributes
+                .take_context_menu()
+                .map(|items| Rc::new(RefCell::new(items))),
+        };
+
         Tray {
             window_handle: SyncWindowHandle(window),
-            internal_id: COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed),
+            // Also the icon's `uID`, which the shell only compares
+            // per window, so wrapping after 2^32 trays is harmless.
+            internal_id: winit_extras_core::tray_icon_id::TrayIconId::allocate().into_raw() as u32,
+            #[cfg(feature = "menu")]
+            has_menu: self.native_menu.is_some() || menu_items.is_some(),
+            #[cfg(feature = "menu")]
+            menu_items,
+            last_pointer_position: Arc::new(Mutex::new(None)),
+            icon_state: Arc::new(Mutex::new(IconState {
+                icon: self.attributes.icon.clone(),
+                hover_icon: self.attributes.hover_icon.clone(),
+                pressed_icon: self.attributes.pressed_icon.clone(),
+                pointer: Pointer::Outside,
+                status_dot: None,
+                disabled: false,
+                tooltip: self.attributes.tooltip.clone().map(OsString::from),
+            })),
             _marker: std::marker::PhantomData,
         }
     }
 
-    unsafe fn create_tray_data(&self, _tray: &Tray<T>) -> WindowData {
+    unsafe fn create_tray_data(&mut self, tray: &Tray<T>) -> WindowData {
         let proxy = self.proxy.clone();
 
-        let event_sender: ErasedEventSender = Box::new(move |hwnd, state, position, button| {
-            let tray_icon_id = winit_extras_core::tray_icon_id::TrayIconId::from_raw(hwnd as usize);
+        let tray_icon_id =
+            winit_extras_core::tray_icon_id::TrayIconId::from_raw(tray.internal_id as usize);
+        let event_sender: ErasedEventSender = Box::new(move |_, state, position, button| {
             (proxy)(Event::PointerButton {
                 tray_icon_id,
                 state,
                 position,
                 button,
+                timestamp: util::message_time(),
             });
         });
 
+        let proxy = self.proxy.clone();
+        let double_click_sender: ErasedDoubleClickSender = Box::new(move |_, position, button| {
+            (proxy)(Event::DoubleClick {
+                tray_icon_id,
+                button,
+                position,
+            });
+        });
+
+        let proxy = self.proxy.clone();
+        let icon_size_sender: ErasedIconSizeSender = Box::new(move |_, size| {
+            (proxy)(Event::IconSizeChanged { tray_icon_id, size });
+        });
+
+        let proxy = self.proxy.clone();
+        let notification_sender: ErasedNotificationSender = Box::new(move || {
+            (proxy)(Event::NotificationClicked { tray_icon_id });
+        });
+
+        #[cfg(feature = "menu")]
+        let activation = self.attributes.menu_activation;
+        #[cfg(feature = "menu")]
+        let menu_handler = if let Some(native_menu) = self.native_menu.take() {
+            let proxy = self.proxy.clone();
+            Some(Box::new(move |hwnd, x, y| {
+                let command = unsafe {
+                    crate::menu::show_native_tray_menu(hwnd, native_menu.hmenu, x, y, activation)
+                };
+                if let Some(id) = command.and_then(&native_menu.command_to_id) {
+                    (proxy)(Event::MenuItemClicked {
+                        id,
+                        checked: None,
+                        timestamp: util::message_time(),
+                    });
+                }
+            }) as ErasedMenuHandler)
+        } else {
+            tray.menu_items.clone().map(|items| {
+                let proxy = self.proxy.clone();
+                Box::new(move |hwnd, x, y| {
+                    let mut items = items.borrow_mut();
+                    let selected =
+                        unsafe { crate::menu::show_tray_menu(hwnd, &items, x, y, activation) };
+                    let Some(item) = selected.and_then(|index| items.item_mut(index)) else {
+                        return;
+                    };
+
+                    let checked = item.toggle();
+                    let id = item.id.clone();
+                    crate::menu::perform_action(item.action.as_ref());
+                    drop(items);
+
+                    (proxy)(Event::MenuItemClicked {
+                        id,
+                        checked,
+                        timestamp: util::message_time(),
+                    });
+                }) as ErasedMenuHandler
+            })
+        };
+
         WindowData {
+            tray_id: tray.internal_id,
             userdata_removed: Cell::new(false),
             recurse_depth: Cell::new(0),
             runner: self.runner.clone(),
             event_sender,
+            double_click_sender,
+            last_pointer_position: tray.last_pointer_position.clone(),
+            icon_size: Cell::new(util::tray_icon_size(tray.hwnd())),
+            icon_size_sender,
+            notification_sender,
+            icon_state: tray.icon_state.clone(),
+            button_filter: self.attributes.button_filter,
+            #[cfg(feature = "menu")]
+            primary_action: self.attributes.primary_action,
+            #[cfg(feature = "menu")]
+            menu_handler,
         }
     }
 
     unsafe fn on_nccreate(&mut self, window: HWND) -> Option<isize> {
-        let res = self.runner.catch_unwind(|| {
+        let runner = self.runner.clone();
+        let res = runner.catch_unwind(|| {
             let tray = unsafe { self.create_tray(window) };
             let tray_data = unsafe { self.create_tray_data(&tray) };
             (tray, tray_data)
@@ -243,8 +1042,9 @@ impl<T: Clone + Send + Sync + 'static> InitData<T> {
 
 unsafe fn init<T: Clone + Send + Sync + 'static>(
     proxy: EventCallback<T>,
-    attr: TrayIconAttributes,
-) -> Result<Tray<T>, anyhow::Error> {
+    attr: TrayIconAttributes<T>,
+    #[cfg(feature = "menu")] native_menu: Option<NativeMenu<T>>,
+) -> Result<Tray<T>, TrayError> {
     let class_name = util::encode_wide(&attr.class_name);
 
     let class = WNDCLASSEXW {
@@ -271,6 +1071,10 @@ unsafe fn init<T: Clone + Send + Sync + 'static>(
     };
 
     let mut initdata = InitData::new(attr, proxy, Default::default());
+    #[cfg(feature = "menu")]
+    {
+        initdata.native_menu = native_menu;
+    }
 
     let handle = unsafe {
         CreateWindowExW(
@@ -299,17 +1103,28 @@ unsafe fn init<T: Clone + Send + Sync + 'static>(
 
     let tray = initdata.tray.unwrap();
 
+    // Elevated processes only get Explorer's broadcast when they allow it.
+    unsafe {
+        ChangeWindowMessageFilterEx(
+            tray.hwnd(),
+            TASKBAR_CREATED_MSG_ID.get(),
+            MSGFLT_ALLOW,
+            ptr::null_mut(),
+        )
+    };
+
     let hicon = initdata
         .attributes
         .icon
         .as_ref()
-        .and_then(util::icon_to_hicon);
+        .map(|icon| util::icon_to_hicon(icon, util::tray_icon_size(tray.hwnd())))
+        .transpose()?;
 
     if !unsafe {
         register_tray_icon(
             tray.hwnd(),
             tray.internal_id,
-            hicon,
+            hicon.as_ref().map(OwnedIcon::handle),
             initdata.attributes.tooltip.as_ref(),
         )
     } {
@@ -409,6 +1224,18 @@ unsafe fn public_window_callback_inner(
                     || lparam as u32 == WM_MBUTTONDOWN
                     || lparam as u32 == WM_XBUTTONDOWN) =>
             {
+                if !userdata.is_interactive() {
+                    result = ProcResult::Value(0);
+                    return;
+                }
+                if matches!(
+                    lparam as u32,
+                    WM_LBUTTONDOWN | WM_RBUTTONDOWN | WM_MBUTTONDOWN | WM_XBUTTONDOWN
+                ) {
+                    userdata.set_pointer(window, Pointer::Pressed);
+                } else {
+                    userdata.set_pointer(window, Pointer::Over);
+                }
                 let mut point = POINT { x: 0, y: 0 };
                 if unsafe { GetCursorPos(&mut point) } == 0 {
                     result = ProcResult::Value(-1);
@@ -447,12 +1274,97 @@ unsafe fn public_window_callback_inner(
                     winit_core::event::ButtonSource::Mouse(button),
                 );
 
+                #[cfg(feature = "menu")]
+                if state == ElementState::Released {
+                    userdata.show_menu_for(window, button, point);
+                }
+
+                result = ProcResult::Value(0);
+            }
+
+            WM_USER_TRAYICON
+                if matches!(
+                    lparam as u32,
+                    WM_LBUTTONDBLCLK | WM_RBUTTONDBLCLK | WM_MBUTTONDBLCLK
+                ) =>
+            {
+                if !userdata.is_interactive() {
+                    result = ProcResult::Value(0);
+                    return;
+                }
+                let mut point = POINT { x: 0, y: 0 };
+                if unsafe { GetCursorPos(&mut point) } == 0 {
+                    result = ProcResult::Value(-1);
+                    return;
+                }
+                let button = match lparam as u32 {
+                    WM_LBUTTONDBLCLK => MouseButton::Left,
+                    WM_RBUTTONDBLCLK => MouseButton::Right,
+                    _ => MouseButton::Middle,
+                };
+                userdata.send_double_click(
+                    window,
+                    PhysicalPosition::new(point.x as f64, point.y as f64),
+                    button,
+                );
+                result = ProcResult::Value(0);
+            }
+
+            WM_USER_TRAYICON if lparam as u32 == WM_MOUSEMOVE => {
+                userdata.pointer_moved(window);
+                result = ProcResult::Value(0);
+            }
+
+            WM_USER_TRAYICON if lparam as u32 == NIN_BALLOONUSERCLICK => {
+                (userdata.notification_sender)();
+                result = ProcResult::Value(0);
+            }
+
+            WM_TIMER if wparam == HOVER_TIMER_ID => {
+                userdata.check_pointer_left(window);
+                result = ProcResult::Value(0);
+            }
+
+            WM_TIMER if wparam == TOOLTIP_TIMER_ID => {
+                userdata.revert_tooltip(window);
                 result = ProcResult::Value(0);
             }
 
+            WM_DPICHANGED | WM_DISPLAYCHANGE | WM_SETTINGCHANGE => {
+                userdata.check_icon_size(window);
+                result = ProcResult::DefWindowProc(wparam);
+            }
+
+            WM_NCDESTROY => {
+                // Last message the window receives: free the window data once
+                // the outermost window procedure call returns.
+                unsafe { util::set_window_long(window, GWL_USERDATA, 0) };
+                userdata.userdata_removed.set(true);
+                result = ProcResult::DefWindowProc(wparam);
+            }
+
             _ => {
                 if msg == DESTROY_MSG_ID.get() {
-                    unsafe { DestroyWindow(window) };
+                    unsafe { remove_tray_icon(window, userdata.tray_id) };
+                    if unsafe { DestroyWindow(window) } == 0 {
+                        warn!(
+                            internal_id = userdata.tray_id,
+                            "Failed to destroy tray window: {}",
+                            std::io::Error::last_os_error()
+                        );
+                    }
+                    result = ProcResult::Value(0);
+                } else if msg == FLASH_TOOLTIP_MSG_ID.get() {
+                    // Setting the timer again restarts it, so the latest
+                    // flash decides when the tooltip reverts.
+                    unsafe { SetTimer(window, TOOLTIP_TIMER_ID, wparam as u32, None) };
+                    result = ProcResult::Value(0);
+                } else if msg == TASKBAR_CREATED_MSG_ID.get() {
+                    userdata.restore_icon(window);
+                    result = ProcResult::Value(0);
+                } else if msg == OPEN_MENU_MSG_ID.get() {
+                    #[cfg(feature = "menu")]
+                    userdata.open_menu(window);
                     result = ProcResult::Value(0);
                 } else {
                     result = ProcResult::DefWindowProc(wparam);
@@ -473,7 +1385,64 @@ pub(crate) enum ProcResult {
     Value(isize),
 }
 
-const WM_USER_TRAYICON: u32 = 6002;
+/// The message the shell sends the tray window for input on the icon.
+///
+/// In the `WM_APP` range, which is reserved for private messages of the
+/// application's own window classes.
+const WM_USER_TRAYICON: u32 = WM_APP + 1;
+
+/// Timer that checks whether the pointer has left an icon showing its
+/// hover icon, and how often it runs.
+const HOVER_TIMER_ID: usize = 1;
+const HOVER_POLL_MS: u32 = 100;
+
+/// Timer that reverts a flashed tooltip.
+const TOOLTIP_TIMER_ID: usize = 2;
+
+/// Removes the notification icon, logging if the shell refuses.
+unsafe fn remove_tray_icon(hwnd: HWND, tray_icon_id: u32) {
+    let mut nid = NOTIFYICONDATAW {
+        cbSize: std::mem::size_of::<NOTIFYICONDATAW>() as u32,
+        hWnd: hwnd,
+        uID: tray_icon_id,
+        ..unsafe { std::mem::zeroed() }
+    };
+
+    if unsafe { Shell_NotifyIconW(NIM_DELETE, &mut nid as _) } == 0 {
+        warn!(
+            internal_id = tray_icon_id,
+            "NIM_DELETE failed, tray icon may linger until hovered"
+        );
+    }
+}
+
+/// Replaces the image of an existing tray icon.
+unsafe fn update_tray_icon(hwnd: HWND, tray_icon_id: u32, hicon: HICON) -> bool {
+    let mut nid = NOTIFYICONDATAW {
+        cbSize: std::mem::size_of::<NOTIFYICONDATAW>() as u32,
+        uFlags: NIF_ICON,
+        hWnd: hwnd,
+        uID: tray_icon_id,
+        hIcon: hicon,
+        ..unsafe { std::mem::zeroed() }
+    };
+    unsafe { Shell_NotifyIconW(NIM_MODIFY, &mut nid as _) != 0 }
+}
+
+/// The icon shown for trays without one: the executable's `tray-default`
+/// icon resource, or else the stock application icon.
+unsafe fn default_tray_icon() -> Option<HICON> {
+    let mut handle = unsafe {
+        LoadIconW(
+            GetModuleHandleW(std::ptr::null()),
+            util::encode_wide("tray-default").as_ptr(),
+        )
+    };
+    if handle.is_null() {
+        handle = unsafe { LoadIconW(0 as _, IDI_APPLICATION) };
+    }
+    (!handle.is_null()).then_some(handle)
+}
 
 #[inline]
 unsafe fn register_tray_icon<S: AsRef<OsStr>>(
@@ -485,34 +1454,17 @@ unsafe fn register_tray_icon<S: AsRef<OsStr>>(
     let mut flags = NIF_MESSAGE | NIF_ICON;
     let mut sz_tip: [u16; 128] = [0; 128];
 
-    let h_icon = if let Some(hicon) = hicon {
-        hicon
-    } else {
-        let mut handle = unsafe {
-            LoadIconW(
-                GetModuleHandleW(std::ptr::null()),
-                util::encode_wide("tray-default").as_ptr(),
-            )
-        };
-        if handle.is_null() {
-            handle = unsafe { LoadIconW(0 as _, IDI_APPLICATION) };
-        }
-        if handle.is_null() {
-            return false;
-        }
-        handle
+    let Some(h_icon) = hicon.or_else(|| unsafe { default_tray_icon() }) else {
+        return false;
     };
 
     if let Some(tooltip) = tooltip {
         flags |= NIF_TIP;
-        let tip = util::encode_wide(tooltip);
-        #[allow(clippy::manual_memcpy)]
-        for i in 0..tip.len().min(128) {
-            sz_tip[i] = tip[i];
-        }
+        util::copy_wide(&mut sz_tip, tooltip);
     }
 
     let mut nid = NOTIFYICONDATAW {
+        cbSize: std::mem::size_of::<NOTIFYICONDATAW>() as u32,
         uFlags: flags,
         hWnd: hwnd,
         uID: tray_icon_id,
@@ -1,11 +1,30 @@
-use std::{ffi::OsStr, iter::once, os::windows::ffi::OsStrExt as _, ptr};
+use std::{
+    ffi::OsStr,
+    iter::once,
+    os::windows::ffi::OsStrExt as _,
+    ptr,
+    time::{Duration, Instant},
+};
 
+use dpi::{PhysicalPosition, PhysicalSize};
 use windows_sys::Win32::{
-    Foundation::{HMODULE, HWND},
-    System::SystemServices::IMAGE_DOS_HEADER,
-    UI::WindowsAndMessaging::{CreateIcon, HICON, WINDOW_LONG_PTR_INDEX},
+    Foundation::{HMODULE, HWND, POINT, S_OK},
+    Graphics::Gdi::{MONITOR_DEFAULTTONULL, MonitorFromPoint},
+    System::{SystemInformation::GetTickCount, SystemServices::IMAGE_DOS_HEADER},
+    UI::{
+        HiDpi::{GetDpiForMonitor, GetDpiForWindow, GetSystemMetricsForDpi, MDT_EFFECTIVE_DPI},
+        Shell::{
+            QUNS_ACCEPTS_NOTIFICATIONS, QUNS_APP, SE_ERR_NOASSOC, SHQueryUserNotificationState,
+            ShellExecuteW,
+        },
+        WindowsAndMessaging::{
+            CreateIcon, DestroyIcon, GetMessageTime, HICON, SM_CXSMICON, SM_CYSMICON,
+            SW_SHOWNORMAL, WINDOW_LONG_PTR_INDEX,
+        },
+    },
 };
-use winit_core::icon::{Icon, RgbaIcon};
+use winit_core::icon::Icon;
+use winit_extras_core::icon::{IconError, PreparedIcon};
 
 pub fn get_instance_handle() -> HMODULE {
     // Gets the instance handle by taking the address of the
@@ -49,57 +68,257 @@ pub(crate) unsafe fn set_window_long(
     };
 }
 
+/// When the message most recently retrieved by this thread was posted.
+///
+/// `GetMessageTime` and `GetTickCount` share the same millisecond clock, so
+/// their difference is the age of the message. Both wrap after ~49 days,
+/// which the wrapping subtraction accounts for.
+pub(crate) fn message_time() -> Instant {
+    let age = unsafe { GetTickCount().wrapping_sub(GetMessageTime() as u32) };
+    let now = Instant::now();
+    now.checked_sub(Duration::from_millis(age.into()))
+        .unwrap_or(now)
+}
+
+/// The size the notification area draws icons at for `hwnd`'s monitor.
+pub(crate) fn tray_icon_size(hwnd: HWND) -> PhysicalSize<u32> {
+    unsafe {
+        let dpi = GetDpiForWindow(hwnd);
+        PhysicalSize::new(
+            GetSystemMetricsForDpi(SM_CXSMICON, dpi) as u32,
+            GetSystemMetricsForDpi(SM_CYSMICON, dpi) as u32,
+        )
+    }
+}
+
+/// The scale factor of the monitor containing `position`, in screen
+/// coordinates as tray events report them. `None` if no monitor contains it.
+pub fn scale_factor_at(position: PhysicalPosition<f64>) -> Option<f64> {
+    let point = POINT {
+        x: position.x.round() as i32,
+        y: position.y.round() as i32,
+    };
+    unsafe {
+        let monitor = MonitorFromPoint(point, MONITOR_DEFAULTTONULL);
+        if monitor.is_null() {
+            return None;
+        }
+        let (mut dpi_x, mut dpi_y) = (0, 0);
+        if GetDpiForMonitor(monitor, MDT_EFFECTIVE_DPI, &mut dpi_x, &mut dpi_y) != S_OK {
+            return None;
+        }
+        Some(dpi_x as f64 / 96.0)
+    }
+}
+
+/// Opens the Notifications page of the Settings app, where users allow or
+/// silence notifications per app.
+pub fn open_notification_settings() -> std::io::Result<()> {
+    let operation = encode_wide("open");
+    let uri = encode_wide("ms-settings:notifications");
+    let result = unsafe {
+        ShellExecuteW(
+            ptr::null_mut(),
+            operation.as_ptr(),
+            uri.as_ptr(),
+            ptr::null(),
+            ptr::null(),
+            SW_SHOWNORMAL,
+        )
+    };
+    // Anything above 32 is success; lower values are error codes, and the
+    // last error isn't set.
+    let code = result as usize as u32;
+    if code > 32 {
+        return Ok(());
+    }
+    Err(match code {
+        0 => std::io::ErrorKind::OutOfMemory.into(),
+        // These match the Win32 error codes of the same value.
+        2 | 3 | 5 | 8 | 11 => std::io::Error::from_raw_os_error(code as i32),
+        SE_ERR_NOASSOC => std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "no application is registered for ms-settings: URIs",
+        ),
+        _ => std::io::Error::other(format!("ShellExecuteW failed with code {code}")),
+    })
+}
+
+/// Whether the shell currently shows notifications, as reported by
+/// `SHQueryUserNotificationState`.
+///
+/// `false` during quiet hours, presentations and full-screen games or
+/// videos. Focus Assist has no public API and is only seen when it also
+/// sets one of those states. `true` if the state can't be read.
+pub fn notifications_allowed() -> bool {
+    let mut state = 0;
+    if unsafe { SHQueryUserNotificationState(&mut state) } != S_OK {
+        return true;
+    }
+    // A Store app in the foreground doesn't hold notifications back.
+    matches!(state, QUNS_ACCEPTS_NOTIFICATIONS | QUNS_APP)
+}
+
 pub fn encode_wide(string: impl AsRef<OsStr>) -> Vec<u16> {
     string.as_ref().encode_wide().chain(once(0)).collect()
 }
 
-#[repr(C)]
-struct Pixel {
-    r: u8,
-    g: u8,
-    b: u8,
-    a: u8,
+/// Copies `string` into a fixed-size buffer such as `szTip`, truncating it to
+/// leave room for the terminating null.
+///
+/// Truncation never splits a surrogate pair, so emoji at the cut are dropped
+/// whole rather than left as half a character.
+pub fn copy_wide(buffer: &mut [u16], string: impl AsRef<OsStr>) {
+    let Some(capacity) = buffer.len().checked_sub(1) else {
+        return;
+    };
+    let wide: Vec<u16> = string.as_ref().encode_wide().collect();
+    let mut len = wide.len().min(capacity);
+    if len < wide.len() && len > 0 && is_high_surrogate(wide[len - 1]) {
+        len -= 1;
+    }
+    buffer[..len].copy_from_slice(&wide[..len]);
+    buffer[len] = 0;
+}
+
+fn is_high_surrogate(unit: u16) -> bool {
+    (0xD800..0xDC00).contains(&unit)
+}
+
+/// Encodes a menu label, escaping `&` unless it marks access keys.
+#[cfg(feature = "menu")]
+pub fn encode_label(label: &str, mnemonic: bool) -> Vec<u16> {
+    encode_shortcut_label(label, mnemonic, None)
 }
 
-impl Pixel {
-    fn convert_to_bgra(&mut self) {
-        std::mem::swap(&mut self.r, &mut self.b);
+/// Like [`encode_label`], but puts `shortcut` after a tab, which menus draw
+/// right-aligned in a column of its own.
+#[cfg(feature = "menu")]
+pub fn encode_shortcut_label(label: &str, mnemonic: bool, shortcut: Option<&str>) -> Vec<u16> {
+    let label = if mnemonic {
+        std::borrow::Cow::Borrowed(label)
+    } else {
+        winit_extras_core::menu::escape_mnemonics(label)
+    };
+    match shortcut {
+        Some(shortcut) => encode_wide(format!("{label}\t{shortcut}")),
+        None => encode_wide(&*label),
     }
 }
 
-const PIXEL_SIZE: usize = std::mem::size_of::<Pixel>();
+/// Encodes the label of `item`, with its accelerator if it has one.
+#[cfg(feature = "menu")]
+pub fn encode_item_label<T>(item: &winit_extras_core::MenuItem<T>) -> Vec<u16> {
+    let shortcut = item
+        .accelerator
+        .map(|accelerator| accelerator.display_string());
+    encode_shortcut_label(&item.label, item.mnemonic, shortcut.as_deref())
+}
 
-pub fn icon_to_hicon(icon: &Icon) -> Option<HICON> {
-    if let Some(rgba) = icon.0.cast_ref::<RgbaIcon>() {
-        let pixel_count = rgba.buffer().len() / PIXEL_SIZE;
-        let mut and_mask = Vec::with_capacity(pixel_count);
+/// An `HICON` this crate created, destroyed when dropped.
+///
+/// `Shell_NotifyIconW` and `DrawIconEx` don't take ownership, so the icon
+/// can be dropped as soon as the call returns. Shared icons, such as those
+/// from `LoadIconW`, must not be wrapped.
+pub struct OwnedIcon(HICON);
 
-        let mut bgra_buffer = rgba.buffer().to_vec();
-        let pixels = unsafe {
-            std::slice::from_raw_parts_mut(bgra_buffer.as_mut_ptr() as *mut Pixel, pixel_count)
-        };
+impl OwnedIcon {
+    pub fn handle(&self) -> HICON {
+        self.0
+    }
+}
 
-        for pixel in pixels {
-            and_mask.push(pixel.a.wrapping_sub(u8::MAX));
-            pixel.convert_to_bgra();
-        }
+impl Drop for OwnedIcon {
+    fn drop(&mut self) {
+        unsafe { DestroyIcon(self.0) };
+    }
+}
 
-        let handle = unsafe {
-            CreateIcon(
-                ptr::null_mut(),
-                rgba.width() as i32,
-                rgba.height() as i32,
-                1,
-                (PIXEL_SIZE * 8) as u8,
-                and_mask.as_ptr(),
-                bgra_buffer.as_ptr(),
-            )
-        };
-
-        if !handle.is_null() {
-            return Some(handle);
-        }
+/// Converts a winit Icon to an `HICON`, rasterizing scalable icons at `size`.
+///
+/// Icons already converted with [`PreparedIcon::prepare`] skip the pixel
+/// conversion step.
+pub fn icon_to_hicon(icon: &Icon, size: PhysicalSize<u32>) -> Result<OwnedIcon, IconError> {
+    let prepared = PreparedIcon::try_prepare_sized(icon, size)?;
+
+    let handle = unsafe {
+        CreateIcon(
+            ptr::null_mut(),
+            prepared.width() as i32,
+            prepared.height() as i32,
+            1,
+            32,
+            prepared.mask().as_ptr(),
+            prepared.data().as_ptr(),
+        )
+    };
+
+    if handle.is_null() {
+        Err(IconError::ConversionFailed)
+    } else {
+        Ok(OwnedIcon(handle))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_wide_emoji() {
+        // U+1F4BE FLOPPY DISK is outside the BMP, so it takes a surrogate pair.
+        let wide = encode_wide("\u{1F4BE} Save");
+        assert_eq!(&wide[..2], &[0xD83D, 0xDCBE]);
+        assert_eq!(wide.len(), "\u{1F4BE} Save".encode_utf16().count() + 1);
+        assert_eq!(wide.last(), Some(&0));
+        assert_eq!(
+            String::from_utf16(&wide[..wide.len() - 1]).unwrap(),
+            "\u{1F4BE} Save"
+        );
+    }
+
+    #[cfg(feature = "menu")]
+    #[test]
+    fn test_encode_label_emoji() {
+        let wide = encode_label("Caf\u{e9} & \u{1F375}", false);
+        assert_eq!(
+            String::from_utf16(&wide[..wide.len() - 1]).unwrap(),
+            "Caf\u{e9} && \u{1F375}"
+        );
     }
 
-    None
+    #[cfg(feature = "menu")]
+    #[test]
+    fn test_encode_item_label_shortcut() {
+        use winit_core::keyboard::{KeyCode, ModifiersState};
+        use winit_extras_core::{Accelerator, MenuItem};
+
+        let item = MenuItem::new((), "Save & Quit").accelerator(Accelerator::new(
+            ModifiersState::CONTROL | ModifiersState::SHIFT,
+            KeyCode::KeyS,
+        ));
+        let wide = encode_item_label(&item);
+        assert_eq!(
+            String::from_utf16(&wide[..wide.len() - 1]).unwrap(),
+            "Save && Quit\tCtrl+Shift+S"
+        );
+    }
+
+    #[test]
+    fn test_copy_wide_keeps_surrogate_pairs() {
+        let mut buffer = [0xFFFF; 4];
+        // Two units of text, then an emoji that only half fits.
+        copy_wide(&mut buffer, "ab\u{1F600}");
+        assert_eq!(buffer, [b'a' as u16, b'b' as u16, 0, 0xFFFF]);
+
+        let mut buffer = [0xFFFF; 5];
+        copy_wide(&mut buffer, "ab\u{1F600}");
+        assert_eq!(buffer, [b'a' as u16, b'b' as u16, 0xD83D, 0xDE00, 0]);
+
+        // Long text is always null-terminated.
+        let mut buffer = [0xFFFF; 128];
+        copy_wide(&mut buffer, "\u{1F600}".repeat(100));
+        assert_eq!(buffer[126], 0);
+        assert!(is_high_surrogate(buffer[124]));
+    }
 }